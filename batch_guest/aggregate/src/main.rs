@@ -0,0 +1,25 @@
+//! RISC Zero guest that folds consecutive proofs: reads an `AggregationInput`,
+//! verifies each child's receipt by composition (the host adds them as
+//! assumptions) and commits the `AggregateJournal` of the whole window.
+//! Children are batch guest receipts or receipts of this guest itself, whose
+//! image id comes in as input and goes out in the journal for settlement to check.
+
+#![no_main]
+
+use risc0_zkvm::{guest::env, serde::to_vec};
+use zk::{aggregate, aggregate::ProvedJournal, AggregationInput};
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    let input: AggregationInput = env::read();
+    for child in &input.children {
+        let (image_id, journal) = match &child.journal {
+            ProvedJournal::Batch(journal) => (input.batch_image_id, to_vec(journal)),
+            ProvedJournal::Aggregate(journal) => (input.aggregate_image_id, to_vec(journal)),
+        };
+        env::verify(image_id, &journal.expect("journal serializes")).expect("child receipt verifies");
+    }
+    let aggregation = aggregate(&input).unwrap_or_else(|e| panic!("{}", e));
+    env::commit(&aggregation.journal);
+}
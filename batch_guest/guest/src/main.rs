@@ -0,0 +1,17 @@
+//! RISC Zero guest for rollup batches: reads a `BatchWitness`, re-executes its
+//! signed transactions over the accounts they touch, checking every signature on
+//! the way, and commits a `BatchJournal` with the state roots before and after.
+//! A failed check panics, so a bad batch gets no receipt.
+
+#![no_main]
+
+use risc0_zkvm::guest::env;
+use zk::{BatchJournal, BatchWitness};
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    let witness: BatchWitness = env::read();
+    let journal = BatchJournal::execute(&witness).unwrap_or_else(|e| panic!("batch {} does not execute: {}", witness.batch_id, e));
+    env::commit(&journal);
+}
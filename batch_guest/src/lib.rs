@@ -0,0 +1,4 @@
+//! The guests' ELFs, image ids and paths, built by `build.rs`: `BATCH_GUEST_*`
+//! for batches and `AGGREGATE_GUEST_*` for folding their receipts.
+
+include!(concat!(env!("OUT_DIR"), "/methods.rs"));
@@ -0,0 +1,101 @@
+//! Runs the guest with `RISC0_DEV_MODE`: it executes for real, but the receipt is
+//! fake, so these tests need the guest built and nothing else.
+
+use batch_guest_methods::{AGGREGATE_GUEST_ELF, AGGREGATE_GUEST_ID, BATCH_GUEST_ELF, BATCH_GUEST_ID};
+use ed25519_dalek::{Signer, SigningKey};
+use risc0_zkvm::{default_prover, ExecutorEnv, Receipt};
+use zk::{
+    aggregate, execute, AccountWitness, AggregateJournal, AggregationChild, AggregationInput, BatchJournal, BatchWitness,
+    StateTree,
+};
+
+/// A legacy transaction in wire format: `signer` pays the fee and sends `lamports` to `to`.
+fn transfer(signer: &SigningKey, to: [u8; 32], lamports: u64) -> Vec<u8> {
+    let mut message = vec![1, 0, 1, 3];
+    message.extend(signer.verifying_key().to_bytes());
+    message.extend(to);
+    message.extend([0; 32]);
+    message.extend([9; 32]);
+    message.extend([1, 2, 2, 0, 1, 12, 2, 0, 0, 0]);
+    message.extend(lamports.to_le_bytes());
+
+    let mut wire = vec![1];
+    wire.extend(signer.sign(&message).to_bytes());
+    wire.extend(message);
+    wire
+}
+
+/// alice, with a million lamports, and the account at `[7; 32]`, both entering the empty state.
+fn witness(transactions: Vec<Vec<u8>>, alice: &SigningKey) -> BatchWitness {
+    let alice = AccountWitness { lamports: 1_000_000, ..AccountWitness::empty(alice.verifying_key().to_bytes()) };
+    BatchWitness {
+        batch_id: "dev".to_string(),
+        slots: 4,
+        transfers: Vec::new(),
+        transactions,
+        state_paths: StateTree::new().paths(&[alice.pubkey, [7; 32]]).unwrap(),
+        accounts: vec![alice, AccountWitness::empty([7; 32])],
+    }
+}
+
+fn prove(witness: &BatchWitness) -> anyhow::Result<Receipt> {
+    std::env::set_var("RISC0_DEV_MODE", "1");
+    let env = ExecutorEnv::builder().write(witness)?.build()?;
+    Ok(default_prover().prove(env, BATCH_GUEST_ELF)?.receipt)
+}
+
+#[test]
+fn guest_commits_the_roots_native_execution_computes() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let witness = witness(vec![transfer(&alice, [7; 32], 300_000)], &alice);
+
+    let receipt = prove(&witness).unwrap();
+    receipt.verify(BATCH_GUEST_ID).unwrap();
+    let journal: BatchJournal = receipt.journal.decode().unwrap();
+    assert_eq!(journal, BatchJournal::execute(&witness).unwrap());
+    assert_ne!(journal.pre_state_root, journal.post_state_root);
+    assert_eq!(journal.pre_state_root, StateTree::new().root_bytes());
+}
+
+#[test]
+fn guest_refuses_a_forged_transfer() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let mut forged = transfer(&alice, [7; 32], 300_000);
+    // raise the amount after signing
+    let last = forged.len() - 1;
+    forged[last - 5] = 0xff;
+
+    assert!(prove(&witness(vec![forged], &alice)).is_err());
+}
+
+#[test]
+fn aggregate_guest_folds_batch_receipts() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let first = witness(vec![transfer(&alice, [7; 32], 300_000)], &alice);
+    let mut second = witness(vec![transfer(&alice, [7; 32], 100_000)], &alice);
+    second.batch_id = "dev-2".to_string();
+    // the second batch starts from the state tree the first left
+    let mut state = StateTree::new();
+    second.accounts = execute(&first.accounts, &first.state_paths, &first.transactions).unwrap().accounts;
+    for account in &second.accounts {
+        state.set(&account.pubkey, account.lamports).unwrap();
+    }
+    second.state_paths = state.paths(&second.accounts.iter().map(|account| account.pubkey).collect::<Vec<_>>()).unwrap();
+
+    let input = AggregationInput {
+        batch_image_id: BATCH_GUEST_ID,
+        aggregate_image_id: AGGREGATE_GUEST_ID,
+        children: vec![AggregationChild::batch(&first).unwrap(), AggregationChild::batch(&second).unwrap()],
+    };
+    let env = ExecutorEnv::builder()
+        .add_assumption(prove(&first).unwrap())
+        .add_assumption(prove(&second).unwrap())
+        .write(&input)
+        .unwrap()
+        .build()
+        .unwrap();
+    let receipt = default_prover().prove(env, AGGREGATE_GUEST_ELF).unwrap().receipt;
+    receipt.verify(AGGREGATE_GUEST_ID).unwrap();
+    let journal: AggregateJournal = receipt.journal.decode().unwrap();
+    assert_eq!(journal, aggregate(&input).unwrap().journal);
+}
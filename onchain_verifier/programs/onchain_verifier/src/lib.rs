@@ -77,6 +77,17 @@ pub struct VerifiedRisc0Proof {
     pub bump: u8,
 }
 
+/// The hash of the verifying key an authority settles one circuit size class
+/// with; `verify_groth16_proof` only accepts keys registered here
+#[account]
+pub struct RegisteredVerifyingKey {
+    pub authority: Pubkey,
+    pub size_class: u32,
+    pub vk_hash: [u8; 32],
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
 /// Context for verifying and storing Groth16 proofs
 #[derive(Accounts)]
 #[instruction(proof_id: String)]
@@ -93,6 +104,14 @@ pub struct VerifyGroth16<'info> {
     )]
     pub proof_account: Account<'info, VerifiedGroth16Proof>,
 
+    /// The authority's registration of the key the proof is checked with
+    #[account(
+        seeds = [b"verifying_key", authority.key().as_ref(), &registry.size_class.to_le_bytes()],
+        bump = registry.bump,
+        has_one = authority
+    )]
+    pub registry: Account<'info, RegisteredVerifyingKey>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -115,6 +134,40 @@ pub struct VerifyRisc0<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Context for registering the verifying key of a size class
+#[derive(Accounts)]
+#[instruction(size_class: u32)]
+pub struct RegisterVerifyingKey<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RegisteredVerifyingKey>(),
+        seeds = [b"verifying_key", authority.key().as_ref(), &size_class.to_le_bytes()],
+        bump
+    )]
+    pub registry: Account<'info, RegisteredVerifyingKey>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Context for replacing the registered verifying key of a size class
+#[derive(Accounts)]
+#[instruction(size_class: u32)]
+pub struct RotateVerifyingKey<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"verifying_key", authority.key().as_ref(), &size_class.to_le_bytes()],
+        bump = registry.bump,
+        has_one = authority
+    )]
+    pub registry: Account<'info, RegisteredVerifyingKey>,
+}
+
 #[program]
 pub mod onchain_verifier {
     use super::*;
@@ -132,12 +185,16 @@ pub mod onchain_verifier {
             proof_id
         );
 
+        // Only the key the authority registered for this size class may check the proof
+        let vk_hash = hash_verifying_key(&verifying_key);
+        require!(
+            vk_hash == ctx.accounts.registry.vk_hash,
+            VerifierError::UnregisteredVerifyingKey
+        );
+
         // Verify the proof using alt-bn254 syscalls
         verify_groth16_with_alt_bn254(&proof, &public_inputs, &verifying_key)?;
 
-        // Calculate verifying key hash for reference
-        let vk_hash = hash_verifying_key(&verifying_key);
-
         // Store the verified proof
         let proof_account = &mut ctx.accounts.proof_account;
         proof_account.authority = ctx.accounts.authority.key();
@@ -183,6 +240,40 @@ pub mod onchain_verifier {
         msg!("RISC0 proof verified and stored successfully!");
         Ok(())
     }
+
+    /// Register the hash of the verifying key for a circuit size class, as
+    /// computed by `hash_verifying_key`. A class can only be registered once;
+    /// `rotate_verifying_key` replaces its key.
+    pub fn register_verifying_key(
+        ctx: Context<RegisterVerifyingKey>,
+        size_class: u32,
+        vk_hash: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.authority.key();
+        registry.size_class = size_class;
+        registry.vk_hash = vk_hash;
+        registry.registered_at = Clock::get()?.unix_timestamp;
+        registry.bump = ctx.bumps.registry;
+
+        msg!("Verifying key registered for size class {}", size_class);
+        Ok(())
+    }
+
+    /// Replace the registered verifying key hash of a size class
+    pub fn rotate_verifying_key(
+        ctx: Context<RotateVerifyingKey>,
+        size_class: u32,
+        vk_hash: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(registry.vk_hash != vk_hash, VerifierError::VerifyingKeyUnchanged);
+        registry.vk_hash = vk_hash;
+        registry.registered_at = Clock::get()?.unix_timestamp;
+
+        msg!("Verifying key rotated for size class {}", size_class);
+        Ok(())
+    }
 }
 
 /// Verify Groth16 proof using Solana's alt-bn254 syscalls
@@ -347,7 +438,7 @@ fn verify_scalar_in_field(x: &[u8; 32]) -> Result<()> {
 }
 
 /// Hash a verifying key for reference
-fn hash_verifying_key(vk: &Groth16VerifyingKey) -> [u8; 32] {
+pub fn hash_verifying_key(vk: &Groth16VerifyingKey) -> [u8; 32] {
     let mut data = Vec::new();
     data.extend_from_slice(&vk.alpha_g1);
     data.extend_from_slice(&vk.beta_g2);
@@ -517,6 +608,18 @@ pub mod client {
         )
     }
 
+    /// Generate PDA holding the registered verifying key of a size class
+    pub fn get_verifying_key_pda(
+        authority: &Pubkey,
+        size_class: u32,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"verifying_key", authority.as_ref(), &size_class.to_le_bytes()],
+            program_id,
+        )
+    }
+
     /// Helper to create instruction data for Groth16 verification
     pub fn build_groth16_verify_instruction_data(
         proof_id: String,
@@ -568,4 +671,8 @@ pub enum VerifierError {
     PairingError,
     #[msg("Proof verification failed")]
     VerificationError,
+    #[msg("Verifying key does not match the registered one")]
+    UnregisteredVerifyingKey,
+    #[msg("Verifying key is already registered")]
+    VerifyingKeyUnchanged,
 }
@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OnchainVerifier } from "../target/types/onchain_verifier";
 import { expect } from "chai";
+import { createHash } from "crypto";
 import * as fs from "fs";
 import * as path from "path";
 
@@ -30,6 +31,16 @@ function negateG1Point(point: number[]): number[] {
   return point;
 }
 
+// The hash `hash_verifying_key` computes on-chain
+function vkHash(vk: any): number[] {
+  const bytes: number[] = [];
+  for (const part of [vk.alphaG1, vk.betaG2, vk.gammaG2, vk.deltaG2, vk.ic]) {
+    // ic is a list of points, or already flattened
+    bytes.push(...([] as number[]).concat(...part));
+  }
+  return Array.from(createHash("sha256").update(Buffer.from(bytes)).digest());
+}
+
 describe("onchain_verifier", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -40,6 +51,45 @@ describe("onchain_verifier", () => {
   // Test keypair
   const authority = anchor.web3.Keypair.generate();
 
+  // Size class the Groth16 tests register their verifying keys under
+  const GROTH16_SIZE_CLASS = 1;
+
+  function registryPda(sizeClass: number, owner = authority.publicKey) {
+    const sizeClassBytes = Buffer.alloc(4);
+    sizeClassBytes.writeUInt32LE(sizeClass);
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("verifying_key"), owner.toBuffer(), sizeClassBytes],
+      program.programId
+    )[0];
+  }
+
+  // Registers `vk` for GROTH16_SIZE_CLASS, rotating away from whatever was registered before
+  async function useVerifyingKey(vk: any) {
+    const registry = registryPda(GROTH16_SIZE_CLASS);
+    const hash = vkHash(vk);
+    const registered =
+      await program.account.registeredVerifyingKey.fetchNullable(registry);
+    if (registered === null) {
+      await program.methods
+        .registerVerifyingKey(GROTH16_SIZE_CLASS, hash)
+        .accounts({
+          authority: authority.publicKey,
+          registry,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    } else if (
+      Buffer.compare(Buffer.from(registered.vkHash), Buffer.from(hash)) !== 0
+    ) {
+      await program.methods
+        .rotateVerifyingKey(GROTH16_SIZE_CLASS, hash)
+        .accounts({ authority: authority.publicKey, registry })
+        .signers([authority])
+        .rpc();
+    }
+  }
+
   before(async () => {
     // Airdrop SOL to the test authority
     const airdropSignature = await provider.connection.requestAirdrop(
@@ -142,12 +192,15 @@ describe("onchain_verifier", () => {
       console.log("  • Public input (y):", PROOF_DATA.public_inputs.inputs[0]);
       console.log("  • VK nPublic:", VK_DATA.nPublic);
 
+      await useVerifyingKey(realVk);
+
       try {
         const tx = await program.methods
           .verifyGroth16Proof(proofId, realProof, realPublicInputs, realVk)
           .accounts({
             authority: authority.publicKey,
             proofAccount: proofPda,
+            registry: registryPda(GROTH16_SIZE_CLASS),
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .signers([authority])
@@ -203,12 +256,15 @@ describe("onchain_verifier", () => {
         program.programId
       );
 
+      await useVerifyingKey(invalidVk);
+
       try {
         await program.methods
           .verifyGroth16Proof(proofId, invalidProof, publicInputs, invalidVk)
           .accounts({
             authority: authority.publicKey,
             proofAccount: proofPda,
+            registry: registryPda(GROTH16_SIZE_CLASS),
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .signers([authority])
@@ -253,12 +309,15 @@ describe("onchain_verifier", () => {
         program.programId
       );
 
+      await useVerifyingKey(mockVk);
+
       try {
         await program.methods
           .verifyGroth16Proof(proofId, mockProof, invalidPublicInputs, mockVk)
           .accounts({
             authority: authority.publicKey,
             proofAccount: proofPda,
+            registry: registryPda(GROTH16_SIZE_CLASS),
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .signers([authority])
@@ -309,12 +368,15 @@ describe("onchain_verifier", () => {
         program.programId
       );
 
+      await useVerifyingKey(mockVk);
+
       try {
         await program.methods
           .verifyGroth16Proof(proofId, mockProof, validPublicInputs, mockVk)
           .accounts({
             authority: authority.publicKey,
             proofAccount: proofPda,
+            registry: registryPda(GROTH16_SIZE_CLASS),
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .signers([authority])
@@ -529,6 +591,111 @@ describe("onchain_verifier", () => {
     });
   });
 
+  describe("Verifying Key Registry", () => {
+    const sizeClass = 4;
+
+    it("Should register a size class once and rotate it explicitly", async () => {
+      const registry = registryPda(sizeClass);
+      const register = (hash: number[]) =>
+        program.methods
+          .registerVerifyingKey(sizeClass, hash)
+          .accounts({
+            authority: authority.publicKey,
+            registry,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+
+      await register(Array(32).fill(7));
+      let registered = await program.account.registeredVerifyingKey.fetch(
+        registry
+      );
+      expect(registered.sizeClass).to.equal(sizeClass);
+      expect(registered.vkHash).to.deep.equal(Array(32).fill(7));
+
+      try {
+        await register(Array(32).fill(8));
+        expect.fail("Registering a size class twice should fail");
+      } catch (error) {
+        expect(error.message).to.include("already in use");
+      }
+
+      await program.methods
+        .rotateVerifyingKey(sizeClass, Array(32).fill(8))
+        .accounts({ authority: authority.publicKey, registry })
+        .signers([authority])
+        .rpc();
+      registered = await program.account.registeredVerifyingKey.fetch(registry);
+      expect(registered.vkHash).to.deep.equal(Array(32).fill(8));
+    });
+
+    it("Should only let the registering authority rotate a size class", async () => {
+      const intruder = anchor.web3.Keypair.generate();
+
+      try {
+        await program.methods
+          .rotateVerifyingKey(sizeClass, Array(32).fill(9))
+          .accounts({
+            authority: intruder.publicKey,
+            registry: registryPda(sizeClass),
+          })
+          .signers([intruder])
+          .rpc();
+        expect.fail("Rotating another authority's key should fail");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("Should reject a proof under a verifying key that is not registered", async () => {
+      const proofId = "unregistered_vk";
+      const registeredVk = {
+        alphaG1: new Array(64).fill(1),
+        betaG2: new Array(128).fill(2),
+        gammaG2: new Array(128).fill(3),
+        deltaG2: new Array(128).fill(4),
+        ic: [new Array(64).fill(5), new Array(64).fill(6)],
+      };
+      const otherVk = { ...registeredVk, alphaG1: new Array(64).fill(9) };
+      await useVerifyingKey(registeredVk);
+
+      const [proofPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("groth16_proof"),
+          authority.publicKey.toBuffer(),
+          Buffer.from(proofId),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .verifyGroth16Proof(
+            proofId,
+            {
+              piA: new Array(64).fill(1),
+              piB: new Array(128).fill(2),
+              piC: new Array(64).fill(3),
+            },
+            { inputs: [new Array(32).fill(0).map((_, i) => (i === 31 ? 49 : 0))] },
+            otherVk
+          )
+          .accounts({
+            authority: authority.publicKey,
+            proofAccount: proofPda,
+            registry: registryPda(GROTH16_SIZE_CLASS),
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        expect.fail("A proof under an unregistered key should be rejected");
+      } catch (error) {
+        expect(error.message).to.include("UnregisteredVerifyingKey");
+      }
+    });
+  });
+
   describe("Edge Cases", () => {
     it("Should handle multiple proofs from same authority", async () => {
       const proofId1 = "multi_proof_1";
@@ -0,0 +1,47 @@
+//! Standalone prover worker: leases batches from a node's prover service and proves them.
+//!
+//! Configured through the environment:
+//! - `PROVER_SERVICE_URL`: the node's prover service (default `http://127.0.0.1:8080/prover`)
+//! - `PROVER_SHARED_SECRET`: the node's `prover.remote.shared_secret` (required)
+//! - `PROVER_KEY_DIR`: a copy of the node's key store, `batch_<slots>/` directories (default `build/keys`)
+//! - `PROVER_DEV_SEED`: dev mode only; accept seeded or uncontributed keys, and set up missing ones from this seed
+//! - `PROVER_WORKER_ID`: name shown in job status (default `worker-<pid>`)
+//! - `PROVER_POLL_INTERVAL_MS`: wait between polls of an empty queue (default 1000)
+//! - `ROLLUP_API_KEY`: sent as a bearer token when the node protects `/prover`
+
+use std::{env, path::PathBuf, sync::atomic::AtomicBool, time::Duration};
+
+use anyhow::{Context, Result};
+use prover_service::{CoordinatorClient, Worker};
+use zk::KeyStore;
+
+fn main() -> Result<()> {
+    // arkworks opens an "r1cs" span per gadget call, which would otherwise be logged one by one
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info,r1cs=off,tracing::span=off")).init();
+
+    let url = env::var("PROVER_SERVICE_URL").unwrap_or_else(|_| "http://127.0.0.1:8080/prover".to_string());
+    let shared_secret = env::var("PROVER_SHARED_SECRET").context("PROVER_SHARED_SECRET must be set")?;
+    let key_dir = PathBuf::from(env::var("PROVER_KEY_DIR").unwrap_or_else(|_| "build/keys".to_string()));
+    let worker_id = env::var("PROVER_WORKER_ID").unwrap_or_else(|_| format!("worker-{}", std::process::id()));
+    let poll_interval = match env::var("PROVER_POLL_INTERVAL_MS") {
+        Ok(ms) => Duration::from_millis(ms.parse().context("PROVER_POLL_INTERVAL_MS must be a number")?),
+        Err(_) => Duration::from_secs(1),
+    };
+
+    anyhow::ensure!(key_dir.is_dir(), "Key directory {} does not exist", key_dir.display());
+    let mut keys = KeyStore::new(key_dir);
+    if let Ok(seed) = env::var("PROVER_DEV_SEED") {
+        log::warn!("Dev mode: proving with keys that are not safe for settlement");
+        keys = keys.with_dev_setup(seed.parse().context("PROVER_DEV_SEED must be a number")?);
+    }
+    let mut client = CoordinatorClient::new(&url, shared_secret);
+    if let Ok(api_key) = env::var("ROLLUP_API_KEY") {
+        client = client.with_api_key(api_key);
+    }
+
+    log::info!("Worker {} polling {}", worker_id, url);
+    Worker::new(client, keys, worker_id)
+        .with_poll_interval(poll_interval)
+        .run(&AtomicBool::new(false));
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Blocking client for the prover service, used by workers.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rollup_api::ApiError;
+use serde::{de::DeserializeOwned, Serialize};
+use zk::BatchWitness;
+
+use crate::{
+    http::SECRET_HEADER,
+    protocol::{
+        CompleteRequest, HeartbeatRequest, HeartbeatResponse, JobOutcome, JobResult, JobStatus, LeaseRequest, LeasedJob,
+        SubmitJobRequest, SubmitJobResponse,
+    },
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct CoordinatorClient {
+    /// The node's prover service root, e.g. `http://127.0.0.1:8080/prover`.
+    base_url: String,
+    shared_secret: String,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl CoordinatorClient {
+    /// `shared_secret` is the node's `prover.remote.shared_secret`.
+    pub fn new(base_url: impl Into<String>, shared_secret: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            shared_secret: shared_secret.into(),
+            api_key: None,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+
+    /// Sends `Authorization: Bearer <api_key>`, for nodes with `/prover` protected.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn submit(&self, witness: BatchWitness) -> Result<String> {
+        let response: Option<SubmitJobResponse> = self.post("/jobs", &SubmitJobRequest { witness })?;
+        Ok(response.ok_or_else(|| anyhow!("prover service returned no job id"))?.job_id)
+    }
+
+    /// Gives up on a job, so no worker proves it.
+    pub fn cancel(&self, job_id: &str) -> Result<()> {
+        self.post::<_, serde_json::Value>(&format!("/jobs/{}/cancel", job_id), &serde_json::json!({}))?;
+        Ok(())
+    }
+
+    pub fn status(&self, job_id: &str) -> Result<JobStatus> {
+        self.get(&format!("/jobs/{}", job_id))
+    }
+
+    pub fn result(&self, job_id: &str) -> Result<JobResult> {
+        self.get(&format!("/jobs/{}/result", job_id))
+    }
+
+    /// `None` when no job is waiting.
+    pub fn lease(&self, worker_id: &str) -> Result<Option<LeasedJob>> {
+        self.post("/lease", &LeaseRequest { worker_id: worker_id.to_string() })
+    }
+
+    pub fn heartbeat(&self, job_id: &str, lease_id: &str) -> Result<HeartbeatResponse> {
+        let request = HeartbeatRequest { lease_id: lease_id.to_string() };
+        self.post(&format!("/jobs/{}/heartbeat", job_id), &request)?
+            .ok_or_else(|| anyhow!("prover service returned no lease"))
+    }
+
+    pub fn complete(&self, job_id: &str, lease_id: &str, outcome: JobOutcome) -> Result<()> {
+        let request = CompleteRequest { lease_id: lease_id.to_string(), outcome };
+        self.post::<_, serde_json::Value>(&format!("/jobs/{}/complete", job_id), &request)?;
+        Ok(())
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.send(self.request("GET", path).call())?;
+        Ok(response.into_json()?)
+    }
+
+    /// `None` for `204 No Content`.
+    fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<Option<T>> {
+        let response = self.send(self.request("POST", path).send_json(body))?;
+        if response.status() == 204 {
+            return Ok(None);
+        }
+        Ok(Some(response.into_json()?))
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}{}", self.base_url, path))
+            .set(SECRET_HEADER, &self.shared_secret);
+        match &self.api_key {
+            Some(key) => request.set("Authorization", &format!("Bearer {}", key)),
+            None => request,
+        }
+    }
+
+    fn send(&self, result: Result<ureq::Response, ureq::Error>) -> Result<ureq::Response> {
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(status, response)) => match response.into_json::<ApiError>() {
+                Ok(error) => Err(anyhow!(error)),
+                Err(_) => Err(anyhow!("prover service answered {}", status)),
+            },
+            Err(e) => Err(anyhow!("prover service unreachable: {}", e)),
+        }
+    }
+}
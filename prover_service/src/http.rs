@@ -0,0 +1,159 @@
+//! The prover service's HTTP routes, mounted by the node under `/prover`.
+//!
+//! | Route | |
+//! |-------|-|
+//! | `POST /jobs` | submit a witness, answers `202` with the job id |
+//! | `GET /jobs/{job_id}` | job state, attempts and current worker |
+//! | `GET /jobs/{job_id}/result` | the proof, `409` until the job has finished |
+//! | `POST /lease` | lease the next job, `204` when there is none |
+//! | `POST /jobs/{job_id}/heartbeat` | renew a lease, `409` once it is lost |
+//! | `POST /jobs/{job_id}/complete` | report a proof or a proving failure |
+//! | `POST /jobs/{job_id}/cancel` | give up on a job, it is not proved or retried |
+//!
+//! Handlers expect the [`JobQueue`] and the [`SharedSecret`] as `web::Data`. Every
+//! request must carry the secret in [`SECRET_HEADER`]; without a configured
+//! secret every request is refused.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+};
+
+use actix_web::{dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use rollup_api::{ApiError, ErrorCode};
+
+use crate::{
+    protocol::{
+        CompleteRequest, HeartbeatRequest, HeartbeatResponse, JobResult, LeaseRequest, SubmitJobRequest, SubmitJobResponse,
+    },
+    queue::{JobQueue, QueueError},
+};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/jobs", web::post().to(submit_job))
+        .route("/jobs/{job_id}", web::get().to(job_status))
+        .route("/jobs/{job_id}/result", web::get().to(job_result))
+        .route("/jobs/{job_id}/heartbeat", web::post().to(heartbeat))
+        .route("/jobs/{job_id}/complete", web::post().to(complete))
+        .route("/jobs/{job_id}/cancel", web::post().to(cancel))
+        .route("/lease", web::post().to(lease));
+}
+
+/// Header that carries the shared secret of the node and its workers.
+pub const SECRET_HEADER: &str = "X-Prover-Secret";
+
+/// The secret every prover service request must present.
+pub struct SharedSecret(String);
+
+impl SharedSecret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    fn matches(&self, presented: &[u8]) -> bool {
+        let expected = self.0.as_bytes();
+        expected.len() == presented.len() && expected.iter().zip(presented).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
+
+/// Extracted by every handler, so a request without the shared secret never reaches the queue.
+struct Authorized;
+
+impl FromRequest for Authorized {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, ServiceError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(secret) = req.app_data::<web::Data<SharedSecret>>() else {
+            return ready(Err(ServiceError(ApiError::new(ErrorCode::Unavailable, "prover service has no shared secret configured"))));
+        };
+        let presented = req.headers().get(SECRET_HEADER).map(|value| value.as_bytes()).unwrap_or_default();
+        if secret.matches(presented) {
+            ready(Ok(Authorized))
+        } else {
+            ready(Err(ServiceError(ApiError::new(ErrorCode::Unauthorized, format!("missing or wrong {} header", SECRET_HEADER)))))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ServiceError(pub ApiError);
+
+impl From<QueueError> for ServiceError {
+    fn from(error: QueueError) -> Self {
+        let code = match error {
+            QueueError::UnknownJob(_) => ErrorCode::NotFound,
+            QueueError::LeaseLost(_) | QueueError::NotFinished(_) | QueueError::JobFailed(_) => ErrorCode::Conflict,
+            QueueError::TimedOut(_) => ErrorCode::Timeout,
+            QueueError::Full(_) => ErrorCode::Unavailable,
+        };
+        ServiceError(ApiError::new(code, error.to_string()))
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ResponseError for ServiceError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.0.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(&self.0)
+    }
+}
+
+async fn submit_job(
+    _: Authorized,
+    queue: web::Data<JobQueue>,
+    body: web::Json<SubmitJobRequest>,
+) -> Result<HttpResponse, ServiceError> {
+    let job_id = queue.submit(body.into_inner().witness)?;
+    Ok(HttpResponse::Accepted().json(SubmitJobResponse { job_id }))
+}
+
+async fn job_status(_: Authorized, queue: web::Data<JobQueue>, job_id: web::Path<String>) -> Result<HttpResponse, ServiceError> {
+    Ok(HttpResponse::Ok().json(queue.status(&job_id)?))
+}
+
+async fn job_result(_: Authorized, queue: web::Data<JobQueue>, job_id: web::Path<String>) -> Result<HttpResponse, ServiceError> {
+    let artifact = queue.result(&job_id)?;
+    Ok(HttpResponse::Ok().json(JobResult { job_id: job_id.into_inner(), artifact }))
+}
+
+async fn lease(_: Authorized, queue: web::Data<JobQueue>, body: web::Json<LeaseRequest>) -> HttpResponse {
+    match queue.lease(&body.worker_id) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => HttpResponse::NoContent().finish(),
+    }
+}
+
+async fn heartbeat(
+    _: Authorized,
+    queue: web::Data<JobQueue>,
+    job_id: web::Path<String>,
+    body: web::Json<HeartbeatRequest>,
+) -> Result<HttpResponse, ServiceError> {
+    let remaining = queue.heartbeat(&job_id, &body.lease_id)?;
+    Ok(HttpResponse::Ok().json(HeartbeatResponse { lease_secs: remaining.as_secs() }))
+}
+
+async fn complete(
+    _: Authorized,
+    queue: web::Data<JobQueue>,
+    job_id: web::Path<String>,
+    body: web::Json<CompleteRequest>,
+) -> Result<HttpResponse, ServiceError> {
+    let CompleteRequest { lease_id, outcome } = body.into_inner();
+    queue.complete(&job_id, &lease_id, outcome)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn cancel(_: Authorized, queue: web::Data<JobQueue>, job_id: web::Path<String>) -> Result<HttpResponse, ServiceError> {
+    queue.cancel(&job_id)?;
+    Ok(HttpResponse::NoContent().finish())
+}
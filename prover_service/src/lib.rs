@@ -0,0 +1,20 @@
+//! Remote proving for the rollup node.
+//!
+//! The node keeps a [`JobQueue`] of batches to prove and serves it over HTTP
+//! (see [`http`]). `prover-worker` processes lease jobs from it, prove them
+//! with their own copy of the proving key, and report the proof back; while a
+//! job is leased its worker sends heartbeats, and a job whose lease runs out
+//! goes back to the queue for another worker. Every request carries a secret
+//! shared by the node and its workers.
+
+pub mod client;
+pub mod http;
+pub mod protocol;
+pub mod queue;
+pub mod worker;
+
+pub use client::CoordinatorClient;
+pub use http::SharedSecret;
+pub use protocol::*;
+pub use queue::{JobQueue, QueueError};
+pub use worker::{groth16_artifact, Worker};
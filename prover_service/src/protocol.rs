@@ -0,0 +1,82 @@
+//! Request and response bodies of the prover service. Errors use the node's
+//! `{ "code", "error" }` envelope (`rollup_api::ApiError`).
+
+use rollup_api::ProofArtifact;
+use serde::{Deserialize, Serialize};
+use zk::BatchWitness;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitJobRequest {
+    pub witness: BatchWitness,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitJobResponse {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Leased,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub batch_id: String,
+    pub state: JobState,
+    /// Leases handed out so far, including the current one.
+    pub attempts: u32,
+    /// The worker holding the job, while it is leased.
+    pub worker_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobResult {
+    pub job_id: String,
+    pub artifact: ProofArtifact,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseRequest {
+    pub worker_id: String,
+}
+
+/// A job handed to a worker. It is the worker's until `lease_secs` pass without a heartbeat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeasedJob {
+    pub job_id: String,
+    pub lease_id: String,
+    pub lease_secs: u64,
+    pub witness: BatchWitness,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatRequest {
+    pub lease_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatResponse {
+    /// Seconds until the lease runs out unless renewed again.
+    pub lease_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteRequest {
+    pub lease_id: String,
+    pub outcome: JobOutcome,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum JobOutcome {
+    Proved { artifact: ProofArtifact },
+    /// Proving itself failed, e.g. the batch is invalid. Not retried.
+    Failed { error: String },
+}
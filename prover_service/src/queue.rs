@@ -0,0 +1,318 @@
+//! The coordinator's job queue: submission, leasing, heartbeats and retry.
+//!
+//! Leases are checked lazily, whenever the queue is touched, and by callers
+//! blocked in [`JobQueue::wait`], so no reaper thread is needed. An expired
+//! lease puts the job back at the front of the queue until it has been leased
+//! `max_attempts` times; after that the job fails.
+//!
+//! At most `capacity` jobs are unfinished at once; a submitter that gives up on
+//! a job cancels it, so it stops holding a slot or a worker.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use rollup_api::ProofArtifact;
+use zk::BatchWitness;
+
+use crate::protocol::{JobOutcome, JobState, JobStatus, LeasedJob};
+
+/// Finished jobs stay queryable this long, then are dropped on the next submission.
+const FINISHED_RETENTION: Duration = Duration::from_secs(60 * 60);
+/// Unfinished jobs a queue holds unless told otherwise.
+pub const DEFAULT_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueError {
+    UnknownJob(String),
+    /// The lease expired (and the job may have moved to another worker) or never existed.
+    LeaseLost(String),
+    NotFinished(String),
+    JobFailed(String),
+    TimedOut(String),
+    /// The queue already holds this many unfinished jobs.
+    Full(usize),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::UnknownJob(job_id) => write!(f, "no job {}", job_id),
+            QueueError::LeaseLost(job_id) => write!(f, "lease on job {} is no longer held", job_id),
+            QueueError::NotFinished(job_id) => write!(f, "job {} is not finished", job_id),
+            QueueError::JobFailed(error) => write!(f, "proving failed: {}", error),
+            QueueError::TimedOut(job_id) => write!(f, "job {} did not finish in time", job_id),
+            QueueError::Full(capacity) => write!(f, "job queue is full ({} unfinished jobs)", capacity),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+struct Lease {
+    lease_id: String,
+    worker_id: String,
+    expires_at: Instant,
+}
+
+struct Job {
+    witness: BatchWitness,
+    state: JobState,
+    attempts: u32,
+    lease: Option<Lease>,
+    artifact: Option<ProofArtifact>,
+    error: Option<String>,
+    finished_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct State {
+    jobs: HashMap<String, Job>,
+    queued: VecDeque<String>,
+}
+
+pub struct JobQueue {
+    state: Mutex<State>,
+    changed: Condvar,
+    lease_duration: Duration,
+    max_attempts: u32,
+    capacity: usize,
+}
+
+impl JobQueue {
+    pub fn new(lease_duration: Duration, max_attempts: u32) -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+            lease_duration,
+            max_attempts,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Unfinished (queued or leased) jobs the queue holds before refusing submissions.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn submit(&self, witness: BatchWitness) -> Result<String, QueueError> {
+        let job_id = random_id();
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .jobs
+            .retain(|_, job| job.finished_at.is_none_or(|at| now.duration_since(at) < FINISHED_RETENTION));
+        if state.jobs.values().filter(|job| job.finished_at.is_none()).count() >= self.capacity {
+            return Err(QueueError::Full(self.capacity));
+        }
+
+        log::info!("Queued proving job {} for batch {}", job_id, witness.batch_id);
+        state.jobs.insert(
+            job_id.clone(),
+            Job {
+                witness,
+                state: JobState::Queued,
+                attempts: 0,
+                lease: None,
+                artifact: None,
+                error: None,
+                finished_at: None,
+            },
+        );
+        state.queued.push_back(job_id.clone());
+        Ok(job_id)
+    }
+
+    /// Hands the oldest queued job to `worker_id`, if there is one.
+    pub fn lease(&self, worker_id: &str) -> Option<LeasedJob> {
+        let mut state = self.state.lock().unwrap();
+        self.expire_leases(&mut state);
+
+        let job_id = state.queued.pop_front()?;
+        let job = state.jobs.get_mut(&job_id).expect("queued jobs exist");
+        let lease_id = random_id();
+        job.state = JobState::Leased;
+        job.attempts += 1;
+        job.lease = Some(Lease {
+            lease_id: lease_id.clone(),
+            worker_id: worker_id.to_string(),
+            expires_at: Instant::now() + self.lease_duration,
+        });
+        log::info!("Leased job {} to worker {} (attempt {})", job_id, worker_id, job.attempts);
+
+        Some(LeasedJob {
+            job_id,
+            lease_id,
+            lease_secs: self.lease_duration.as_secs(),
+            witness: job.witness.clone(),
+        })
+    }
+
+    /// Extends the lease by another lease duration.
+    pub fn heartbeat(&self, job_id: &str, lease_id: &str) -> Result<Duration, QueueError> {
+        let mut state = self.state.lock().unwrap();
+        self.expire_leases(&mut state);
+
+        let lease = held_lease(&mut state, job_id, lease_id)?;
+        lease.expires_at = Instant::now() + self.lease_duration;
+        Ok(self.lease_duration)
+    }
+
+    pub fn complete(&self, job_id: &str, lease_id: &str, outcome: JobOutcome) -> Result<(), QueueError> {
+        let mut state = self.state.lock().unwrap();
+        self.expire_leases(&mut state);
+
+        held_lease(&mut state, job_id, lease_id)?;
+        let job = state.jobs.get_mut(job_id).expect("checked by held_lease");
+        job.lease = None;
+        job.finished_at = Some(Instant::now());
+        match outcome {
+            JobOutcome::Proved { artifact } => {
+                log::info!("Job {} proved", job_id);
+                job.state = JobState::Completed;
+                job.artifact = Some(artifact);
+            }
+            JobOutcome::Failed { error } => {
+                log::warn!("Job {} failed: {}", job_id, error);
+                job.state = JobState::Failed;
+                job.error = Some(error);
+            }
+        }
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Gives up on a job nobody waits for anymore. A queued job is never leased; a
+    /// leased one loses its lease, so its worker's heartbeats and report are refused.
+    /// Finished jobs are left as they are.
+    pub fn cancel(&self, job_id: &str) -> Result<(), QueueError> {
+        let mut state = self.state.lock().unwrap();
+        let job = state.jobs.get_mut(job_id).ok_or_else(|| QueueError::UnknownJob(job_id.to_string()))?;
+        if job.finished_at.is_some() {
+            return Ok(());
+        }
+
+        log::info!("Cancelled job {} (batch {})", job_id, job.witness.batch_id);
+        job.state = JobState::Failed;
+        job.error = Some("cancelled by its submitter".to_string());
+        job.lease = None;
+        job.finished_at = Some(Instant::now());
+        state.queued.retain(|queued| queued != job_id);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    pub fn status(&self, job_id: &str) -> Result<JobStatus, QueueError> {
+        let mut state = self.state.lock().unwrap();
+        self.expire_leases(&mut state);
+
+        let job = state.jobs.get(job_id).ok_or_else(|| QueueError::UnknownJob(job_id.to_string()))?;
+        Ok(JobStatus {
+            job_id: job_id.to_string(),
+            batch_id: job.witness.batch_id.clone(),
+            state: job.state,
+            attempts: job.attempts,
+            worker_id: job.lease.as_ref().map(|lease| lease.worker_id.clone()),
+            error: job.error.clone(),
+        })
+    }
+
+    pub fn result(&self, job_id: &str) -> Result<ProofArtifact, QueueError> {
+        let mut state = self.state.lock().unwrap();
+        self.expire_leases(&mut state);
+        finished(&state, job_id).unwrap_or_else(|| Err(QueueError::NotFinished(job_id.to_string())))
+    }
+
+    /// Blocks until the job finishes or `timeout` passes.
+    pub fn wait(&self, job_id: &str, timeout: Duration) -> Result<ProofArtifact, QueueError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        loop {
+            self.expire_leases(&mut state);
+            if let Some(result) = finished(&state, job_id) {
+                return result;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(QueueError::TimedOut(job_id.to_string()));
+            }
+            // wake at least once per lease duration so lost workers are noticed without queue traffic
+            let wake = (deadline - now).min(self.lease_duration);
+            state = self.changed.wait_timeout(state, wake).unwrap().0;
+        }
+    }
+
+    /// Jobs waiting for a worker.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn lease_duration(&self) -> Duration {
+        self.lease_duration
+    }
+
+    fn expire_leases(&self, state: &mut State) {
+        let now = Instant::now();
+        let mut requeue = Vec::new();
+        let mut failed = false;
+
+        for (job_id, job) in state.jobs.iter_mut() {
+            let Some(lease) = &job.lease else { continue };
+            if lease.expires_at > now {
+                continue;
+            }
+
+            log::warn!("Worker {} lost job {} (lease expired after attempt {})", lease.worker_id, job_id, job.attempts);
+            job.lease = None;
+            if job.attempts >= self.max_attempts {
+                job.state = JobState::Failed;
+                job.error = Some(format!("lease expired on all {} attempts", job.attempts));
+                job.finished_at = Some(now);
+                failed = true;
+            } else {
+                job.state = JobState::Queued;
+                requeue.push(job_id.clone());
+            }
+        }
+
+        // retried jobs go first, they have waited longest
+        for job_id in requeue {
+            state.queued.push_front(job_id);
+        }
+        if failed {
+            self.changed.notify_all();
+        }
+    }
+}
+
+fn held_lease<'a>(state: &'a mut State, job_id: &str, lease_id: &str) -> Result<&'a mut Lease, QueueError> {
+    let job = state.jobs.get_mut(job_id).ok_or_else(|| QueueError::UnknownJob(job_id.to_string()))?;
+    job.lease
+        .as_mut()
+        .filter(|lease| lease.lease_id == lease_id)
+        .ok_or_else(|| QueueError::LeaseLost(job_id.to_string()))
+}
+
+fn finished(state: &State, job_id: &str) -> Option<Result<ProofArtifact, QueueError>> {
+    let Some(job) = state.jobs.get(job_id) else {
+        return Some(Err(QueueError::UnknownJob(job_id.to_string())));
+    };
+    match job.state {
+        JobState::Completed => Some(Ok(job.artifact.clone().expect("completed jobs have an artifact"))),
+        JobState::Failed => Some(Err(QueueError::JobFailed(job.error.clone().unwrap_or_default()))),
+        JobState::Queued | JobState::Leased => None,
+    }
+}
+
+fn random_id() -> String {
+    rand::random::<[u8; 16]>().iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,114 @@
+//! The worker loop: lease a job, prove it while heartbeating, report back.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::Result;
+use rollup_api::{ProofArtifact, ProofData};
+use zk::{BatchProof, KeyStore};
+
+use crate::{client::CoordinatorClient, protocol::JobOutcome, LeasedJob};
+
+/// The Groth16 artifact the node settles, from a native batch proof.
+pub fn groth16_artifact(proof: &BatchProof) -> ProofArtifact {
+    let snarkjs = proof.to_snarkjs();
+    ProofArtifact::Groth16 {
+        proof: Box::new(ProofData {
+            pi_a: snarkjs.pi_a,
+            pi_b: snarkjs.pi_b,
+            pi_c: snarkjs.pi_c,
+            protocol: snarkjs.protocol,
+            curve: snarkjs.curve,
+        }),
+        public_inputs: proof.public_inputs_decimal(),
+        slots: proof.slots,
+    }
+}
+
+pub struct Worker {
+    client: CoordinatorClient,
+    keys: KeyStore,
+    worker_id: String,
+    poll_interval: Duration,
+}
+
+impl Worker {
+    /// `keys` should hold copies of the node's proving keys; a job for a size class
+    /// without one fails.
+    pub fn new(client: CoordinatorClient, keys: KeyStore, worker_id: impl Into<String>) -> Self {
+        Self {
+            client,
+            keys,
+            worker_id: worker_id.into(),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// How long to wait after finding the queue empty or the node unreachable.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Serves jobs until `stop` is set.
+    pub fn run(&self, stop: &AtomicBool) {
+        log::info!("Worker {} proving with keys from {}", self.worker_id, self.keys.dir().display());
+        while !stop.load(Ordering::Relaxed) {
+            match self.run_once() {
+                Ok(true) => {}
+                Ok(false) => thread::sleep(self.poll_interval),
+                Err(e) => {
+                    log::warn!("Worker {}: {:#}", self.worker_id, e);
+                    thread::sleep(self.poll_interval);
+                }
+            }
+        }
+    }
+
+    /// Leases and proves at most one job. `false` when there was nothing to do.
+    pub fn run_once(&self) -> Result<bool> {
+        let Some(job) = self.client.lease(&self.worker_id)? else {
+            return Ok(false);
+        };
+        log::info!("Proving job {} (batch {})", job.job_id, job.witness.batch_id);
+
+        let outcome = self.prove_with_heartbeats(&job);
+        if let JobOutcome::Failed { error } = &outcome {
+            log::warn!("Job {} failed: {}", job.job_id, error);
+        }
+        self.client.complete(&job.job_id, &job.lease_id, outcome)?;
+        log::info!("Reported job {}", job.job_id);
+        Ok(true)
+    }
+
+    fn prove_with_heartbeats(&self, job: &LeasedJob) -> JobOutcome {
+        // renew well before the lease runs out, so one slow request does not lose it
+        let interval = Duration::from_secs(job.lease_secs.max(3) / 3);
+        let (done, beats_stop) = mpsc::channel::<()>();
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                while let Err(mpsc::RecvTimeoutError::Timeout) = beats_stop.recv_timeout(interval) {
+                    if let Err(e) = self.client.heartbeat(&job.job_id, &job.lease_id) {
+                        log::warn!("Heartbeat for job {} failed: {:#}", job.job_id, e);
+                    }
+                }
+            });
+
+            let witness = &job.witness;
+            let proved = self.keys.get(witness.slots).and_then(|prover| prover.prove(witness.transfers.clone(), &witness.state_paths));
+            let outcome = match proved {
+                Ok(proof) => JobOutcome::Proved { artifact: groth16_artifact(&proof) },
+                Err(e) => JobOutcome::Failed { error: e.to_string() },
+            };
+            drop(done);
+            outcome
+        })
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    process::{Child, Command},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_web::{web, App, HttpServer};
+use prover_service::{http, CoordinatorClient, JobOutcome, JobQueue, JobState, QueueError, SharedSecret};
+use rollup_api::{ApiError, ErrorCode, ProofArtifact};
+use zk::{BatchWitness, KeyStore, StateTree, TransferWitness};
+
+const SECRET: &str = "test-prover-secret";
+
+fn witness(batch_id: &str) -> BatchWitness {
+    BatchWitness {
+        batch_id: batch_id.to_string(),
+        slots: 4,
+        transfers: vec![TransferWitness {
+            from: [1; 32],
+            to: [2; 32],
+            amount: 1_000_000,
+            fee: 5_000,
+            signature_first_byte: 42,
+            from_balance_before: 5_000_000,
+            from_balance_after: 3_995_000,
+            to_balance_before: 0,
+            to_balance_after: 1_000_000,
+        }],
+        transactions: Vec::new(),
+        accounts: Vec::new(),
+        state_paths: StateTree::new().paths(&[[1; 32], [2; 32]]).unwrap(),
+    }
+}
+
+/// Serves `queue` under `/prover` on an ephemeral port, like the node does.
+fn serve(queue: Arc<JobQueue>) -> SocketAddr {
+    let (addr_sender, addr_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        actix_web::rt::System::new().block_on(async move {
+            let server = HttpServer::new(move || {
+                App::new()
+                    .app_data(web::Data::from(queue.clone()))
+                    .app_data(web::Data::new(SharedSecret::new(SECRET)))
+                    .service(web::scope("/prover").configure(http::configure))
+            })
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+            addr_sender.send(server.addrs()[0]).unwrap();
+            server.run().await
+        })
+    });
+    addr_receiver.recv().unwrap()
+}
+
+struct WorkerProcess(Child);
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn expired_leases_are_retried_then_failed() {
+    let queue = JobQueue::new(Duration::from_millis(50), 2);
+    let job_id = queue.submit(witness("batch_a")).unwrap();
+
+    let first = queue.lease("lost-worker").unwrap();
+    thread::sleep(Duration::from_millis(80));
+    assert_eq!(queue.heartbeat(&job_id, &first.lease_id), Err(QueueError::LeaseLost(job_id.clone())));
+
+    let second = queue.lease("other-worker").unwrap();
+    assert_eq!(second.job_id, job_id);
+    assert_eq!(queue.status(&job_id).unwrap().attempts, 2);
+
+    // a second expiry uses up max_attempts, so waiters get the failure
+    let result = queue.wait(&job_id, Duration::from_secs(1));
+    assert!(matches!(result, Err(QueueError::JobFailed(_))));
+    assert!(queue.lease("other-worker").is_none());
+}
+
+#[test]
+fn heartbeats_keep_the_lease_and_completion_wakes_waiters() {
+    let queue = Arc::new(JobQueue::new(Duration::from_millis(100), 1));
+    let job_id = queue.submit(witness("batch_b")).unwrap();
+    let job = queue.lease("worker").unwrap();
+
+    for _ in 0..4 {
+        thread::sleep(Duration::from_millis(50));
+        queue.heartbeat(&job_id, &job.lease_id).unwrap();
+    }
+    assert_eq!(queue.status(&job_id).unwrap().state, JobState::Leased);
+
+    let waiter = {
+        let (queue, job_id) = (queue.clone(), job_id.clone());
+        thread::spawn(move || queue.wait(&job_id, Duration::from_secs(5)))
+    };
+    queue
+        .complete(&job_id, &job.lease_id, JobOutcome::Proved { artifact: ProofArtifact::Mock })
+        .unwrap();
+    assert!(matches!(waiter.join().unwrap(), Ok(ProofArtifact::Mock)));
+    assert!(queue.complete(&job_id, &job.lease_id, JobOutcome::Failed { error: "late".into() }).is_err());
+}
+
+#[test]
+fn a_full_queue_refuses_jobs_until_one_is_cancelled() {
+    let queue = JobQueue::new(Duration::from_secs(30), 1).with_capacity(2);
+    let queued = queue.submit(witness("batch_d")).unwrap();
+    let leased = queue.submit(witness("batch_e")).unwrap();
+    let lease = queue.lease("worker").unwrap();
+    assert_eq!(lease.job_id, queued);
+    assert_eq!(queue.submit(witness("batch_f")), Err(QueueError::Full(2)));
+
+    // a cancelled job is never leased, and its worker loses the lease
+    queue.cancel(&leased).unwrap();
+    assert!(queue.lease("worker").is_none());
+    queue.cancel(&queued).unwrap();
+    assert_eq!(queue.heartbeat(&queued, &lease.lease_id), Err(QueueError::LeaseLost(queued.clone())));
+    assert_eq!(queue.status(&queued).unwrap().state, JobState::Failed);
+    assert!(matches!(queue.wait(&queued, Duration::from_secs(1)), Err(QueueError::JobFailed(_))));
+
+    queue.submit(witness("batch_f")).unwrap();
+}
+
+#[test]
+fn requests_need_the_shared_secret_and_a_full_queue_answers_503() {
+    let queue = Arc::new(JobQueue::new(Duration::from_secs(30), 1).with_capacity(1));
+    let addr = serve(queue.clone());
+    let code = |error: anyhow::Error| error.downcast_ref::<ApiError>().map(|error| error.code);
+
+    let intruder = CoordinatorClient::new(format!("http://{}/prover", addr), "not-the-secret");
+    assert_eq!(intruder.submit(witness("batch_g")).map_err(code).unwrap_err(), Some(ErrorCode::Unauthorized));
+    assert!(queue.is_empty());
+
+    let client = CoordinatorClient::new(format!("http://{}/prover", addr), SECRET);
+    let job_id = client.submit(witness("batch_g")).unwrap();
+    assert_eq!(client.submit(witness("batch_h")).map_err(code).unwrap_err(), Some(ErrorCode::Unavailable));
+    client.cancel(&job_id).unwrap();
+    client.submit(witness("batch_h")).unwrap();
+}
+
+#[test]
+fn worker_process_proves_a_job_after_another_worker_is_lost() {
+    let key_dir: PathBuf = std::env::temp_dir().join(format!("prover_service_test_{}", std::process::id()));
+    let keys = KeyStore::new(&key_dir).with_dev_setup(7);
+    keys.get(4).unwrap();
+
+    let queue = Arc::new(JobQueue::new(Duration::from_secs(2), 3));
+    let addr = serve(queue.clone());
+    let client = CoordinatorClient::new(format!("http://{}/prover", addr), SECRET);
+
+    let job_id = client.submit(witness("batch_c")).unwrap();
+    // a worker that leases the job and then disappears without a heartbeat
+    let lost = client.lease("lost-worker").unwrap().unwrap();
+    assert_eq!(lost.job_id, job_id);
+    assert!(client.lease("lost-worker").unwrap().is_none());
+
+    let _worker = WorkerProcess(
+        Command::new(env!("CARGO_BIN_EXE_prover-worker"))
+            .env("PROVER_SERVICE_URL", format!("http://{}/prover", addr))
+            .env("PROVER_SHARED_SECRET", SECRET)
+            .env("PROVER_KEY_DIR", &key_dir)
+            .env("PROVER_DEV_SEED", "7")
+            .env("PROVER_WORKER_ID", "local-worker")
+            .env("PROVER_POLL_INTERVAL_MS", "100")
+            .spawn()
+            .expect("prover-worker starts"),
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let result = loop {
+        match client.result(&job_id) {
+            Ok(result) => break result,
+            Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(200)),
+            Err(e) => panic!("job did not finish: {:#}", e),
+        }
+    };
+    let _ = std::fs::remove_dir_all(&key_dir);
+
+    match result.artifact {
+        ProofArtifact::Groth16 { proof, public_inputs, slots } => {
+            // the state roots before and after
+            assert_eq!(public_inputs.len(), 2);
+            assert_eq!(slots, 4);
+            assert_eq!(proof.protocol, "groth16");
+        }
+        other => panic!("expected a Groth16 proof, got {:?}", other),
+    }
+    let status = client.status(&job_id).unwrap();
+    assert_eq!(status.state, JobState::Completed);
+    assert_eq!(status.attempts, 2);
+
+    // the lost worker's late report is refused
+    assert!(client.complete(&job_id, &lost.lease_id, JobOutcome::Failed { error: "late".into() }).is_err());
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::ProofStatus;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SequencerStatus {
+    pub paused: bool,
+    pub pending: usize,
+    pub capacity: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SealOutcome {
+    /// Transactions that will go into the sealed batch.
+    pub pending: usize,
+}
+
+/// What an admin command did. `failed` lists batches that could not be queued again:
+/// for settlement, or for proving if their proof failed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct AdminOutcome {
+    pub message: String,
+    pub queued: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnsettledProof {
+    pub batch_id: String,
+    pub status: ProofStatus,
+    pub transaction_count: usize,
+    pub retry_count: u32,
+    /// Whether the retry cycle will still pick this proof up on its own.
+    pub auto_retry_eligible: bool,
+    pub age_secs: u64,
+    pub secs_since_update: u64,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CircuitBreakerState {
+    pub consecutive_failures: u32,
+    pub retry_cycles: u32,
+    pub secs_since_last_cycle: Option<u64>,
+    pub open: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnsettledReport {
+    pub proofs: Vec<UnsettledProof>,
+    pub circuit_breaker: CircuitBreakerState,
+}
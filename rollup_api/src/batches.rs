@@ -0,0 +1,222 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::TransactionWithHash;
+
+/// Groth16 proof in snarkjs' JSON layout.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProofData {
+    pub pi_a: [String; 3],
+    pub pi_b: [[String; 2]; 3],
+    pub pi_c: [String; 3],
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// What a prover backend produced for a batch. The variant decides how the batch is settled.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "system", rename_all = "snake_case")]
+pub enum ProofArtifact {
+    /// BN254 Groth16 proof of the batch circuit, verified by the Groth16 verifier program.
+    Groth16 {
+        proof: Box<ProofData>,
+        /// Decimal field elements, as in snarkjs' `public.json`.
+        public_inputs: Vec<String>,
+        /// Size class of the circuit, which picks the verifying key.
+        slots: usize,
+    },
+    /// RISC Zero receipt compressed to Groth16, verified by the RISC Zero verifier program.
+    RiscZero {
+        /// Base64 of the 256-byte Groth16 seal.
+        seal: String,
+        /// Hex guest image id.
+        image_id: String,
+        /// Hex SHA-256 of the journal.
+        journal_digest: String,
+        /// Hex roots of the global state tree before and after the proved batches, as
+        /// the journal commits them; settlement checks they chain.
+        pre_state_root: String,
+        post_state_root: String,
+    },
+    /// Succinct RISC Zero receipt of one batch, waiting to be folded with the batches after
+    /// it into an aggregate `RiscZero` proof. Cannot be verified on-chain by itself.
+    RiscZeroSuccinct {
+        /// Base64 of the bincode `Receipt`.
+        receipt: String,
+    },
+    /// No proof at all, from the development mock prover. Settled without verification.
+    Mock,
+}
+
+impl ProofArtifact {
+    /// Short name of the proof system, for logs and metric labels.
+    pub fn system(&self) -> &'static str {
+        match self {
+            ProofArtifact::Groth16 { .. } => "groth16",
+            ProofArtifact::RiscZero { .. } => "risc_zero",
+            ProofArtifact::RiscZeroSuccinct { .. } => "risc_zero_succinct",
+            ProofArtifact::Mock => "mock",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum ProofStatus {
+    Generated,
+    Posted,
+    Verified,
+    Failed,
+    /// Given up on by an operator; never retried automatically.
+    Abandoned,
+    /// Rejected by the node's own verification before it was posted. The same proof
+    /// would fail again, so it is never retried automatically.
+    Invalid,
+    /// Committed to L1 by the fallback transaction without any proof (dev mode only).
+    /// Nothing was verified, so it is never reported as verified.
+    Unproven,
+}
+
+impl ProofStatus {
+    pub const ALL: [ProofStatus; 7] = [
+        ProofStatus::Generated,
+        ProofStatus::Posted,
+        ProofStatus::Verified,
+        ProofStatus::Failed,
+        ProofStatus::Abandoned,
+        ProofStatus::Invalid,
+        ProofStatus::Unproven,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofStatus::Generated => "generated",
+            ProofStatus::Posted => "posted",
+            ProofStatus::Verified => "verified",
+            ProofStatus::Failed => "failed",
+            ProofStatus::Abandoned => "abandoned",
+            ProofStatus::Invalid => "invalid",
+            ProofStatus::Unproven => "unproven",
+        }
+    }
+}
+
+impl FromStr for ProofStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProofStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown proof status {:?}", s))
+    }
+}
+
+/// One row of the batch listing. Times are unix seconds.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchSummary {
+    pub batch_id: String,
+    pub status: ProofStatus,
+    pub transaction_count: usize,
+    pub retry_count: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub settlement_signature: Option<String>,
+}
+
+/// A batch with its proof and the transactions it covers, as far as the node still has them.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchDetails {
+    pub batch_id: String,
+    pub status: ProofStatus,
+    pub transaction_signatures: Vec<String>,
+    pub transactions: Vec<TransactionWithHash>,
+    /// `None` if proving the batch failed; retrying it proves it again.
+    pub proof: Option<ProofArtifact>,
+    pub retry_count: u32,
+    pub error_message: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// L1 transaction that verified the proof.
+    pub settlement_signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListBatchesParams {
+    pub page: Option<u32>,     // 1-based
+    pub per_page: Option<u32>, // default 50, max 500
+    /// Only batches in this proof status, e.g. `failed`.
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct BatchList {
+    pub batches: Vec<BatchSummary>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+    pub has_more: bool,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct BatchIdPath {
+    pub batch_id: String,
+}
+
+/// A Groth16 proof to check against the node's verifying key for its size class.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifyProofRequest {
+    pub proof: ProofData,
+    /// Decimal field elements, as in snarkjs' `public.json`.
+    pub public_inputs: Vec<String>,
+    /// Size class of the circuit that proved it, which picks the verifying key.
+    pub slots: usize,
+}
+
+/// What the node's off-chain verification, the same check settlement runs before
+/// posting a proof, made of a proof.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProofVerification {
+    pub valid: bool,
+    /// Why the proof was rejected.
+    pub error: Option<String>,
+}
+
+impl ProofData {
+    pub fn from_json_file(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_content = std::fs::read_to_string(file_path)?;
+        let json_value: serde_json::Value = serde_json::from_str(&file_content)?;
+
+        Ok(ProofData {
+            pi_a: [
+                json_value["pi_a"][0].as_str().ok_or_else(|| format!("Missing or invalid pi_a[0] in {}", file_path))?.to_string(),
+                json_value["pi_a"][1].as_str().ok_or_else(|| format!("Missing or invalid pi_a[1] in {}", file_path))?.to_string(),
+                json_value["pi_a"][2].as_str().ok_or_else(|| format!("Missing or invalid pi_a[2] in {}", file_path))?.to_string(),
+            ],
+            pi_b: [
+                [
+                    json_value["pi_b"][0][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[0][0] in {}", file_path))?.to_string(),
+                    json_value["pi_b"][0][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[0][1] in {}", file_path))?.to_string(),
+                ],
+                [
+                    json_value["pi_b"][1][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[1][0] in {}", file_path))?.to_string(),
+                    json_value["pi_b"][1][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[1][1] in {}", file_path))?.to_string(),
+                ],
+                [
+                    json_value["pi_b"][2][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[2][0] in {}", file_path))?.to_string(),
+                    json_value["pi_b"][2][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[2][1] in {}", file_path))?.to_string(),
+                ],
+            ],
+            pi_c: [
+                json_value["pi_c"][0].as_str().ok_or_else(|| format!("Missing or invalid pi_c[0] in {}", file_path))?.to_string(),
+                json_value["pi_c"][1].as_str().ok_or_else(|| format!("Missing or invalid pi_c[1] in {}", file_path))?.to_string(),
+                json_value["pi_c"][2].as_str().ok_or_else(|| format!("Missing or invalid pi_c[2] in {}", file_path))?.to_string(),
+            ],
+            protocol: json_value["protocol"].as_str().ok_or_else(|| format!("Missing or invalid protocol in {}", file_path))?.to_string(),
+            curve: json_value["curve"].as_str().ok_or_else(|| format!("Missing or invalid curve in {}", file_path))?.to_string(),
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    RateLimited,
+    /// RollupDB or another internal component is not running.
+    Unavailable,
+    /// An internal component did not answer in time.
+    Timeout,
+    Internal,
+}
+
+impl ErrorCode {
+    /// HTTP status the node answers with for this code.
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::BadRequest => 400,
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Conflict => 409,
+            ErrorCode::PayloadTooLarge => 413,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::Unavailable => 503,
+            ErrorCode::Timeout => 504,
+            ErrorCode::Internal => 500,
+        }
+    }
+}
+
+/// Body of every failed request other than a transaction rejection.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub error: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        Self { code, error: error.into() }
+    }
+
+    pub fn bad_request(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BadRequest, error)
+    }
+
+    pub fn not_found(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, error)
+    }
+
+    pub fn unavailable(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, error)
+    }
+
+    pub fn timeout(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Timeout, error)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.error)
+    }
+}
+
+impl std::error::Error for ApiError {}
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    Sequencer,
+    RollupDb,
+    SettlementWorker,
+    RetryTimer,
+    /// The proving pipeline's collector, which releases proved batches to settlement.
+    Prover,
+}
+
+impl Component {
+    pub const ALL: [Component; 5] = [
+        Component::Sequencer,
+        Component::RollupDb,
+        Component::SettlementWorker,
+        Component::RetryTimer,
+        Component::Prover,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "state", content = "error")]
+pub enum ComponentStatus {
+    Starting,
+    Running,
+    Stopped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    /// Unix seconds of the last unit of work (message, batch, tick) the component handled.
+    pub last_activity: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct L1Health {
+    pub connected: bool,
+    pub last_slot: Option<u64>,
+    pub last_checked: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Body of both `/health` and `/ready`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub ready: bool,
+    /// Why the node is not ready, empty when it is.
+    pub reasons: Vec<String>,
+    pub components: BTreeMap<Component, ComponentHealth>,
+    pub channel_depths: BTreeMap<String, usize>,
+    pub last_batch_at: Option<u64>,
+    pub last_settlement_at: Option<u64>,
+    pub l1: L1Health,
+}
@@ -0,0 +1,18 @@
+//! Wire types of the rollup node's HTTP API, shared by the node and its clients.
+//!
+//! Every type derives `utoipa::ToSchema`, so the node can publish an OpenAPI
+//! document that matches exactly what it serializes. Failed requests always
+//! answer with a non-2xx status and a `{ "code", "error" }` body: an
+//! [`ApiError`], or a [`TransactionRejection`] for refused transactions.
+
+mod admin;
+mod batches;
+mod error;
+mod health;
+mod transactions;
+
+pub use admin::*;
+pub use batches::*;
+pub use error::*;
+pub use health::*;
+pub use transactions::*;
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::transaction::Transaction;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct GetTransaction {
+    /// If present → fetch a single tx by this base58 hash or transaction signature.
+    pub get_tx: Option<String>,
+    /// For list mode:
+    pub page: Option<u32>,     // 1-based
+    pub per_page: Option<u32>, // default 50, max 500
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct RollupTransaction {
+    pub sender: Option<String>,
+    /// A transaction in solana-sdk's serde form. Prefer `encoded_transaction`.
+    #[schema(value_type = Option<Object>)]
+    pub sol_transaction: Option<Transaction>,
+    /// Standard bincode wire bytes, encoded as given by `encoding`. Takes precedence over `sol_transaction`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoded_transaction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TransactionEncoding>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionEncoding {
+    #[default]
+    Base64,
+    Base58,
+}
+
+/// Answer to an accepted `/submit_transaction`.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct SubmitTransactionResponse {
+    /// `Submitted`, or `Submitted (test)` for a dev-mode test transfer.
+    #[serde(rename = "Transaction status")]
+    pub status: String,
+    /// First signature of the transaction, the handle for `get_transaction`.
+    pub signature: String,
+}
+
+/// Many wire-encoded transactions submitted in one request.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SubmitTransactionBatch {
+    pub transactions: Vec<String>,
+    #[serde(default)]
+    pub encoding: TransactionEncoding,
+}
+
+/// Outcome for one entry of a `SubmitTransactionBatch`, in request order.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SubmitBatchEntry {
+    pub signature: Option<String>,
+    pub rejection: Option<TransactionRejection>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SubmitBatchResponse {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub results: Vec<SubmitBatchEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TransactionWithHash {
+    pub hash: String,
+    #[schema(value_type = Object)]
+    pub transaction: Transaction,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct RollupTransactionsList {
+    pub sender: Option<String>,
+    pub transactions: Vec<TransactionWithHash>, // raw transactions
+    pub page: u32,
+    pub per_page: u32,
+    pub total: Option<u64>,
+    pub has_more: bool,
+    pub error: Option<String>,
+}
+
+/// Answer to `/get_transaction`: one transaction when `get_tx` is set, otherwise a page.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(untagged)]
+pub enum GetTransactionResponse {
+    // the list has required fields, so it must be tried first when deserializing
+    List(RollupTransactionsList),
+    Single(RollupTransaction),
+}
+
+/// Path parameter naming a transaction by its first signature.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct SignaturePath {
+    /// Base58 transaction signature.
+    pub signature: String,
+}
+
+/// Lamport/owner/data changes the SVM produced for one account during simulation.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AccountDiff {
+    pub pubkey: String,
+    pub lamports_before: u64,
+    pub lamports_after: u64,
+    pub owner_before: Option<String>, // None if the account did not exist yet
+    pub owner_after: String,
+    pub data_len_before: usize,
+    pub data_len_after: usize,
+    pub data_changed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SimulationResult {
+    pub signature: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+    pub account_diffs: Vec<AccountDiff>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RejectCode {
+    MalformedTransaction,
+    MissingSignature,
+    InvalidSignature,
+    PacketTooLarge,
+    SanitizeFailure,
+    /// Not a single system transfer paid by its sender, the only transaction the batch circuit proves.
+    UnsupportedTransaction,
+    FeePayerNotFound,
+    InsufficientFundsForFee,
+    DuplicateTransaction,
+    BlockhashExpired,
+    FeePayerLimitExceeded,
+    MempoolFull,
+}
+
+impl RejectCode {
+    /// Same spelling as the serialized code, for log and metric labels.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectCode::MalformedTransaction => "MALFORMED_TRANSACTION",
+            RejectCode::MissingSignature => "MISSING_SIGNATURE",
+            RejectCode::InvalidSignature => "INVALID_SIGNATURE",
+            RejectCode::PacketTooLarge => "PACKET_TOO_LARGE",
+            RejectCode::SanitizeFailure => "SANITIZE_FAILURE",
+            RejectCode::UnsupportedTransaction => "UNSUPPORTED_TRANSACTION",
+            RejectCode::FeePayerNotFound => "FEE_PAYER_NOT_FOUND",
+            RejectCode::InsufficientFundsForFee => "INSUFFICIENT_FUNDS_FOR_FEE",
+            RejectCode::DuplicateTransaction => "DUPLICATE_TRANSACTION",
+            RejectCode::BlockhashExpired => "BLOCKHASH_EXPIRED",
+            RejectCode::FeePayerLimitExceeded => "FEE_PAYER_LIMIT_EXCEEDED",
+            RejectCode::MempoolFull => "MEMPOOL_FULL",
+        }
+    }
+}
+
+/// Why a submitted transaction was refused. Same `{ code, error }` shape as `ApiError`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransactionRejection {
+    pub code: RejectCode,
+    pub error: String,
+}
+
+impl TransactionRejection {
+    pub fn new(code: RejectCode, error: impl Into<String>) -> Self {
+        Self { code, error: error.into() }
+    }
+}
+
+impl fmt::Display for TransactionRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.error)
+    }
+}
+
+impl std::error::Error for TransactionRejection {}
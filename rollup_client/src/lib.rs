@@ -2,23 +2,16 @@ use anyhow::{anyhow, Result};
 use reqwest::Client;
 use std::collections::HashMap;
 use solana_system_interface::instruction as system_instruction;
-use rollup_core::frontend::{RollupTransaction, TransactionWithHash};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rollup_api::{
+    ApiError, BatchDetails, BatchList, ProofData, ProofStatus, ProofVerification, RollupTransaction,
+    RollupTransactionsList, SimulationResult, SubmitBatchResponse, SubmitTransactionBatch, SubmitTransactionResponse,
+    TransactionEncoding, TransactionRejection, TransactionWithHash, VerifyProofRequest,
+};
 use solana_sdk::{
     hash::Hash, keccak, signature::{Keypair, Signer}, system_instruction::transfer, transaction::Transaction
 };
 
-/// List response (matches server's paginated JSON)
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct RollupTransactionsList {
-    pub sender: Option<String>,
-    pub transactions: Vec<TransactionWithHash>,
-    pub page: u32,
-    pub per_page: u32,
-    pub total: Option<u64>,
-    pub has_more: bool,
-    pub error: Option<String>,
-}
-
 /// Create a Solana transaction for testing/demonstration
 pub fn create_solana_transaction(
     from: &Keypair,
@@ -30,16 +23,42 @@ pub fn create_solana_transaction(
     Transaction::new_signed_with_payer(&[ix], Some(&from.pubkey()), &[from], recent_blockhash)
 }
 
-/// Submit a transaction to the rollup server
+/// Encode a transaction as base64 bincode wire bytes, the format the rollup expects
+pub fn encode_transaction(transaction: &Transaction) -> Result<String> {
+    Ok(STANDARD.encode(bincode::serialize(transaction)?))
+}
+
+/// Turns a non-2xx response into an error carrying the server's `{ code, error }` body.
+/// Callers can downcast it to `TransactionRejection` or `ApiError`.
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await?;
+    // the two code sets are disjoint, so a rejection never parses as an ApiError or vice versa
+    if let Ok(rejection) = serde_json::from_str::<TransactionRejection>(&body) {
+        return Err(rejection.into());
+    }
+    match serde_json::from_str::<ApiError>(&body) {
+        Ok(error) => Err(error.into()),
+        Err(_) => Err(anyhow!("Request failed with {}: {}", status, body)),
+    }
+}
+
+/// Submit a transaction to the rollup server.
+/// On success the response carries the transaction `signature`, usable with `get_transaction`.
 pub async fn submit_transaction_to_rollup(
     client: &Client,
     base_url: &str,
     sender_name: Option<&str>,
     transaction: Transaction,
-) -> Result<HashMap<String, String>> {
+) -> Result<SubmitTransactionResponse> {
     let rollup_tx = RollupTransaction {
         sender: sender_name.map(|s| s.to_string()),
-        sol_transaction: Some(transaction),
+        sol_transaction: None,
+        encoded_transaction: Some(encode_transaction(&transaction)?),
+        encoding: Some(TransactionEncoding::Base64),
         error: None,
     };
 
@@ -47,14 +66,65 @@ pub async fn submit_transaction_to_rollup(
         .post(&format!("{}/submit_transaction", base_url.trim_end_matches('/')))
         .json(&rollup_tx)
         .send()
-        .await?
-        .error_for_status()? // surface non-2xx as Err
-        .json::<HashMap<String, String>>()
         .await?;
 
+    // validation failures come back with a structured reject code
+    let response = check_response(response).await?.json::<SubmitTransactionResponse>().await?;
+
     Ok(response)
 }
 
+/// Submit many transactions in one request; results come back in the same order
+pub async fn submit_transactions_to_rollup(
+    client: &Client,
+    base_url: &str,
+    transactions: &[Transaction],
+) -> Result<SubmitBatchResponse> {
+    let batch = SubmitTransactionBatch {
+        transactions: transactions.iter().map(encode_transaction).collect::<Result<Vec<_>>>()?,
+        encoding: TransactionEncoding::Base64,
+    };
+
+    let resp = client
+        .post(&format!("{}/submit_transactions", base_url.trim_end_matches('/')))
+        .json(&batch)
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<SubmitBatchResponse>()
+        .await?;
+
+    Ok(resp)
+}
+
+/// Dry-run a transaction against the rollup's current state (nothing is committed)
+pub async fn simulate_transaction_on_rollup(
+    client: &Client,
+    base_url: &str,
+    transaction: Transaction,
+) -> Result<SimulationResult> {
+    let rollup_tx = RollupTransaction {
+        sender: None,
+        sol_transaction: None,
+        encoded_transaction: Some(encode_transaction(&transaction)?),
+        encoding: Some(TransactionEncoding::Base64),
+        error: None,
+    };
+
+    let resp = client
+        .post(&format!("{}/simulate_transaction", base_url.trim_end_matches('/')))
+        .json(&rollup_tx)
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<SimulationResult>()
+        .await?;
+
+    Ok(resp)
+}
+
 /// Calculate the keccak hash of a transaction signature for lookup (string form)
 pub fn calculate_signature_hash(signature: &str) -> String {
     keccak::hashv(&[signature.as_bytes()]).to_string()
@@ -73,8 +143,9 @@ pub async fn get_transaction_from_rollup(
         .post(&format!("{}/get_transaction", base_url.trim_end_matches('/')))
         .json(&get_request)
         .send()
+        .await?;
+    let resp = check_response(resp)
         .await?
-        .error_for_status()?
         .json::<RollupTransaction>()
         .await?;
 
@@ -95,14 +166,94 @@ pub async fn get_transactions_page_from_rollup(
         .post(&format!("{}/get_transaction", base_url.trim_end_matches('/')))
         .json(&get_request)
         .send()
+        .await?;
+    let resp = check_response(resp)
         .await?
-        .error_for_status()?
         .json::<RollupTransactionsList>()
         .await?;
 
     Ok(resp)
 }
 
+/// List batches newest first, optionally only those in one proof status
+pub async fn list_batches_from_rollup(
+    client: &Client,
+    base_url: &str,
+    page: u32,
+    per_page: u32,
+    status: Option<ProofStatus>,
+) -> Result<BatchList> {
+    let mut query = vec![("page", page.to_string()), ("per_page", per_page.to_string())];
+    if let Some(status) = status {
+        query.push(("status", status.as_str().to_string()));
+    }
+
+    let resp = client
+        .get(&format!("{}/batches", base_url.trim_end_matches('/')))
+        .query(&query)
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<BatchList>()
+        .await?;
+
+    Ok(resp)
+}
+
+/// Fetch one batch with its proof, status and transactions
+pub async fn get_batch_from_rollup(client: &Client, base_url: &str, batch_id: &str) -> Result<BatchDetails> {
+    let resp = client
+        .get(&format!("{}/batches/{}", base_url.trim_end_matches('/'), batch_id))
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<BatchDetails>()
+        .await?;
+
+    Ok(resp)
+}
+
+/// Fetch the batch a transaction was sequenced into, by its signature
+pub async fn get_batch_for_transaction_from_rollup(
+    client: &Client,
+    base_url: &str,
+    signature: &str,
+) -> Result<BatchDetails> {
+    let resp = client
+        .get(&format!("{}/transactions/{}/batch", base_url.trim_end_matches('/'), signature))
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<BatchDetails>()
+        .await?;
+
+    Ok(resp)
+}
+
+/// Check a Groth16 proof against the node's verifying key for `slots`
+pub async fn verify_proof_on_rollup(
+    client: &Client,
+    base_url: &str,
+    proof: ProofData,
+    public_inputs: Vec<String>,
+    slots: usize,
+) -> Result<ProofVerification> {
+    let resp = client
+        .post(&format!("{}/verify_proof", base_url.trim_end_matches('/')))
+        .json(&VerifyProofRequest { proof, public_inputs, slots })
+        .send()
+        .await?;
+    let resp = check_response(resp)
+        .await?
+        .json::<ProofVerification>()
+        .await?;
+
+    Ok(resp)
+}
+
 /// Simple rollup client wrapper
 pub struct RollupClient {
     client: Client,
@@ -114,6 +265,16 @@ impl RollupClient {
         Self { client: Client::new(), base_url }
     }
 
+    /// Sends `Authorization: Bearer <api_key>` (an API key or JWT) with every request
+    pub fn with_api_key(base_url: String, api_key: &str) -> Result<Self> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))?;
+        value.set_sensitive(true);
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+        let client = Client::builder().default_headers(headers).build()?;
+        Ok(Self { client, base_url })
+    }
+
 pub async fn health_check(&self) -> Result<HashMap<String, String>> {
     let url = format!("{}/", self.base_url.trim_end_matches('/'));
     let resp = self.client.get(&url).send().await?.error_for_status()?;
@@ -127,10 +288,19 @@ pub async fn health_check(&self) -> Result<HashMap<String, String>> {
         &self,
         sender_name: Option<&str>,
         transaction: Transaction,
-    ) -> Result<HashMap<String, String>> {
+    ) -> Result<SubmitTransactionResponse> {
         submit_transaction_to_rollup(&self.client, &self.base_url, sender_name, transaction).await
     }
 
+    pub async fn submit_transactions(&self, transactions: &[Transaction]) -> Result<SubmitBatchResponse> {
+        submit_transactions_to_rollup(&self.client, &self.base_url, transactions).await
+    }
+
+    /// Preflight a transaction without submitting it
+    pub async fn simulate_transaction(&self, transaction: Transaction) -> Result<SimulationResult> {
+        simulate_transaction_on_rollup(&self.client, &self.base_url, transaction).await
+    }
+
     /// Fetch a single tx by its signature-hash
     pub async fn get_transaction(&self, signature_hash: &str) -> Result<RollupTransaction> {
         get_transaction_from_rollup(&self.client, &self.base_url, signature_hash).await
@@ -145,6 +315,25 @@ pub async fn health_check(&self) -> Result<HashMap<String, String>> {
         get_transactions_page_from_rollup(&self.client, &self.base_url, page, per_page).await
     }
 
+    /// Fetch one page of batches, newest first
+    pub async fn list_batches(&self, page: u32, per_page: u32, status: Option<ProofStatus>) -> Result<BatchList> {
+        list_batches_from_rollup(&self.client, &self.base_url, page, per_page, status).await
+    }
+
+    pub async fn get_batch(&self, batch_id: &str) -> Result<BatchDetails> {
+        get_batch_from_rollup(&self.client, &self.base_url, batch_id).await
+    }
+
+    /// Which batch a transaction landed in (and whether it has settled)
+    pub async fn get_batch_for_transaction(&self, signature: &str) -> Result<BatchDetails> {
+        get_batch_for_transaction_from_rollup(&self.client, &self.base_url, signature).await
+    }
+
+    /// Whether the node's off-chain verification accepts a proof, as settlement would
+    pub async fn verify_proof(&self, proof: ProofData, public_inputs: Vec<String>, slots: usize) -> Result<ProofVerification> {
+        verify_proof_on_rollup(&self.client, &self.base_url, proof, public_inputs, slots).await
+    }
+
     /// Convenience: fetch **all pages** (beware of large datasets)
     pub async fn get_all_transactions_paged(&self, per_page: u32) -> Result<Vec<TransactionWithHash>> {
         let per_page = per_page.clamp(1, 500);
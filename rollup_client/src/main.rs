@@ -40,7 +40,10 @@ async fn main() -> Result<()> {
     let tx = create_solana_transaction(&keypair2, &keypair, 100_000, recent_blockhash);
 
     // Create rollup client
-    let rollup_client = RollupClient::new("http://127.0.0.1:8080".to_string());
+    let rollup_client = match std::env::var("ROLLUP_API_KEY") {
+        Ok(api_key) => RollupClient::with_api_key("http://127.0.0.1:8080".to_string(), &api_key)?,
+        Err(_) => RollupClient::new("http://127.0.0.1:8080".to_string()),
+    };
 
     println!("starting test response...");
     let test_response = rollup_client.health_check().await?;
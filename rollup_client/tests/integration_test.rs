@@ -119,10 +119,7 @@ async fn test_complete_rollup_flow() -> Result<()> {
         .submit_transaction("Integration Test", sol_transaction.clone())
         .await?;
     println!("Submit response: {:#?}", submit_response);
-    assert_eq!(
-        submit_response.get("Transaction status"),
-        Some(&"Submitted".to_string())
-    );
+    assert_eq!(submit_response.status, "Submitted");
 
     // Calculate transaction hash for retrieval using library function
     println!("\n4. Calculating transaction hash...");
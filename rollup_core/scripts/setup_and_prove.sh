@@ -3,33 +3,46 @@ set -e
 
 echo "Setting up System Transfer circuits and generating proofs..."
 
-mkdir -p build/keys
+# the node passes its configured prover paths; defaults match running from rollup_core/
+CIRCUIT_DIR="${CIRCUIT_DIR:-circuit}"
+BUILD_DIR="${BUILD_DIR:-build}"
+# a phase 2 ready .ptau from a public ceremony; without it a local one-party ceremony
+# is run, whose keys are only fit for development
+PTAU_FILE="${PTAU_FILE:-$BUILD_DIR/keys/pot12_final.ptau}"
+
+mkdir -p "$BUILD_DIR/keys"
+
+# fresh for every contribution, and never written down
+random_entropy() {
+    head -c 64 /dev/urandom | od -An -tx1 | tr -d ' \n'
+}
 
 setup_powers_of_tau() {
-    if [ ! -f "build/keys/pot12_final.ptau" ]; then
-        echo "Setting up Powers of Tau ceremony..."
-        snarkjs powersoftau new bn128 12 build/keys/pot12_0000.ptau -v
-        snarkjs powersoftau contribute build/keys/pot12_0000.ptau build/keys/pot12_0001.ptau --name="System transfer contribution" -v -e="system transfer entropy"
-        snarkjs powersoftau prepare phase2 build/keys/pot12_0001.ptau build/keys/pot12_final.ptau -v
+    if [ ! -f "$PTAU_FILE" ]; then
+        echo "No Powers of Tau file, running a local ceremony (development only)..."
+        snarkjs powersoftau new bn128 12 $BUILD_DIR/keys/pot12_0000.ptau -v
+        snarkjs powersoftau contribute $BUILD_DIR/keys/pot12_0000.ptau $BUILD_DIR/keys/pot12_0001.ptau --name="System transfer contribution" -v -e="$(random_entropy)"
+        snarkjs powersoftau prepare phase2 $BUILD_DIR/keys/pot12_0001.ptau "$PTAU_FILE" -v
         echo " Powers of Tau ceremony complete"
     fi
+    echo "Powers of Tau: $PTAU_FILE (sha256 $(sha256sum "$PTAU_FILE" | cut -d' ' -f1))"
 }
 
 setup_single_circuit() {
     echo ""
     echo "Setting up SINGLE system transfer circuit..."
     
-    if [ ! -f "build/system_transfer.r1cs" ]; then
+    if [ ! -f "$BUILD_DIR/system_transfer.r1cs" ]; then
         echo "Compiling single transfer circuit..."
-        circom circuit/system_transfer.circom --r1cs --wasm --sym -o build/
+        circom $CIRCUIT_DIR/system_transfer.circom --r1cs --wasm --sym -o "$BUILD_DIR"/
         echo " Single circuit compiled"
     fi
     
-    if [ ! -f "build/keys/verification_key_single.json" ]; then
+    if [ ! -f "$BUILD_DIR/keys/verification_key_single.json" ]; then
         echo "Creating single circuit keys..."
-        snarkjs groth16 setup build/system_transfer.r1cs build/keys/pot12_final.ptau build/keys/single_0000.zkey
-        snarkjs zkey contribute build/keys/single_0000.zkey build/keys/single_0001.zkey --name="Single transfer contribution" -v -e="single entropy"
-        snarkjs zkey export verificationkey build/keys/single_0001.zkey build/keys/verification_key_single.json
+        snarkjs groth16 setup $BUILD_DIR/system_transfer.r1cs "$PTAU_FILE" $BUILD_DIR/keys/single_0000.zkey
+        snarkjs zkey contribute $BUILD_DIR/keys/single_0000.zkey $BUILD_DIR/keys/single_0001.zkey --name="Single transfer contribution" -v -e="$(random_entropy)"
+        snarkjs zkey export verificationkey $BUILD_DIR/keys/single_0001.zkey $BUILD_DIR/keys/verification_key_single.json
         echo " Single circuit keys generated"
     fi
 }
@@ -38,17 +51,17 @@ setup_batch_circuit() {
     echo ""
     echo "Setting up BATCH system transfer circuit..."
     
-    if [ ! -f "build/batch_system_transfer.r1cs" ]; then
+    if [ ! -f "$BUILD_DIR/batch_system_transfer.r1cs" ]; then
         echo "Compiling batch transfer circuit..."
-        circom circuit/batch_system_transfer.circom --r1cs --wasm --sym -o build/
+        circom $CIRCUIT_DIR/batch_system_transfer.circom --r1cs --wasm --sym -o "$BUILD_DIR"/
         echo " Batch circuit compiled"
     fi
     
-    if [ ! -f "build/keys/verification_key_batch.json" ]; then
+    if [ ! -f "$BUILD_DIR/keys/verification_key_batch.json" ]; then
         echo "Creating batch circuit keys..."
-        snarkjs groth16 setup build/batch_system_transfer.r1cs build/keys/pot12_final.ptau build/keys/batch_0000.zkey
-        snarkjs zkey contribute build/keys/batch_0000.zkey build/keys/batch_0001.zkey --name="Batch transfer contribution" -v -e="batch entropy"
-        snarkjs zkey export verificationkey build/keys/batch_0001.zkey build/keys/verification_key_batch.json
+        snarkjs groth16 setup $BUILD_DIR/batch_system_transfer.r1cs "$PTAU_FILE" $BUILD_DIR/keys/batch_0000.zkey
+        snarkjs zkey contribute $BUILD_DIR/keys/batch_0000.zkey $BUILD_DIR/keys/batch_0001.zkey --name="Batch transfer contribution" -v -e="$(random_entropy)"
+        snarkjs zkey export verificationkey $BUILD_DIR/keys/batch_0001.zkey $BUILD_DIR/keys/verification_key_batch.json
         echo " Batch circuit keys generated"
     fi
 }
@@ -57,9 +70,9 @@ generate_single_proof() {
     echo ""
     echo "Generating SINGLE system transfer proof..."
  
-    if [ ! -f "build/input_single.json" ]; then
+    if [ ! -f "$BUILD_DIR/input_single.json" ]; then
         echo "Creating single transfer input..."
-        cat > build/input_single.json << EOL
+        cat > $BUILD_DIR/input_single.json << EOL
 {
   "amount": "1000000",
   "signature_first_byte": "42",
@@ -70,13 +83,13 @@ EOL
     fi
     
     echo "Generating witness for single transfer..."
-    node build/system_transfer_js/generate_witness.js build/system_transfer_js/system_transfer.wasm build/input_single.json build/witness_single.wtns
+    node $BUILD_DIR/system_transfer_js/generate_witness.js $BUILD_DIR/system_transfer_js/system_transfer.wasm $BUILD_DIR/input_single.json $BUILD_DIR/witness_single.wtns
     
     echo "Generating zero-knowledge proof for single transfer..."
-    snarkjs groth16 prove build/keys/single_0001.zkey build/witness_single.wtns build/proof_single.json build/public_single.json
+    snarkjs groth16 prove $BUILD_DIR/keys/single_0001.zkey $BUILD_DIR/witness_single.wtns $BUILD_DIR/proof_single.json $BUILD_DIR/public_single.json
     
     echo " Verifying single transfer proof..."
-    snarkjs groth16 verify build/keys/verification_key_single.json build/public_single.json build/proof_single.json
+    snarkjs groth16 verify $BUILD_DIR/keys/verification_key_single.json $BUILD_DIR/public_single.json $BUILD_DIR/proof_single.json
     
     if [ $? -eq 0 ]; then
         echo "Single system transfer proof verified successfully!"
@@ -92,10 +105,10 @@ generate_batch_proof() {
 
     if [ -n "$INPUT_FILE" ] && [ -f "$INPUT_FILE" ]; then
         echo "Using rollup-provided input file: $INPUT_FILE"
-        cp "$INPUT_FILE" build/input_batch.json
-    elif [ ! -f "build/input_batch.json" ]; then
+        cp "$INPUT_FILE" $BUILD_DIR/input_batch.json
+    elif [ ! -f "$BUILD_DIR/input_batch.json" ]; then
         echo "Creating default batch transfer input..."
-        cat > build/input_batch.json << EOL
+        cat > $BUILD_DIR/input_batch.json << EOL
 {
   "amounts": ["1000000", "1000000", "1000000"],
   "signature_first_bytes": ["42", "156", "201"],
@@ -106,13 +119,13 @@ EOL
     fi
     
     echo "Generating witness for batch transfers..."
-    node build/batch_system_transfer_js/generate_witness.js build/batch_system_transfer_js/batch_system_transfer.wasm build/input_batch.json build/witness_batch.wtns
+    node $BUILD_DIR/batch_system_transfer_js/generate_witness.js $BUILD_DIR/batch_system_transfer_js/batch_system_transfer.wasm $BUILD_DIR/input_batch.json $BUILD_DIR/witness_batch.wtns
     
     echo "Generating zero-knowledge proof for batch transfers..."
-    snarkjs groth16 prove build/keys/batch_0001.zkey build/witness_batch.wtns build/proof_batch.json build/public_batch.json
+    snarkjs groth16 prove $BUILD_DIR/keys/batch_0001.zkey $BUILD_DIR/witness_batch.wtns $BUILD_DIR/proof_batch.json $BUILD_DIR/public_batch.json
     
     echo " Verifying batch transfer proof..."
-    snarkjs groth16 verify build/keys/verification_key_batch.json build/public_batch.json build/proof_batch.json
+    snarkjs groth16 verify $BUILD_DIR/keys/verification_key_batch.json $BUILD_DIR/public_batch.json $BUILD_DIR/proof_batch.json
     
     if [ $? -eq 0 ]; then
         echo "Batch system transfer proof verified successfully"
@@ -136,14 +149,14 @@ echo "SUCCESS! System transfer circuits set up and proofs generated!"
 echo ""
 echo "Generated files:"
 echo "   SINGLE TRANSFER:"
-echo "   - build/proof_single.json (single transfer proof)"
-echo "   - build/public_single.json (single public inputs)"
-echo "   - build/keys/verification_key_single.json (single verification key)"
+echo "   - $BUILD_DIR/proof_single.json (single transfer proof)"
+echo "   - $BUILD_DIR/public_single.json (single public inputs)"
+echo "   - $BUILD_DIR/keys/verification_key_single.json (single verification key)"
 echo ""
 echo "   BATCH TRANSFERS:"
-echo "   - build/proof_batch.json (batch transfer proof)"
-echo "   - build/public_batch.json (batch public inputs)" 
-echo "   - build/keys/verification_key_batch.json (batch verification key)"
+echo "   - $BUILD_DIR/proof_batch.json (batch transfer proof)"
+echo "   - $BUILD_DIR/public_batch.json (batch public inputs)" 
+echo "   - $BUILD_DIR/keys/verification_key_batch.json (batch verification key)"
 echo ""
 echo "What this proves:"
 echo "   ✓ Transfer amounts are within valid ranges"
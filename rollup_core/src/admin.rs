@@ -0,0 +1,292 @@
+//! Operator endpoints under `/admin`, all behind a bearer token.
+//!
+//! Sequencer controls act on the mempool directly; everything that touches
+//! proofs goes through RollupDB like the rest of the HTTP layer.
+
+use std::time::Duration;
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use crossbeam::channel::Sender as CBSender;
+use rollup_api::BatchIdPath;
+pub use rollup_api::{SealOutcome, SequencerStatus};
+
+use crate::{
+    auth::constant_time_eq,
+    config::NodeConfig,
+    error::{ApiError, ErrorCode, HttpError},
+    frontend::{query_db, FrontendMessage},
+    mempool::Mempool,
+    rollupdb::{AdminOutcome, RollupDBMessage, UnsettledReport},
+};
+
+const DB_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn authorize(req: &HttpRequest, config: &NodeConfig) -> Result<(), HttpError> {
+    let Some(expected) = config.admin.token.as_deref() else {
+        return Err(ApiError::new(ErrorCode::Forbidden, "Admin API is disabled, set admin.token to enable it").into());
+    };
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        log::warn!("Rejected admin request to {} with a bad or missing token", req.path());
+        Err(ApiError::new(ErrorCode::Unauthorized, "Invalid admin token").into())
+    }
+}
+
+fn sequencer_status(mempool: &Mempool) -> SequencerStatus {
+    SequencerStatus {
+        paused: mempool.is_paused(),
+        pending: mempool.len(),
+        capacity: mempool.capacity(),
+    }
+}
+
+/// Sends a command to RollupDB and turns its reply into a response.
+async fn db_command(
+    message: RollupDBMessage,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+) -> Result<HttpResponse, HttpError> {
+    match query_db(rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { admin: Some(outcome), .. } => Ok(HttpResponse::Ok().json(outcome)),
+        FrontendMessage { unsettled: Some(report), .. } => Ok(HttpResponse::Ok().json(report)),
+        FrontendMessage { error: Some(e), .. } => Err(ApiError::bad_request(e).into()),
+        _ => Err(ApiError::new(ErrorCode::Internal, "RollupDB answered with an unexpected reply").into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/sequencer",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Sequencer state after the command", body = SequencerStatus),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn get_sequencer(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    mempool: web::Data<Mempool>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    Ok(HttpResponse::Ok().json(sequencer_status(&mempool)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sequencer/pause",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Sequencer state after the command", body = SequencerStatus),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn pause_sequencer(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    mempool: web::Data<Mempool>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    if !mempool.pause() {
+        log::warn!("Sequencer paused by operator ({} transactions pending)", mempool.len());
+    }
+    Ok(HttpResponse::Ok().json(sequencer_status(&mempool)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sequencer/resume",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Sequencer state after the command", body = SequencerStatus),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn resume_sequencer(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    mempool: web::Data<Mempool>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    if mempool.resume() {
+        log::info!("Sequencer resumed by operator");
+    }
+    Ok(HttpResponse::Ok().json(sequencer_status(&mempool)))
+}
+
+/// Seals whatever is pending into the next batch, even while paused.
+#[utoipa::path(
+    post,
+    path = "/admin/sequencer/seal",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 202, description = "The pending transactions will be sealed into the next batch", body = SealOutcome),
+        (status = 409, description = "Nothing is pending", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn seal_batch(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    mempool: web::Data<Mempool>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let pending = mempool.request_seal();
+    if pending == 0 {
+        return Err(ApiError::new(ErrorCode::Conflict, "No pending transactions to seal").into());
+    }
+    log::info!("Operator requested an early batch seal ({} transactions pending)", pending);
+    Ok(HttpResponse::Accepted().json(SealOutcome { pending }))
+}
+
+/// Queues every failed proof with automatic retries left, bypassing the circuit breaker.
+#[utoipa::path(
+    post,
+    path = "/admin/retry",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Batches queued for settlement, or for proving if their proof failed", body = AdminOutcome),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn retry_failed(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let message = RollupDBMessage {
+        retry_failed_proofs: Some(true),
+        ..Default::default()
+    };
+    db_command(message, &rollupdb_sender).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/batches/{batch_id}/requeue",
+    tag = "admin",
+    params(BatchIdPath),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Batch queued for settlement (or proving, if its proof failed) with a fresh retry budget", body = AdminOutcome),
+        (status = 400, description = "Unknown batch, or one that cannot be requeued", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn requeue_batch(
+    req: HttpRequest,
+    batch_id: web::Path<String>,
+    config: web::Data<NodeConfig>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let message = RollupDBMessage {
+        requeue_batch: Some(batch_id.into_inner()),
+        ..Default::default()
+    };
+    db_command(message, &rollupdb_sender).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/batches/{batch_id}/abandon",
+    tag = "admin",
+    params(BatchIdPath),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Batch will no longer be retried", body = AdminOutcome),
+        (status = 400, description = "Unknown batch, or one that is already settled", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn abandon_batch(
+    req: HttpRequest,
+    batch_id: web::Path<String>,
+    config: web::Data<NodeConfig>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let message = RollupDBMessage {
+        abandon_batch: Some(batch_id.into_inner()),
+        ..Default::default()
+    };
+    db_command(message, &rollupdb_sender).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/proofs/unsettled",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Proofs not yet verified on L1, with the circuit breaker state", body = UnsettledReport),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn unsettled_proofs(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let message = RollupDBMessage {
+        get_unsettled_proofs: Some(true),
+        ..Default::default()
+    };
+    db_command(message, &rollupdb_sender).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/circuit-breaker/reset",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Circuit breaker closed", body = AdminOutcome),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+        (status = 401, description = "Missing or wrong admin token", body = ApiError),
+        (status = 403, description = "Admin API is disabled", body = ApiError),
+    )
+)]
+pub async fn reset_circuit_breaker(
+    req: HttpRequest,
+    config: web::Data<NodeConfig>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    authorize(&req, &config)?;
+    let message = RollupDBMessage {
+        reset_circuit_breaker: Some(true),
+        ..Default::default()
+    };
+    db_command(message, &rollupdb_sender).await
+}
@@ -0,0 +1,238 @@
+//! Aggregation stage between the proof collector and settlement.
+//!
+//! With `prover.aggregation.window` above 1 the collector hands proved batches
+//! here instead of to settlement, still in batch order. They gather until the
+//! window is full or its oldest batch has waited `max_wait_secs`, the backend
+//! folds their proofs into one, and the whole window settles in a single L1
+//! transaction. A batch without a proof would break the chain of state between
+//! its neighbours, so the batches before it are folded right away.
+//!
+//! Every window must start from the global state root the one before it left
+//! (the empty tree's for the first). One that does not could never settle after
+//! it, so its batches are marked invalid before any proving time goes into them.
+//!
+//! A fold that fails is tried once more. If it fails again its batches are left
+//! with succinct receipts that can never settle on their own, so they are marked
+//! invalid rather than retried.
+
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use crossbeam::channel::{Receiver as CBReceiver, RecvTimeoutError, Sender as CBSender};
+use tracing::Span;
+use zk::BatchJournal;
+
+/// Attempts at folding one window before its batches are given up on.
+const FOLD_ATTEMPTS: u32 = 2;
+
+use crate::{
+    config::AggregationConfig,
+    health::{Component, NodeHealth},
+    metrics::METRICS,
+    prover::{BatchWitness, ProofArtifact, ProverBackend, StateTree},
+    rollupdb::{AggregateGroup, ProofStatus, RollupDBMessage, StoreBatchProofMessage, UpdateProofStatusMessage},
+    settle::SettlementJob,
+};
+
+/// What the collector releases, in batch order.
+pub enum Released {
+    Proved {
+        witness: BatchWitness,
+        artifact: ProofArtifact,
+        transaction_signatures: Vec<String>,
+        span: Span,
+    },
+    /// The next batch in order has no proof.
+    Gap,
+}
+
+struct Pending {
+    witness: BatchWitness,
+    artifact: ProofArtifact,
+    transaction_signatures: Vec<String>,
+    span: Span,
+}
+
+/// Spawns the aggregator thread. It runs until the returned sender is dropped.
+pub fn start(
+    config: AggregationConfig,
+    prover: Arc<dyn ProverBackend>,
+    rollupdb_sender: CBSender<RollupDBMessage>,
+    settler_sender: CBSender<SettlementJob>,
+    health: Arc<NodeHealth>,
+) -> Result<CBSender<Released>> {
+    log::info!(
+        "Aggregating up to {} batches per settlement, {} proofs per step",
+        config.window,
+        config.fan_in()
+    );
+    let (sender, receiver) = crossbeam::channel::unbounded();
+    thread::Builder::new().name("proof-aggregator".to_string()).spawn(move || {
+        if let Err(e) = run(receiver, &config, prover.as_ref(), &rollupdb_sender, &settler_sender, &health) {
+            log::error!("Proof aggregator stopping: {}", e);
+            health.mark_failed(Component::Prover, e);
+        }
+    })?;
+    Ok(sender)
+}
+
+fn run(
+    released: CBReceiver<Released>,
+    config: &AggregationConfig,
+    prover: &dyn ProverBackend,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    settler_sender: &CBSender<SettlementJob>,
+    health: &NodeHealth,
+) -> Result<()> {
+    let mut window: Vec<Pending> = Vec::new();
+    // the sequencer's tree starts out empty with the node
+    let mut state_root = StateTree::new().root_bytes();
+    let mut opened_at = Instant::now();
+    loop {
+        let next = if window.is_empty() {
+            released.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            released.recv_timeout(config.max_wait().saturating_sub(opened_at.elapsed()))
+        };
+        match next {
+            Ok(Released::Proved { witness, artifact, transaction_signatures, span }) => {
+                if window.is_empty() {
+                    opened_at = Instant::now();
+                }
+                window.push(Pending { witness, artifact, transaction_signatures, span });
+                if window.len() < config.window {
+                    continue;
+                }
+            }
+            Ok(Released::Gap) if window.is_empty() => continue,
+            Ok(Released::Gap) => log::warn!("A batch after {} has no proof, folding the window early", window.len()),
+            Err(RecvTimeoutError::Timeout) => {
+                log::info!("Aggregation window timed out with {} of {} batches", window.len(), config.window);
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        health.record_activity(Component::Prover);
+        fold(std::mem::take(&mut window), &mut state_root, prover, rollupdb_sender, settler_sender)?;
+    }
+}
+
+/// Aggregates the window and sends it to settlement, moving `state_root` to the root it
+/// leaves. Only fails if a channel is gone.
+fn fold(
+    window: Vec<Pending>,
+    state_root: &mut [u8; 32],
+    prover: &dyn ProverBackend,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    settler_sender: &CBSender<SettlementJob>,
+) -> Result<()> {
+    let span = tracing::info_span!(parent: &window[0].span, "aggregate", batches = window.len());
+    let _entered = span.enter();
+    let batch_ids: Vec<String> = window.iter().map(|pending| pending.witness.batch_id.clone()).collect();
+    log::info!("Aggregating the proofs of batches {:?}", batch_ids);
+
+    let post_state_root = match window_roots(&window) {
+        Ok((pre, post)) if pre == *state_root => post,
+        Ok((pre, _)) => {
+            let error = format!(
+                "Window starts from state root {} but the window before it left {}",
+                hex(&pre),
+                hex(state_root)
+            );
+            log::error!("Not aggregating batches {:?}: {}", batch_ids, error);
+            return invalidate(batch_ids, error, rollupdb_sender);
+        }
+        Err(e) => {
+            log::error!("Not aggregating batches {:?}: {:#}", batch_ids, e);
+            return invalidate(batch_ids, format!("Window cannot be executed: {:#}", e), rollupdb_sender);
+        }
+    };
+
+    let batches: Vec<(BatchWitness, ProofArtifact)> =
+        window.iter().map(|pending| (pending.witness.clone(), pending.artifact.clone())).collect();
+    let mut attempt = 1;
+    let result = loop {
+        let timer = METRICS.aggregation_seconds.start_timer();
+        let result = catch_unwind(AssertUnwindSafe(|| prover.aggregate(&batches)))
+            .unwrap_or_else(|_| Err(anyhow!("{} prover panicked while aggregating", prover.name())));
+        timer.observe_duration();
+        match result {
+            Err(e) if attempt < FOLD_ATTEMPTS => {
+                log::warn!("Aggregating batches {:?} failed on attempt {}, trying again: {:#}", batch_ids, attempt, e);
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+
+    let artifact = match result {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            log::error!("Aggregating batches {:?} failed: {:#}", batch_ids, e);
+            METRICS.proof_generation_failures.inc();
+            // a succinct receipt cannot settle alone, retrying settlement would only fail again
+            return invalidate(batch_ids, format!("Aggregation failed {} times: {:#}", FOLD_ATTEMPTS, e), rollupdb_sender);
+        }
+    };
+    *state_root = post_state_root;
+
+    // every batch keeps the proof that settles it, and the group it settles with
+    let group = AggregateGroup { group_id: batch_ids[0].clone(), members: batch_ids.clone() };
+    let mut transaction_signatures = Vec::new();
+    for pending in &window {
+        rollupdb_sender.send(RollupDBMessage {
+            store_batch_proof: Some(StoreBatchProofMessage {
+                batch_id: pending.witness.batch_id.clone(),
+                artifact: artifact.clone(),
+                transaction_signatures: pending.transaction_signatures.clone(),
+                aggregate: Some(group.clone()),
+            }),
+            span: Some(Span::current()),
+            ..Default::default()
+        })?;
+        transaction_signatures.extend(pending.transaction_signatures.iter().cloned());
+    }
+
+    let mut batch_ids = batch_ids.into_iter();
+    settler_sender.send(SettlementJob {
+        batch_id: batch_ids.next().unwrap_or_default(),
+        aggregated: batch_ids.collect(),
+        artifact,
+        transaction_signatures,
+        span: window[0].span.clone(),
+    })?;
+    Ok(())
+}
+
+/// The state roots before the window's first batch and after its last, as their journals commit them.
+fn window_roots(window: &[Pending]) -> Result<([u8; 32], [u8; 32])> {
+    let (Some(first), Some(last)) = (window.first(), window.last()) else {
+        return Err(anyhow!("empty window"));
+    };
+    let pre = BatchJournal::execute(&first.witness)?.pre_state_root;
+    Ok((pre, BatchJournal::execute(&last.witness)?.post_state_root))
+}
+
+fn invalidate(batch_ids: Vec<String>, error: String, rollupdb_sender: &CBSender<RollupDBMessage>) -> Result<()> {
+    for batch_id in batch_ids {
+        rollupdb_sender.send(RollupDBMessage {
+            update_proof_status: Some(UpdateProofStatusMessage {
+                batch_id,
+                new_status: ProofStatus::Invalid,
+                error_message: Some(error.clone()),
+                settlement_signature: None,
+            }),
+            span: Some(Span::current()),
+            ..Default::default()
+        })?;
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
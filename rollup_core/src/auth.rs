@@ -0,0 +1,392 @@
+//! API authentication and per-client rate limiting for the public HTTP server.
+//!
+//! Requests to protected routes must carry `Authorization: Bearer <credential>`,
+//! either a configured API key or an HS256 JWT signed with `api.jwt_secret`.
+//! Every request that is not exempt draws from a token bucket per IP address;
+//! authenticated requests also draw from their client's bucket, so a leaked key
+//! can't be spread over many addresses nor one address cycle through keys.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, ResponseError,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ApiError, ErrorCode, HttpError};
+
+// idle buckets refill to full and are indistinguishable from new ones, so they can be dropped
+const BUCKET_PRUNE_THRESHOLD: usize = 10_000;
+// pruning scans every bucket under the lock, so it runs at most this often however many there are
+const BUCKET_PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Route prefixes that require a credential. Auth is only enforced once a key or JWT secret is set.
+    pub protected_routes: Vec<String>,
+    /// Route prefixes that are never rate limited (probes and scrapes).
+    pub unlimited_routes: Vec<String>,
+    pub keys: Vec<ApiKey>,
+    /// HS256 secret for JWTs; the `sub` claim names the client.
+    pub jwt_secret: Option<String>,
+    /// Upper bound on request bodies, JSON or otherwise.
+    pub max_body_bytes: usize,
+    /// Lets requests without a transaction have the node sign a test transfer with the operator keypair.
+    pub dev_mode: bool,
+    pub rate_limit: RateLimitConfig,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    pub name: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Sustained requests per second for an authenticated client, and for the
+    /// authenticated requests of one IP address whichever keys they use.
+    pub per_client_rps: f64,
+    pub per_client_burst: u32,
+    /// Sustained requests per second for an anonymous IP address.
+    pub per_ip_rps: f64,
+    pub per_ip_burst: u32,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            protected_routes: vec![
+                "/submit_transaction".to_string(),
+                "/simulate_transaction".to_string(),
+                "/verify_proof".to_string(),
+                "/prover".to_string(),
+            ],
+            unlimited_routes: vec!["/health".to_string(), "/ready".to_string(), "/metrics".to_string()],
+            keys: Vec::new(),
+            jwt_secret: None,
+            // a full /submit_transactions batch of base64 transactions is ~450 KiB
+            max_body_bytes: 1024 * 1024,
+            dev_mode: false,
+            rate_limit: RateLimitConfig::default(),
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_client_rps: 50.0,
+            per_client_burst: 100,
+            per_ip_rps: 5.0,
+            per_ip_burst: 20,
+        }
+    }
+}
+
+// the config is logged at startup, keep credentials out of it
+impl std::fmt::Debug for ApiConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiConfig")
+            .field("protected_routes", &self.protected_routes)
+            .field("unlimited_routes", &self.unlimited_routes)
+            .field("keys", &self.keys.iter().map(|k| k.name.as_str()).collect::<Vec<_>>())
+            .field("jwt_secret", &self.jwt_secret.as_ref().map(|_| "<redacted>"))
+            .field("max_body_bytes", &self.max_body_bytes)
+            .field("dev_mode", &self.dev_mode)
+            .field("rate_limit", &self.rate_limit)
+            .finish()
+    }
+}
+
+impl ApiConfig {
+    pub fn auth_enabled(&self) -> bool {
+        !self.keys.is_empty() || self.jwt_secret.is_some()
+    }
+
+    fn is_protected(&self, path: &str) -> bool {
+        self.protected_routes.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    fn is_unlimited(&self, path: &str) -> bool {
+        self.unlimited_routes.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientId {
+    Client(String),
+    /// Authenticated requests from one address, kept apart from its anonymous ones
+    /// so they get the client rate rather than the anonymous one.
+    AuthenticatedIp(IpAddr),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, rate: f64, burst: u32, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst as f64);
+        self.updated = now;
+    }
+
+    fn is_full(&self, rate: f64, burst: u32, now: Instant) -> bool {
+        self.tokens + now.duration_since(self.updated).as_secs_f64() * rate >= burst as f64
+    }
+}
+
+struct Buckets {
+    buckets: HashMap<ClientId, TokenBucket>,
+    last_pruned: Instant,
+}
+
+/// Shared by every server worker through `web::Data`.
+pub struct ApiGuard {
+    config: ApiConfig,
+    decoding_key: Option<DecodingKey>,
+    buckets: Mutex<Buckets>,
+}
+
+impl ApiGuard {
+    pub fn new(config: ApiConfig) -> Self {
+        let decoding_key = config.jwt_secret.as_ref().map(|secret| DecodingKey::from_secret(secret.as_bytes()));
+        Self {
+            config,
+            decoding_key,
+            buckets: Mutex::new(Buckets { buckets: HashMap::new(), last_pruned: Instant::now() }),
+        }
+    }
+
+    fn authenticate(&self, credential: &str) -> Option<String> {
+        if let Some(key) = self.config.keys.iter().find(|k| constant_time_eq(k.key.as_bytes(), credential.as_bytes())) {
+            return Some(key.name.clone());
+        }
+        let decoding_key = self.decoding_key.as_ref()?;
+        jsonwebtoken::decode::<Claims>(credential, decoding_key, &Validation::new(Algorithm::HS256))
+            .ok()
+            .map(|token| format!("jwt:{}", token.claims.sub))
+    }
+
+    fn limits(&self, id: &ClientId) -> (f64, u32) {
+        let limits = &self.config.rate_limit;
+        match id {
+            ClientId::Client(_) | ClientId::AuthenticatedIp(_) => (limits.per_client_rps, limits.per_client_burst),
+            ClientId::Ip(_) => (limits.per_ip_rps, limits.per_ip_burst),
+        }
+    }
+
+    /// Takes a token from the bucket of `client` (if authenticated) and of `ip`, or from
+    /// neither: returns the seconds until both have one.
+    fn check_rate(&self, client: Option<&str>, ip: Option<IpAddr>) -> Result<(), f64> {
+        let ids: Vec<ClientId> = client
+            .map(|name| ClientId::Client(name.to_string()))
+            .into_iter()
+            .chain(ip.map(|ip| if client.is_some() { ClientId::AuthenticatedIp(ip) } else { ClientId::Ip(ip) }))
+            .collect();
+        let now = Instant::now();
+
+        let mut state = self.buckets.lock().unwrap();
+        if state.buckets.len() > BUCKET_PRUNE_THRESHOLD && now.duration_since(state.last_pruned) >= BUCKET_PRUNE_INTERVAL {
+            state.buckets.retain(|id, bucket| {
+                let (rate, burst) = self.limits(id);
+                !bucket.is_full(rate, burst, now)
+            });
+            state.last_pruned = now;
+        }
+
+        let mut wait: f64 = 0.0;
+        for id in &ids {
+            let (rate, burst) = self.limits(id);
+            let bucket = state.buckets.entry(id.clone()).or_insert(TokenBucket { tokens: burst as f64, updated: now });
+            bucket.refill(rate, burst, now);
+            if bucket.tokens < 1.0 {
+                wait = wait.max((1.0 - bucket.tokens) / rate);
+            }
+        }
+        if wait > 0.0 {
+            return Err(wait);
+        }
+        for id in &ids {
+            if let Some(bucket) = state.buckets.get_mut(id) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Middleware: authenticates protected routes, then applies the caller's rate limit.
+pub async fn guard(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let Some(api) = req.app_data::<web::Data<ApiGuard>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    let path = req.path().to_string();
+
+    let credential = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    // /admin has its own token, which must not be mistaken for an API key
+    let client = match credential {
+        Some(credential) if api.config.auth_enabled() && !path.starts_with("/admin") => api.authenticate(&credential),
+        _ => None,
+    };
+
+    if api.config.auth_enabled() && api.config.is_protected(&path) && client.is_none() {
+        log::warn!("Rejected unauthenticated request to {} from {:?}", path, req.peer_addr());
+        let response = HttpError(ApiError::new(ErrorCode::Unauthorized, "A valid API key or JWT is required")).error_response();
+        return Ok(req.into_response(response).map_into_boxed_body());
+    }
+
+    if !api.config.is_unlimited(&path) {
+        let ip = req.peer_addr().map(|addr| addr.ip());
+        if client.is_some() || ip.is_some() {
+            if let Err(retry_after) = api.check_rate(client.as_deref(), ip) {
+                log::debug!("Rate limited {:?} from {:?} on {}", client, ip, path);
+                let mut response = HttpError(ApiError::new(ErrorCode::RateLimited, "Rate limit exceeded")).error_response();
+                response.headers_mut().insert(
+                    header::RETRY_AFTER,
+                    header::HeaderValue::from(retry_after.ceil().max(1.0) as u64),
+                );
+                return Ok(req.into_response(response).map_into_boxed_body());
+            }
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_boxed_body)
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        middleware::from_fn,
+        test::{call_service, init_service, TestRequest},
+        App, HttpResponse,
+    };
+    use jsonwebtoken::{EncodingKey, Header};
+
+    use super::*;
+
+    const KEY: &str = "0123456789abcdef0123456789abcdef";
+    const JWT_SECRET: &str = "fedcba9876543210fedcba9876543210";
+
+    fn guard_with(rate_limit: RateLimitConfig) -> ApiGuard {
+        ApiGuard::new(ApiConfig {
+            keys: vec![ApiKey { name: "indexer".to_string(), key: KEY.to_string() }],
+            jwt_secret: Some(JWT_SECRET.to_string()),
+            rate_limit,
+            ..Default::default()
+        })
+    }
+
+    fn jwt(secret: &str, sub: &str) -> String {
+        let claims = serde_json::json!({ "sub": sub, "exp": 4_102_444_800u64 });
+        jsonwebtoken::encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[test]
+    fn authenticates_api_keys_and_jwts() {
+        let guard = guard_with(RateLimitConfig::default());
+        assert_eq!(guard.authenticate(KEY).as_deref(), Some("indexer"));
+        assert_eq!(guard.authenticate(&jwt(JWT_SECRET, "alice")).as_deref(), Some("jwt:alice"));
+
+        assert_eq!(guard.authenticate("0123456789abcdef0123456789abcdeX"), None);
+        assert_eq!(guard.authenticate(&jwt("another secret of thirty-two chars", "alice")), None);
+    }
+
+    #[test]
+    fn limits_authenticated_requests_by_client_and_address() {
+        // refills far too slowly to matter within the test
+        let guard = guard_with(RateLimitConfig { per_client_rps: 0.001, per_client_burst: 2, ..Default::default() });
+        assert!(guard.check_rate(Some("a"), ip(1)).is_ok());
+        assert!(guard.check_rate(Some("a"), ip(1)).is_ok());
+        assert!(guard.check_rate(Some("a"), ip(1)).is_err());
+
+        // another key from the same address, and the same key from another address
+        assert!(guard.check_rate(Some("b"), ip(1)).is_err());
+        assert!(guard.check_rate(Some("a"), ip(2)).is_err());
+
+        // the refusals above took nothing from b's bucket nor from address 2's
+        assert!(guard.check_rate(Some("b"), ip(2)).is_ok());
+        assert!(guard.check_rate(Some("b"), ip(2)).is_ok());
+        assert!(guard.check_rate(Some("b"), ip(2)).is_err());
+    }
+
+    #[test]
+    fn anonymous_requests_have_their_own_address_limit() {
+        let guard = guard_with(RateLimitConfig { per_ip_rps: 0.001, per_ip_burst: 1, ..Default::default() });
+        assert!(guard.check_rate(None, ip(1)).is_ok());
+        let retry_after = guard.check_rate(None, ip(1)).unwrap_err();
+        assert!(retry_after > 0.0);
+
+        // anonymous traffic from a shared address doesn't starve the clients behind it
+        assert!(guard.check_rate(Some("a"), ip(1)).is_ok());
+        assert!(guard.check_rate(None, ip(2)).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn protected_routes_need_a_credential() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(guard))
+                .app_data(web::Data::new(guard_with(RateLimitConfig::default())))
+                .route("/submit_transaction", web::post().to(HttpResponse::Ok))
+                .route("/batches", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let anonymous = TestRequest::post().uri("/submit_transaction").to_request();
+        assert_eq!(call_service(&app, anonymous).await.status(), StatusCode::UNAUTHORIZED);
+
+        let wrong_key = TestRequest::post()
+            .uri("/submit_transaction")
+            .insert_header((header::AUTHORIZATION, "Bearer not-a-key"))
+            .to_request();
+        assert_eq!(call_service(&app, wrong_key).await.status(), StatusCode::UNAUTHORIZED);
+
+        let with_key = TestRequest::post()
+            .uri("/submit_transaction")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", KEY)))
+            .to_request();
+        assert_eq!(call_service(&app, with_key).await.status(), StatusCode::OK);
+
+        let open_route = TestRequest::get().uri("/batches").to_request();
+        assert_eq!(call_service(&app, open_route).await.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,374 @@
+//! Benchmarks of the proof systems behind `ProverBackend`, the comparison this
+//! rollup was built to make.
+//!
+//! Every backend proves full batches of signed system transfers at each size
+//! class, under the same keys and code paths the node uses. Per proof we record:
+//! - the circuit's constraint count and witness generation time, for backends
+//!   with an R1CS circuit of their own
+//! - proving time, and this process's peak memory while proving
+//! - the size of the proof as the verifier program receives it
+//! - arkworks verification time, for Groth16 proofs
+//! - compute units of `verify_groth16_proof`, executed in a local SVM
+//!
+//! The on-chain figure needs the compiled verifier program
+//! (`onchain_verifier/target/deploy/onchain_verifier.so`, from `anchor build`).
+//! It covers the whole instruction, including creating the proof account, as
+//! settlement pays for it.
+
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use anchor_lang::AccountSerialize;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use prover_service::JobQueue;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_compute_budget::{compute_budget::SVMTransactionExecutionBudget, compute_budget_limits::ComputeBudgetLimits};
+use solana_fee_structure::FeeDetails;
+use solana_sdk::{
+    account::{create_account_shared_data_for_test, Account, AccountSharedData},
+    fee::FeeStructure,
+    hash::Hash,
+    native_loader,
+    pubkey::Pubkey,
+    rent_collector::RentCollector,
+    signature::{Keypair, Signer},
+    system_transaction,
+    sysvar::{self, clock::Clock, rent::Rent},
+    transaction::{self, SanitizedTransaction, Transaction},
+};
+use solana_svm::{
+    account_loader::CheckedTransactionDetails,
+    transaction_processing_result::ProcessedTransaction,
+    transaction_processor::{
+        ExecutionRecordingConfig, TransactionProcessingConfig, TransactionProcessingEnvironment,
+    },
+};
+use solana_svm_feature_set::SVMFeatureSet;
+use zk::{execute::LAMPORTS_PER_SIGNATURE, AccountWitness, SnarkJsVerifyingKey};
+
+use crate::{
+    config::{NodeConfig, ProverBackendKind, CIRCOM_CIRCUIT_SLOTS},
+    loader::RollupAccountLoader,
+    processor::{create_transaction_batch_processor, RollupForkGraph},
+    prover::{self, BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend, StateTree, TransferWitness},
+    rollupdb::ProofData,
+    settle::{groth16_verify_instruction, onchain_vk_hash, verify_groth16_offchain},
+    vk_registry::{registry_address, registry_pda},
+};
+
+/// Lamports each sample sender starts with; far more than a transfer costs.
+const SENDER_LAMPORTS: u64 = 1_000_000_000;
+/// The most a transaction may ask for; Groth16 verification needs more than the default.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+pub struct BenchOptions {
+    pub backends: Vec<ProverBackendKind>,
+    /// Batch sizes to prove. The circom circuit has one size, whatever this says.
+    pub sizes: Vec<usize>,
+    /// Proofs per backend and size.
+    pub runs: usize,
+    /// Keys and working files, kept apart from the node's own build directory.
+    pub build_dir: PathBuf,
+    /// Compiled verifier program; without it no compute units are measured.
+    pub verifier_program: Option<PathBuf>,
+}
+
+/// One proof. Figures a backend cannot provide are left empty, and a failed run
+/// keeps what was measured before `error`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BenchResult {
+    pub backend: String,
+    pub slots: usize,
+    pub run: usize,
+    pub constraints: Option<usize>,
+    pub witness_ms: Option<f64>,
+    pub prove_ms: Option<f64>,
+    pub peak_memory_bytes: Option<u64>,
+    pub proof_bytes: Option<usize>,
+    pub public_inputs: Option<usize>,
+    pub verify_ms: Option<f64>,
+    pub onchain_verify_cu: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Runs every backend at every size. A backend that cannot start fails the whole
+/// benchmark; a proof that fails is reported in its result.
+pub fn run(config: &NodeConfig, options: &BenchOptions) -> Result<Vec<BenchResult>> {
+    let verifier_program = match &options.verifier_program {
+        Some(path) => Some(std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?),
+        None => None,
+    };
+
+    let mut results = Vec::new();
+    for &kind in &options.backends {
+        if kind == ProverBackendKind::Remote {
+            return Err(anyhow!("the remote backend proves on workers; benchmark the backend they run instead"));
+        }
+        let sizes = match kind {
+            ProverBackendKind::Circom => vec![CIRCOM_CIRCUIT_SLOTS],
+            _ => options.sizes.clone(),
+        };
+
+        let mut prover_config = config.prover.clone();
+        prover_config.backend = kind;
+        prover_config.build_dir = options.build_dir.clone();
+        prover_config.size_classes = sizes.clone();
+        // missing keys get a seeded setup, as on a dev node
+        let jobs = Arc::new(JobQueue::new(prover_config.job_timeout(), prover_config.remote.max_attempts));
+        let backend = prover::init(&prover_config, true, jobs)
+            .with_context(|| format!("Failed to start the {} backend", kind.as_str()))?;
+
+        for slots in sizes {
+            for run in 0..options.runs {
+                log::info!("Benchmarking {} with {} slots, run {}", backend.name(), slots, run + 1);
+                let mut result = BenchResult { backend: backend.name().to_string(), slots, run, ..Default::default() };
+                if let Err(e) = measure(backend.as_ref(), slots, verifier_program.as_deref(), &mut result) {
+                    log::warn!("{} with {} slots failed: {:#}", backend.name(), slots, e);
+                    result.error = Some(format!("{:#}", e));
+                }
+                results.push(result);
+            }
+        }
+    }
+    Ok(results)
+}
+
+fn measure(backend: &dyn ProverBackend, slots: usize, verifier_program: Option<&[u8]>, result: &mut BenchResult) -> Result<()> {
+    let witness = sample_batch(format!("bench-{}-{}-{}", backend.name(), slots, result.run), slots);
+
+    if let Some(stats) = backend.measure_circuit(&witness)? {
+        result.constraints = Some(stats.constraints);
+        result.witness_ms = Some(millis(stats.witness_time));
+    }
+
+    let reset = zk::bench::reset_peak_memory();
+    let start = Instant::now();
+    let artifact = backend.prove(&witness)?;
+    result.prove_ms = Some(millis(start.elapsed()));
+    result.peak_memory_bytes = zk::bench::peak_memory_bytes().filter(|_| reset);
+
+    let (proof, public_inputs) = match &artifact {
+        ProofArtifact::Groth16 { proof, public_inputs, .. } => (proof, public_inputs),
+        other => {
+            result.proof_bytes = Some(proof_bytes(other)?);
+            return Ok(());
+        }
+    };
+    // the uncompressed a, b and c points the verifier program takes
+    result.proof_bytes = Some(64 + 128 + 64);
+    result.public_inputs = Some(public_inputs.len());
+
+    let BackendVerifyingKey::Groth16(vk) = backend.verifying_key(slots)? else {
+        return Err(anyhow!("{} produced a Groth16 proof without a Groth16 verifying key", backend.name()));
+    };
+    let start = Instant::now();
+    let verified = verify_groth16_offchain(&vk, proof, public_inputs);
+    result.verify_ms = Some(millis(start.elapsed()));
+    verified.map_err(|reason| anyhow!("the proof does not verify: {}", reason))?;
+
+    if let Some(program) = verifier_program {
+        result.onchain_verify_cu = Some(onchain_verify_units(program, slots, proof, public_inputs, &vk)?);
+    }
+    Ok(())
+}
+
+/// A full batch: `slots` senders, each paying one shared receiver.
+fn sample_batch(batch_id: String, slots: usize) -> BatchWitness {
+    let receiver = Keypair::new().pubkey();
+    let mut received = 0;
+    let mut witness = BatchWitness {
+        batch_id,
+        slots,
+        transfers: Vec::with_capacity(slots),
+        transactions: Vec::with_capacity(slots),
+        accounts: vec![AccountWitness::empty(receiver.to_bytes())],
+        state_paths: Vec::new(),
+    };
+
+    for i in 0..slots {
+        let sender = Keypair::new();
+        // the circom circuit needs every amount to be non-zero
+        let amount = 1_000 + i as u64;
+        let transaction = system_transaction::transfer(&sender, &receiver, amount, Hash::default());
+        witness.transfers.push(TransferWitness {
+            from: sender.pubkey().to_bytes(),
+            to: receiver.to_bytes(),
+            amount,
+            fee: LAMPORTS_PER_SIGNATURE,
+            signature_first_byte: transaction.signatures[0].as_ref()[0],
+            from_balance_before: SENDER_LAMPORTS,
+            from_balance_after: SENDER_LAMPORTS - amount - LAMPORTS_PER_SIGNATURE,
+            to_balance_before: received,
+            to_balance_after: received + amount,
+        });
+        received += amount;
+        witness.transactions.push(bincode::serialize(&transaction).expect("transactions always serialize"));
+        witness.accounts.push(AccountWitness { lamports: SENDER_LAMPORTS, ..AccountWitness::empty(sender.pubkey().to_bytes()) });
+    }
+    // every account enters an empty state
+    let keys: Vec<_> = witness.accounts.iter().map(|account| account.pubkey).collect();
+    witness.state_paths = StateTree::new().paths(&keys).expect("an empty tree has room for any account");
+    witness
+}
+
+/// Executes `verify_groth16_proof` in a local SVM with the verifier program
+/// loaded and `vk` registered, and returns the compute units it consumed.
+fn onchain_verify_units(
+    program: &[u8],
+    slots: usize,
+    proof: &ProofData,
+    public_inputs: &[String],
+    vk: &SnarkJsVerifyingKey,
+) -> Result<u64> {
+    let payer = Keypair::new();
+    let program_id = onchain_verifier::ID;
+    let ix = groth16_verify_instruction(&program_id, &payer.pubkey(), "bench", slots, proof, public_inputs, vk)?;
+    let transaction = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], Hash::default());
+    let sanitized_tx = SanitizedTransaction::try_from_legacy_transaction(transaction.clone(), &HashSet::new())?;
+
+    // every account the instruction touches is local, so the RPC client is never used
+    let rpc_client = RpcClient::new("http://127.0.0.1:0".to_string());
+    let mut loader = RollupAccountLoader::new(&rpc_client);
+    loader.add_account(payer.pubkey(), AccountSharedData::new(10 * SENDER_LAMPORTS, 0, &solana_sdk::system_program::id()));
+    for key in &transaction.message.account_keys {
+        if *key != payer.pubkey() {
+            loader.add_account(*key, AccountSharedData::default());
+        }
+    }
+    loader.add_account(registry_address(&program_id, &payer.pubkey(), slots), registered_key(&program_id, &payer.pubkey(), slots, vk)?);
+    loader.add_account(program_id, executable(program.to_vec(), solana_sdk_ids::bpf_loader::id()));
+    loader.add_account(solana_sdk_ids::bpf_loader::id(), executable(b"solana_bpf_loader_program".to_vec(), native_loader::id()));
+    loader.add_account(solana_sdk::system_program::id(), executable(b"system_program".to_vec(), native_loader::id()));
+    loader.add_account(sysvar::clock::id(), create_account_shared_data_for_test(&Clock::default()));
+    loader.add_account(sysvar::rent::id(), create_account_shared_data_for_test(&Rent::default()));
+
+    let compute_budget = SVMTransactionExecutionBudget::default();
+    let feature_set = SVMFeatureSet::all_enabled();
+    let fee_structure = FeeStructure::default();
+    let rent_collector = RentCollector::default();
+    let fork_graph = Arc::new(RwLock::new(RollupForkGraph {}));
+    let processor = create_transaction_batch_processor(&loader, &feature_set, &compute_budget, Arc::clone(&fork_graph));
+    processor.fill_missing_sysvar_cache_entries(&loader);
+
+    let processing_environment = TransactionProcessingEnvironment {
+        blockhash: Hash::default(),
+        blockhash_lamports_per_signature: fee_structure.lamports_per_signature,
+        epoch_total_stake: 0,
+        feature_set,
+        rent_collector: Some(&rent_collector),
+    };
+    let processing_config = TransactionProcessingConfig {
+        recording_config: ExecutionRecordingConfig::new_single_setting(true),
+        ..Default::default()
+    };
+
+    let results = processor.load_and_execute_sanitized_transactions(
+        &loader,
+        &[sanitized_tx],
+        check_results_with_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        &processing_environment,
+        &processing_config,
+    );
+    match results.processing_results.into_iter().next() {
+        Some(Ok(ProcessedTransaction::Executed(executed))) => {
+            let details = &executed.execution_details;
+            match &details.status {
+                Ok(()) => Ok(details.executed_units),
+                Err(e) => Err(anyhow!(
+                    "verifier program failed after {} units: {} ({})",
+                    details.executed_units,
+                    e,
+                    details.log_messages.as_deref().unwrap_or_default().join("; ")
+                )),
+            }
+        }
+        Some(Ok(ProcessedTransaction::FeesOnly(fees_only))) => Err(anyhow!("verifier program did not load: {}", fees_only.load_error)),
+        Some(Err(e)) => Err(anyhow!("verifier transaction was rejected: {}", e)),
+        None => Err(anyhow!("SVM returned no result")),
+    }
+}
+
+fn executable(data: Vec<u8>, owner: Pubkey) -> AccountSharedData {
+    AccountSharedData::from(Account { lamports: 1, data, owner, executable: true, rent_epoch: 0 })
+}
+
+/// The `RegisteredVerifyingKey` account `register_verifying_key` would leave for `vk`.
+fn registered_key(program_id: &Pubkey, authority: &Pubkey, slots: usize, vk: &SnarkJsVerifyingKey) -> Result<AccountSharedData> {
+    let (_, bump) = registry_pda(program_id, authority, slots);
+    let registered = onchain_verifier::RegisteredVerifyingKey {
+        authority: *authority,
+        size_class: slots as u32,
+        vk_hash: onchain_vk_hash(vk)?,
+        registered_at: 0,
+        bump,
+    };
+    let mut data = Vec::new();
+    registered.try_serialize(&mut data)?;
+    let lamports = Rent::default().minimum_balance(data.len());
+    Ok(AccountSharedData::from(Account { lamports, data, owner: *program_id, executable: false, rent_epoch: 0 }))
+}
+
+/// Like `processor::get_transaction_check_results`, with room for more than the default 200k units.
+fn check_results_with_unit_limit(units: u32) -> Vec<transaction::Result<CheckedTransactionDetails>> {
+    let limits = ComputeBudgetLimits { compute_unit_limit: units, ..ComputeBudgetLimits::default() };
+    vec![Ok(CheckedTransactionDetails::new(
+        None,
+        Ok(limits.get_compute_budget_and_limits(limits.loaded_accounts_bytes, FeeDetails::default())),
+    ))]
+}
+
+/// Bytes of proof a non-Groth16 artifact carries.
+fn proof_bytes(artifact: &ProofArtifact) -> Result<usize> {
+    let decoded = |base64: &str| base64::engine::general_purpose::STANDARD.decode(base64).map(|bytes| bytes.len());
+    Ok(match artifact {
+        ProofArtifact::RiscZero { seal, .. } => decoded(seal)?,
+        ProofArtifact::RiscZeroSuccinct { receipt } => decoded(receipt)?,
+        ProofArtifact::Groth16 { .. } | ProofArtifact::Mock => 0,
+    })
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub fn write_json(results: &[BenchResult], out: impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(out, results)?;
+    Ok(())
+}
+
+/// One row per result, in `BenchResult`'s field order; empty cells for missing figures.
+pub fn write_csv(results: &[BenchResult], mut out: impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "backend,slots,run,constraints,witness_ms,prove_ms,peak_memory_bytes,proof_bytes,public_inputs,verify_ms,onchain_verify_cu,error"
+    )?;
+    fn cell<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+    for r in results {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            r.backend,
+            r.slots,
+            r.run,
+            cell(r.constraints),
+            cell(r.witness_ms),
+            cell(r.prove_ms),
+            cell(r.peak_memory_bytes),
+            cell(r.proof_bytes),
+            cell(r.public_inputs),
+            cell(r.verify_ms),
+            cell(r.onchain_verify_cu),
+            cell(r.error.as_ref().map(|e| format!("\"{}\"", e.replace('"', "\"\""))))
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Compares the prover backends: constraints, witness and proving time, memory,
+//! proof size, and off-chain and on-chain verification cost per batch size.
+//!
+//! ```text
+//! cargo run --release --bin bench -- --backends arkworks,circom --sizes 4,16,64 \
+//!     --verifier-program ../onchain_verifier/target/deploy/onchain_verifier.so --format csv --output bench.csv
+//! ```
+
+use std::{fs::File, io, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use rollup_core::{
+    bench::{self, BenchOptions},
+    config::{NodeConfig, ProverBackendKind},
+};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Benchmarks the rollup's prover backends")]
+struct Args {
+    /// Node config to take the prover settings from (circom paths, dev seed)
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Backends to compare: `arkworks`, `circom`, `risc_zero`, `mock`
+    #[arg(long, value_delimiter = ',', default_value = "arkworks")]
+    backends: Vec<ProverBackendKind>,
+    /// Batch sizes, in transfers
+    #[arg(long, value_delimiter = ',', default_value = "1,4,16")]
+    sizes: Vec<usize>,
+    /// Proofs per backend and size
+    #[arg(long, default_value_t = 3)]
+    runs: usize,
+    /// Keys and working files; seeded keys are set up here when missing
+    #[arg(long, default_value = "build/bench")]
+    build_dir: PathBuf,
+    /// Compiled `onchain_verifier` program, to measure verification compute units
+    #[arg(long)]
+    verifier_program: Option<PathBuf>,
+    #[arg(long, value_enum, default_value = "json")]
+    format: Format,
+    /// Where to write the results (default stdout)
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    // progress goes to stderr, so results can go to stdout; the account loader logs whole program binaries
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,r1cs=warn,rollup_core::loader=warn"));
+    tracing_subscriber::fmt().with_env_filter(filter).with_writer(io::stderr).init();
+    let args = Args::parse();
+
+    let config = match &args.config {
+        Some(path) => NodeConfig::from_file(path)?,
+        None => NodeConfig::default(),
+    };
+    let mut sizes = args.sizes.clone();
+    sizes.sort_unstable();
+    sizes.dedup();
+    anyhow::ensure!(!sizes.is_empty() && sizes[0] > 0, "--sizes needs at least one non-zero batch size");
+
+    let options = BenchOptions {
+        backends: args.backends,
+        sizes,
+        runs: args.runs,
+        build_dir: args.build_dir,
+        verifier_program: args.verifier_program,
+    };
+    let results = bench::run(&config, &options)?;
+
+    let out: Box<dyn io::Write> = match &args.output {
+        Some(path) => Box::new(File::create(path).with_context(|| format!("Failed to create {}", path.display()))?),
+        None => Box::new(io::stdout().lock()),
+    };
+    match args.format {
+        Format::Json => bench::write_json(&results, out),
+        Format::Csv => bench::write_csv(&results, out),
+    }
+}
@@ -0,0 +1,880 @@
+//! Node configuration: defaults, overridden by a TOML file, then by env vars,
+//! then by CLI flags. Loaded and validated once at startup, then shared by
+//! every component as `Arc<NodeConfig>`.
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signer::keypair::read_keypair_file};
+
+use crate::{auth::ApiConfig, mempool::MempoolConfig, otlp::OtlpTarget};
+
+/// Config file read when `--config` is not given, if it exists.
+const DEFAULT_CONFIG_PATH: &str = "rollup.toml";
+/// Shortest accepted admin token, API key or JWT secret.
+const MIN_SECRET_LEN: usize = 16;
+/// The circom circuit has a fixed number of transfer slots, whatever `prover.size_classes` says.
+pub const CIRCOM_CIRCUIT_SLOTS: usize = 3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub server: ServerConfig,
+    pub l1: L1Config,
+    pub settlement: SettlementConfig,
+    pub sequencer: SequencerConfig,
+    pub prover: ProverConfig,
+    pub retry: RetryConfig,
+    pub mempool: MempoolConfig,
+    pub telemetry: TelemetryConfig,
+    pub admin: AdminConfig,
+    pub api: ApiConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct L1Config {
+    pub rpc_url: String,
+    /// One of `processed`, `confirmed`, `finalized`.
+    pub commitment: String,
+    /// How often `/ready` re-checks that the RPC answers.
+    pub probe_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettlementConfig {
+    pub verifier_program_id: String,
+    /// Verifies RISC Zero receipts; only used by the `risc_zero` prover backend.
+    pub risc0_verifier_program_id: String,
+    /// Keypair that pays for settlement (and signs test transactions).
+    pub payer_keypair_path: PathBuf,
+    /// Only settle proofs that also show every transfer was signed by its sender. Of the
+    /// backends only `risc_zero` proves that; the Groth16 batch circuit leaves signatures to the node.
+    pub require_authorization: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SequencerConfig {
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProverConfig {
+    pub backend: ProverBackendKind,
+    /// Holds the keys and each backend's working files; created on first start.
+    pub build_dir: PathBuf,
+    /// Transfer slots of each batch circuit, ascending. A batch is proved by the
+    /// smallest one it fits, and each has its own keys.
+    pub size_classes: Vec<usize>,
+    /// Batches proved concurrently. Each worker holds a full proof's memory.
+    pub workers: usize,
+    /// Executed batches that may wait for a worker before the sequencer pauses.
+    pub queue_capacity: usize,
+    pub circom: CircomConfig,
+    pub risc0: Risc0Config,
+    pub remote: RemoteProverConfig,
+    pub aggregation: AggregationConfig,
+    pub keys: KeysConfig,
+}
+
+/// Which proof system proves batches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProverBackendKind {
+    /// Native Groth16 over the batch circuit, in-process.
+    #[default]
+    Arkworks,
+    /// The circom circuit, proven by snarkjs; needs Node.js, circom and snarkjs installed.
+    Circom,
+    /// A RISC Zero guest, compressed to Groth16. Needs the `risc0` cargo feature.
+    RiscZero,
+    /// No proof at all, for development. Batches settle without on-chain verification, so
+    /// it is refused outside `api.dev_mode`.
+    Mock,
+    /// `prover-worker` processes lease batches from this node's `/prover` routes.
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircomConfig {
+    pub circuit_dir: PathBuf,
+    /// Compiles the circuit and creates its keys when they are missing.
+    pub script_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Risc0Config {
+    /// The guest program's ELF binary.
+    pub guest_elf: Option<PathBuf>,
+    /// The aggregation guest's ELF binary; needed when `prover.aggregation.window` is above 1.
+    pub aggregate_elf: Option<PathBuf>,
+}
+
+/// Settles several consecutive batches with one recursive proof. Only the
+/// `risc_zero` backend can verify proofs inside a proof.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AggregationConfig {
+    /// Batches folded into one settlement; 1 settles every batch on its own.
+    pub window: usize,
+    /// Levels of the aggregation tree. Each level folds `fan_in()` proofs at a time,
+    /// so deeper trees prove smaller, parallelizable steps.
+    pub depth: usize,
+    /// A window that has not filled up after this long is settled as it is.
+    pub max_wait_secs: u64,
+}
+
+/// Where the Groth16 keys come from. Production keys are made with `key-manager`
+/// from a Powers of Tau ceremony; see `zk::keys` for the layout under `keys_dir()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    /// Seeds the setup of size classes without a key. Only used in dev mode:
+    /// anyone who knows the seed can forge proofs.
+    pub dev_seed: u64,
+    /// Hex SHA-256 of the `.ptau` file every key must come from.
+    pub ptau_sha256: Option<String>,
+    /// Register the verifying key hash of each size class that has none with the
+    /// verifier program on start, instead of refusing to start.
+    pub register: bool,
+    /// Replace registered verifying key hashes that differ from the loaded keys on
+    /// start, instead of refusing to start. Only for a deliberate key rotation.
+    pub rotate: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoteProverConfig {
+    /// A leased job goes back to the queue when its worker misses heartbeats for this long.
+    pub lease_secs: u64,
+    /// Leases per job before it fails for good.
+    pub max_attempts: u32,
+    /// Give up on a batch that has not been proved after this long.
+    pub job_timeout_secs: u64,
+    /// Unfinished jobs `/prover` holds before answering submissions with 503.
+    pub queue_capacity: usize,
+    /// Sent by workers in `X-Prover-Secret`; `/prover` refuses every request without it.
+    pub shared_secret: Option<String>,
+}
+
+impl std::fmt::Debug for RemoteProverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteProverConfig")
+            .field("lease_secs", &self.lease_secs)
+            .field("max_attempts", &self.max_attempts)
+            .field("job_timeout_secs", &self.job_timeout_secs)
+            .field("queue_capacity", &self.queue_capacity)
+            .field("shared_secret", &self.shared_secret.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub service_name: String,
+    /// OTLP/HTTP traces endpoint, e.g. `http://localhost:4318/v1/traces`.
+    pub otlp_endpoint: Option<String>,
+    /// Append OTLP/JSON export requests to this file, one per line.
+    pub otlp_file: Option<PathBuf>,
+}
+
+/// The admin API is disabled unless a token is configured.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Bearer token required on every `/admin` request.
+    pub token: Option<String>,
+}
+
+// the config is logged at startup, keep the token out of it
+impl std::fmt::Debug for AdminConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminConfig")
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self { bind_address: "127.0.0.1:8080".to_string() }
+    }
+}
+
+impl Default for L1Config {
+    fn default() -> Self {
+        Self {
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            commitment: "confirmed".to_string(),
+            probe_interval_secs: 15,
+        }
+    }
+}
+
+impl Default for SettlementConfig {
+    fn default() -> Self {
+        Self {
+            verifier_program_id: "Aa3rXCBoxPVZ537nqccEiVsLBoZ2G7gdfNjypM9wP8Yi".to_string(),
+            risc0_verifier_program_id: "HxRTWtxpFWCjbuxxp8fT33Tau6rx6AxqBuht6KdmaYd8".to_string(),
+            payer_keypair_path: default_keypair_path(),
+            require_authorization: false,
+        }
+    }
+}
+
+impl Default for SequencerConfig {
+    fn default() -> Self {
+        Self { batch_size: 4 }
+    }
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self {
+            backend: ProverBackendKind::default(),
+            build_dir: PathBuf::from("build"),
+            size_classes: vec![4, 16, 64, 256],
+            workers: 1,
+            queue_capacity: 4,
+            circom: CircomConfig::default(),
+            risc0: Risc0Config::default(),
+            remote: RemoteProverConfig::default(),
+            aggregation: AggregationConfig::default(),
+            keys: KeysConfig::default(),
+        }
+    }
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self { dev_seed: 42, ptau_sha256: None, register: false, rotate: false }
+    }
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self { window: 1, depth: 1, max_wait_secs: 60 }
+    }
+}
+
+impl Default for RemoteProverConfig {
+    fn default() -> Self {
+        Self {
+            lease_secs: 30,
+            max_attempts: 3,
+            job_timeout_secs: 30 * 60,
+            queue_capacity: prover_service::queue::DEFAULT_CAPACITY,
+            shared_secret: None,
+        }
+    }
+}
+
+impl Default for CircomConfig {
+    fn default() -> Self {
+        Self {
+            circuit_dir: PathBuf::from("circuit"),
+            script_path: PathBuf::from("scripts/setup_and_prove.sh"),
+        }
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            service_name: "rollup_core".to_string(),
+            otlp_endpoint: None,
+            otlp_file: None,
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self { interval_secs: 300 }
+    }
+}
+
+// KEYPAIR2 predates the config file, keep honouring it
+fn default_keypair_path() -> PathBuf {
+    std::env::var("KEYPAIR2")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            let home = std::env::var("HOME").unwrap_or_default();
+            Path::new(&home).join(".config/solana/id.json")
+        })
+}
+
+/// Command line flags. Every flag can also be set through the env var shown in `--help`.
+#[derive(Debug, Parser)]
+#[command(name = "rollup_core", about = "SVM rollup node")]
+pub struct Cli {
+    /// Path to a TOML config file
+    #[arg(long, env = "ROLLUP_CONFIG")]
+    pub config: Option<PathBuf>,
+    #[arg(long, env = "ROLLUP_BIND_ADDRESS")]
+    pub bind_address: Option<String>,
+    #[arg(long, env = "ROLLUP_L1_RPC_URL")]
+    pub rpc_url: Option<String>,
+    #[arg(long, env = "ROLLUP_L1_COMMITMENT")]
+    pub commitment: Option<String>,
+    #[arg(long, env = "ROLLUP_L1_PROBE_INTERVAL_SECS")]
+    pub l1_probe_interval_secs: Option<u64>,
+    #[arg(long, env = "ROLLUP_VERIFIER_PROGRAM_ID")]
+    pub verifier_program_id: Option<String>,
+    #[arg(long, env = "ROLLUP_PAYER_KEYPAIR")]
+    pub payer_keypair: Option<PathBuf>,
+    /// Refuse to start with a prover backend whose proofs do not cover signatures
+    #[arg(long, env = "ROLLUP_REQUIRE_AUTHORIZATION")]
+    pub require_authorization: bool,
+    #[arg(long, env = "ROLLUP_BATCH_SIZE")]
+    pub batch_size: Option<usize>,
+    /// One of `arkworks`, `circom`, `risc_zero`, `mock`, `remote`
+    #[arg(long, env = "ROLLUP_PROVER_BACKEND")]
+    pub prover_backend: Option<ProverBackendKind>,
+    #[arg(long, env = "ROLLUP_BUILD_DIR")]
+    pub build_dir: Option<PathBuf>,
+    #[arg(long, env = "ROLLUP_PROVER_WORKERS")]
+    pub prover_workers: Option<usize>,
+    /// Batches settled by one aggregate proof
+    #[arg(long, env = "ROLLUP_AGGREGATION_WINDOW")]
+    pub aggregation_window: Option<usize>,
+    #[arg(long, env = "ROLLUP_AGGREGATION_DEPTH")]
+    pub aggregation_depth: Option<usize>,
+    #[arg(long, env = "ROLLUP_RETRY_INTERVAL_SECS")]
+    pub retry_interval_secs: Option<u64>,
+    #[arg(long, env = "ROLLUP_MEMPOOL_CAPACITY")]
+    pub mempool_capacity: Option<usize>,
+    #[arg(long, env = "ROLLUP_MEMPOOL_MAX_PER_FEE_PAYER")]
+    pub mempool_max_per_fee_payer: Option<usize>,
+    #[arg(long, env = "ROLLUP_MEMPOOL_MAX_BLOCKHASH_AGE_SECS")]
+    pub mempool_max_blockhash_age_secs: Option<u64>,
+    #[arg(long, env = "ROLLUP_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+    #[arg(long, env = "ROLLUP_OTLP_FILE")]
+    pub otlp_file: Option<PathBuf>,
+    #[arg(long, env = "ROLLUP_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+    #[arg(long, env = "ROLLUP_JWT_SECRET", hide_env_values = true)]
+    pub jwt_secret: Option<String>,
+    #[arg(long, env = "ROLLUP_MAX_BODY_BYTES")]
+    pub max_body_bytes: Option<usize>,
+    /// Register the verifying key hash of every size class with the verifier program on start
+    #[arg(long, env = "ROLLUP_REGISTER_VERIFYING_KEYS")]
+    pub register_verifying_keys: bool,
+    /// Replace registered verifying key hashes that differ from the loaded keys on start
+    #[arg(long, env = "ROLLUP_ROTATE_VERIFYING_KEYS")]
+    pub rotate_verifying_keys: bool,
+    /// Allow submissions without a transaction to be signed by the operator keypair, and
+    /// proving keys from a seeded setup or without a phase 2 contribution
+    #[arg(long, env = "ROLLUP_DEV_MODE")]
+    pub dev_mode: bool,
+}
+
+impl NodeConfig {
+    /// Builds the config from the process arguments and environment, then validates it.
+    pub fn load() -> Result<Self> {
+        let config = Self::from_cli(Cli::parse())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_cli(cli: Cli) -> Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?,
+            None => Self::default(),
+        };
+        config.apply_overrides(cli);
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    fn apply_overrides(&mut self, cli: Cli) {
+        if let Some(v) = cli.bind_address { self.server.bind_address = v; }
+        if let Some(v) = cli.rpc_url { self.l1.rpc_url = v; }
+        if let Some(v) = cli.commitment { self.l1.commitment = v; }
+        if let Some(v) = cli.l1_probe_interval_secs { self.l1.probe_interval_secs = v; }
+        if let Some(v) = cli.verifier_program_id { self.settlement.verifier_program_id = v; }
+        if let Some(v) = cli.payer_keypair { self.settlement.payer_keypair_path = v; }
+        if cli.require_authorization { self.settlement.require_authorization = true; }
+        if let Some(v) = cli.batch_size { self.sequencer.batch_size = v; }
+        if let Some(v) = cli.prover_backend { self.prover.backend = v; }
+        if let Some(v) = cli.build_dir { self.prover.build_dir = v; }
+        if let Some(v) = cli.prover_workers { self.prover.workers = v; }
+        if let Some(v) = cli.aggregation_window { self.prover.aggregation.window = v; }
+        if let Some(v) = cli.aggregation_depth { self.prover.aggregation.depth = v; }
+        if cli.register_verifying_keys { self.prover.keys.register = true; }
+        if cli.rotate_verifying_keys { self.prover.keys.rotate = true; }
+        if let Some(v) = cli.retry_interval_secs { self.retry.interval_secs = v; }
+        if let Some(v) = cli.mempool_capacity { self.mempool.capacity = v; }
+        if let Some(v) = cli.mempool_max_per_fee_payer { self.mempool.max_per_fee_payer = v; }
+        if let Some(v) = cli.mempool_max_blockhash_age_secs { self.mempool.max_blockhash_age_secs = v; }
+        if let Some(v) = cli.otlp_endpoint { self.telemetry.otlp_endpoint = Some(v); }
+        if let Some(v) = cli.otlp_file { self.telemetry.otlp_file = Some(v); }
+        if let Some(v) = cli.admin_token { self.admin.token = Some(v); }
+        if let Some(v) = cli.jwt_secret { self.api.jwt_secret = Some(v); }
+        if let Some(v) = cli.max_body_bytes { self.api.max_body_bytes = v; }
+        if cli.dev_mode { self.api.dev_mode = true; }
+    }
+
+    /// Fails fast on anything that would otherwise only surface mid-batch or mid-settlement.
+    pub fn validate(&self) -> Result<()> {
+        SocketAddr::from_str(&self.server.bind_address)
+            .map_err(|e| anyhow!("server.bind_address {:?} is invalid: {}", self.server.bind_address, e))?;
+
+        if !(self.l1.rpc_url.starts_with("http://") || self.l1.rpc_url.starts_with("https://")) {
+            return Err(anyhow!("l1.rpc_url {:?} must be an http(s) URL", self.l1.rpc_url));
+        }
+        CommitmentConfig::from_str(&self.l1.commitment)
+            .map_err(|_| anyhow!("l1.commitment {:?} is not processed, confirmed or finalized", self.l1.commitment))?;
+        if self.l1.probe_interval_secs == 0 {
+            return Err(anyhow!("l1.probe_interval_secs must be greater than zero"));
+        }
+
+        self.verifier_program_id()?;
+        read_keypair_file(&self.settlement.payer_keypair_path).map_err(|e| {
+            anyhow!("settlement.payer_keypair_path {} is not a readable keypair: {}", self.settlement.payer_keypair_path.display(), e)
+        })?;
+
+        let classes = &self.prover.size_classes;
+        if classes.first().is_none_or(|&smallest| smallest == 0) || classes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(anyhow!("prover.size_classes must be non-zero and strictly ascending, got {:?}", classes));
+        }
+        let max_batch_size = self.prover.max_batch_size();
+        if self.sequencer.batch_size == 0 || self.sequencer.batch_size > max_batch_size {
+            return Err(anyhow!(
+                "sequencer.batch_size must be between 1 and {} (the largest circuit), got {}",
+                max_batch_size,
+                self.sequencer.batch_size
+            ));
+        }
+
+        if self.prover.build_dir.is_file() {
+            return Err(anyhow!("prover.build_dir {} is a file", self.prover.build_dir.display()));
+        }
+        if self.prover.workers == 0 {
+            return Err(anyhow!("prover.workers must be greater than zero"));
+        }
+        if self.prover.queue_capacity == 0 {
+            return Err(anyhow!("prover.queue_capacity must be greater than zero"));
+        }
+        // the sequencer checks signatures natively either way, but only a RISC Zero receipt shows it to the verifier
+        if self.settlement.require_authorization && self.prover.backend != ProverBackendKind::RiscZero {
+            return Err(anyhow!(
+                "settlement.require_authorization needs the risc_zero backend, {} proofs do not cover signatures",
+                self.prover.backend.as_str()
+            ));
+        }
+        match self.prover.backend {
+            ProverBackendKind::Circom => {
+                let circom = &self.prover.circom;
+                if !circom.circuit_dir.is_dir() {
+                    return Err(anyhow!("prover.circom.circuit_dir {} does not exist", circom.circuit_dir.display()));
+                }
+                if !circom.script_path.is_file() {
+                    return Err(anyhow!("prover.circom.script_path {} does not exist", circom.script_path.display()));
+                }
+            }
+            ProverBackendKind::RiscZero => {
+                if !cfg!(feature = "risc0") {
+                    return Err(anyhow!("prover.backend risc_zero needs a build with the risc0 feature"));
+                }
+                match &self.prover.risc0.guest_elf {
+                    Some(elf) if elf.is_file() => {}
+                    Some(elf) => return Err(anyhow!("prover.risc0.guest_elf {} does not exist", elf.display())),
+                    None => return Err(anyhow!("prover.risc0.guest_elf is required for the risc_zero backend")),
+                }
+                self.risc0_verifier_program_id()?;
+            }
+            ProverBackendKind::Remote => {
+                let remote = &self.prover.remote;
+                if remote.lease_secs < 3 {
+                    return Err(anyhow!("prover.remote.lease_secs must be at least 3, workers heartbeat every third of it"));
+                }
+                if remote.max_attempts == 0 || remote.job_timeout_secs == 0 {
+                    return Err(anyhow!("prover.remote.max_attempts and job_timeout_secs must be greater than zero"));
+                }
+                // the node's own prover workers must always find room
+                if remote.queue_capacity < self.prover.workers {
+                    return Err(anyhow!("prover.remote.queue_capacity must be at least prover.workers"));
+                }
+                if remote.shared_secret.as_ref().is_none_or(|secret| secret.len() < MIN_SECRET_LEN) {
+                    return Err(anyhow!("prover.remote.shared_secret of at least {} characters is required", MIN_SECRET_LEN));
+                }
+            }
+            ProverBackendKind::Mock if !self.api.dev_mode => {
+                return Err(anyhow!("prover.backend mock settles batches without any proof and needs dev_mode"));
+            }
+            ProverBackendKind::Arkworks | ProverBackendKind::Mock => {}
+        }
+        if let Some(hash) = &self.prover.keys.ptau_sha256 {
+            if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(anyhow!("prover.keys.ptau_sha256 must be 64 hex digits, got {:?}", hash));
+            }
+        }
+        let aggregation = &self.prover.aggregation;
+        if aggregation.window == 0 || aggregation.depth == 0 || aggregation.max_wait_secs == 0 {
+            return Err(anyhow!("prover.aggregation.window, depth and max_wait_secs must be greater than zero"));
+        }
+        if aggregation.is_enabled() {
+            // a BN254 Groth16 proof cannot be verified inside a BN254 circuit at any sensible cost
+            if self.prover.backend != ProverBackendKind::RiscZero {
+                return Err(anyhow!("prover.aggregation.window above 1 needs the risc_zero backend"));
+            }
+            match &self.prover.risc0.aggregate_elf {
+                Some(elf) if elf.is_file() => {}
+                Some(elf) => return Err(anyhow!("prover.risc0.aggregate_elf {} does not exist", elf.display())),
+                None => return Err(anyhow!("prover.risc0.aggregate_elf is required to aggregate proofs")),
+            }
+        }
+
+        if self.retry.interval_secs == 0 {
+            return Err(anyhow!("retry.interval_secs must be greater than zero"));
+        }
+
+        if self.mempool.capacity == 0 || self.mempool.max_per_fee_payer == 0 {
+            return Err(anyhow!("mempool.capacity and mempool.max_per_fee_payer must be greater than zero"));
+        }
+        if self.mempool.max_per_fee_payer > self.mempool.capacity {
+            return Err(anyhow!("mempool.max_per_fee_payer cannot exceed mempool.capacity"));
+        }
+        if self.mempool.blockhash_check_interval_secs == 0 {
+            return Err(anyhow!("mempool.blockhash_check_interval_secs must be greater than zero"));
+        }
+
+        match (&self.telemetry.otlp_endpoint, &self.telemetry.otlp_file) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow!("set at most one of telemetry.otlp_endpoint and telemetry.otlp_file"));
+            }
+            (Some(endpoint), None) if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) => {
+                return Err(anyhow!("telemetry.otlp_endpoint {:?} must be an http(s) URL", endpoint));
+            }
+            _ => {}
+        }
+
+        if let Some(token) = &self.admin.token {
+            if token.len() < MIN_SECRET_LEN {
+                return Err(anyhow!("admin.token must be at least {} characters", MIN_SECRET_LEN));
+            }
+        }
+
+        if self.api.max_body_bytes == 0 {
+            return Err(anyhow!("api.max_body_bytes must be greater than zero"));
+        }
+        let limits = &self.api.rate_limit;
+        if !(limits.per_client_rps > 0.0 && limits.per_ip_rps > 0.0) || limits.per_client_burst == 0 || limits.per_ip_burst == 0 {
+            return Err(anyhow!("api.rate_limit rates and bursts must be greater than zero"));
+        }
+        if let Some(secret) = &self.api.jwt_secret {
+            if secret.len() < MIN_SECRET_LEN {
+                return Err(anyhow!("api.jwt_secret must be at least {} characters", MIN_SECRET_LEN));
+            }
+        }
+        for key in &self.api.keys {
+            if key.key.len() < MIN_SECRET_LEN {
+                return Err(anyhow!("api key {:?} must be at least {} characters", key.name, MIN_SECRET_LEN));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn verifier_program_id(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.settlement.verifier_program_id)
+            .map_err(|e| anyhow!("settlement.verifier_program_id {:?} is invalid: {}", self.settlement.verifier_program_id, e))
+    }
+
+    pub fn risc0_verifier_program_id(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.settlement.risc0_verifier_program_id).map_err(|e| {
+            anyhow!("settlement.risc0_verifier_program_id {:?} is invalid: {}", self.settlement.risc0_verifier_program_id, e)
+        })
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        CommitmentConfig::from_str(&self.l1.commitment).unwrap_or_else(|_| CommitmentConfig::confirmed())
+    }
+
+    pub fn retry_interval(&self) -> Duration {
+        Duration::from_secs(self.retry.interval_secs)
+    }
+
+    pub fn l1_probe_interval(&self) -> Duration {
+        Duration::from_secs(self.l1.probe_interval_secs)
+    }
+}
+
+impl TelemetryConfig {
+    pub fn otlp_target(&self) -> Option<OtlpTarget> {
+        match (&self.otlp_endpoint, &self.otlp_file) {
+            (Some(endpoint), _) => Some(OtlpTarget::Http(endpoint.clone())),
+            (None, Some(path)) => Some(OtlpTarget::File(path.clone())),
+            (None, None) => None,
+        }
+    }
+}
+
+impl ProverBackendKind {
+    pub const ALL: [ProverBackendKind; 5] = [
+        ProverBackendKind::Arkworks,
+        ProverBackendKind::Circom,
+        ProverBackendKind::RiscZero,
+        ProverBackendKind::Mock,
+        ProverBackendKind::Remote,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProverBackendKind::Arkworks => "arkworks",
+            ProverBackendKind::Circom => "circom",
+            ProverBackendKind::RiscZero => "risc_zero",
+            ProverBackendKind::Mock => "mock",
+            ProverBackendKind::Remote => "remote",
+        }
+    }
+}
+
+impl FromStr for ProverBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProverBackendKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown prover backend {:?}", s))
+    }
+}
+
+impl AggregationConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.window > 1
+    }
+
+    /// Proofs folded by each aggregation step: the smallest count that covers the
+    /// window within `depth` levels.
+    pub fn fan_in(&self) -> usize {
+        let mut fan_in: usize = 2;
+        while fan_in.checked_pow(self.depth as u32).is_some_and(|covered| covered < self.window) {
+            fan_in += 1;
+        }
+        fan_in
+    }
+
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_secs(self.max_wait_secs)
+    }
+}
+
+impl ProverConfig {
+    pub fn job_timeout(&self) -> Duration {
+        Duration::from_secs(self.remote.job_timeout_secs)
+    }
+
+    /// The slots of the smallest circuit that fits `transfers`, if any does.
+    pub fn size_class_for(&self, transfers: usize) -> Option<usize> {
+        match self.backend {
+            ProverBackendKind::Circom => Some(CIRCOM_CIRCUIT_SLOTS).filter(|&slots| transfers <= slots),
+            _ => self.size_classes.iter().copied().find(|&slots| transfers <= slots),
+        }
+    }
+
+    pub fn max_batch_size(&self) -> usize {
+        match self.backend {
+            ProverBackendKind::Circom => CIRCOM_CIRCUIT_SLOTS,
+            _ => self.size_classes.last().copied().unwrap_or(0),
+        }
+    }
+
+    /// The versioned key store, one `batch_<slots>/` directory per size class.
+    pub fn keys_dir(&self) -> PathBuf {
+        self.build_dir.join("keys")
+    }
+
+    /// snarkjs-format verification key of the active Groth16 backend for one size
+    /// class, read at settlement.
+    pub fn verification_key_path(&self, slots: usize) -> PathBuf {
+        self.keys_dir().join(format!("verification_key_batch_{}.json", slots))
+    }
+
+    /// Compiled circuit, keys and per-batch job files of the circom backend.
+    pub fn circom_build_dir(&self) -> PathBuf {
+        self.build_dir.join("circom")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use solana_sdk::signer::keypair::{write_keypair_file, Keypair};
+
+    use super::*;
+
+    /// The defaults with a payer keypair that exists, which is all they need to validate.
+    fn valid() -> NodeConfig {
+        static PAYER: OnceLock<PathBuf> = OnceLock::new();
+        let path = PAYER.get_or_init(|| {
+            let path = std::env::temp_dir().join(format!("rollup-config-test-{}.json", std::process::id()));
+            write_keypair_file(&Keypair::new(), &path).unwrap();
+            path
+        });
+        let mut config = NodeConfig::default();
+        config.settlement.payer_keypair_path = path.clone();
+        config
+    }
+
+    fn rejection(config: NodeConfig) -> String {
+        config.validate().expect_err("config should be rejected").to_string()
+    }
+
+    #[test]
+    fn defaults_validate() {
+        valid().validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_l1_and_settlement_settings() {
+        let mut config = valid();
+        config.l1.rpc_url = "ws://localhost:8900".to_string();
+        assert!(rejection(config).contains("l1.rpc_url"));
+
+        let mut config = valid();
+        config.l1.commitment = "final".to_string();
+        assert!(rejection(config).contains("l1.commitment"));
+
+        let mut config = valid();
+        config.settlement.verifier_program_id = "not a pubkey".to_string();
+        assert!(rejection(config).contains("settlement.verifier_program_id"));
+
+        let mut config = valid();
+        config.settlement.payer_keypair_path = PathBuf::from("/nonexistent/payer.json");
+        assert!(rejection(config).contains("settlement.payer_keypair_path"));
+    }
+
+    #[test]
+    fn rejects_batches_no_circuit_fits() {
+        let mut config = valid();
+        config.prover.size_classes = vec![16, 4];
+        assert!(rejection(config).contains("prover.size_classes"));
+
+        let mut config = valid();
+        config.sequencer.batch_size = 257;
+        assert!(rejection(config).contains("sequencer.batch_size"));
+
+        let mut config = valid();
+        config.sequencer.batch_size = 0;
+        assert!(rejection(config).contains("sequencer.batch_size"));
+    }
+
+    #[test]
+    fn mock_backend_needs_dev_mode() {
+        let mut config = valid();
+        config.prover.backend = ProverBackendKind::Mock;
+        assert!(rejection(config.clone()).contains("dev_mode"));
+
+        config.api.dev_mode = true;
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn required_authorization_needs_risc_zero() {
+        for backend in [ProverBackendKind::Arkworks, ProverBackendKind::Circom, ProverBackendKind::Remote] {
+            let mut config = valid();
+            config.prover.backend = backend;
+            config.prover.remote.shared_secret = Some("a-long-enough-worker-secret".to_string());
+            config.sequencer.batch_size = CIRCOM_CIRCUIT_SLOTS;
+            config.settlement.require_authorization = true;
+            assert!(rejection(config).contains("settlement.require_authorization"));
+        }
+    }
+
+    #[test]
+    fn remote_backend_needs_a_shared_secret() {
+        let mut config = valid();
+        config.prover.backend = ProverBackendKind::Remote;
+        assert!(rejection(config.clone()).contains("prover.remote.shared_secret"));
+
+        config.prover.remote.shared_secret = Some("a-long-enough-worker-secret".to_string());
+        config.validate().unwrap();
+        config.prover.remote.queue_capacity = 0;
+        assert!(rejection(config).contains("prover.remote.queue_capacity"));
+    }
+
+    #[test]
+    fn only_risc_zero_aggregates() {
+        let mut config = valid();
+        config.prover.aggregation.window = 4;
+        assert!(rejection(config).contains("risc_zero"));
+    }
+
+    #[test]
+    fn rejects_bad_mempool_limits() {
+        let mut config = valid();
+        config.mempool.capacity = 0;
+        assert!(rejection(config).contains("mempool.capacity"));
+
+        let mut config = valid();
+        config.mempool.max_per_fee_payer = config.mempool.capacity + 1;
+        assert!(rejection(config).contains("cannot exceed mempool.capacity"));
+
+        let mut config = valid();
+        config.mempool.blockhash_check_interval_secs = 0;
+        assert!(rejection(config).contains("mempool.blockhash_check_interval_secs"));
+    }
+
+    #[test]
+    fn rejects_short_secrets_and_zero_rate_limits() {
+        let mut config = valid();
+        config.admin.token = Some("short".to_string());
+        assert!(rejection(config).contains("admin.token"));
+
+        let mut config = valid();
+        config.api.jwt_secret = Some("short".to_string());
+        assert!(rejection(config).contains("api.jwt_secret"));
+
+        let mut config = valid();
+        config.api.rate_limit.per_ip_rps = 0.0;
+        assert!(rejection(config).contains("api.rate_limit"));
+
+        let mut config = valid();
+        config.api.max_body_bytes = 0;
+        assert!(rejection(config).contains("api.max_body_bytes"));
+    }
+
+    #[test]
+    fn rejects_two_otlp_targets() {
+        let mut config = valid();
+        config.telemetry.otlp_endpoint = Some("http://localhost:4318".to_string());
+        config.telemetry.otlp_file = Some(PathBuf::from("spans.jsonl"));
+        assert!(rejection(config).contains("telemetry.otlp_endpoint"));
+    }
+}
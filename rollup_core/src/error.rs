@@ -0,0 +1,65 @@
+//! The JSON error envelope every HTTP handler answers with.
+//!
+//! Handlers return `Result<_, HttpError>` and build the error from a
+//! `rollup_api::ApiError`; the HTTP status follows from its code. Extractor
+//! failures (bad JSON, query or path) are routed through the same envelope.
+
+use std::fmt;
+
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::{header, StatusCode},
+    HttpRequest, HttpResponse, ResponseError,
+};
+pub use rollup_api::{ApiError, ErrorCode};
+
+#[derive(Debug)]
+pub struct HttpError(pub ApiError);
+
+impl From<ApiError> for HttpError {
+    fn from(error: ApiError) -> Self {
+        HttpError(error)
+    }
+}
+
+impl From<actix_web::error::BlockingError> for HttpError {
+    fn from(error: actix_web::error::BlockingError) -> Self {
+        HttpError(ApiError::new(ErrorCode::Internal, error.to_string()))
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ResponseError for HttpError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.0.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if self.0.code == ErrorCode::Unauthorized {
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(&self.0)
+    }
+}
+
+pub fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let code = match err {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => ErrorCode::PayloadTooLarge,
+        _ => ErrorCode::BadRequest,
+    };
+    HttpError(ApiError::new(code, err.to_string())).into()
+}
+
+pub fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    HttpError(ApiError::bad_request(err.to_string())).into()
+}
+
+pub fn path_error(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    HttpError(ApiError::bad_request(err.to_string())).into()
+}
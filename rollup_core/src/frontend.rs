@@ -1,121 +1,339 @@
 use std::{collections::HashMap, str::FromStr, time::Duration};
 
-use actix_web::{error, web, HttpResponse, Responder};
-use async_channel::Receiver;
+use actix_web::{web, HttpResponse, Responder};
 use crossbeam::channel::Sender as CBSender;
-use serde::{Deserialize, Serialize};
-use solana_sdk::{keccak::Hash, transaction::Transaction};
-use tokio::time::timeout;
+use solana_sdk::{account::AccountSharedData, keccak::Hash, pubkey::Pubkey, transaction::Transaction};
+use tokio::{sync::oneshot, time::timeout};
 use solana_client::nonblocking::rpc_client::RpcClient; 
 use solana_sdk::{
     message::Message,
-    signature::Signer,
-    commitment_config::CommitmentConfig,
+    signature::{Signature, Signer},
 };
 use solana_system_interface::instruction as system_instruction;
+use rollup_api::{BatchIdPath, ProofVerification, SignaturePath, VerifyProofRequest};
 
-use crate::rollupdb::RollupDBMessage;
+use crate::{
+    config::NodeConfig,
+    error::{ApiError, ErrorCode, HttpError},
+    health::{HealthReport, NodeHealth},
+    mempool::{self, Mempool},
+    metrics::METRICS,
+    rollupdb::{AdminOutcome, BatchDetails, BatchSummary, ListBatchesQuery, ProofStatus, RollupDBMessage, UnsettledReport},
+    settle, simulate,
+    validation::{self, RejectCode, TransactionRejection},
+};
+
+pub use rollup_api::{
+    AccountDiff, BatchList, GetTransaction, GetTransactionResponse, ListBatchesParams, RollupTransaction,
+    RollupTransactionsList, SimulationResult, SubmitBatchEntry, SubmitBatchResponse, SubmitTransactionBatch,
+    SubmitTransactionResponse, TransactionEncoding, TransactionWithHash,
+};
 
+#[derive(Default)]
 pub struct FrontendMessage {
     pub get_tx: Option<Hash>,
     pub transaction: Option<Transaction>,                 // single
     pub transactions: Option<Vec<TransactionWithHash>>,  // list
     pub total: Option<u64>,
     pub has_more: Option<bool>,
+    pub accounts: Option<Vec<(Pubkey, AccountSharedData)>>, // read-only snapshot
+    pub batches: Option<Vec<BatchSummary>>,
+    pub batch: Option<BatchDetails>,
+    pub unsettled: Option<UnsettledReport>,
+    pub admin: Option<AdminOutcome>,
     pub error: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct GetTransaction {
-    /// If present → fetch a single tx by this base58 hash.
-    pub get_tx: Option<String>,
-    /// For list mode:
-    pub page: Option<u32>,     // 1-based
-    pub per_page: Option<u32>, // default 50, max 500
+/// The transaction carried by a request, from either the wire encoding or `sol_transaction`.
+fn decode_request(body: &RollupTransaction) -> Result<Option<Transaction>, TransactionRejection> {
+    match &body.encoded_transaction {
+        Some(encoded) => validation::decode_transaction(encoded, body.encoding.unwrap_or_default()).map(Some),
+        None => Ok(body.sol_transaction.clone()),
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct RollupTransaction {
-    pub sender: Option<String>,
-    pub sol_transaction: Option<Transaction>,
-    pub error: Option<String>,
-}
+/// How long a handler waits on RollupDB before answering 504.
+const DB_REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long submission waits on L1 for a fee payer L2 has not seen.
+const L1_ACCOUNT_TIMEOUT: Duration = Duration::from_secs(5);
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TransactionWithHash {
-    pub hash: String,
-    pub transaction: Transaction,
-}
+/// Sends `message` to RollupDB and waits up to `dur` for the answer, which comes back on
+/// a channel of this request's own.
+pub(crate) async fn query_db(
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    message: RollupDBMessage,
+    dur: Duration,
+) -> Result<FrontendMessage, HttpError> {
+    let (reply, response) = oneshot::channel();
+    rollupdb_sender
+        .send(RollupDBMessage { reply: Some(reply), ..message })
+        .map_err(backend_unavailable)?;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct RollupTransactionsList {
-    pub sender: Option<String>,
-    pub transactions: Vec<TransactionWithHash>, // raw transactions
-    pub page: u32,
-    pub per_page: u32,
-    pub total: Option<u64>,
-    pub has_more: bool,
-    pub error: Option<String>,
+    match timeout(dur, response).await {
+        Ok(Ok(message)) => Ok(message),
+        // RollupDB dropped the request without answering, which only happens when it stops
+        Ok(Err(e)) => Err(backend_unavailable(e)),
+        Err(_) => Err(backend_timeout()),
+    }
 }
 
-async fn recv_once<T>(rx: &Receiver<T>, dur: Duration) -> Option<T> {
-    timeout(dur, rx.recv()).await.ok().and_then(Result::ok)
+fn backend_unavailable(e: impl std::fmt::Display) -> HttpError {
+    log::error!("Failed to send request to RollupDB: {e}");
+    ApiError::unavailable("RollupDB is not running").into()
 }
 
-fn ok_json<T: Serialize>(v: T) -> actix_web::Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(v))
+fn backend_timeout() -> HttpError {
+    ApiError::timeout("RollupDB did not reply in time").into()
 }
 
-fn err_json(msg: &str) -> actix_web::Result<HttpResponse> {
-    ok_json(HashMap::from([("error", msg)]))
+fn unexpected_reply() -> HttpError {
+    ApiError::new(ErrorCode::Internal, "RollupDB answered with an unexpected reply").into()
 }
 
+#[utoipa::path(
+    post,
+    path = "/submit_transaction",
+    tag = "transactions",
+    request_body = RollupTransaction,
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Transaction accepted into the mempool", body = SubmitTransactionResponse),
+        (status = 400, description = "Transaction failed validation", body = TransactionRejection),
+        (status = 401, description = "Missing or invalid credential", body = ApiError),
+        (status = 429, description = "Fee payer has too many pending transactions", body = TransactionRejection),
+        (status = 503, description = "Mempool is full", body = TransactionRejection),
+        (status = 504, description = "RollupDB did not reply in time to the fee payer lookup", body = ApiError),
+    )
+)]
 pub async fn submit_transaction(
     body: web::Json<RollupTransaction>,
-    sequencer_sender: web::Data<CBSender<Transaction>>,
-) -> actix_web::Result<impl Responder> {
+    mempool: web::Data<Mempool>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+    l1_client: web::Data<RpcClient>,
+    config: web::Data<NodeConfig>,
+) -> Result<HttpResponse, HttpError> {
     log::info!("Submitted transaction");
     log::info!("Json({:?})", body);
 
-    match body.sol_transaction.clone() {
+    let decoded = match decode_request(&body) {
+        Ok(decoded) => decoded,
+        Err(rejection) => {
+            log::warn!("Rejected undecodable transaction: {}", rejection);
+            return Ok(rejection_response(rejection));
+        }
+    };
+
+    match decoded {
         Some(tx) => {
-            match sequencer_sender.send(tx) {
-                Ok(_) => Ok(HttpResponse::Ok().json(HashMap::from([("Transaction status", "Submitted")]))),
-                Err(e) => {
-                    log::error!("Failed to send transaction to sequencer: {}", e);
-                    Ok(HttpResponse::InternalServerError().json(HashMap::from([
-                        ("error", "Failed to submit transaction to sequencer")
-                    ])))
+            if let Err(rejection) = validate_submission(&tx, &rollupdb_sender, &mempool, &l1_client).await? {
+                log::warn!("Rejected transaction {:?}: {}", tx.signatures.first(), rejection);
+                return Ok(rejection_response(rejection));
+            }
+
+            // the first signature is the handle callers use to track the tx
+            let signature = tx.signatures[0].to_string();
+            let span = tracing::info_span!("transaction", signature = %signature);
+            match mempool.insert(tx, span) {
+                Ok(()) => {
+                    METRICS.transactions_submitted.inc();
+                    Ok(HttpResponse::Ok().json(SubmitTransactionResponse {
+                        status: "Submitted".to_string(),
+                        signature,
+                    }))
+                }
+                Err(rejection) => {
+                    log::warn!("Mempool refused transaction {}: {}", signature, rejection);
+                    Ok(rejection_response(rejection))
                 }
             }
         }
+        // the test path signs with the operator keypair, so it must never be reachable in production
+        None if !config.api.dev_mode => Err(ApiError::bad_request(
+            "A transaction is required (test transactions are only created in dev mode)",
+        )
+        .into()),
         None => {
             log::info!("Creating test transaction for testing");
             let sender_name = body.sender.as_deref().unwrap_or("unknown");
-            match create_test_transaction(sender_name).await {
+            match create_test_transaction(sender_name, &config).await {
                 Ok(dummy_tx) => {
-                    sequencer_sender.send(dummy_tx).unwrap();
-                    Ok(HttpResponse::Ok().json(HashMap::from([("Transaction status", "Submitted (test)")])))
+                    let signature = dummy_tx.signatures[0].to_string();
+                    let span = tracing::info_span!("transaction", signature = %signature, test = true);
+                    if let Err(rejection) = mempool.insert(dummy_tx, span) {
+                        return Ok(rejection_response(rejection));
+                    }
+                    METRICS.transactions_submitted.inc();
+                    Ok(HttpResponse::Ok().json(SubmitTransactionResponse {
+                        status: "Submitted (test)".to_string(),
+                        signature,
+                    }))
                 }
                 Err(e) => {
                     log::error!("Failed to create test transaction: {}", e);
-                    Ok(HttpResponse::BadRequest().json(HashMap::from([("error", format!("Failed to create transaction: {}", e))])))
+                    Err(ApiError::new(ErrorCode::Internal, format!("Failed to create transaction: {}", e)).into())
                 }
             }
         }
     }
 }
 
-async fn create_test_transaction(_sender: &str) -> Result<Transaction, Box<dyn std::error::Error>> {
-    let keypair_path = std::env::var("KEYPAIR2")
-        .unwrap_or_else(|_| format!("{}/.config/solana/id.json", std::env::var("HOME").unwrap()));
-    
-    let payer = solana_sdk::signer::keypair::read_keypair_file(&keypair_path)?;
+/// Upper bound on transactions accepted by one `/submit_transactions` request.
+const MAX_SUBMIT_BATCH: usize = 256;
 
-    let rpc_client = RpcClient::new_with_commitment(
-        "https://api.devnet.solana.com".to_string(),
-        CommitmentConfig::confirmed(),
-    );
+#[utoipa::path(
+    post,
+    path = "/submit_transactions",
+    tag = "transactions",
+    request_body = SubmitTransactionBatch,
+    responses(
+        (status = 200, description = "Every entry was handled; see each result", body = SubmitBatchResponse),
+        (status = 400, description = "Batch is too large", body = ApiError),
+        (status = 429, description = "Some entries hit the fee payer limit", body = SubmitBatchResponse),
+        (status = 503, description = "Some entries hit a full mempool", body = SubmitBatchResponse),
+        (status = 504, description = "RollupDB did not reply in time to a fee payer lookup", body = ApiError),
+    )
+)]
+pub async fn submit_transactions(
+    body: web::Json<SubmitTransactionBatch>,
+    mempool: web::Data<Mempool>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+    l1_client: web::Data<RpcClient>,
+) -> Result<HttpResponse, HttpError> {
+    log::info!("Submitted batch of {} encoded transactions", body.transactions.len());
+
+    if body.transactions.len() > MAX_SUBMIT_BATCH {
+        return Err(ApiError::bad_request(format!(
+            "Batch of {} exceeds the limit of {} transactions",
+            body.transactions.len(),
+            MAX_SUBMIT_BATCH
+        ))
+        .into());
+    }
+
+    let mut results = Vec::with_capacity(body.transactions.len());
+    for encoded in &body.transactions {
+        let outcome = match validation::decode_transaction(encoded, body.encoding) {
+            Ok(tx) => validate_submission(&tx, &rollupdb_sender, &mempool, &l1_client).await?.map(|()| tx),
+            Err(rejection) => Err(rejection),
+        };
+
+        let outcome = outcome.and_then(|tx| {
+            let signature = tx.signatures[0].to_string();
+            let span = tracing::info_span!("transaction", signature = %signature);
+            mempool.insert(tx, span).map(|()| signature)
+        });
+
+        match outcome {
+            Ok(signature) => {
+                METRICS.transactions_submitted.inc();
+                results.push(SubmitBatchEntry { signature: Some(signature), rejection: None });
+            }
+            Err(rejection) => {
+                log::warn!("Rejected transaction in batch: {}", rejection);
+                record_rejection(&rejection);
+                results.push(SubmitBatchEntry { signature: None, rejection: Some(rejection) });
+            }
+        }
+    }
+
+    let accepted = results.iter().filter(|entry| entry.rejection.is_none()).count();
+    let rejected_for = |code: RejectCode| {
+        results.iter().any(|entry| entry.rejection.as_ref().map(|r| r.code) == Some(code))
+    };
+    // partial acceptance is still reported entry by entry, but backpressure wins the status code
+    let mut response = if rejected_for(RejectCode::MempoolFull) {
+        let mut builder = HttpResponse::ServiceUnavailable();
+        builder.insert_header(("Retry-After", RETRY_AFTER_SECS));
+        builder
+    } else if rejected_for(RejectCode::FeePayerLimitExceeded) {
+        let mut builder = HttpResponse::TooManyRequests();
+        builder.insert_header(("Retry-After", RETRY_AFTER_SECS));
+        builder
+    } else {
+        HttpResponse::Ok()
+    };
+
+    Ok(response.json(SubmitBatchResponse {
+        accepted,
+        rejected: results.len() - accepted,
+        results,
+    }))
+}
+
+/// Seconds a client should back off when the mempool pushes back.
+const RETRY_AFTER_SECS: &str = "1";
+
+/// Maps a rejection to its HTTP status: backpressure is 503/429, everything else 400.
+fn rejection_response(rejection: TransactionRejection) -> HttpResponse {
+    record_rejection(&rejection);
+    match rejection.code {
+        RejectCode::MempoolFull => HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", RETRY_AFTER_SECS))
+            .json(rejection),
+        RejectCode::FeePayerLimitExceeded => HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", RETRY_AFTER_SECS))
+            .json(rejection),
+        _ => HttpResponse::BadRequest().json(rejection),
+    }
+}
+
+fn record_rejection(rejection: &TransactionRejection) {
+    METRICS.transactions_rejected.with_label_values(&[rejection.code.as_str()]).inc();
+}
+
+/// Stateless checks, then the fee payer's balance. RollupDB failing to answer is the
+/// node's problem rather than the transaction's, so it fails the request instead.
+async fn validate_submission(
+    tx: &Transaction,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    mempool: &Mempool,
+    l1_client: &RpcClient,
+) -> Result<Result<(), TransactionRejection>, HttpError> {
+    if let Err(rejection) = validation::check_transaction(tx) {
+        return Ok(Err(rejection));
+    }
+
+    // the mempool refuses a blockhash L1 has let expire; each one is only asked about once
+    let blockhash = tx.message.recent_blockhash;
+    if mempool.needs_blockhash_check(&blockhash) {
+        mempool::check_blockhash(mempool, l1_client, &blockhash).await;
+    }
+
+    // check_transaction guarantees at least one account key
+    let fee_payer = tx.message.account_keys[0];
+    let message = RollupDBMessage { get_account_snapshot: Some(vec![fee_payer]), ..Default::default() };
+    let fee_payer_account = match query_db(rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { accounts: Some(accounts), .. } => {
+            accounts.into_iter().find(|(pubkey, _)| *pubkey == fee_payer).map(|(_, account)| account)
+        }
+        _ => return Err(unexpected_reply()),
+    };
+    // an account L2 has not touched yet still has its L1 balance, which the sequencer loads
+    let fee_payer_account = match fee_payer_account {
+        Some(account) => Some(account),
+        None => l1_account(l1_client, &fee_payer).await?,
+    };
+
+    Ok(validation::check_fee_payer(tx, fee_payer_account.as_ref()))
+}
+
+/// `pubkey` as L1 has it, `None` if it does not exist there. L1 not answering fails the
+/// request, as RollupDB not answering does.
+async fn l1_account(l1_client: &RpcClient, pubkey: &Pubkey) -> Result<Option<AccountSharedData>, HttpError> {
+    match timeout(L1_ACCOUNT_TIMEOUT, l1_client.get_account_with_commitment(pubkey, l1_client.commitment())).await {
+        Ok(Ok(response)) => Ok(response.value.map(AccountSharedData::from)),
+        Ok(Err(e)) => {
+            log::warn!("Could not load account {} from L1: {}", pubkey, e);
+            Err(ApiError::unavailable("L1 is not reachable").into())
+        }
+        Err(_) => Err(ApiError::timeout(format!("L1 did not return account {} in time", pubkey)).into()),
+    }
+}
+
+async fn create_test_transaction(_sender: &str, config: &NodeConfig) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let payer = solana_sdk::signer::keypair::read_keypair_file(&config.settlement.payer_keypair_path)?;
+
+    let rpc_client = RpcClient::new_with_commitment(config.l1.rpc_url.clone(), config.commitment());
 
     let recent_blockhash = rpc_client.get_latest_blockhash().await?;
     
@@ -135,11 +353,23 @@ async fn create_test_transaction(_sender: &str) -> Result<Transaction, Box<dyn s
     Ok(transaction)
 }
 
+#[utoipa::path(
+    post,
+    path = "/get_transaction",
+    tag = "transactions",
+    request_body = GetTransaction,
+    responses(
+        (status = 200, description = "The transaction named by `get_tx`, or a page of transactions", body = GetTransactionResponse),
+        (status = 400, description = "`get_tx` is neither a hash nor a signature", body = ApiError),
+        (status = 404, description = "No such transaction", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+    )
+)]
 pub async fn get_transaction(
     body: web::Json<GetTransaction>,
     rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
-    frontend_receiver: web::Data<Receiver<FrontendMessage>>,
-) -> actix_web::Result<impl Responder> {
+) -> Result<HttpResponse, HttpError> {
     log::info!("Requested transaction: {:?}", body);
 
     // === CASE A: specific hash supplied => return single tx (raw) ===
@@ -149,54 +379,36 @@ pub async fn get_transaction(
         .map(str::trim)
         .filter(|s| !s.is_empty())
     {
-        let wanted_hash =
-            Hash::from_str(sig).map_err(|_| error::ErrorBadRequest("Invalid hash format"))?;
-
-        if let Err(e) = rollupdb_sender.send(RollupDBMessage {
-            lock_accounts: None,
-            add_processed_transaction: None,
-            frontend_get_tx: Some(wanted_hash),
-            add_settle_proof: None,
-            add_new_data: None,
-            store_batch_proof: None,
-            update_proof_status: None,
-            get_proof_by_batch_id: None,
-            get_unsettled_proofs: None,
-            retry_failed_proofs: None,
-            list_offset: None,
-            list_limit: None,
-            trigger_retry_cycle: None,
-        }) {
-            log::error!("Failed to request specific tx: {e}");
-            return err_json("Backend request failed");
-        }
+        let wanted_hash = match Hash::from_str(sig) {
+            Ok(hash) => hash,
+            // the signature returned by submit_transaction maps onto the same lookup hash
+            Err(_) => Signature::from_str(sig)
+                .map(|signature| solana_sdk::keccak::hashv(&[signature.to_string().as_bytes()]))
+                .map_err(|_| ApiError::bad_request("Invalid hash or signature format"))?,
+        };
 
-        if let Some(frontend_message) = recv_once(&frontend_receiver, Duration::from_secs(2)).await
-        {
-            if let Some(tx) = frontend_message.transaction {
+        let message = RollupDBMessage { frontend_get_tx: Some(wanted_hash), ..Default::default() };
+        return match query_db(&rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+            // a transaction dropped from its batch comes back with the reason in `error`
+            FrontendMessage { transaction: Some(tx), error, .. } => {
                 let sender = tx
                     .message
                     .account_keys
-                    .get(0)
+                    .first()
                     .map(|k| k.to_string())
                     .unwrap_or_else(|| "unknown".into());
 
-                return ok_json(RollupTransaction {
+                Ok(HttpResponse::Ok().json(GetTransactionResponse::Single(RollupTransaction {
                     sender: Some(sender),
                     sol_transaction: Some(tx), // raw tx
-                    error: None,
-                });
-            } else if let Some(err) = frontend_message.error {
-                return ok_json(RollupTransaction {
-                    sender: None,
-                    sol_transaction: None,
-                    error: Some(err),
-                });
+                    encoded_transaction: None,
+                    encoding: None,
+                    error,
+                })))
             }
-        }
-
-        // Fallback if nothing arrives in time
-        return ok_json(HashMap::from([("Transaction status", "requested")]));
+            FrontendMessage { error: Some(err), .. } => Err(ApiError::not_found(err).into()),
+            _ => Err(unexpected_reply()),
+        };
     }
 
     // === CASE B: no hash => return paginated list (raw) ===
@@ -204,42 +416,14 @@ pub async fn get_transaction(
     let page: u32 = body.page.unwrap_or(1).max(1);
     let offset: u64 = (page as u64 - 1) * per_page as u64;
 
-    if let Err(e) = rollupdb_sender.send(RollupDBMessage {
-        lock_accounts: None,
-        add_processed_transaction: None,
-        frontend_get_tx: None, // list mode
-        add_settle_proof: None,
-        add_new_data: None,
-        store_batch_proof: None,
-        update_proof_status: None,
-        get_proof_by_batch_id: None,
-        get_unsettled_proofs: None,
-        retry_failed_proofs: None,
-        list_offset: Some(offset),
-        list_limit: Some(per_page),
-        trigger_retry_cycle: None,
-    }) {
-        log::error!("Failed to request paged list from RollupDB: {e}");
-        return ok_json(RollupTransactionsList {
-            sender: None,
-            transactions: vec![],
-            page,
-            per_page,
-            total: None,
-            has_more: false,
-            error: Some("Backend request failed".into()),
-        });
-    }
-
-    if let Some(msg) = recv_once(&frontend_receiver, Duration::from_secs(2)).await {
-        if let Some(list) = msg.transactions {
+    let message = RollupDBMessage { list_offset: Some(offset), list_limit: Some(per_page), ..Default::default() };
+    match query_db(&rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { transactions: Some(list), total, has_more, .. } => {
             // Prefer DB-provided has_more; else infer from total if present.
-            let total = msg.total;
-            let has_more = msg
-                .has_more
-                .unwrap_or_else(|| total.map(|t| offset + (list.len() as u64) < t).unwrap_or(false));
+            let has_more =
+                has_more.unwrap_or_else(|| total.map(|t| offset + (list.len() as u64) < t).unwrap_or(false));
 
-            return ok_json(RollupTransactionsList {
+            Ok(HttpResponse::Ok().json(GetTransactionResponse::List(RollupTransactionsList {
                 sender: None,
                 transactions: list, // raw txs
                 page,
@@ -247,31 +431,236 @@ pub async fn get_transaction(
                 total,
                 has_more,
                 error: None,
-            });
-        } else if let Some(err) = msg.error {
-            return ok_json(RollupTransactionsList {
-                sender: None,
-                transactions: vec![],
-                page,
-                per_page,
-                total: None,
-                has_more: false,
-                error: Some(err),
-            });
+            })))
         }
+        FrontendMessage { error: Some(err), .. } => Err(ApiError::new(ErrorCode::Internal, err).into()),
+        _ => Err(unexpected_reply()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/batches",
+    tag = "batches",
+    params(ListBatchesParams),
+    responses(
+        (status = 200, description = "Batches, newest first", body = BatchList),
+        (status = 400, description = "Unknown proof status", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+    )
+)]
+pub async fn list_batches(
+    query: web::Query<ListBatchesParams>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    let status = query
+        .status
+        .as_deref()
+        .map(ProofStatus::from_str)
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 500);
+    let page = query.page.unwrap_or(1).max(1);
+    let offset = (page as u64 - 1) * per_page as u64;
+
+    let message = RollupDBMessage {
+        list_batches: Some(ListBatchesQuery { offset, limit: per_page, status }),
+        ..Default::default()
+    };
+    match query_db(&rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { batches: Some(batches), total, has_more, .. } => Ok(HttpResponse::Ok().json(BatchList {
+            batches,
+            page,
+            per_page,
+            total: total.unwrap_or_default(),
+            has_more: has_more.unwrap_or(false),
+        })),
+        _ => Err(unexpected_reply()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/batches/{batch_id}",
+    tag = "batches",
+    params(BatchIdPath),
+    responses(
+        (status = 200, description = "The batch with its proof and transactions", body = BatchDetails),
+        (status = 404, description = "No such batch", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+    )
+)]
+pub async fn get_batch(
+    batch_id: web::Path<String>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    let message = RollupDBMessage {
+        get_proof_by_batch_id: Some(batch_id.into_inner()),
+        ..Default::default()
+    };
+    batch_lookup(message, &rollupdb_sender).await
+}
+
+/// The batch a transaction was sequenced into, by transaction signature.
+#[utoipa::path(
+    get,
+    path = "/transactions/{signature}/batch",
+    tag = "batches",
+    params(SignaturePath),
+    responses(
+        (status = 200, description = "The batch containing the transaction", body = BatchDetails),
+        (status = 400, description = "Malformed signature", body = ApiError),
+        (status = 404, description = "Transaction is not in any batch", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+    )
+)]
+pub async fn get_batch_for_transaction(
+    signature: web::Path<String>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+) -> Result<HttpResponse, HttpError> {
+    let signature = Signature::from_str(&signature).map_err(|_| ApiError::bad_request("Invalid signature format"))?;
+    let message = RollupDBMessage {
+        get_batch_by_signature: Some(signature.to_string()),
+        ..Default::default()
+    };
+    batch_lookup(message, &rollupdb_sender).await
+}
+
+async fn batch_lookup(
+    message: RollupDBMessage,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+) -> Result<HttpResponse, HttpError> {
+    match query_db(rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { batch: Some(details), .. } => Ok(HttpResponse::Ok().json(details)),
+        FrontendMessage { error: Some(e), .. } => Err(ApiError::not_found(e).into()),
+        _ => Err(unexpected_reply()),
     }
-    ok_json(RollupTransactionsList {
-        sender: None,
-        transactions: vec![],
-        page,
-        per_page,
-        total: None,
-        has_more: false,
-        error: Some("Timeout waiting for backend".into()),
+}
+
+#[utoipa::path(
+    post,
+    path = "/simulate_transaction",
+    tag = "transactions",
+    request_body = RollupTransaction,
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Logs and account changes; `error` is set if the transaction failed", body = SimulationResult),
+        (status = 400, description = "Missing or undecodable transaction", body = ApiError),
+        (status = 401, description = "Missing or invalid credential", body = ApiError),
+        (status = 503, description = "RollupDB is not running", body = ApiError),
+        (status = 504, description = "RollupDB did not reply in time", body = ApiError),
+    )
+)]
+pub async fn simulate_transaction(
+    body: web::Json<RollupTransaction>,
+    rollupdb_sender: web::Data<CBSender<RollupDBMessage>>,
+    config: web::Data<NodeConfig>,
+) -> Result<HttpResponse, HttpError> {
+    let tx = match decode_request(&body) {
+        Ok(Some(tx)) => tx,
+        Ok(None) => return Err(ApiError::bad_request("Missing transaction").into()),
+        Err(rejection) => return Err(ApiError::bad_request(rejection.to_string()).into()),
+    };
+    log::info!("Simulating transaction: {:?}", tx.signatures.first());
+
+    let message = RollupDBMessage { get_account_snapshot: Some(tx.message.account_keys.clone()), ..Default::default() };
+    let snapshot = match query_db(&rollupdb_sender, message, DB_REPLY_TIMEOUT).await? {
+        FrontendMessage { accounts: Some(accounts), .. } => accounts,
+        _ => return Err(unexpected_reply()),
+    };
+
+    // SVM execution is blocking, keep it off the actix worker
+    let rpc_url = config.l1.rpc_url.clone();
+    let result = web::block(move || {
+        let rpc_client = solana_client::rpc_client::RpcClient::new(rpc_url);
+        simulate::simulate_transaction(&tx, snapshot, &rpc_client)
     })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/verify_proof",
+    tag = "batches",
+    request_body = VerifyProofRequest,
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Whether the proof verifies against the node's key for `slots`, as settlement checks before posting", body = ProofVerification),
+        (status = 401, description = "Missing or invalid credential", body = ApiError),
+        (status = 404, description = "No verifying key for this size class", body = ApiError),
+    )
+)]
+pub async fn verify_proof(
+    body: web::Json<VerifyProofRequest>,
+    config: web::Data<NodeConfig>,
+) -> Result<HttpResponse, HttpError> {
+    let request = body.into_inner();
+    let vk = settle::published_verifying_key(&config.prover, request.slots)
+        .map_err(|e| ApiError::not_found(e.to_string()))?;
+
+    // parsing the points and the pairing check are CPU-bound, keep them off the actix worker
+    let verified = web::block(move || settle::verify_groth16_offchain(&vk, &request.proof, &request.public_inputs)).await?;
+    Ok(HttpResponse::Ok().json(ProofVerification { valid: verified.is_ok(), error: verified.err() }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "node",
+    responses((status = 200, description = "Prometheus text exposition", content_type = "text/plain", body = String))
+)]
+pub async fn metrics(mempool: web::Data<Mempool>) -> impl Responder {
+    // mempool depth is cheap to read, so sample it at scrape time
+    METRICS.mempool_depth.set(mempool.len() as i64);
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(METRICS.encode())
+}
+
+/// Liveness: every component is still running. Failing this means the node should be restarted.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "node",
+    responses(
+        (status = 200, description = "Every component is running", body = HealthReport),
+        (status = 503, description = "A component stopped or failed", body = HealthReport),
+    )
+)]
+pub async fn health(health: web::Data<NodeHealth>) -> impl Responder {
+    let report = health.report();
+    if report.healthy {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+/// Readiness: healthy and the L1 RPC is reachable, so submitted batches can settle.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "node",
+    responses(
+        (status = 200, description = "Healthy and connected to L1", body = HealthReport),
+        (status = 503, description = "Not ready; see `reasons`", body = HealthReport),
+    )
+)]
+pub async fn ready(health: web::Data<NodeHealth>) -> impl Responder {
+    let report = health.report();
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
 }
 
 pub async fn test() -> impl Responder {
     log::info!("Test request");
     HttpResponse::Ok().json(HashMap::from([("test", "success")]))
-}
\ No newline at end of file
+}
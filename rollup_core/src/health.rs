@@ -0,0 +1,199 @@
+//! Liveness and readiness of the node's long-running components.
+//!
+//! Each component holds a `ComponentGuard` for as long as it runs. The guard
+//! marks the component stopped when dropped, or failed if its thread panicked,
+//! so `/health` notices a dead sequencer or settlement worker without polling.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+
+pub use rollup_api::{Component, ComponentHealth, ComponentStatus, HealthReport, L1Health};
+
+/// An L1 probe older than this no longer counts as connected.
+const L1_STATUS_MAX_AGE: Duration = Duration::from_secs(60);
+const L1_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type DepthProbe = Box<dyn Fn() -> usize + Send + Sync>;
+
+struct HealthState {
+    components: BTreeMap<Component, ComponentHealth>,
+    last_batch_at: Option<SystemTime>,
+    last_settlement_at: Option<SystemTime>,
+    l1: L1Health,
+    l1_checked_at: Option<SystemTime>,
+}
+
+pub struct NodeHealth {
+    state: Mutex<HealthState>,
+    channels: Vec<(&'static str, DepthProbe)>,
+}
+
+impl Default for NodeHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeHealth {
+    pub fn new() -> Self {
+        let components = Component::ALL
+            .iter()
+            .map(|component| (*component, ComponentHealth { status: ComponentStatus::Starting, last_activity: None }))
+            .collect();
+        Self {
+            state: Mutex::new(HealthState {
+                components,
+                last_batch_at: None,
+                last_settlement_at: None,
+                l1: L1Health::default(),
+                l1_checked_at: None,
+            }),
+            channels: Vec::new(),
+        }
+    }
+
+    /// Reports the depth of a queue on every health check.
+    pub fn with_channel(mut self, name: &'static str, depth: impl Fn() -> usize + Send + Sync + 'static) -> Self {
+        self.channels.push((name, Box::new(depth)));
+        self
+    }
+
+    /// Marks the component running until the returned guard is dropped.
+    pub fn start(self: &Arc<Self>, component: Component) -> ComponentGuard {
+        self.set_status(component, ComponentStatus::Running);
+        self.record_activity(component);
+        ComponentGuard { health: Arc::clone(self), component }
+    }
+
+    pub fn mark_failed(&self, component: Component, error: impl ToString) {
+        self.set_status(component, ComponentStatus::Failed(error.to_string()));
+    }
+
+    pub fn record_activity(&self, component: Component) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.components.get_mut(&component) {
+            entry.last_activity = Some(unix_secs(SystemTime::now()));
+        }
+    }
+
+    pub fn record_batch(&self) {
+        self.state.lock().unwrap().last_batch_at = Some(SystemTime::now());
+    }
+
+    pub fn record_settlement(&self) {
+        self.state.lock().unwrap().last_settlement_at = Some(SystemTime::now());
+    }
+
+    pub fn record_l1(&self, result: Result<u64, String>) {
+        let mut state = self.state.lock().unwrap();
+        let now = SystemTime::now();
+        state.l1_checked_at = Some(now);
+        state.l1.last_checked = Some(unix_secs(now));
+        match result {
+            Ok(slot) => {
+                state.l1.connected = true;
+                state.l1.last_slot = Some(slot);
+                state.l1.error = None;
+            }
+            Err(e) => {
+                state.l1.connected = false;
+                state.l1.error = Some(e);
+            }
+        }
+    }
+
+    fn set_status(&self, component: Component, status: ComponentStatus) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.components.get_mut(&component) {
+            // a recorded failure is more useful than the "stopped" that follows it
+            if !matches!(entry.status, ComponentStatus::Failed(_)) || status == ComponentStatus::Running {
+                entry.status = status;
+            }
+        }
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let channel_depths = self.channels.iter().map(|(name, depth)| (name.to_string(), depth())).collect();
+        let state = self.state.lock().unwrap();
+
+        let mut reasons = Vec::new();
+        for (component, health) in &state.components {
+            match &health.status {
+                ComponentStatus::Running => {}
+                ComponentStatus::Failed(e) => reasons.push(format!("{:?} failed: {}", component, e)),
+                other => reasons.push(format!("{:?} is {:?}", component, other).to_lowercase()),
+            }
+        }
+        let healthy = reasons.is_empty();
+
+        let mut l1 = state.l1.clone();
+        let l1_fresh = state
+            .l1_checked_at
+            .and_then(|checked| checked.elapsed().ok())
+            .map(|age| age <= L1_STATUS_MAX_AGE)
+            .unwrap_or(false);
+        l1.connected &= l1_fresh;
+        if !l1.connected {
+            reasons.push(match (&l1.error, state.l1_checked_at) {
+                (Some(e), _) => format!("L1 RPC unreachable: {}", e),
+                (None, None) => "L1 RPC not checked yet".to_string(),
+                (None, Some(_)) => "L1 RPC status is stale".to_string(),
+            });
+        }
+
+        HealthReport {
+            healthy,
+            ready: reasons.is_empty(),
+            reasons,
+            components: state.components.clone(),
+            channel_depths,
+            last_batch_at: state.last_batch_at.map(unix_secs),
+            last_settlement_at: state.last_settlement_at.map(unix_secs),
+            l1,
+        }
+    }
+}
+
+pub struct ComponentGuard {
+    health: Arc<NodeHealth>,
+    component: Component,
+}
+
+impl Drop for ComponentGuard {
+    fn drop(&mut self) {
+        let status = if std::thread::panicking() {
+            ComponentStatus::Failed("panicked".to_string())
+        } else {
+            ComponentStatus::Stopped
+        };
+        self.health.set_status(self.component, status);
+    }
+}
+
+/// Polls `getSlot` on the L1 RPC so readiness reflects connectivity.
+pub async fn run_l1_probe(health: Arc<NodeHealth>, rpc_url: String, interval: Duration) {
+    let rpc_client = RpcClient::new(rpc_url);
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        let result = match tokio::time::timeout(L1_PROBE_TIMEOUT, rpc_client.get_slot()).await {
+            Ok(Ok(slot)) => Ok(slot),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("getSlot timed out after {:?}", L1_PROBE_TIMEOUT)),
+        };
+        if let Err(e) = &result {
+            log::warn!("L1 RPC probe failed: {}", e);
+        }
+        health.record_l1(result);
+    }
+}
+
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -1,4 +1,18 @@
 //added this file for accessing contents in following files for testing
+mod auth;
+pub mod bench;
+pub mod config;
+mod error;
 pub mod frontend;
-mod rollupdb;
+mod health;
+mod loader;
+mod mempool;
+mod metrics;
+mod otlp;
+mod processor;
+mod prover;
+pub mod rollupdb;
 pub mod settle;
+mod simulate;
+pub mod validation;
+mod vk_registry;
@@ -1,41 +1,119 @@
-use std::thread;
+use std::{sync::Arc, thread};
 
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware::from_fn, web, App, HttpServer};
 use async_channel;
+use auth::ApiGuard;
+use config::NodeConfig;
 use crossbeam;
-use frontend::FrontendMessage;
+use health::{Component, NodeHealth};
+use mempool::Mempool;
+use prover_service::{JobQueue, SharedSecret};
 use rollupdb::{RollupDB, RollupDBMessage};
 use settle::SettlementJob;
-use solana_sdk::{account::AccountSharedData, pubkey::Pubkey, transaction::Transaction};
+use solana_sdk::{account::AccountSharedData, pubkey::Pubkey};
 use tokio::{time::{interval, Duration}, runtime::Builder, join, signal};
 use tokio_util::sync::CancellationToken;
+mod admin;
+mod aggregation;
+mod auth;
+mod config;
+mod error;
 mod frontend;
+mod health;
 mod processor;
+mod prover;
+mod proving;
 mod rollupdb;
 mod sequencer;
 mod settle;
 mod loader;
+mod mempool;
+mod metrics;
+mod openapi;
+mod otlp;
+mod simulate;
+mod telemetry;
+mod validation;
+mod vk_registry;
 
 // #[actix_web::main]
 fn main() {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("debug"));
+    dotenvy::dotenv().ok();
 
-    log::info!("starting HTTP server at http://localhost:8080");
+    // logging is configured from the node config, so config errors go straight to stderr
+    let config = match NodeConfig::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("Invalid node configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    let telemetry_guard = telemetry::init(&config.telemetry);
+    log::debug!("Node configuration: {:?}", config);
+
+    // remote prover workers lease batches from this queue over /prover
+    let prover_jobs = Arc::new(
+        JobQueue::new(Duration::from_secs(config.prover.remote.lease_secs), config.prover.remote.max_attempts)
+            .with_capacity(config.prover.remote.queue_capacity),
+    );
+    // built once and shared by every batch; in dev mode the first start also generates the keys
+    let prover = match prover::init(&config.prover, config.api.dev_mode, prover_jobs.clone()) {
+        Ok(prover) => prover,
+        Err(e) => {
+            log::error!("Failed to initialise the prover: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    // the verifier program rejects proofs under keys other than the registered ones
+    if let Err(e) = vk_registry::check(&config, prover.as_ref()) {
+        log::error!("Verifying keys do not match the verifier program's registry: {:#}", e);
+        std::process::exit(1);
+    }
+
+    log::info!("starting HTTP server at http://{}", config.server.bind_address);
 
     // Create a shared shutdown token for coordinated shutdown
     let shutdown_token = CancellationToken::new();
 
-    let (sequencer_sender, sequencer_receiver) = crossbeam::channel::unbounded::<Transaction>();
+    // validated transactions wait here, ordered by priority fee, until the sequencer takes a batch
+    let mempool = Arc::new(Mempool::new(config.mempool.clone()));
     let (rollupdb_sender, rollupdb_receiver) = crossbeam::channel::unbounded::<RollupDBMessage>();
     pub type PubkeyAccountSharedData = Option<Vec<(Pubkey, AccountSharedData)>>;
     let (account_sender, account_receiver) = async_channel::unbounded::<PubkeyAccountSharedData>();
     // let (sequencer_sender, sequencer_receiver) = async_channel::bounded::<Transaction>(100); // Channel for communication between frontend and sequencer
     // let (rollupdb_sender, rollupdb_receiver) = async_channel::unbounded::<RollupDBMessage>(); // Channel for communication between sequencer and accountsdb
-    let (frontend_sender, frontend_receiver) = async_channel::unbounded::<FrontendMessage>(); // Channel for communication between data availability layer and frontend
                                                                                               // std::thread::spawn(sequencer::run(sequencer_receiver, rollupdb_sender.clone()));
     let (settler_sender,settler_receiver) = crossbeam::channel::unbounded::<SettlementJob>();
+    // executed batches wait here for a prover worker
+    let (proving_queue, proving_receiver) = proving::queue(config.prover.queue_capacity);
+
+    let health = Arc::new(
+        NodeHealth::new()
+            .with_channel("mempool", { let mempool = mempool.clone(); move || mempool.len() })
+            .with_channel("rollupdb", { let sender = rollupdb_sender.clone(); move || sender.len() })
+            .with_channel("accounts", { let sender = account_sender.clone(); move || sender.len() })
+            .with_channel("proving", { let queue = proving_queue.clone(); move || queue.len() })
+            .with_channel("remote_proving", { let jobs = prover_jobs.clone(); move || jobs.len() })
+            .with_channel("settlement", { let sender = settler_sender.clone(); move || sender.len() }),
+    );
+
+    // prover workers run on their own threads; proved batches reach settlement in batch order
+    if let Err(e) = proving::start(
+        proving_receiver,
+        prover,
+        config.prover.workers,
+        &config.prover.aggregation,
+        rollupdb_sender.clone(),
+        settler_sender.clone(),
+        health.clone(),
+    ) {
+        log::error!("Failed to start the proving pipeline: {:#}", e);
+        std::process::exit(1);
+    }
 
     let db_sender_for_settlement = rollupdb_sender.clone(); 
+    let settlement_config = config.clone();
+    let settlement_health = health.clone();
     let shutdown_token_settlement = shutdown_token.clone();
     let settler_handle = thread::spawn(move || {
         log::info!("Settlement worker starting...");
@@ -46,9 +124,10 @@ fn main() {
 
         rt.block_on(async move {
             tokio::select! {
-                result = settle::run_settlement_worker(settler_receiver, db_sender_for_settlement) => {
+                result = settle::run_settlement_worker(settler_receiver, db_sender_for_settlement, settlement_config, settlement_health.clone()) => {
                     if let Err(e) = result {
                         log::error!("Settlement worker error: {}", e);
+                        settlement_health.mark_failed(Component::SettlementWorker, e);
                     }
                 }
                 _ = shutdown_token_settlement.cancelled() => {
@@ -66,10 +145,22 @@ fn main() {
     //     .build()
     //     .unwrap();
     let db_sender2 = rollupdb_sender.clone();
-    let fe_2 = frontend_sender.clone();
     let acc_sender = account_sender.clone();
     let settler_sender_for_db = settler_sender.clone();
+    let db_proving_queue = proving_queue.clone();
     let retry_db_sender = rollupdb_sender.clone();
+    let sequencer_mempool = mempool.clone();
+    let sequencer_config = config.clone();
+    let retry_interval_duration = config.retry_interval();
+    let processing_health = health.clone();
+    let l1_rpc_url = config.l1.rpc_url.clone();
+    let l1_probe_interval = config.l1_probe_interval();
+    let blockhash_mempool = mempool.clone();
+    let blockhash_rpc_client = solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
+        config.l1.rpc_url.clone(),
+        config.commitment(),
+    );
+    let blockhash_check_interval = config.mempool.blockhash_check_interval();
     let shutdown_token_processing = shutdown_token.clone();
     let asdserver_thread = thread::spawn(move || {
         log::info!("thread starting...");
@@ -82,12 +173,14 @@ fn main() {
         rt.block_on(async {
             let seq_handle = tokio::spawn({
                 let shutdown_token_seq = shutdown_token_processing.clone();
+                let health = processing_health.clone();
                 async move {
                 log::info!("Sequencer starting...");
                 tokio::select! {
-                    result = sequencer::run(sequencer_receiver, db_sender2, account_receiver, settler_sender) => {
+                    result = sequencer::run(sequencer_mempool, db_sender2, account_receiver, proving_queue, sequencer_config, health.clone()) => {
                         if let Err(e) = result {
                             log::error!("Sequencer error: {}", e);
+                            health.mark_failed(Component::Sequencer, e);
                         }
                     }
                     _ = shutdown_token_seq.cancelled() => {
@@ -100,14 +193,16 @@ fn main() {
 
             let db_handle = tokio::spawn({
                 let shutdown_token_db = shutdown_token_processing.clone();
+                let health = processing_health.clone();
                 async move {
                     log::info!("RollupDB starting...");
                     tokio::select! {
                         _ = RollupDB::run(
                             rollupdb_receiver, 
-                            fe_2,
                             acc_sender,
-                            settler_sender_for_db
+                            settler_sender_for_db,
+                            db_proving_queue,
+                            health
                         ) => {
                             log::info!("RollupDB completed naturally");
                         }
@@ -121,29 +216,21 @@ fn main() {
         // Automatic retry every 5 minutes 
         let retry_handle = tokio::spawn({
             let shutdown_token_retry = shutdown_token_processing.clone();
+            let health = processing_health.clone();
             async move {
-                let mut retry_interval = interval(Duration::from_secs(300)); 
-                log::info!("retry timer starting (5min intervals)");
+                let _alive = health.start(Component::RetryTimer);
+                let mut retry_interval = interval(retry_interval_duration); 
+                log::info!("retry timer starting ({:?} intervals)", retry_interval_duration);
                 
                 loop {
                     tokio::select! {
                         _ = retry_interval.tick() => {
                             log::debug!("triggering periodic retry check...");
+                            health.record_activity(Component::RetryTimer);
                             
                             let retry_message = RollupDBMessage {
-                                lock_accounts: None,
-                                add_processed_transaction: None,
-                                frontend_get_tx: None,
-                                list_offset: None,
-                                list_limit: None,
-                                add_settle_proof: None,
-                                add_new_data: None,
-                                store_batch_proof: None,
-                                update_proof_status: None,
-                                get_proof_by_batch_id: None,
-                                get_unsettled_proofs: None,
-                                retry_failed_proofs: None,
                                 trigger_retry_cycle: Some(true),
+                                ..Default::default()
                             };
                             
                             if retry_db_sender.send(retry_message).is_err() {
@@ -164,6 +251,29 @@ fn main() {
             }
         });
 
+            // readiness depends on the L1 RPC answering; the probe only ends at shutdown
+            let l1_probe_handle = tokio::spawn({
+                let shutdown_token_probe = shutdown_token_processing.clone();
+                let probe_health = processing_health.clone();
+                async move {
+                    tokio::select! {
+                        _ = health::run_l1_probe(probe_health, l1_rpc_url, l1_probe_interval) => {}
+                        _ = shutdown_token_probe.cancelled() => {}
+                    }
+                }
+            });
+
+            // pending transactions whose blockhash expired on L1 are dropped before sequencing
+            let blockhash_handle = tokio::spawn({
+                let shutdown_token_blockhash = shutdown_token_processing.clone();
+                async move {
+                    tokio::select! {
+                        _ = mempool::run_blockhash_monitor(blockhash_mempool, blockhash_rpc_client, blockhash_check_interval) => {}
+                        _ = shutdown_token_blockhash.cancelled() => {}
+                    }
+                }
+            });
+
             // Wait for all processing tasks to complete or shutdown signal
             tokio::select! {
                 _ = shutdown_token_processing.cancelled() => {
@@ -171,7 +281,7 @@ fn main() {
                 }
                 // If no shutdown signal, wait for all tasks to complete naturally
                 else => {
-                    let _ = join!(seq_handle, db_handle, retry_handle);
+                    let _ = join!(seq_handle, db_handle, retry_handle, l1_probe_handle, blockhash_handle);
                     log::info!("All processing tasks completed");
                 }
             }
@@ -191,6 +301,15 @@ fn main() {
 
     // Spawn the Actix Web server in a separate thread
     let shutdown_token_server = shutdown_token.clone();
+    let bind_address = config.server.bind_address.clone();
+    let max_body_bytes = config.api.max_body_bytes;
+    let api_guard = Arc::new(ApiGuard::new(config.api.clone()));
+    if !config.api.auth_enabled() {
+        log::warn!("No API keys or JWT secret configured, protected routes are open to everyone");
+    }
+    if config.api.dev_mode {
+        log::warn!("Dev mode is on: submissions without a transaction are signed with the operator keypair");
+    }
     let server_thread = thread::spawn(move || {
         // Create a separate Tokio runtime for Actix Web
         let rt2 = Builder::new_multi_thread()
@@ -201,22 +320,65 @@ fn main() {
 
         // Create frontend server
         rt2.block_on(async {
+            // shared by the handlers that ask L1 about recent blockhashes
+            let l1_client = web::Data::new(solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
+                config.l1.rpc_url.clone(),
+                config.commitment(),
+            ));
+            // without a secret every /prover request is refused
+            let prover_secret = config.prover.remote.shared_secret.clone().map(|secret| web::Data::new(SharedSecret::new(secret)));
             let server = HttpServer::new(move || {
                 App::new()
-                    .app_data(web::Data::new(sequencer_sender.clone()))
+                    .wrap(from_fn(auth::guard))
+                    .app_data(web::Data::from(api_guard.clone()))
+                    .app_data(web::JsonConfig::default().limit(max_body_bytes).error_handler(error::json_error))
+                    .app_data(web::QueryConfig::default().error_handler(error::query_error))
+                    .app_data(web::PathConfig::default().error_handler(error::path_error))
+                    .app_data(web::PayloadConfig::new(max_body_bytes))
+                    .app_data(web::Data::from(mempool.clone()))
+                    .app_data(web::Data::from(config.clone()))
+                    .app_data(web::Data::from(health.clone()))
+                    .app_data(web::Data::from(prover_jobs.clone()))
                     .app_data(web::Data::new(rollupdb_sender.clone()))
-                    .app_data(web::Data::new(frontend_sender.clone()))
-                    .app_data(web::Data::new(frontend_receiver.clone()))
+                    .app_data(l1_client.clone())
                     .route("/", web::get().to(frontend::test))
+                    .route("/health", web::get().to(frontend::health))
+                    .route("/ready", web::get().to(frontend::ready))
+                    .route("/metrics", web::get().to(frontend::metrics))
+                    .route("/openapi.json", web::get().to(openapi::openapi_json))
                     .route("/get_transaction", web::post().to(frontend::get_transaction))
                     .route("/submit_transaction", web::post().to(frontend::submit_transaction))
+                    .route("/submit_transactions", web::post().to(frontend::submit_transactions))
+                    .route("/simulate_transaction", web::post().to(frontend::simulate_transaction))
+                    .route("/verify_proof", web::post().to(frontend::verify_proof))
+                    .route("/batches", web::get().to(frontend::list_batches))
+                    .route("/batches/{batch_id}", web::get().to(frontend::get_batch))
+                    .route("/transactions/{signature}/batch", web::get().to(frontend::get_batch_for_transaction))
+                    .service(web::scope("/prover").configure(|cfg| {
+                        if let Some(secret) = &prover_secret {
+                            cfg.app_data(secret.clone());
+                        }
+                        prover_service::http::configure(cfg);
+                    }))
+                    .service(
+                        web::scope("/admin")
+                            .route("/sequencer", web::get().to(admin::get_sequencer))
+                            .route("/sequencer/pause", web::post().to(admin::pause_sequencer))
+                            .route("/sequencer/resume", web::post().to(admin::resume_sequencer))
+                            .route("/sequencer/seal", web::post().to(admin::seal_batch))
+                            .route("/retry", web::post().to(admin::retry_failed))
+                            .route("/batches/{batch_id}/requeue", web::post().to(admin::requeue_batch))
+                            .route("/batches/{batch_id}/abandon", web::post().to(admin::abandon_batch))
+                            .route("/proofs/unsettled", web::get().to(admin::unsettled_proofs))
+                            .route("/circuit-breaker/reset", web::post().to(admin::reset_circuit_breaker)),
+                    )
                 //  .service(
                 //      web::resource("/submit_transaction")
                 //          .route(web::post().to(frontend::submit_transaction)),
                 // )
             })
             .worker_max_blocking_threads(2)
-            .bind("127.0.0.1:8080")
+            .bind(&bind_address)
             .unwrap()
             .run();
             
@@ -280,5 +442,7 @@ fn main() {
     // rt.shutdown_timeout(std::time::Duration::from_secs(20));
 
     log::info!("All threads stopped. Exiting.");
+    // flush buffered spans before the process goes away
+    drop(telemetry_guard);
     // Ok(())
 }
\ No newline at end of file
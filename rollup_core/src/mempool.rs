@@ -0,0 +1,559 @@
+//! Bounded, priority-ordered pool of validated transactions waiting for the sequencer.
+//!
+//! Transactions are ordered by prioritization fee (highest first, FIFO on ties).
+//! The pool enforces a global capacity, a per-fee-payer limit, and drops
+//! transactions whose recent blockhash L1 no longer accepts. Each blockhash is
+//! checked with L1 once at submission and again while transactions using it wait.
+//! Signatures already handed to the sequencer are remembered for as long as their
+//! blockhash is, so a replayed transaction is refused instead of executed twice.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tracing::Span;
+use solana_sdk::{
+    compute_budget, hash::Hash, pubkey::Pubkey, signature::Signature, transaction::Transaction,
+};
+
+use crate::validation::{RejectCode, TransactionRejection};
+
+// compute budget defaults, mirrored from the runtime
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+const BLOCKHASH_RECORD_RETENTION: u32 = 10;
+const BLOCKHASH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MempoolConfig {
+    pub capacity: usize,
+    pub max_per_fee_payer: usize,
+    /// Upper bound on how long a recent blockhash stays usable after we first see it
+    /// (~150 slots on L1). Only binding while L1 can't be asked.
+    pub max_blockhash_age_secs: u64,
+    /// How often L1 is asked again about the blockhashes of pending transactions.
+    pub blockhash_check_interval_secs: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            max_per_fee_payer: 64,
+            max_blockhash_age_secs: 60,
+            blockhash_check_interval_secs: 5,
+        }
+    }
+}
+
+impl MempoolConfig {
+    fn max_blockhash_age(&self) -> Duration {
+        Duration::from_secs(self.max_blockhash_age_secs)
+    }
+
+    pub fn blockhash_check_interval(&self) -> Duration {
+        Duration::from_secs(self.blockhash_check_interval_secs)
+    }
+}
+
+/// What the pool knows about a recent blockhash.
+struct BlockhashRecord {
+    first_seen: Instant,
+    /// L1 answered that it no longer accepts the blockhash.
+    expired_on_l1: bool,
+    /// L1 has been asked about it at least once.
+    checked: bool,
+}
+
+impl BlockhashRecord {
+    fn new(now: Instant) -> Self {
+        Self { first_seen: now, expired_on_l1: false, checked: false }
+    }
+}
+
+struct PendingTransaction {
+    priority_fee: u64,
+    arrival: u64,
+    fee_payer: Pubkey,
+    signature: Signature,
+    transaction: Transaction,
+    /// Submission span, kept open while queued so the batch can link back to it.
+    span: Span,
+}
+
+impl PartialEq for PendingTransaction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingTransaction {}
+
+impl PartialOrd for PendingTransaction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingTransaction {
+    // max-heap: higher fee first, then earlier arrival
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority_fee
+            .cmp(&other.priority_fee)
+            .then_with(|| other.arrival.cmp(&self.arrival))
+    }
+}
+
+#[derive(Default)]
+struct MempoolInner {
+    queue: BinaryHeap<PendingTransaction>,
+    signatures: HashSet<Signature>,
+    per_fee_payer: HashMap<Pubkey, usize>,
+    blockhashes: HashMap<Hash, BlockhashRecord>,
+    /// Signatures handed out in a batch, by blockhash; dropped with the blockhash record.
+    sequenced: HashMap<Hash, HashSet<Signature>>,
+    next_arrival: u64,
+    /// Set by the admin API: transactions are still accepted but no batch is handed out.
+    paused: bool,
+    /// Hand out whatever is pending as the next batch, even if it is short of the batch size.
+    seal_requested: bool,
+}
+
+impl MempoolInner {
+    fn is_expired(&self, blockhash: &Hash, max_age: Duration, now: Instant) -> bool {
+        self.blockhashes
+            .get(blockhash)
+            .map(|record| record.expired_on_l1 || now.duration_since(record.first_seen) > max_age)
+            .unwrap_or(false)
+    }
+
+    fn forget(&mut self, pending: &PendingTransaction) {
+        self.signatures.remove(&pending.signature);
+        if let Some(count) = self.per_fee_payer.get_mut(&pending.fee_payer) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_fee_payer.remove(&pending.fee_payer);
+            }
+        }
+    }
+
+    fn purge_expired(&mut self, max_age: Duration) -> usize {
+        let now = Instant::now();
+        let (expired, live): (Vec<_>, Vec<_>) = std::mem::take(&mut self.queue)
+            .into_iter()
+            .partition(|pending| self.is_expired(&pending.transaction.message.recent_blockhash, max_age, now));
+        for pending in &expired {
+            self.forget(pending);
+        }
+        self.queue = live.into();
+        // keep records well past expiry so an old blockhash can't come back as "new"
+        self.blockhashes
+            .retain(|_, record| now.duration_since(record.first_seen) <= max_age * BLOCKHASH_RECORD_RETENTION);
+        let blockhashes = &self.blockhashes;
+        self.sequenced.retain(|blockhash, _| blockhashes.contains_key(blockhash));
+        expired.len()
+    }
+}
+
+pub struct Mempool {
+    config: MempoolConfig,
+    inner: Mutex<MempoolInner>,
+    available: Condvar,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(MempoolInner::default()),
+            available: Condvar::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.config.capacity
+    }
+
+    /// Adds a validated transaction. Fails with a rejection the HTTP layer maps to 429/503.
+    pub fn insert(&self, transaction: Transaction, span: Span) -> Result<(), TransactionRejection> {
+        let signature = transaction.signatures[0];
+        let fee_payer = transaction.message.account_keys[0];
+        let blockhash = transaction.message.recent_blockhash;
+        let now = Instant::now();
+
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.signatures.contains(&signature) {
+            return Err(TransactionRejection::new(
+                RejectCode::DuplicateTransaction,
+                format!("Transaction {} is already pending", signature),
+            ));
+        }
+        if inner.sequenced.get(&blockhash).is_some_and(|sequenced| sequenced.contains(&signature)) {
+            return Err(TransactionRejection::new(
+                RejectCode::DuplicateTransaction,
+                format!("Transaction {} was already sequenced", signature),
+            ));
+        }
+
+        if inner.is_expired(&blockhash, self.config.max_blockhash_age(), now) {
+            return Err(TransactionRejection::new(
+                RejectCode::BlockhashExpired,
+                format!("Blockhash {} is older than {:?}", blockhash, self.config.max_blockhash_age()),
+            ));
+        }
+
+        if inner.per_fee_payer.get(&fee_payer).copied().unwrap_or(0) >= self.config.max_per_fee_payer {
+            return Err(TransactionRejection::new(
+                RejectCode::FeePayerLimitExceeded,
+                format!("Fee payer {} already has {} pending transactions", fee_payer, self.config.max_per_fee_payer),
+            ));
+        }
+
+        if inner.queue.len() >= self.config.capacity && inner.purge_expired(self.config.max_blockhash_age()) == 0 {
+            return Err(TransactionRejection::new(
+                RejectCode::MempoolFull,
+                format!("Mempool is at capacity ({})", self.config.capacity),
+            ));
+        }
+
+        inner.blockhashes.entry(blockhash).or_insert_with(|| BlockhashRecord::new(now));
+        *inner.per_fee_payer.entry(fee_payer).or_insert(0) += 1;
+        inner.signatures.insert(signature);
+        let arrival = inner.next_arrival;
+        inner.next_arrival += 1;
+        inner.queue.push(PendingTransaction {
+            priority_fee: prioritization_fee(&transaction),
+            arrival,
+            fee_payer,
+            signature,
+            transaction,
+            span,
+        });
+        drop(inner);
+
+        self.available.notify_one();
+        Ok(())
+    }
+
+    /// Whether L1 has yet to be asked about `blockhash`.
+    pub fn needs_blockhash_check(&self, blockhash: &Hash) -> bool {
+        self.inner.lock().unwrap().blockhashes.get(blockhash).is_none_or(|record| !record.checked)
+    }
+
+    /// Records L1's answer on `blockhash`. Once expired it stays expired; pending
+    /// transactions using it are dropped before the next batch is handed out.
+    pub fn record_blockhash(&self, blockhash: Hash, valid: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let record = inner.blockhashes.entry(blockhash).or_insert_with(|| BlockhashRecord::new(Instant::now()));
+        record.checked = true;
+        record.expired_on_l1 |= !valid;
+    }
+
+    /// Distinct blockhashes of pending transactions that have not expired yet.
+    pub fn pending_blockhashes(&self) -> Vec<Hash> {
+        let inner = self.inner.lock().unwrap();
+        let blockhashes: HashSet<Hash> = inner.queue.iter().map(|pending| pending.transaction.message.recent_blockhash).collect();
+        blockhashes
+            .into_iter()
+            .filter(|blockhash| !inner.blockhashes.get(blockhash).is_some_and(|record| record.expired_on_l1))
+            .collect()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.lock().unwrap().paused
+    }
+
+    /// Stops handing out batches until `resume`. Returns whether it was already paused.
+    pub fn pause(&self) -> bool {
+        std::mem::replace(&mut self.inner.lock().unwrap().paused, true)
+    }
+
+    /// Returns whether it was paused.
+    pub fn resume(&self) -> bool {
+        let was_paused = std::mem::replace(&mut self.inner.lock().unwrap().paused, false);
+        self.available.notify_all();
+        was_paused
+    }
+
+    /// Makes the next batch whatever is pending now, paused or not. Returns the
+    /// number of pending transactions; nothing is requested when there are none.
+    pub fn request_seal(&self) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let pending = inner.queue.len();
+        if pending > 0 {
+            inner.seal_requested = true;
+            drop(inner);
+            self.available.notify_all();
+        }
+        pending
+    }
+
+    /// Blocks until `batch_size` live transactions are pending (or a seal was requested),
+    /// then pops them in priority order with their submission spans. Returns `None` if the
+    /// timeout elapses first, which is always the case while paused.
+    pub fn wait_for_batch(&self, batch_size: usize, timeout: Duration) -> Option<Vec<(Transaction, Span)>> {
+        let deadline = Instant::now() + timeout;
+        let mut inner = self.inner.lock().unwrap();
+
+        loop {
+            let wanted = match (inner.seal_requested, inner.paused) {
+                (true, _) => 1,
+                (false, true) => usize::MAX,
+                (false, false) => batch_size,
+            };
+            if inner.queue.len() >= wanted {
+                inner.purge_expired(self.config.max_blockhash_age());
+                if inner.queue.len() >= wanted {
+                    break;
+                }
+                // everything pending expired, there is nothing left to seal
+                inner.seal_requested = false;
+            }
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            inner = self.available.wait_timeout(inner, remaining).unwrap().0;
+        }
+        inner.seal_requested = false;
+
+        let mut batch = Vec::with_capacity(batch_size);
+        while batch.len() < batch_size {
+            let Some(pending) = inner.queue.pop() else { break };
+            inner.forget(&pending);
+            inner.sequenced.entry(pending.transaction.message.recent_blockhash).or_default().insert(pending.signature);
+            batch.push((pending.transaction, pending.span));
+        }
+        Some(batch)
+    }
+}
+
+/// Asks L1 whether `blockhash` is still valid and records the answer. An unreachable
+/// L1 leaves the blockhash bounded only by `max_blockhash_age_secs`.
+pub async fn check_blockhash(mempool: &Mempool, rpc_client: &RpcClient, blockhash: &Hash) {
+    match tokio::time::timeout(BLOCKHASH_CHECK_TIMEOUT, rpc_client.is_blockhash_valid(blockhash, rpc_client.commitment())).await {
+        Ok(Ok(valid)) => {
+            if !valid {
+                log::info!("Blockhash {} expired on L1", blockhash);
+            }
+            mempool.record_blockhash(*blockhash, valid);
+        }
+        Ok(Err(e)) => log::warn!("Could not check blockhash {} with L1: {}", blockhash, e),
+        Err(_) => log::warn!("Checking blockhash {} with L1 timed out after {:?}", blockhash, BLOCKHASH_CHECK_TIMEOUT),
+    }
+}
+
+/// Re-checks the blockhashes of pending transactions with L1 every `interval`, so
+/// transactions whose blockhash expired while they waited are not sequenced.
+pub async fn run_blockhash_monitor(mempool: Arc<Mempool>, rpc_client: RpcClient, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        for blockhash in mempool.pending_blockhashes() {
+            check_blockhash(&mempool, &rpc_client, &blockhash).await;
+        }
+    }
+}
+
+/// Prioritization fee in lamports: compute unit price * compute unit limit.
+fn prioritization_fee(transaction: &Transaction) -> u64 {
+    let message = &transaction.message;
+    let mut unit_price: Option<u64> = None;
+    let mut unit_limit: Option<u64> = None;
+    let mut other_instructions = 0u64;
+
+    for instruction in &message.instructions {
+        let is_compute_budget = message
+            .account_keys
+            .get(instruction.program_id_index as usize)
+            .map(|program_id| *program_id == compute_budget::id())
+            .unwrap_or(false);
+        if !is_compute_budget {
+            other_instructions += 1;
+            continue;
+        }
+
+        // ComputeBudgetInstruction discriminants: 2 = SetComputeUnitLimit(u32), 3 = SetComputeUnitPrice(u64)
+        match instruction.data.split_first() {
+            Some((&2, rest)) if rest.len() >= 4 => {
+                unit_limit = Some(u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64);
+            }
+            Some((&3, rest)) if rest.len() >= 8 => {
+                unit_price = Some(u64::from_le_bytes(rest[..8].try_into().unwrap()));
+            }
+            _ => {}
+        }
+    }
+
+    let unit_limit = unit_limit
+        .unwrap_or(other_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+    let micro_lamports = unit_price.unwrap_or(0) as u128 * unit_limit as u128;
+    micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, signature::Keypair, signer::Signer};
+    use solana_system_interface::instruction as system_instruction;
+
+    use super::*;
+
+    const NO_WAIT: Duration = Duration::from_millis(10);
+
+    fn transfer(payer: &Keypair, blockhash: Hash, unit_price: Option<u64>) -> Transaction {
+        let mut instructions: Vec<_> = unit_price.map(ComputeBudgetInstruction::set_compute_unit_price).into_iter().collect();
+        instructions.push(system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1));
+        Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[payer], blockhash)
+    }
+
+    fn insert(mempool: &Mempool, tx: &Transaction) -> Result<(), RejectCode> {
+        mempool.insert(tx.clone(), Span::none()).map_err(|rejection| rejection.code)
+    }
+
+    fn signatures(batch: Vec<(Transaction, Span)>) -> Vec<Signature> {
+        batch.into_iter().map(|(tx, _)| tx.signatures[0]).collect()
+    }
+
+    #[test]
+    fn orders_by_fee_then_arrival() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let blockhash = Hash::new_unique();
+        let first_free = transfer(&Keypair::new(), blockhash, None);
+        let high = transfer(&Keypair::new(), blockhash, Some(1_000));
+        let second_free = transfer(&Keypair::new(), blockhash, None);
+        let low = transfer(&Keypair::new(), blockhash, Some(10));
+        for tx in [&first_free, &high, &second_free, &low] {
+            insert(&mempool, tx).unwrap();
+        }
+
+        let batch = mempool.wait_for_batch(4, NO_WAIT).unwrap();
+        let expected: Vec<_> = [&high, &low, &first_free, &second_free].iter().map(|tx| tx.signatures[0]).collect();
+        assert_eq!(signatures(batch), expected);
+    }
+
+    #[test]
+    fn rejects_a_transaction_that_is_already_pending() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let tx = transfer(&Keypair::new(), Hash::new_unique(), None);
+        insert(&mempool, &tx).unwrap();
+
+        assert_eq!(insert(&mempool, &tx), Err(RejectCode::DuplicateTransaction));
+        assert_eq!(mempool.len(), 1);
+    }
+
+    #[test]
+    fn rejects_a_transaction_that_was_already_sequenced() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let tx = transfer(&Keypair::new(), Hash::new_unique(), None);
+        insert(&mempool, &tx).unwrap();
+        mempool.wait_for_batch(1, NO_WAIT).unwrap();
+
+        assert_eq!(insert(&mempool, &tx), Err(RejectCode::DuplicateTransaction));
+        assert!(mempool.is_empty());
+    }
+
+    #[test]
+    fn rejects_transactions_at_capacity() {
+        let mempool = Mempool::new(MempoolConfig { capacity: 2, ..Default::default() });
+        let blockhash = Hash::new_unique();
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+
+        assert_eq!(insert(&mempool, &transfer(&Keypair::new(), blockhash, None)), Err(RejectCode::MempoolFull));
+
+        // expired transactions make room when the pool is full
+        mempool.record_blockhash(blockhash, false);
+        insert(&mempool, &transfer(&Keypair::new(), Hash::new_unique(), None)).unwrap();
+        assert_eq!(mempool.len(), 1);
+    }
+
+    #[test]
+    fn limits_pending_transactions_per_fee_payer() {
+        let mempool = Mempool::new(MempoolConfig { max_per_fee_payer: 2, ..Default::default() });
+        let payer = Keypair::new();
+        let blockhash = Hash::new_unique();
+        insert(&mempool, &transfer(&payer, blockhash, None)).unwrap();
+        insert(&mempool, &transfer(&payer, blockhash, None)).unwrap();
+
+        assert_eq!(insert(&mempool, &transfer(&payer, blockhash, None)), Err(RejectCode::FeePayerLimitExceeded));
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+
+        // sequenced transactions no longer count against the payer
+        mempool.wait_for_batch(3, NO_WAIT).unwrap();
+        insert(&mempool, &transfer(&payer, blockhash, None)).unwrap();
+    }
+
+    #[test]
+    fn refuses_blockhashes_expired_on_l1() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let expired = Hash::new_unique();
+        assert!(mempool.needs_blockhash_check(&expired));
+        mempool.record_blockhash(expired, false);
+        assert!(!mempool.needs_blockhash_check(&expired));
+
+        assert_eq!(insert(&mempool, &transfer(&Keypair::new(), expired, None)), Err(RejectCode::BlockhashExpired));
+
+        let valid = Hash::new_unique();
+        mempool.record_blockhash(valid, true);
+        insert(&mempool, &transfer(&Keypair::new(), valid, None)).unwrap();
+        assert_eq!(mempool.pending_blockhashes(), vec![valid]);
+    }
+
+    #[test]
+    fn drops_pending_transactions_whose_blockhash_expires() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let blockhash = Hash::new_unique();
+        mempool.record_blockhash(blockhash, true);
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+
+        // a later check finds it expired while the transaction waits
+        mempool.record_blockhash(blockhash, false);
+        assert!(mempool.pending_blockhashes().is_empty());
+        assert!(mempool.wait_for_batch(1, NO_WAIT).is_none());
+        assert!(mempool.is_empty());
+    }
+
+    #[test]
+    fn bounds_unchecked_blockhashes_by_age_since_first_seen() {
+        let mempool = Mempool::new(MempoolConfig { max_blockhash_age_secs: 0, ..Default::default() });
+        let blockhash = Hash::new_unique();
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(insert(&mempool, &transfer(&Keypair::new(), blockhash, None)), Err(RejectCode::BlockhashExpired));
+    }
+
+    #[test]
+    fn seal_hands_out_a_short_batch_even_while_paused() {
+        let mempool = Mempool::new(MempoolConfig::default());
+        let blockhash = Hash::new_unique();
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+        assert!(mempool.wait_for_batch(10, NO_WAIT).is_none());
+
+        assert!(!mempool.pause());
+        assert_eq!(mempool.request_seal(), 2);
+        assert_eq!(mempool.wait_for_batch(10, NO_WAIT).unwrap().len(), 2);
+        // nothing pending, nothing to seal
+        assert_eq!(mempool.request_seal(), 0);
+
+        insert(&mempool, &transfer(&Keypair::new(), blockhash, None)).unwrap();
+        assert!(mempool.wait_for_batch(1, NO_WAIT).is_none());
+        assert!(mempool.resume());
+        assert_eq!(mempool.wait_for_batch(1, NO_WAIT).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+//! Prometheus metrics for the whole pipeline, served as text on `/metrics`.
+//!
+//! Components record into the process-wide `METRICS`; gauges that mirror state
+//! (mempool depth, RollupDB counts) are set by whoever owns that state.
+
+use std::sync::LazyLock;
+
+use prometheus::{
+    Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+// proving and settlement take seconds to minutes, the default buckets top out at 10s
+const SLOW_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+pub struct Metrics {
+    registry: Registry,
+
+    pub transactions_submitted: IntCounter,
+    pub transactions_rejected: IntCounterVec,
+    pub transactions_dropped: IntCounter,
+    pub mempool_depth: IntGauge,
+
+    pub batch_size: Histogram,
+    pub batches_failed: IntCounter,
+    pub svm_execution_seconds: Histogram,
+    pub proof_generation_seconds: Histogram,
+    pub proof_generation_failures: IntCounter,
+    pub proofs_awaiting_order: IntGauge,
+    pub aggregation_seconds: Histogram,
+
+    pub settlement_seconds: Histogram,
+    pub settlements: IntCounterVec,
+    pub batches_per_settlement: Histogram,
+    pub settlement_fee_per_batch_lamports: Histogram,
+
+    pub proofs_by_status: IntGaugeVec,
+    pub db_accounts: IntGaugeVec,
+    pub db_transactions: IntGauge,
+
+    pub retry_cycles: IntCounter,
+    pub retry_consecutive_failures: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("rollup".to_string()), None)
+            .expect("valid metrics namespace");
+
+        let metrics = Self {
+            transactions_submitted: IntCounter::new(
+                "transactions_submitted_total",
+                "Transactions accepted into the mempool",
+            ).unwrap(),
+            transactions_rejected: IntCounterVec::new(
+                Opts::new("transactions_rejected_total", "Transactions rejected at submission, by reject code"),
+                &["code"],
+            ).unwrap(),
+            transactions_dropped: IntCounter::new(
+                "transactions_dropped_total",
+                "Sequenced transactions dropped from their batch because they could not be executed or proved",
+            ).unwrap(),
+            mempool_depth: IntGauge::new("mempool_depth", "Transactions waiting in the mempool").unwrap(),
+
+            batch_size: Histogram::with_opts(
+                HistogramOpts::new("batch_size", "Transactions per sealed batch")
+                    .buckets(vec![1.0, 2.0, 3.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0]),
+            ).unwrap(),
+            batches_failed: IntCounter::new(
+                "batches_failed_total",
+                "Batches dropped because execution failed or account state was unavailable",
+            ).unwrap(),
+            svm_execution_seconds: Histogram::with_opts(HistogramOpts::new(
+                "svm_execution_seconds",
+                "SVM execution time per batch",
+            )).unwrap(),
+            proof_generation_seconds: Histogram::with_opts(
+                HistogramOpts::new("proof_generation_seconds", "Groth16 proof generation time per batch")
+                    .buckets(SLOW_BUCKETS.to_vec()),
+            ).unwrap(),
+            proof_generation_failures: IntCounter::new(
+                "proof_generation_failures_total",
+                "Batches whose proof generation failed",
+            ).unwrap(),
+            proofs_awaiting_order: IntGauge::new(
+                "proofs_awaiting_order",
+                "Proved batches held back until every earlier batch is proved",
+            ).unwrap(),
+            aggregation_seconds: Histogram::with_opts(
+                HistogramOpts::new("aggregation_seconds", "Time to fold a window of batch proofs into one")
+                    .buckets(SLOW_BUCKETS.to_vec()),
+            ).unwrap(),
+
+            settlement_seconds: Histogram::with_opts(
+                HistogramOpts::new("settlement_seconds", "Time from settlement job pickup to L1 confirmation or failure")
+                    .buckets(SLOW_BUCKETS.to_vec()),
+            ).unwrap(),
+            settlements: IntCounterVec::new(
+                Opts::new("settlements_total", "Settlement attempts, by result"),
+                &["result"],
+            ).unwrap(),
+            batches_per_settlement: Histogram::with_opts(
+                HistogramOpts::new("batches_per_settlement", "Batches settled by each verified L1 transaction")
+                    .buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0]),
+            ).unwrap(),
+            settlement_fee_per_batch_lamports: Histogram::with_opts(
+                HistogramOpts::new(
+                    "settlement_fee_per_batch_lamports",
+                    "L1 fee of each verified settlement transaction, divided by the batches it settles",
+                )
+                .buckets(vec![100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0, 50_000.0]),
+            ).unwrap(),
+
+            proofs_by_status: IntGaugeVec::new(
+                Opts::new("proofs", "Batch proofs in RollupDB, by status"),
+                &["status"],
+            ).unwrap(),
+            db_accounts: IntGaugeVec::new(
+                Opts::new("db_accounts", "Accounts held by RollupDB, by state"),
+                &["state"],
+            ).unwrap(),
+            db_transactions: IntGauge::new("db_transactions", "Processed transactions stored in RollupDB").unwrap(),
+
+            retry_cycles: IntCounter::new("retry_cycles_total", "Retry cycles approved by the circuit breaker").unwrap(),
+            retry_consecutive_failures: IntGauge::new(
+                "retry_consecutive_failures",
+                "Consecutive retry cycles with only failures; the circuit breaker backs off from 5",
+            ).unwrap(),
+
+            registry,
+        };
+        metrics.register_all();
+        metrics
+    }
+
+    fn register_all(&self) {
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(self.transactions_submitted.clone()),
+            Box::new(self.transactions_rejected.clone()),
+            Box::new(self.transactions_dropped.clone()),
+            Box::new(self.mempool_depth.clone()),
+            Box::new(self.batch_size.clone()),
+            Box::new(self.batches_failed.clone()),
+            Box::new(self.svm_execution_seconds.clone()),
+            Box::new(self.proof_generation_seconds.clone()),
+            Box::new(self.proof_generation_failures.clone()),
+            Box::new(self.proofs_awaiting_order.clone()),
+            Box::new(self.aggregation_seconds.clone()),
+            Box::new(self.settlement_seconds.clone()),
+            Box::new(self.settlements.clone()),
+            Box::new(self.batches_per_settlement.clone()),
+            Box::new(self.settlement_fee_per_batch_lamports.clone()),
+            Box::new(self.proofs_by_status.clone()),
+            Box::new(self.db_accounts.clone()),
+            Box::new(self.db_transactions.clone()),
+            Box::new(self.retry_cycles.clone()),
+            Box::new(self.retry_consecutive_failures.clone()),
+        ];
+        for collector in collectors {
+            self.registry.register(collector).expect("metric names are unique");
+        }
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                log::error!("Failed to encode metrics: {}", e);
+                String::new()
+            })
+    }
+}
@@ -0,0 +1,64 @@
+//! OpenAPI document for the HTTP API, generated from the handler annotations
+//! and the `rollup_api` types, served at `/openapi.json`.
+
+use actix_web::{HttpResponse, Responder};
+use utoipa::{
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{admin, error::ErrorCode, frontend, validation::RejectCode};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ZKSVM Rollup node",
+        description = "Failed requests answer with a non-2xx status and a `{ code, error }` body."
+    ),
+    paths(
+        frontend::submit_transaction,
+        frontend::submit_transactions,
+        frontend::get_transaction,
+        frontend::simulate_transaction,
+        frontend::list_batches,
+        frontend::get_batch,
+        frontend::get_batch_for_transaction,
+        frontend::verify_proof,
+        frontend::health,
+        frontend::ready,
+        frontend::metrics,
+        admin::get_sequencer,
+        admin::pause_sequencer,
+        admin::resume_sequencer,
+        admin::seal_batch,
+        admin::retry_failed,
+        admin::requeue_batch,
+        admin::abandon_batch,
+        admin::unsettled_proofs,
+        admin::reset_circuit_breaker,
+    ),
+    components(schemas(ErrorCode, RejectCode)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "transactions", description = "Submitting, simulating and looking up transactions"),
+        (name = "batches", description = "Sequenced batches and their proofs"),
+        (name = "node", description = "Health probes and metrics"),
+        (name = "admin", description = "Operator controls, behind `admin.token`"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        // an API key or a JWT signed with api.jwt_secret
+        components.add_security_scheme("api_key", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+        components.add_security_scheme("admin_token", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+    }
+}
+
+pub async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
@@ -0,0 +1,358 @@
+//! Minimal OTLP/JSON trace exporter as a `tracing_subscriber` layer.
+//!
+//! Spans are turned into OTLP span records when they close and handed to a
+//! background thread, which batches them into `ExportTraceServiceRequest`
+//! documents. Each document is either POSTed to an OTLP/HTTP collector
+//! (`/v1/traces`) or appended as one line to a JSON-lines file.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+const MAX_BATCH_SPANS: usize = 512;
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+// bound memory for long-lived spans that log a lot
+const MAX_EVENTS_PER_SPAN: usize = 128;
+const SPAN_KIND_INTERNAL: u8 = 1;
+// an unreachable collector would otherwise warn on every flush
+const FAILURE_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub enum OtlpTarget {
+    Http(String),
+    File(PathBuf),
+}
+
+struct SpanData {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    name: &'static str,
+    target: &'static str,
+    start: SystemTime,
+    attributes: Vec<(String, Value)>,
+    events: Vec<EventData>,
+    dropped_events: u32,
+    links: Vec<([u8; 16], [u8; 8])>,
+}
+
+struct EventData {
+    time: SystemTime,
+    name: String,
+    attributes: Vec<(String, Value)>,
+}
+
+enum ExportMessage {
+    Span(SpanData, SystemTime),
+    Flush(Sender<()>),
+}
+
+pub struct OtlpLayer {
+    sender: Sender<ExportMessage>,
+}
+
+/// Flushes buffered spans when dropped.
+pub struct OtlpExporterHandle {
+    sender: Sender<ExportMessage>,
+    worker: Option<JoinHandle<()>>,
+}
+
+pub fn layer(service_name: &str, target: OtlpTarget) -> (OtlpLayer, OtlpExporterHandle) {
+    let (sender, receiver) = mpsc::channel();
+    let service_name = service_name.to_string();
+    let worker = thread::Builder::new()
+        .name("otlp-exporter".to_string())
+        .spawn(move || run_exporter(receiver, service_name, target))
+        .expect("failed to spawn OTLP exporter thread");
+
+    (
+        OtlpLayer { sender: sender.clone() },
+        OtlpExporterHandle { sender, worker: Some(worker) },
+    )
+}
+
+impl OtlpExporterHandle {
+    /// Blocks until everything closed so far has been exported, or the timeout passes.
+    pub fn flush(&self, timeout: Duration) {
+        let (done_sender, done_receiver) = mpsc::channel();
+        if self.sender.send(ExportMessage::Flush(done_sender)).is_ok() {
+            let _ = done_receiver.recv_timeout(timeout);
+        }
+    }
+}
+
+impl Drop for OtlpExporterHandle {
+    fn drop(&mut self) {
+        self.flush(Duration::from_secs(2));
+        // the layer keeps its own sender alive, so the worker is detached rather than joined
+        drop(self.worker.take());
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+
+        // the trace id is inherited, so a batch span and everything under it share one trace
+        let parent_ids = span.parent().and_then(|parent| {
+            parent.extensions().get::<SpanData>().map(|data| (data.trace_id, data.span_id))
+        });
+        let (trace_id, parent_span_id) = match parent_ids {
+            Some((trace_id, parent_span_id)) => (trace_id, Some(parent_span_id)),
+            None => (rand::random(), None),
+        };
+
+        let mut data = SpanData {
+            trace_id,
+            span_id: rand::random(),
+            parent_span_id,
+            name: attrs.metadata().name(),
+            target: attrs.metadata().target(),
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+            events: Vec::new(),
+            dropped_events: 0,
+            links: Vec::new(),
+        };
+        attrs.record(&mut AttributeVisitor(&mut data.attributes));
+        span.extensions_mut().insert(data);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<SpanData>() {
+            values.record(&mut AttributeVisitor(&mut data.attributes));
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, S>) {
+        let (Some(span), Some(follows)) = (ctx.span(id), ctx.span(follows)) else { return };
+        let link = follows.extensions().get::<SpanData>().map(|data| (data.trace_id, data.span_id));
+        let mut extensions = span.extensions_mut();
+        if let (Some(link), Some(data)) = (link, extensions.get_mut::<SpanData>()) {
+            data.links.push(link);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else { return };
+        let mut extensions = span.extensions_mut();
+        let Some(data) = extensions.get_mut::<SpanData>() else { return };
+
+        if data.events.len() >= MAX_EVENTS_PER_SPAN {
+            data.dropped_events += 1;
+            return;
+        }
+
+        let mut attributes = Vec::new();
+        event.record(&mut AttributeVisitor(&mut attributes));
+        // log records bridged from the `log` crate carry their text in `message`
+        let name = attributes
+            .iter()
+            .position(|(key, _)| key == "message")
+            .map(|index| match attributes.remove(index).1 {
+                Value::String(message) => message,
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| event.metadata().name().to_string());
+        attributes.push(("level".to_string(), Value::String(event.metadata().level().to_string())));
+
+        data.events.push(EventData { time: SystemTime::now(), name, attributes });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let data = span.extensions_mut().remove::<SpanData>();
+        if let Some(data) = data {
+            let _ = self.sender.send(ExportMessage::Span(data, SystemTime::now()));
+        }
+    }
+}
+
+struct AttributeVisitor<'a>(&'a mut Vec<(String, Value)>);
+
+impl AttributeVisitor<'_> {
+    fn set(&mut self, field: &Field, value: Value) {
+        let key = field.name();
+        match self.0.iter_mut().find(|(existing, _)| existing == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::Bool(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, Value::String(format!("{:?}", value)));
+    }
+}
+
+/// Export failures since the last success, logged at most once per `FAILURE_LOG_INTERVAL`.
+#[derive(Default)]
+struct ExportFailures {
+    count: u32,
+    last_logged: Option<Instant>,
+}
+
+impl ExportFailures {
+    // these records are logged from the exporter thread, outside any span, so the
+    // layer drops them instead of feeding them back into the export
+    fn record(&mut self, target: &OtlpTarget, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                if self.count > 0 {
+                    log::info!("OTLP export to {:?} recovered after {} failed exports", target, self.count);
+                }
+                *self = Self::default();
+            }
+            Err(e) => {
+                self.count += 1;
+                if self.last_logged.is_none_or(|last| last.elapsed() >= FAILURE_LOG_INTERVAL) {
+                    log::warn!("OTLP export to {:?} failed ({} in a row, spans are dropped): {}", target, self.count, e);
+                    self.last_logged = Some(Instant::now());
+                }
+            }
+        }
+    }
+}
+
+fn run_exporter(receiver: Receiver<ExportMessage>, service_name: String, target: OtlpTarget) {
+    let mut pending: Vec<Value> = Vec::new();
+    let mut failures = ExportFailures::default();
+    let mut flush = |pending: &mut Vec<Value>| {
+        if !pending.is_empty() {
+            failures.record(&target, export(&service_name, &target, pending));
+        }
+    };
+
+    loop {
+        match receiver.recv_timeout(EXPORT_INTERVAL) {
+            Ok(ExportMessage::Span(data, end)) => {
+                pending.push(span_to_otlp(data, end));
+                if pending.len() >= MAX_BATCH_SPANS {
+                    flush(&mut pending);
+                }
+            }
+            Ok(ExportMessage::Flush(done)) => {
+                flush(&mut pending);
+                let _ = done.send(());
+            }
+            Err(RecvTimeoutError::Timeout) => flush(&mut pending),
+            Err(RecvTimeoutError::Disconnected) => {
+                flush(&mut pending);
+                return;
+            }
+        }
+    }
+}
+
+fn export(service_name: &str, target: &OtlpTarget, pending: &mut Vec<Value>) -> Result<(), String> {
+    let request = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &Value::String(service_name.to_string()))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": std::mem::take(pending),
+            }],
+        }],
+    });
+
+    match target {
+        OtlpTarget::Http(endpoint) => ureq::post(endpoint)
+            .set("Content-Type", "application/json")
+            .send_string(&request.to_string())
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        OtlpTarget::File(path) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", request))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn span_to_otlp(data: SpanData, end: SystemTime) -> Value {
+    let mut attributes: Vec<Value> = data.attributes.iter().map(|(key, value)| attribute(key, value)).collect();
+    attributes.push(attribute("code.namespace", &Value::String(data.target.to_string())));
+
+    json!({
+        "traceId": hex(&data.trace_id),
+        "spanId": hex(&data.span_id),
+        "parentSpanId": data.parent_span_id.map(|id| hex(&id)).unwrap_or_default(),
+        "name": data.name,
+        "kind": SPAN_KIND_INTERNAL,
+        "startTimeUnixNano": unix_nanos(data.start),
+        "endTimeUnixNano": unix_nanos(end),
+        "attributes": attributes,
+        "events": data.events.iter().map(|event| json!({
+            "timeUnixNano": unix_nanos(event.time),
+            "name": event.name,
+            "attributes": event.attributes.iter().map(|(key, value)| attribute(key, value)).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "droppedEventsCount": data.dropped_events,
+        "links": data.links.iter().map(|(trace_id, span_id)| json!({
+            "traceId": hex(trace_id),
+            "spanId": hex(span_id),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// OTLP/JSON encodes 64-bit integers as strings
+fn attribute(key: &str, value: &Value) -> Value {
+    let any_value = match value {
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": any_value })
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,141 @@
+//! In-process Groth16 proving of the batch circuit.
+//!
+//! Each size class's proving key is loaded from the key store the first time a
+//! batch of that size is proved, and must come from a contributed ceremony (the
+//! one `prover.keys.ptau_sha256` pins, if set). Only in dev mode does a class
+//! without a key get a setup seeded with `prover.keys.dev_seed`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Context, Result};
+use prover_service::groth16_artifact;
+use zk::{BatchProver, CircuitStats, KeyOrigin, KeyStore, SnarkJsVerifyingKey};
+
+use super::{publish_verification_key, BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend};
+use crate::config::ProverConfig;
+
+pub struct ArkworksBackend {
+    config: ProverConfig,
+    dev_mode: bool,
+    keys: KeyStore,
+    /// Verification keys settlement can already read, by size class.
+    published: Mutex<HashMap<usize, SnarkJsVerifyingKey>>,
+}
+
+impl ArkworksBackend {
+    pub fn load_or_setup(config: &ProverConfig, dev_mode: bool) -> Result<Self> {
+        let mut keys = KeyStore::new(config.keys_dir());
+        if dev_mode {
+            keys = keys.with_dev_setup(config.keys.dev_seed);
+        }
+        Ok(Self { config: config.clone(), dev_mode, keys, published: Mutex::new(HashMap::new()) })
+    }
+
+    /// The prover for one size class, publishing its verification key on first use.
+    pub fn prover(&self, slots: usize) -> Result<Arc<BatchProver>> {
+        self.check_size_class(slots)?;
+        let prover = self
+            .keys
+            .get(slots)
+            .with_context(|| format!("Failed to load the proving key for {} slots", slots))?;
+        self.publish(slots, || Ok(prover.verifying_key_snarkjs()))?;
+        Ok(prover)
+    }
+
+    /// One size class's verification key, published on first use. Its proving key is
+    /// not read, unless dev mode has to set one up.
+    pub fn verification_key(&self, slots: usize) -> Result<SnarkJsVerifyingKey> {
+        self.check_size_class(slots)?;
+        self.publish(slots, || {
+            self.keys
+                .verifying_key(slots)
+                .with_context(|| format!("Failed to load the verification key for {} slots", slots))
+        })
+    }
+
+    fn check_size_class(&self, slots: usize) -> Result<()> {
+        if !self.config.size_classes.contains(&slots) {
+            return Err(anyhow!("No {}-slot circuit in prover.size_classes", slots));
+        }
+        if !self.keys.has_key(slots) && self.dev_mode {
+            log::warn!(
+                "No proving key in {}, running a setup seeded with {} (dev mode only)",
+                self.keys.class_dir(slots).display(),
+                self.config.keys.dev_seed
+            );
+        }
+        Ok(())
+    }
+
+    // the lock is held across loading and writing, so a class is published once
+    fn publish(&self, slots: usize, load: impl FnOnce() -> Result<SnarkJsVerifyingKey>) -> Result<SnarkJsVerifyingKey> {
+        let mut published = self.published.lock().unwrap();
+        if let Some(key) = published.get(&slots) {
+            return Ok(key.clone());
+        }
+        let key = load()?;
+        self.check_origin(slots)?;
+        publish_verification_key(&self.config, slots, &key)?;
+        published.insert(slots, key.clone());
+        Ok(key)
+    }
+
+    /// Checks a class's key against the pinned ceremony and logs where it came from.
+    fn check_origin(&self, slots: usize) -> Result<()> {
+        let manifest = self
+            .keys
+            .current_manifest(slots)?
+            .ok_or_else(|| anyhow!("The proving key for {} slots has no manifest", slots))?;
+        if let Some(pinned) = &self.config.keys.ptau_sha256 {
+            match &manifest.origin {
+                KeyOrigin::PowersOfTau { ptau_sha256, .. } if ptau_sha256.eq_ignore_ascii_case(pinned) => {}
+                _ if self.dev_mode => log::warn!("Proving key for {} slots is not from the pinned ceremony", slots),
+                origin => {
+                    return Err(anyhow!(
+                        "The proving key for {} slots comes from {:?}, not the ceremony prover.keys.ptau_sha256 pins",
+                        slots,
+                        origin
+                    ))
+                }
+            }
+        }
+        if let Some(reason) = manifest.insecure_reason() {
+            log::warn!("Proving key for {} slots is not safe for settlement: {}", slots, reason);
+        }
+        log::info!(
+            "Key for {} slots loaded from {} (version {}, {} contributions)",
+            slots,
+            self.keys.version_dir(slots, manifest.version).display(),
+            manifest.version,
+            manifest.contributions.len()
+        );
+        Ok(())
+    }
+}
+
+impl ProverBackend for ArkworksBackend {
+    fn name(&self) -> &'static str {
+        "arkworks"
+    }
+
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact> {
+        let proof = self
+            .prover(witness.slots)?
+            .prove(witness.transfers.clone(), &witness.state_paths)
+            .map_err(|e| anyhow!("Proof generation failed: {}", e))?;
+        Ok(groth16_artifact(&proof))
+    }
+
+    fn verifying_key(&self, slots: usize) -> Result<BackendVerifyingKey> {
+        Ok(BackendVerifyingKey::Groth16(self.prover(slots)?.verifying_key_snarkjs()))
+    }
+
+    fn measure_circuit(&self, witness: &BatchWitness) -> Result<Option<CircuitStats>> {
+        let stats = zk::bench::synthesize(witness.transfers.clone(), &witness.state_paths, witness.slots)
+            .map_err(|e| anyhow!("Witness generation failed: {}", e))?;
+        Ok(Some(stats))
+    }
+}
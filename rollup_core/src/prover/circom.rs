@@ -0,0 +1,224 @@
+//! Groth16 proving through the circom circuit and the snarkjs CLI.
+//!
+//! Needs `circom`, `node` and `snarkjs` on the PATH. In dev mode the setup
+//! script compiles the circuit and creates its keys on first start; otherwise
+//! they must have been made beforehand, from a real ceremony (`PTAU_FILE`). Each
+//! batch gets its own job directory, so concurrent proofs never share files.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use zk::{CircuitStats, SnarkJsVerifyingKey};
+
+use super::{BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend, TransferWitness};
+use crate::{
+    config::{ProverConfig, CIRCOM_CIRCUIT_SLOTS},
+    rollupdb::ProofData,
+};
+
+/// The circuit's input signals, in the layout `input.json` expects.
+#[derive(Debug, Serialize)]
+struct CircuitInput {
+    amounts: Vec<String>,
+    signature_first_bytes: Vec<String>,
+    from_balances_before: Vec<String>,
+    from_balances_after: Vec<String>,
+}
+
+impl CircuitInput {
+    fn new(transfers: &[TransferWitness]) -> Result<Self> {
+        if transfers.len() > CIRCOM_CIRCUIT_SLOTS {
+            return Err(anyhow!("{} transfers do not fit the {}-slot circuit", transfers.len(), CIRCOM_CIRCUIT_SLOTS));
+        }
+
+        let mut input = Self {
+            amounts: Vec::with_capacity(CIRCOM_CIRCUIT_SLOTS),
+            signature_first_bytes: Vec::with_capacity(CIRCOM_CIRCUIT_SLOTS),
+            from_balances_before: Vec::with_capacity(CIRCOM_CIRCUIT_SLOTS),
+            from_balances_after: Vec::with_capacity(CIRCOM_CIRCUIT_SLOTS),
+        };
+        for transfer in transfers {
+            // the circom circuit only sees the sender, and its amount must be non-zero
+            input.amounts.push(transfer.amount.to_string());
+            input.signature_first_bytes.push(transfer.signature_first_byte.to_string());
+            input.from_balances_before.push(transfer.from_balance_before.to_string());
+            input.from_balances_after.push(transfer.from_balance_after.to_string());
+        }
+        // padding slots pass the circuit's checks, so they never spoil `batch_valid`
+        for _ in transfers.len()..CIRCOM_CIRCUIT_SLOTS {
+            input.amounts.push("1".to_string());
+            input.signature_first_bytes.push("1".to_string());
+            input.from_balances_before.push("0".to_string());
+            input.from_balances_after.push("0".to_string());
+        }
+        Ok(input)
+    }
+}
+
+pub struct CircomBackend {
+    build_dir: PathBuf,
+}
+
+impl CircomBackend {
+    pub fn new(config: &ProverConfig, dev_mode: bool) -> Result<Self> {
+        let backend = Self { build_dir: config.circom_build_dir() };
+
+        let missing = [backend.zkey_path(), backend.wasm_path(), backend.verification_key_path()]
+            .into_iter()
+            .any(|path| !path.is_file());
+        if missing && !dev_mode {
+            return Err(anyhow!(
+                "Circom keys missing under {}; run {} with PTAU_FILE set, or start in dev mode",
+                backend.build_dir.display(),
+                config.circom.script_path.display()
+            ));
+        }
+        if missing {
+            log::warn!("Circom keys missing under {}, running {}", backend.build_dir.display(), config.circom.script_path.display());
+            fs::create_dir_all(&backend.build_dir)?;
+            run(Command::new(&config.circom.script_path)
+                .env("CIRCUIT_DIR", &config.circom.circuit_dir)
+                .env("BUILD_DIR", &backend.build_dir))
+            .context("Circom setup failed")?;
+        }
+
+        Ok(backend)
+    }
+
+    fn zkey_path(&self) -> PathBuf {
+        self.build_dir.join("keys/batch_0001.zkey")
+    }
+
+    fn wasm_path(&self) -> PathBuf {
+        self.build_dir.join("batch_system_transfer_js/batch_system_transfer.wasm")
+    }
+
+    fn verification_key_path(&self) -> PathBuf {
+        self.build_dir.join("keys/verification_key_batch.json")
+    }
+
+    fn r1cs_path(&self) -> PathBuf {
+        self.build_dir.join("batch_system_transfer.r1cs")
+    }
+
+    /// Writes the circuit's witness for `witness` to `job_dir/witness.wtns`.
+    fn generate_witness(&self, job_dir: &Path, witness: &BatchWitness) -> Result<PathBuf> {
+        let input_path = job_dir.join("input.json");
+        let witness_path = job_dir.join("witness.wtns");
+
+        let input = CircuitInput::new(&witness.transfers)?;
+        fs::write(&input_path, serde_json::to_string_pretty(&input)?)?;
+
+        run(Command::new("node")
+            .arg(self.build_dir.join("batch_system_transfer_js/generate_witness.js"))
+            .arg(self.wasm_path())
+            .arg(&input_path)
+            .arg(&witness_path))
+        .context("Witness generation failed")?;
+        Ok(witness_path)
+    }
+
+    /// Runs `f` in a fresh directory for `witness`'s batch, removed afterwards.
+    fn in_job_dir<T>(&self, witness: &BatchWitness, f: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+        let job_dir = self.build_dir.join("jobs").join(&witness.batch_id);
+        fs::create_dir_all(&job_dir)?;
+
+        let result = f(&job_dir);
+        if let Err(e) = fs::remove_dir_all(&job_dir) {
+            log::warn!("Failed to clean up {}: {}", job_dir.display(), e);
+        }
+        result
+    }
+
+    fn prove_in(&self, job_dir: &Path, witness: &BatchWitness) -> Result<ProofArtifact> {
+        let proof_path = job_dir.join("proof.json");
+        let public_path = job_dir.join("public.json");
+
+        let witness_path = self.generate_witness(job_dir, witness)?;
+
+        run(Command::new("snarkjs")
+            .args(["groth16", "prove"])
+            .arg(self.zkey_path())
+            .arg(&witness_path)
+            .arg(&proof_path)
+            .arg(&public_path))
+        .context("snarkjs proving failed")?;
+
+        let proof = ProofData::from_json_file(&proof_path.to_string_lossy())
+            .map_err(|e| anyhow!("Failed to read {}: {}", proof_path.display(), e))?;
+        let public_inputs: Vec<String> = serde_json::from_str(&fs::read_to_string(&public_path)?)?;
+
+        Ok(ProofArtifact::Groth16 { proof: Box::new(proof), public_inputs, slots: CIRCOM_CIRCUIT_SLOTS })
+    }
+}
+
+impl ProverBackend for CircomBackend {
+    fn name(&self) -> &'static str {
+        "circom"
+    }
+
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact> {
+        self.in_job_dir(witness, |job_dir| self.prove_in(job_dir, witness))
+    }
+
+    fn verifying_key(&self, slots: usize) -> Result<BackendVerifyingKey> {
+        if slots != CIRCOM_CIRCUIT_SLOTS {
+            return Err(anyhow!("The circom circuit has {} slots, not {}", CIRCOM_CIRCUIT_SLOTS, slots));
+        }
+        let path = self.verification_key_path();
+        let vk: SnarkJsVerifyingKey = serde_json::from_str(&fs::read_to_string(&path)?)
+            .with_context(|| format!("Invalid verification key {}", path.display()))?;
+        Ok(BackendVerifyingKey::Groth16(vk))
+    }
+
+    fn measure_circuit(&self, witness: &BatchWitness) -> Result<Option<CircuitStats>> {
+        let constraints = r1cs_constraints(&self.r1cs_path())?;
+        let start = Instant::now();
+        self.in_job_dir(witness, |job_dir| self.generate_witness(job_dir, witness))?;
+        Ok(Some(CircuitStats { constraints, witness_time: start.elapsed() }))
+    }
+}
+
+/// Reads the constraint count from the header section of a circom `.r1cs` file.
+fn r1cs_constraints(path: &Path) -> Result<usize> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let invalid = || anyhow!("{} is not an r1cs file", path.display());
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+    if bytes.get(..4) != Some(b"r1cs".as_slice()) {
+        return Err(invalid());
+    }
+
+    // magic, version, section count, then (type: u32, size: u64, contents) sections
+    let sections = u32_at(8).ok_or_else(invalid)?;
+    let mut at = 12;
+    for _ in 0..sections {
+        let kind = u32_at(at).ok_or_else(invalid)?;
+        let size = bytes.get(at + 4..at + 12).map(|b| u64::from_le_bytes(b.try_into().unwrap())).ok_or_else(invalid)?;
+        if kind == 1 {
+            // field size n8, the prime (n8 bytes), then wires, outputs, public and private inputs (u32 each), labels (u64) and constraints (u32)
+            let n8 = u32_at(at + 12).ok_or_else(invalid)? as usize;
+            return u32_at(at + 16 + n8 + 24).map(|n| n as usize).ok_or_else(invalid);
+        }
+        at += 12 + size as usize;
+    }
+    Err(invalid())
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.output().with_context(|| format!("Failed to start {:?}", command.get_program()))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{:?} exited with {}: {}",
+            command.get_program(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
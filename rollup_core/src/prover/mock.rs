@@ -0,0 +1,24 @@
+use anyhow::{anyhow, Result};
+use zk::BatchTransferCircuit;
+
+use super::{BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend};
+
+/// Proves nothing, instantly. Still rejects batches the real circuit would, so
+/// development runs fail where a real prover would.
+pub struct MockBackend;
+
+impl ProverBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact> {
+        BatchTransferCircuit::new(witness.transfers.clone(), &witness.state_paths, witness.slots)
+            .map_err(|e| anyhow!("{}", e))?;
+        Ok(ProofArtifact::Mock)
+    }
+
+    fn verifying_key(&self, _slots: usize) -> Result<BackendVerifyingKey> {
+        Ok(BackendVerifyingKey::None)
+    }
+}
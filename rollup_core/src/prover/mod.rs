@@ -0,0 +1,110 @@
+//! Batch proof generation behind one trait, so proof systems can be swapped
+//! (and benchmarked against each other) by config.
+//!
+//! A backend turns a `BatchWitness` into a `ProofArtifact`; the artifact's
+//! variant decides how settlement verifies it. Backends are built once at
+//! startup and shared by every batch.
+//!
+//! Groth16 backends have one circuit, and so one key pair, per size class
+//! (`prover.size_classes`); the witness names the class that proves it.
+//!
+//! Backends that can verify their own proofs inside a proof also fold windows
+//! of consecutive batch proofs into one (`prover.aggregation`).
+
+mod arkworks;
+mod circom;
+mod mock;
+mod remote;
+#[cfg(feature = "risc0")]
+mod risc0;
+
+use std::{fs, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use prover_service::JobQueue;
+use zk::{CircuitStats, SnarkJsVerifyingKey};
+
+use crate::config::{ProverBackendKind, ProverConfig};
+
+pub use arkworks::ArkworksBackend;
+pub use circom::CircomBackend;
+pub use mock::MockBackend;
+pub use remote::RemoteBackend;
+#[cfg(feature = "risc0")]
+pub use risc0::Risc0Backend;
+pub use rollup_api::ProofArtifact;
+pub use zk::{AccountPath, AccountWitness, BatchWitness, StateTree, TransferWitness};
+
+/// What a verifier needs to check a backend's proofs.
+pub enum BackendVerifyingKey {
+    Groth16(SnarkJsVerifyingKey),
+    /// Receipts carry their image id; this is the one the backend's guest has.
+    #[cfg(feature = "risc0")]
+    RiscZero { image_id: String },
+    /// Mock proofs are not verified.
+    None,
+}
+
+pub trait ProverBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Proves one batch. Blocks for as long as proving takes.
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact>;
+
+    /// The key that checks proofs of `slots`-slot batches.
+    fn verifying_key(&self, slots: usize) -> Result<BackendVerifyingKey>;
+
+    /// Folds consecutive proved batches, oldest first, into one proof that settles
+    /// them all. Blocks for as long as proving takes.
+    fn aggregate(&self, batches: &[(BatchWitness, ProofArtifact)]) -> Result<ProofArtifact> {
+        Err(anyhow!("the {} backend cannot aggregate {} batch proofs", self.name(), batches.len()))
+    }
+
+    /// Constraint count and witness generation time of the circuit that proves
+    /// `witness`, for benchmarks. `None` for backends without an R1CS circuit of their own.
+    fn measure_circuit(&self, _witness: &BatchWitness) -> Result<Option<CircuitStats>> {
+        Ok(None)
+    }
+}
+
+/// Builds the configured backend and publishes its verifying key for settlement.
+/// `jobs` is the queue served to remote workers; only the remote backend uses it.
+/// Only in `dev_mode` do Groth16 backends set up keys that are missing, and only
+/// there may batches be "proved" without a proof (the mock backend, `RISC0_DEV_MODE`).
+pub fn init(config: &ProverConfig, dev_mode: bool, jobs: Arc<JobQueue>) -> Result<Arc<dyn ProverBackend>> {
+    let backend: Arc<dyn ProverBackend> = match config.backend {
+        ProverBackendKind::Arkworks => Arc::new(ArkworksBackend::load_or_setup(config, dev_mode)?),
+        ProverBackendKind::Circom => Arc::new(CircomBackend::new(config, dev_mode)?),
+        #[cfg(feature = "risc0")]
+        ProverBackendKind::RiscZero => Arc::new(Risc0Backend::new(config, dev_mode)?),
+        #[cfg(not(feature = "risc0"))]
+        ProverBackendKind::RiscZero => anyhow::bail!("the risc_zero prover backend needs a build with the risc0 feature"),
+        ProverBackendKind::Mock => {
+            log::warn!("Using the mock prover: batches will settle without any proof");
+            Arc::new(MockBackend)
+        }
+        ProverBackendKind::Remote => Arc::new(RemoteBackend::new(config, dev_mode, jobs)?),
+    };
+
+    // larger classes publish their keys when their first batch is proved
+    let smallest = config.size_class_for(1).context("no circuit size class fits a single transfer")?;
+    match backend.verifying_key(smallest)? {
+        BackendVerifyingKey::Groth16(vk) => publish_verification_key(config, smallest, &vk)?,
+        #[cfg(feature = "risc0")]
+        BackendVerifyingKey::RiscZero { image_id } => log::info!("RISC Zero guest image id: {}", image_id),
+        BackendVerifyingKey::None => {}
+    }
+
+    log::info!("Prover backend: {}", backend.name());
+    Ok(backend)
+}
+
+/// Writes the verification key of one size class where settlement reads it.
+fn publish_verification_key(config: &ProverConfig, slots: usize, vk: &SnarkJsVerifyingKey) -> Result<()> {
+    let vk_path = config.verification_key_path(slots);
+    if let Some(dir) = vk_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&vk_path, serde_json::to_string_pretty(vk)?)
+        .with_context(|| format!("Failed to write verification key {}", vk_path.display()))
+}
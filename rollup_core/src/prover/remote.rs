@@ -0,0 +1,60 @@
+//! Proving on `prover-worker` processes, through this node's job queue.
+//!
+//! Each pipeline worker submits its batch and blocks until some remote worker
+//! reports the proof, so `prover.workers` bounds how many batches are out with
+//! workers at once. Workers prove with copies of this node's key store; the node
+//! itself only reads a class's verification key (or, in dev mode, sets the class
+//! up) before its first job is submitted, never the proving key.
+//! A job that times out is cancelled, so no worker keeps proving it.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use prover_service::{JobQueue, QueueError};
+
+use super::{ArkworksBackend, BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend};
+use crate::config::ProverConfig;
+
+pub struct RemoteBackend {
+    queue: Arc<JobQueue>,
+    timeout: Duration,
+    /// Only reads verification keys, to publish them; never proves.
+    keys: ArkworksBackend,
+}
+
+impl RemoteBackend {
+    pub fn new(config: &ProverConfig, dev_mode: bool, queue: Arc<JobQueue>) -> Result<Self> {
+        let keys = ArkworksBackend::load_or_setup(config, dev_mode)?;
+        log::info!(
+            "Remote proving: workers need copies of the proving keys in {} and lease from /prover",
+            config.keys_dir().display()
+        );
+        Ok(Self { queue, timeout: config.job_timeout(), keys })
+    }
+}
+
+impl ProverBackend for RemoteBackend {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact> {
+        // so the verifying key is published by the time the proof comes back
+        self.keys.verification_key(witness.slots)?;
+        let job_id = self
+            .queue
+            .submit(witness.clone())
+            .map_err(|e| anyhow!("Could not queue batch {} for remote proving: {}", witness.batch_id, e))?;
+        let result = self.queue.wait(&job_id, self.timeout);
+        if let Err(QueueError::TimedOut(_)) = &result {
+            if let Err(e) = self.queue.cancel(&job_id) {
+                log::warn!("Could not cancel timed out job {}: {}", job_id, e);
+            }
+        }
+        result.map_err(|e| anyhow!("Remote proving of job {} failed: {}", job_id, e))
+    }
+
+    fn verifying_key(&self, slots: usize) -> Result<BackendVerifyingKey> {
+        Ok(BackendVerifyingKey::Groth16(self.keys.verification_key(slots)?))
+    }
+}
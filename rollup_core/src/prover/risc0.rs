@@ -0,0 +1,192 @@
+//! Proves batches by running a RISC Zero guest and compressing its receipt to
+//! Groth16, which `proof_risc0_verifier` checks on-chain.
+//!
+//! The guest (`batch_guest`) reads a `BatchWitness` from the executor env,
+//! opens its accounts against the global state tree, re-executes its signed
+//! transactions and commits a `BatchJournal` with the tree's roots; the verifier
+//! program only needs the seal, the image id and the journal digest, and the
+//! roots go along for settlement to chain. With `RISC0_DEV_MODE` set (only allowed in the node's `dev_mode`) the
+//! guest still runs, but its receipts are fake and the batch settles like a mock proof.
+//!
+//! With aggregation on, batch receipts stay succinct and the aggregation guest
+//! folds them, `fan_in` at a time, level by level, until one receipt is left;
+//! only that one is compressed to Groth16.
+
+use std::fs;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use risc0_zkvm::{
+    compute_image_id, default_prover, is_dev_mode,
+    sha::{Digest, Digestible},
+    ExecutorEnv, ProverOpts, Receipt,
+};
+use zk::{aggregate, AggregateJournal, AggregationChild, AggregationInput, BatchJournal};
+
+use super::{BackendVerifyingKey, BatchWitness, ProofArtifact, ProverBackend};
+use crate::config::ProverConfig;
+
+pub struct Risc0Backend {
+    elf: Vec<u8>,
+    image_id: Digest,
+    aggregation: Option<Aggregator>,
+}
+
+struct Aggregator {
+    elf: Vec<u8>,
+    image_id: Digest,
+    fan_in: usize,
+}
+
+impl Risc0Backend {
+    pub fn new(config: &ProverConfig, dev_mode: bool) -> Result<Self> {
+        if is_dev_mode() && !dev_mode {
+            bail!("RISC0_DEV_MODE makes fake receipts and is only allowed in dev_mode");
+        }
+        let path = config
+            .risc0
+            .guest_elf
+            .as_ref()
+            .ok_or_else(|| anyhow!("prover.risc0.guest_elf is not set"))?;
+        let elf = fs::read(path).with_context(|| format!("Failed to read guest ELF {}", path.display()))?;
+        let image_id = compute_image_id(&elf)?;
+
+        let aggregation = match &config.risc0.aggregate_elf {
+            Some(path) if config.aggregation.is_enabled() => {
+                let elf = fs::read(path).with_context(|| format!("Failed to read aggregation guest ELF {}", path.display()))?;
+                let image_id = compute_image_id(&elf)?;
+                log::info!("RISC Zero aggregation guest image id: {}", image_id);
+                Some(Aggregator { elf, image_id, fan_in: config.aggregation.fan_in() })
+            }
+            _ => None,
+        };
+        Ok(Self { elf, image_id, aggregation })
+    }
+
+    /// A receipt the RISC Zero verifier program accepts, or a mock proof for a fake one.
+    /// The state roots are the ones its journal commits.
+    fn settleable(
+        &self,
+        receipt: &Receipt,
+        image_id: Digest,
+        (pre_state_root, post_state_root): ([u8; 32], [u8; 32]),
+        proved: &str,
+    ) -> Result<ProofArtifact> {
+        if is_dev_mode() {
+            log::warn!("RISC0_DEV_MODE receipt for {} cannot be verified on-chain", proved);
+            return Ok(ProofArtifact::Mock);
+        }
+        let seal = &receipt.inner.groth16().map_err(|e| anyhow!("Receipt is not Groth16: {}", e))?.seal;
+        Ok(ProofArtifact::RiscZero {
+            seal: base64::engine::general_purpose::STANDARD.encode(seal),
+            image_id: image_id.to_string(),
+            journal_digest: receipt.journal.digest().to_string(),
+            pre_state_root: Digest::from(pre_state_root).to_string(),
+            post_state_root: Digest::from(post_state_root).to_string(),
+        })
+    }
+
+    /// Proves one aggregation step over `children` and their receipts.
+    fn fold(
+        &self,
+        aggregator: &Aggregator,
+        children: &[(AggregationChild, Receipt)],
+        opts: &ProverOpts,
+    ) -> Result<(AggregationChild, Receipt)> {
+        let input = AggregationInput {
+            batch_image_id: words(self.image_id)?,
+            aggregate_image_id: words(aggregator.image_id)?,
+            children: children.iter().map(|(child, _)| child.clone()).collect(),
+        };
+        // the guest would panic on the same error, after much longer
+        let expected = aggregate(&input).map_err(|e| anyhow!("Batches do not chain: {}", e))?;
+
+        let mut env = ExecutorEnv::builder();
+        for (_, receipt) in children {
+            env.add_assumption(receipt.clone());
+        }
+        let env = env.write(&input)?.build()?;
+        let receipt = default_prover().prove_with_opts(env, &aggregator.elf, opts)?.receipt;
+        receipt
+            .verify(aggregator.image_id)
+            .map_err(|e| anyhow!("Aggregation receipt failed verification: {}", e))?;
+        let journal: AggregateJournal = receipt.journal.decode()?;
+        if journal != expected.journal {
+            return Err(anyhow!("Aggregation guest committed another journal than native folding"));
+        }
+        Ok((expected.into_child(), receipt))
+    }
+}
+
+impl ProverBackend for Risc0Backend {
+    fn name(&self) -> &'static str {
+        "risc_zero"
+    }
+
+    fn prove(&self, witness: &BatchWitness) -> Result<ProofArtifact> {
+        // the guest would panic on the same error, after much longer
+        let expected = BatchJournal::execute(witness).map_err(|e| anyhow!("Batch does not execute: {}", e))?;
+
+        // receipts that are going to be aggregated only need to be verifiable inside the zkVM
+        let opts = if self.aggregation.is_some() { ProverOpts::succinct() } else { ProverOpts::groth16() };
+        let env = ExecutorEnv::builder().write(witness)?.build()?;
+        let receipt = default_prover().prove_with_opts(env, &self.elf, &opts)?.receipt;
+        receipt
+            .verify(self.image_id)
+            .map_err(|e| anyhow!("Guest receipt failed verification: {}", e))?;
+        let journal: BatchJournal = receipt.journal.decode()?;
+        if journal != expected {
+            return Err(anyhow!("Guest committed another journal than native execution of batch {}", witness.batch_id));
+        }
+
+        if self.aggregation.is_some() {
+            return Ok(ProofArtifact::RiscZeroSuccinct {
+                receipt: base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&receipt)?),
+            });
+        }
+        let roots = (journal.pre_state_root, journal.post_state_root);
+        self.settleable(&receipt, self.image_id, roots, &format!("batch {}", witness.batch_id))
+    }
+
+    fn verifying_key(&self, _slots: usize) -> Result<BackendVerifyingKey> {
+        Ok(BackendVerifyingKey::RiscZero { image_id: self.image_id.to_string() })
+    }
+
+    fn aggregate(&self, batches: &[(BatchWitness, ProofArtifact)]) -> Result<ProofArtifact> {
+        let aggregator = self.aggregation.as_ref().ok_or_else(|| anyhow!("prover.aggregation is not enabled"))?;
+        let mut level = batches
+            .iter()
+            .map(|(witness, artifact)| {
+                let ProofArtifact::RiscZeroSuccinct { receipt } = artifact else {
+                    return Err(anyhow!("Batch {} has a {} proof, not a succinct receipt", witness.batch_id, artifact.system()));
+                };
+                let receipt: Receipt = bincode::deserialize(&base64::engine::general_purpose::STANDARD.decode(receipt)?)?;
+                let child = AggregationChild::batch(witness).map_err(|e| anyhow!("Batch does not execute: {}", e))?;
+                Ok((child, receipt))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if level.is_empty() {
+            return Err(anyhow!("No batches to aggregate"));
+        }
+
+        // always at least one step, so what settles is an aggregation guest receipt
+        loop {
+            let last = level.len() <= aggregator.fan_in;
+            let opts = if last { ProverOpts::groth16() } else { ProverOpts::succinct() };
+            level = level
+                .chunks(aggregator.fan_in)
+                .map(|children| self.fold(aggregator, children, &opts))
+                .collect::<Result<Vec<_>>>()?;
+            if last {
+                break;
+            }
+        }
+        let (aggregate, receipt) = &level[0];
+        let roots = aggregate.journal.state_roots();
+        self.settleable(receipt, aggregator.image_id, roots, &format!("{} aggregated batches", batches.len()))
+    }
+}
+
+fn words(digest: Digest) -> Result<[u32; 8]> {
+    Ok(digest.as_words().try_into()?)
+}
@@ -0,0 +1,270 @@
+//! Proving pipeline between the sequencer and settlement.
+//!
+//! The sequencer queues each executed batch and goes straight back to
+//! executing the next one. A pool of worker threads proves queued batches
+//! concurrently, so proofs can finish out of order; the collector holds early
+//! finishers back and hands batches to RollupDB and settlement strictly in the
+//! order they were sealed. With aggregation on, settlement is reached through
+//! the aggregator, which folds windows of them into one proof.
+//!
+//! A batch whose proof fails is stored as failed together with its witness, so
+//! a retry or an operator requeue proves it again. Those come back through the
+//! same workers but skip the ordering: settlement only posts a proof once the
+//! one it builds on has settled.
+
+use std::{
+    collections::BTreeMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use async_channel::{Receiver, Sender, TrySendError};
+use crossbeam::channel::{Receiver as CBReceiver, Sender as CBSender};
+use tracing::Span;
+
+use crate::{
+    aggregation::{self, Released},
+    config::AggregationConfig,
+    health::{Component, NodeHealth},
+    metrics::METRICS,
+    prover::{BatchWitness, ProofArtifact, ProverBackend},
+    rollupdb::{FailedProofMessage, RollupDBMessage, StoreBatchProofMessage},
+    settle::SettlementJob,
+};
+
+/// An executed batch waiting for its proof.
+pub struct ProvingJob {
+    pub witness: BatchWitness,
+    pub transaction_signatures: Vec<String>,
+    /// The batch span, carried on into settlement.
+    pub span: Span,
+}
+
+struct ProvedBatch {
+    job: ProvingJob,
+    result: Result<ProofArtifact>,
+}
+
+/// Jobs in the order they were submitted; `None` for batches proved again, which keep no place in it.
+type Queued = (Option<u64>, ProvingJob);
+
+/// The sequencer's end of the pipeline. Numbers jobs in the order they are submitted.
+#[derive(Clone)]
+pub struct ProvingQueue {
+    sender: Sender<Queued>,
+    next_sequence: Arc<AtomicU64>,
+}
+
+impl ProvingQueue {
+    /// Waits while the queue is full, so slow proving holds the sequencer back
+    /// instead of piling up executed batches.
+    pub async fn submit(&self, job: ProvingJob) -> Result<()> {
+        let sequence = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        self.sender
+            .send((Some(sequence), job))
+            .await
+            .map_err(|_| anyhow!("proving queue closed"))
+    }
+
+    /// Queues a batch whose proof failed to be proved again, without waiting.
+    pub fn reprove(&self, job: ProvingJob) -> Result<()> {
+        self.sender.try_send((None, job)).map_err(|e| match e {
+            TrySendError::Full(_) => anyhow!("proving queue full"),
+            TrySendError::Closed(_) => anyhow!("proving queue closed"),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+}
+
+/// Creates the queue; `start` serves the receiving end.
+pub fn queue(capacity: usize) -> (ProvingQueue, Receiver<Queued>) {
+    let (sender, receiver) = async_channel::bounded(capacity);
+    (ProvingQueue { sender, next_sequence: Arc::new(AtomicU64::new(0)) }, receiver)
+}
+
+/// Where the collector releases proved batches.
+enum Downstream {
+    Settlement(CBSender<SettlementJob>),
+    Aggregator(CBSender<Released>),
+}
+
+/// Spawns `workers` prover threads and the collector, and the aggregator if
+/// `aggregation` is on. They run until the queue closes.
+pub fn start(
+    jobs: Receiver<Queued>,
+    prover: Arc<dyn ProverBackend>,
+    workers: usize,
+    aggregation: &AggregationConfig,
+    rollupdb_sender: CBSender<RollupDBMessage>,
+    settler_sender: CBSender<SettlementJob>,
+    health: Arc<NodeHealth>,
+) -> Result<()> {
+    let (result_sender, result_receiver) = crossbeam::channel::unbounded();
+    let downstream = if aggregation.is_enabled() {
+        Downstream::Aggregator(aggregation::start(
+            aggregation.clone(),
+            prover.clone(),
+            rollupdb_sender.clone(),
+            settler_sender,
+            health.clone(),
+        )?)
+    } else {
+        Downstream::Settlement(settler_sender)
+    };
+
+    for worker in 0..workers {
+        let (jobs, prover, results) = (jobs.clone(), prover.clone(), result_sender.clone());
+        thread::Builder::new()
+            .name(format!("prover-{}", worker))
+            .spawn(move || run_worker(jobs, prover.as_ref(), results))?;
+    }
+    drop(result_sender);
+
+    thread::Builder::new()
+        .name("proof-collector".to_string())
+        .spawn(move || run_collector(result_receiver, rollupdb_sender, downstream, health))?;
+
+    log::info!("Proving pipeline started with {} worker(s)", workers);
+    Ok(())
+}
+
+fn run_worker(jobs: Receiver<Queued>, prover: &dyn ProverBackend, results: CBSender<(Option<u64>, ProvedBatch)>) {
+    while let Ok((sequence, job)) = jobs.recv_blocking() {
+        let result = job.span.in_scope(|| {
+            let _prove_span = tracing::info_span!("prove").entered();
+            log::info!("Generating ZK proof for batch: {}", job.witness.batch_id);
+            let timer = METRICS.proof_generation_seconds.start_timer();
+            // a panicking backend must not take the batch's place in the order with it
+            let result = catch_unwind(AssertUnwindSafe(|| prover.prove(&job.witness)))
+                .unwrap_or_else(|_| Err(anyhow!("{} prover panicked", prover.name())));
+            timer.observe_duration();
+            result
+        });
+
+        if results.send((sequence, ProvedBatch { job, result })).is_err() {
+            break;
+        }
+    }
+}
+
+fn run_collector(
+    results: CBReceiver<(Option<u64>, ProvedBatch)>,
+    rollupdb_sender: CBSender<RollupDBMessage>,
+    downstream: Downstream,
+    health: Arc<NodeHealth>,
+) {
+    let _alive = health.start(Component::Prover);
+    let mut early = BTreeMap::new();
+    let mut next_sequence = 0u64;
+
+    for (sequence, proved) in results {
+        health.record_activity(Component::Prover);
+        let mut ready = Vec::new();
+        match sequence {
+            Some(sequence) => {
+                early.insert(sequence, proved);
+                while let Some(proved) = early.remove(&next_sequence) {
+                    next_sequence += 1;
+                    ready.push((proved, true));
+                }
+            }
+            None => ready.push((proved, false)),
+        }
+        for (proved, in_order) in ready {
+            if let Err(e) = release(proved, in_order, &rollupdb_sender, &downstream, &health) {
+                log::error!("Proving pipeline stopping: {}", e);
+                health.mark_failed(Component::Prover, e);
+                return;
+            }
+        }
+        METRICS.proofs_awaiting_order.set(early.len() as i64);
+    }
+}
+
+/// Stores a batch's proof, or its failure, and passes the batch on. `in_order` is false
+/// for a batch proved again, which comes after batches sealed later than it.
+fn release(
+    proved: ProvedBatch,
+    in_order: bool,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    downstream: &Downstream,
+    health: &NodeHealth,
+) -> Result<()> {
+    let ProvedBatch { job, result } = proved;
+    let _entered = job.span.enter();
+    let batch_id = job.witness.batch_id.clone();
+
+    let artifact = match result {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            log::error!("ZK proof generation failed for batch {}: {:#}", batch_id, e);
+            METRICS.proof_generation_failures.inc();
+            // its transactions are committed already, so keep what it takes to prove it again
+            rollupdb_sender.send(RollupDBMessage {
+                store_failed_proof: Some(FailedProofMessage {
+                    batch_id,
+                    witness: job.witness,
+                    transaction_signatures: job.transaction_signatures,
+                    error_message: format!("Proof generation failed: {:#}", e),
+                }),
+                span: Some(Span::current()),
+                ..Default::default()
+            })?;
+            if let (Downstream::Aggregator(aggregator), true) = (downstream, in_order) {
+                aggregator.send(Released::Gap)?;
+            }
+            return Ok(());
+        }
+    };
+    log::info!("ZK proof generated successfully for batch: {}", batch_id);
+
+    rollupdb_sender.send(RollupDBMessage {
+        store_batch_proof: Some(StoreBatchProofMessage {
+            batch_id: batch_id.clone(),
+            artifact: artifact.clone(),
+            transaction_signatures: job.transaction_signatures.clone(),
+            aggregate: None,
+        }),
+        span: Some(Span::current()),
+        ..Default::default()
+    })?;
+
+    match downstream {
+        Downstream::Settlement(settler_sender) => {
+            log::info!("Sending batch to settlement: {}", batch_id);
+            settler_sender.send(SettlementJob {
+                batch_id,
+                aggregated: Vec::new(),
+                artifact,
+                transaction_signatures: job.transaction_signatures,
+                span: job.span.clone(),
+            })?;
+        }
+        Downstream::Aggregator(aggregator) => {
+            log::info!("Sending batch to aggregation: {}", batch_id);
+            // a batch proved again folds on its own, never in the middle of a later window
+            if !in_order {
+                aggregator.send(Released::Gap)?;
+            }
+            aggregator.send(Released::Proved {
+                witness: job.witness,
+                artifact,
+                transaction_signatures: job.transaction_signatures,
+                span: job.span.clone(),
+            })?;
+            if !in_order {
+                aggregator.send(Released::Gap)?;
+            }
+        }
+    }
+    health.record_batch();
+    Ok(())
+}
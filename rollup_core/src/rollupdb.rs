@@ -1,68 +1,120 @@
 use async_channel::Sender as ASender;
-use crossbeam::channel::{Receiver as CBReceiver, Sender as CBSender};
+use crossbeam::channel::{Receiver as CBReceiver, Sender as CBSender, TrySendError};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     account::AccountSharedData, keccak::Hash, pubkey::Pubkey, transaction::Transaction,
 };
 use std::{
     collections::HashMap,
+    sync::Arc,
     time::{SystemTime, Duration},
 };
-use crate::{frontend::{FrontendMessage, TransactionWithHash}, settle::SettlementJob};
+use tokio::sync::oneshot;
+use tracing::Span;
+use crate::{
+    health::{unix_secs, Component, NodeHealth},
+    metrics::METRICS,
+    frontend::{FrontendMessage, TransactionWithHash},
+    prover::BatchWitness,
+    proving::{ProvingJob, ProvingQueue},
+    settle::SettlementJob,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProofData {
-    pub pi_a: [String; 3],
-    pub pi_b: [[String; 2]; 3],
-    pub pi_c: [String; 3],
-    pub protocol: String,
-    pub curve: String,
-}
+pub use rollup_api::{
+    AdminOutcome, BatchDetails, BatchSummary, CircuitBreakerState, ProofArtifact, ProofStatus, UnsettledProof, UnsettledReport,
+};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum ProofStatus {
-    Generated,
-    Posted,     
-    Verified,   
-    Failed,    
-}
+/// Failed proofs are retried automatically this many times; after that only an operator requeue helps.
+const MAX_AUTO_RETRIES: u32 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchProofRecord {
     pub batch_id: String,
-    pub proof_data: ProofData,
-    pub public_inputs: Vec<String>,
+    /// `None` if proving the batch failed.
+    pub artifact: Option<ProofArtifact>,
+    /// Kept while the batch has no proof, so a retry can prove it again.
+    pub witness: Option<BatchWitness>,
     pub transaction_signatures: Vec<String>,
     pub status: ProofStatus,
     pub created_at: SystemTime,
     pub updated_at: SystemTime,
     pub retry_count: u32,
     pub error_message: Option<String>,
+    /// L1 transaction that verified the proof.
+    pub settlement_signature: Option<String>,
+    /// Set when the proof is an aggregate that settles several batches at once.
+    pub aggregate: Option<AggregateGroup>,
+}
+
+/// Batches whose proofs were folded into one aggregate proof. They settle, fail and
+/// are retried together, in one L1 transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregateGroup {
+    /// The oldest member, which the group's settlement job is named after.
+    pub group_id: String,
+    /// Every batch of the group, oldest first.
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListBatchesQuery {
+    pub offset: u64,
+    pub limit: u32,
+    pub status: Option<ProofStatus>,
 }
 
 pub struct RollupDBMessage {
     pub lock_accounts: Option<Vec<Pubkey>>,
     pub add_processed_transaction: Option<Transaction>,
+    /// A sequenced transaction left out of its batch; unlocks its accounts untouched.
+    pub drop_transaction: Option<DroppedTransaction>,
     pub frontend_get_tx: Option<Hash>,
     pub list_offset: Option<u64>,
     pub list_limit: Option<u32>,
     pub add_settle_proof: Option<String>,
     pub add_new_data: Option<Vec<(Pubkey, AccountSharedData)>>,
     pub store_batch_proof: Option<StoreBatchProofMessage>,
+    /// A committed batch whose proof failed; stored as failed until a retry proves it.
+    pub store_failed_proof: Option<FailedProofMessage>,
     pub update_proof_status: Option<UpdateProofStatusMessage>,
     pub get_proof_by_batch_id: Option<String>,
     pub get_unsettled_proofs: Option<bool>,
     pub retry_failed_proofs: Option<bool>,
     pub trigger_retry_cycle: Option<bool>,
+    pub get_account_snapshot: Option<Vec<Pubkey>>,
+    pub requeue_batch: Option<String>,
+    pub abandon_batch: Option<String>,
+    pub reset_circuit_breaker: Option<bool>,
+    pub list_batches: Option<ListBatchesQuery>,
+    pub get_batch_by_signature: Option<String>,
+    /// Span of the sender (batch or transaction), entered while the message is handled.
+    pub span: Option<Span>,
+    /// Where the answer to a frontend request goes. Every request brings its own, so
+    /// concurrent requests can't be handed each other's replies.
+    pub reply: Option<oneshot::Sender<FrontendMessage>>,
+}
+
+/// A transaction the sequencer took from the mempool but could not execute or prove.
+#[derive(Debug, Clone)]
+pub struct DroppedTransaction {
+    pub transaction: Transaction,
+    pub reason: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct StoreBatchProofMessage {
     pub batch_id: String,
-    pub proof_data: ProofData,
-    pub public_inputs: Vec<String>,
+    pub artifact: ProofArtifact,
     pub transaction_signatures: Vec<String>,
+    pub aggregate: Option<AggregateGroup>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailedProofMessage {
+    pub batch_id: String,
+    pub witness: BatchWitness,
+    pub transaction_signatures: Vec<String>,
+    pub error_message: String,
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +122,7 @@ pub struct UpdateProofStatusMessage {
     pub batch_id: String,
     pub new_status: ProofStatus,
     pub error_message: Option<String>,
+    pub settlement_signature: Option<String>,
 }
 
 #[derive(Debug)]
@@ -77,6 +130,8 @@ pub struct RollupDB {
     accounts_db: HashMap<Pubkey, AccountSharedData>,
     locked_accounts: HashMap<Pubkey, AccountSharedData>,
     transactions: HashMap<Hash, Transaction>,
+    /// Keyed like `transactions`, so a lookup by signature finds why it never made it.
+    dropped_transactions: HashMap<Hash, DroppedTransaction>,
     batch_proofs: HashMap<String, BatchProofRecord>, 
     proof_by_transaction: HashMap<String, String>,
     last_retry_cycle: Option<SystemTime>,
@@ -90,6 +145,7 @@ impl Default for RollupDB {
             accounts_db: HashMap::new(),
             locked_accounts: HashMap::new(),
             transactions: HashMap::new(),
+            dropped_transactions: HashMap::new(),
             batch_proofs: HashMap::new(),
             proof_by_transaction: HashMap::new(),
             last_retry_cycle: None,
@@ -105,7 +161,7 @@ impl RollupDB {
         let now = SystemTime::now();
         
         // if there are too many consecutive failures, back off exponentially
-        if self.consecutive_retry_failures >= 5 {
+        if self.circuit_breaker_open() {
             let backoff_seconds = 60 * (1 << self.consecutive_retry_failures.min(8)); 
             let backoff_duration = Duration::from_secs(backoff_seconds);
             
@@ -127,11 +183,27 @@ impl RollupDB {
         }
         self.last_retry_cycle = Some(now);
         self.retry_cycle_count += 1;
+        METRICS.retry_cycles.inc();
         
         log::info!("DB: Retry cycle #{} approved by circuit breaker", self.retry_cycle_count);
         true
     }
     
+    fn circuit_breaker_open(&self) -> bool {
+        self.consecutive_retry_failures >= 5
+    }
+
+    fn circuit_breaker_state(&self) -> CircuitBreakerState {
+        CircuitBreakerState {
+            consecutive_failures: self.consecutive_retry_failures,
+            retry_cycles: self.retry_cycle_count,
+            secs_since_last_cycle: self.last_retry_cycle
+                .and_then(|last| last.elapsed().ok())
+                .map(|elapsed| elapsed.as_secs()),
+            open: self.circuit_breaker_open(),
+        }
+    }
+
     fn record_retry_cycle_result(&mut self, success_count: usize, fail_count: usize) {
         if success_count > 0 {
             // success resets the failure counter
@@ -143,41 +215,170 @@ impl RollupDB {
             log::warn!("DB: Retry cycle had only failures - circuit breaker counter: {}", 
                       self.consecutive_retry_failures);
         }
+        METRICS.retry_consecutive_failures.set(self.consecutive_retry_failures as i64);
+    }
+
+    /// The batches that settle together with `batch_id`, oldest first: its aggregate group, or itself.
+    fn settlement_group(&self, batch_id: &str) -> Vec<String> {
+        match self.batch_proofs.get(batch_id).and_then(|proof| proof.aggregate.as_ref()) {
+            Some(group) => group.members.clone(),
+            None => vec![batch_id.to_string()],
+        }
+    }
+
+    /// Failed proofs the retry budget still allows, as settlement groups, oldest first.
+    /// A group is only retried when every member is eligible, so one aggregate proof is
+    /// never posted once per member.
+    fn retryable_groups(&self) -> Vec<Vec<String>> {
+        let eligible = |batch_id: &String| {
+            self.batch_proofs
+                .get(batch_id)
+                .is_some_and(|proof| proof.status == ProofStatus::Failed && proof.retry_count < MAX_AUTO_RETRIES)
+        };
+        let mut failed: Vec<&BatchProofRecord> = self.batch_proofs.values().filter(|proof| eligible(&proof.batch_id)).collect();
+        failed.sort_by_key(|proof| proof.created_at);
+
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for proof in failed {
+            let group = self.settlement_group(&proof.batch_id);
+            if !groups.contains(&group) && group.iter().all(eligible) {
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    fn verified_member<'a>(&self, group: &'a [String]) -> Option<&'a String> {
+        group.iter().find(|batch_id| self.batch_proofs.get(*batch_id).is_some_and(|proof| proof.status == ProofStatus::Verified))
+    }
+
+    fn update_group(&mut self, group: &[String], update: impl Fn(&mut BatchProofRecord)) {
+        for batch_id in group {
+            if let Some(proof) = self.batch_proofs.get_mut(batch_id) {
+                update(proof);
+                proof.updated_at = SystemTime::now();
+            }
+        }
+    }
+
+    /// The job that settles `group` again with its stored proof.
+    fn settlement_job(&self, group: &[String], span: Span) -> Result<SettlementJob, String> {
+        let mut transaction_signatures = Vec::new();
+        for batch_id in group {
+            let proof = self.batch_proofs.get(batch_id).ok_or_else(|| format!("No proof found for batch {}", batch_id))?;
+            transaction_signatures.extend(proof.transaction_signatures.iter().cloned());
+        }
+        let (first, aggregated) = group.split_first().ok_or("Empty settlement group")?;
+        let artifact = self.batch_proofs[first].artifact.clone().ok_or_else(|| format!("Batch {} has no proof", first))?;
+        Ok(SettlementJob {
+            batch_id: first.clone(),
+            aggregated: aggregated.to_vec(),
+            artifact,
+            transaction_signatures,
+            span,
+        })
+    }
+
+    /// Queues `group` again: a batch whose proving failed goes back to the provers,
+    /// anything else to settlement with its stored proof.
+    fn resubmit(
+        &self,
+        group: &[String],
+        span: Span,
+        settlement_sender: &CBSender<SettlementJob>,
+        proving_queue: &ProvingQueue,
+    ) -> Result<(), String> {
+        if let [batch_id] = group {
+            if let Some(BatchProofRecord { artifact: None, witness: Some(witness), transaction_signatures, .. }) =
+                self.batch_proofs.get(batch_id)
+            {
+                let job = ProvingJob { witness: witness.clone(), transaction_signatures: transaction_signatures.clone(), span };
+                return proving_queue.reprove(job).map_err(|e| e.to_string());
+            }
+        }
+        settlement_sender.try_send(self.settlement_job(group, span)?).map_err(|e| match e {
+            TrySendError::Full(_) => "Settlement queue full".to_string(),
+            TrySendError::Disconnected(_) => "Settlement channel disconnected".to_string(),
+        })
+    }
+
+    fn batch_details(&self, batch_id: &str) -> Option<BatchDetails> {
+        let record = self.batch_proofs.get(batch_id)?.clone();
+        let transactions = record
+            .transaction_signatures
+            .iter()
+            .filter_map(|signature| {
+                let hash = solana_sdk::keccak::hashv(&[signature.as_bytes()]);
+                self.transactions.get(&hash).map(|tx| TransactionWithHash {
+                    hash: hash.to_string(),
+                    transaction: tx.clone(),
+                })
+            })
+            .collect();
+        Some(BatchDetails {
+            batch_id: record.batch_id,
+            status: record.status,
+            transaction_signatures: record.transaction_signatures,
+            transactions,
+            proof: record.artifact,
+            retry_count: record.retry_count,
+            error_message: record.error_message,
+            created_at: unix_secs(record.created_at),
+            updated_at: unix_secs(record.updated_at),
+            settlement_signature: record.settlement_signature,
+        })
+    }
+
+    fn publish_metrics(&self) {
+        METRICS.db_accounts.with_label_values(&["available"]).set(self.accounts_db.len() as i64);
+        METRICS.db_accounts.with_label_values(&["locked"]).set(self.locked_accounts.len() as i64);
+        METRICS.db_transactions.set(self.transactions.len() as i64);
+
+        let mut by_status: HashMap<&'static str, i64> = ProofStatus::ALL.iter().map(|s| (s.as_str(), 0)).collect();
+        for proof in self.batch_proofs.values() {
+            *by_status.entry(proof.status.as_str()).or_insert(0) += 1;
+        }
+        for (status, count) in by_status {
+            METRICS.proofs_by_status.with_label_values(&[status]).set(count);
+        }
     }
 
     pub async fn run(
         rollup_db_receiver: CBReceiver<RollupDBMessage>,
-        frontend_sender: ASender<FrontendMessage>,
         account_sender: ASender<Option<Vec<(Pubkey, AccountSharedData)>>>,
         settlement_sender: CBSender<SettlementJob>,
+        proving_queue: ProvingQueue,
+        health: Arc<NodeHealth>,
     ) {
+        let _alive = health.start(Component::RollupDb);
         let mut db = RollupDB::default();
-        let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
-        
+
         log::info!("RollupDB started with complete retry logic and circuit breaker");
 
         while let Ok(msg) = rollup_db_receiver.recv() {
+            // responses below never wait (the account channel is unbounded, replies are oneshot):
+            // an entered span guard can't be held across an await on a spawned task
+            let _entered = msg.span.clone().map(Span::entered);
+            let reply = msg.reply;
+            health.record_activity(Component::RollupDb);
             log::debug!("RollupDB received a message");
             if let Some(accounts_to_lock) = msg.lock_accounts {
                 log::info!("DB: Locking and fetching {} accounts", accounts_to_lock.len());
                 let mut fetched: Vec<(Pubkey, AccountSharedData)> = Vec::with_capacity(accounts_to_lock.len());
                 
+                // accounts L2 has not touched yet are loaded from L1 by the sequencer, never here:
+                // a blocking RPC call would stall every other message behind it
                 for pubkey in accounts_to_lock {
-                    let account_data = db.accounts_db.remove(&pubkey).or_else(|| {
-                        log::warn!("Account {} not in local DB, fetching from L1", pubkey);
-                        rpc_client.get_account(&pubkey).ok().map(|acc| acc.into())
-                    });
-
-                    if let Some(data) = account_data {
+                    if let Some(data) = db.accounts_db.remove(&pubkey) {
                         db.locked_accounts.insert(pubkey, data.clone());
                         fetched.push((pubkey, data));
                     } else {
-                        log::error!("FATAL: Could not load account {} from L1", pubkey);
+                        log::debug!("Account {} not in L2 state yet", pubkey);
                     }
                 }
 
                 log::info!("DB: Sending {} accounts to sequencer", fetched.len());
-                if let Err(e) = account_sender.send(Some(fetched)).await {
+                if let Err(e) = account_sender.send_blocking(Some(fetched)) {
                     log::error!("Failed to send accounts to sequencer: {}", e);
                 }
             }
@@ -201,6 +402,20 @@ impl RollupDB {
                 log::info!("State update complete. Locked: {}, Available: {}, Total transactions: {}", 
                           db.locked_accounts.len(), db.accounts_db.len(), db.transactions.len());
             }
+            else if let Some(dropped) = msg.drop_transaction {
+                let signature = dropped.transaction.signatures[0];
+                log::info!("DB: Transaction {} dropped from its batch: {}", signature, dropped.reason);
+
+                // the batch locked them as they were, so they go back unchanged; an account a
+                // committed transaction of the same batch also used has already been replaced
+                for pubkey in dropped.transaction.message.account_keys.iter() {
+                    if let Some(account) = db.locked_accounts.remove(pubkey) {
+                        db.accounts_db.entry(*pubkey).or_insert(account);
+                    }
+                }
+                let tx_hash = solana_sdk::keccak::hashv(&[signature.to_string().as_bytes()]);
+                db.dropped_transactions.insert(tx_hash, dropped);
+            }
             // here we perform a single transaction lookup
             else if let Some(get_this_hash_tx) = msg.frontend_get_tx {
                 log::info!("Frontend requesting transaction: {}", get_this_hash_tx);
@@ -210,26 +425,25 @@ impl RollupDB {
                     FrontendMessage {
                         get_tx: Some(get_this_hash_tx),
                         transaction: Some(req_tx.clone()),
-                        transactions: None,
-                        total: None,
-                        has_more: None,
-                        error: None,
+                        ..Default::default()
+                    }
+                } else if let Some(dropped) = db.dropped_transactions.get(&get_this_hash_tx) {
+                    FrontendMessage {
+                        get_tx: Some(get_this_hash_tx),
+                        transaction: Some(dropped.transaction.clone()),
+                        error: Some(format!("Dropped from its batch: {}", dropped.reason)),
+                        ..Default::default()
                     }
                 } else {
                     log::warn!("Transaction not found: {}", get_this_hash_tx);
                     FrontendMessage {
                         get_tx: Some(get_this_hash_tx),
-                        transaction: None,
-                        transactions: None,
-                        total: None,
-                        has_more: None,
                         error: Some("Transaction not found".to_string()),
+                        ..Default::default()
                     }
                 };
                 
-                if let Err(e) = frontend_sender.send(response).await {
-                    log::error!("Failed to send transaction response to frontend: {}", e);
-                }
+                send_reply(reply, response);
             }
             else if let (Some(offset), Some(limit)) = (msg.list_offset, msg.list_limit) {
                 log::info!("Frontend requesting transaction list: offset={}, limit={}", offset, limit);
@@ -256,32 +470,58 @@ impl RollupDB {
                           txs.len(), offset, end, total);
                 
                 let response = FrontendMessage {
-                    get_tx: None,
-                    transaction: None,
                     transactions: Some(txs),
                     total: Some(total),
                     has_more: Some(has_more),
-                    error: None,
+                    ..Default::default()
                 };
                 
-                if let Err(e) = frontend_sender.send(response).await {
-                    log::error!("Failed to send transaction list to frontend: {}", e);
+                send_reply(reply, response);
+            }
+            // read-only view of account state for simulation, nothing is locked or moved
+            else if let Some(snapshot_keys) = msg.get_account_snapshot {
+                log::info!("DB: Building read-only snapshot of {} accounts", snapshot_keys.len());
+                let mut snapshot: Vec<(Pubkey, AccountSharedData)> = Vec::with_capacity(snapshot_keys.len());
+
+                for pubkey in snapshot_keys {
+                    let account_data = db.accounts_db.get(&pubkey)
+                        .or_else(|| db.locked_accounts.get(&pubkey))
+                        .cloned();
+
+                    if let Some(data) = account_data {
+                        snapshot.push((pubkey, data));
+                    } else {
+                        log::debug!("Account {} not found for snapshot", pubkey);
+                    }
                 }
+
+                let response = FrontendMessage {
+                    accounts: Some(snapshot),
+                    ..Default::default()
+                };
+
+                send_reply(reply, response);
             }
             else if let Some(store_proof) = msg.store_batch_proof {
                 log::info!("DB: Storing batch proof: {}", store_proof.batch_id);
                 
                 let now = SystemTime::now();
+                // a batch proved again, or folded into an aggregate, keeps its age and retry count
+                let (created_at, retry_count) = db.batch_proofs
+                    .get(&store_proof.batch_id)
+                    .map_or((now, 0), |proof| (proof.created_at, proof.retry_count));
                 let proof_record = BatchProofRecord {
                     batch_id: store_proof.batch_id.clone(),
-                    proof_data: store_proof.proof_data,
-                    public_inputs: store_proof.public_inputs,
+                    artifact: Some(store_proof.artifact),
+                    witness: None,
                     transaction_signatures: store_proof.transaction_signatures.clone(),
                     status: ProofStatus::Generated,
-                    created_at: now,
+                    created_at,
                     updated_at: now,
-                    retry_count: 0,
+                    retry_count,
                     error_message: None,
+                    settlement_signature: None,
+                    aggregate: store_proof.aggregate,
                 };
 
                 db.batch_proofs.insert(store_proof.batch_id.clone(), proof_record);
@@ -293,14 +533,46 @@ impl RollupDB {
 
                 log::info!("Batch proof stored successfully. Total proofs: {}", db.batch_proofs.len());
             }
+            else if let Some(failed_proof) = msg.store_failed_proof {
+                log::info!("DB: Storing failed proof of batch {}", failed_proof.batch_id);
+
+                let now = SystemTime::now();
+                let (created_at, retry_count) = db.batch_proofs
+                    .get(&failed_proof.batch_id)
+                    .map_or((now, 0), |proof| (proof.created_at, proof.retry_count));
+                for tx_sig in &failed_proof.transaction_signatures {
+                    db.proof_by_transaction.insert(tx_sig.clone(), failed_proof.batch_id.clone());
+                }
+                db.batch_proofs.insert(failed_proof.batch_id.clone(), BatchProofRecord {
+                    batch_id: failed_proof.batch_id,
+                    artifact: None,
+                    witness: Some(failed_proof.witness),
+                    transaction_signatures: failed_proof.transaction_signatures,
+                    status: ProofStatus::Failed,
+                    created_at,
+                    updated_at: now,
+                    retry_count,
+                    error_message: Some(failed_proof.error_message),
+                    settlement_signature: None,
+                    aggregate: None,
+                });
+            }
             else if let Some(update_status) = msg.update_proof_status {
                 log::info!("DB: Updating proof status: {} -> {:?}", 
                           update_status.batch_id, update_status.new_status);
 
                 if let Some(proof_record) = db.batch_proofs.get_mut(&update_status.batch_id) {
+                    // a settlement that was already in flight when the operator gave up must not revive the batch
+                    if proof_record.status == ProofStatus::Abandoned && update_status.new_status != ProofStatus::Verified {
+                        log::info!("Batch {} is abandoned, ignoring status {:?}", update_status.batch_id, update_status.new_status);
+                        continue;
+                    }
                     proof_record.status = update_status.new_status;
                     proof_record.updated_at = SystemTime::now();
                     proof_record.error_message = update_status.error_message;
+                    if update_status.settlement_signature.is_some() {
+                        proof_record.settlement_signature = update_status.settlement_signature;
+                    }
                     
                     log::info!("Proof status updated successfully");
                 } else {
@@ -310,74 +582,210 @@ impl RollupDB {
             else if let Some(batch_id) = msg.get_proof_by_batch_id {
                 log::info!("DB: Looking up proof: {}", batch_id);
                 
-                if let Some(proof_record) = db.batch_proofs.get(&batch_id) {
-                    log::info!("Found proof: {} with status: {:?}", batch_id, proof_record.status);
-                    // TODO: Send proof back through a response channel
-                } else {
-                    log::warn!("No proof found for batch_id: {}", batch_id);
-                }
+                let response = match db.batch_details(&batch_id) {
+                    Some(details) => {
+                        log::info!("Found proof: {} with status: {:?}", batch_id, details.status);
+                        FrontendMessage { batch: Some(details), ..Default::default() }
+                    }
+                    None => {
+                        log::warn!("No proof found for batch_id: {}", batch_id);
+                        FrontendMessage { error: Some(format!("Batch {} not found", batch_id)), ..Default::default() }
+                    }
+                };
+
+                send_reply(reply, response);
+            }
+            else if let Some(signature) = msg.get_batch_by_signature {
+                log::info!("DB: Looking up batch for transaction: {}", signature);
+
+                let dropped = db.dropped_transactions.get(&solana_sdk::keccak::hashv(&[signature.as_bytes()]));
+                let response = match db.proof_by_transaction.get(&signature).and_then(|batch_id| db.batch_details(batch_id)) {
+                    Some(details) => FrontendMessage { batch: Some(details), ..Default::default() },
+                    None => FrontendMessage {
+                        error: Some(match dropped {
+                            Some(dropped) => format!("Transaction {} was dropped from its batch: {}", signature, dropped.reason),
+                            None => format!("No batch found for transaction {}", signature),
+                        }),
+                        ..Default::default()
+                    },
+                };
+
+                send_reply(reply, response);
+            }
+            else if let Some(query) = msg.list_batches {
+                log::info!("DB: Listing batches: offset={}, limit={}, status={:?}", query.offset, query.limit, query.status);
+
+                let mut records: Vec<&BatchProofRecord> = db.batch_proofs
+                    .values()
+                    .filter(|p| query.status.as_ref().is_none_or(|status| &p.status == status))
+                    .collect();
+                // newest first
+                records.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.batch_id.cmp(&b.batch_id)));
+
+                let total = records.len() as u64;
+                let offset = query.offset.min(total) as usize;
+                let limit = query.limit.clamp(1, 500) as usize;
+                let end = (offset + limit).min(total as usize);
+
+                let batches = records[offset..end]
+                    .iter()
+                    .map(|p| BatchSummary {
+                        batch_id: p.batch_id.clone(),
+                        status: p.status.clone(),
+                        transaction_count: p.transaction_signatures.len(),
+                        retry_count: p.retry_count,
+                        created_at: unix_secs(p.created_at),
+                        updated_at: unix_secs(p.updated_at),
+                        settlement_signature: p.settlement_signature.clone(),
+                    })
+                    .collect();
+
+                let response = FrontendMessage {
+                    batches: Some(batches),
+                    total: Some(total),
+                    has_more: Some((end as u64) < total),
+                    ..Default::default()
+                };
+
+                send_reply(reply, response);
             }
             else if let Some(_get_unsettled) = msg.get_unsettled_proofs {
-                let unsettled: Vec<&BatchProofRecord> = db.batch_proofs
+                let mut unsettled: Vec<&BatchProofRecord> = db.batch_proofs
                     .values()
-                    .filter(|p| matches!(p.status, ProofStatus::Generated | ProofStatus::Posted | ProofStatus::Failed))
+                    .filter(|p| p.status != ProofStatus::Verified)
                     .collect();
+                unsettled.sort_by_key(|p| p.created_at);
                 
                 log::info!("DB: Found {} unsettled proofs", unsettled.len());
-                for proof in &unsettled {
-                    log::info!("  - {}: {:?} (retry: {})", 
-                              proof.batch_id, proof.status, proof.retry_count);
-                }
-                // TODO: Send unsettled proofs and diagnostics back thruogh a response channel
+                let proofs = unsettled
+                    .into_iter()
+                    .map(|proof| UnsettledProof {
+                        batch_id: proof.batch_id.clone(),
+                        status: proof.status.clone(),
+                        transaction_count: proof.transaction_signatures.len(),
+                        retry_count: proof.retry_count,
+                        auto_retry_eligible: proof.status == ProofStatus::Failed && proof.retry_count < MAX_AUTO_RETRIES,
+                        age_secs: proof.created_at.elapsed().unwrap_or_default().as_secs(),
+                        secs_since_update: proof.updated_at.elapsed().unwrap_or_default().as_secs(),
+                        error_message: proof.error_message.clone(),
+                    })
+                    .collect();
+
+                let response = FrontendMessage {
+                    unsettled: Some(UnsettledReport { proofs, circuit_breaker: db.circuit_breaker_state() }),
+                    ..Default::default()
+                };
+
+                send_reply(reply, response);
             }
             
             else if let Some(_retry_failed) = msg.retry_failed_proofs {
                 log::info!("DB: Manual retry triggered");
                 
-                let failed_proofs: Vec<(String, BatchProofRecord)> = db.batch_proofs
-                    .iter()
-                    .filter(|(_, proof)| proof.status == ProofStatus::Failed && proof.retry_count < 3)
-                    .map(|(batch_id, proof)| (batch_id.clone(), proof.clone()))
-                    .collect();
-
-                log::info!("DB: Found {} failed proofs eligible for manual retry", failed_proofs.len());
+                let failed_groups = db.retryable_groups();
+                log::info!("DB: Found {} failed settlements eligible for manual retry", failed_groups.len());
                 
-                let mut success_count = 0;
-                let mut fail_count = 0;
+                let mut outcome = AdminOutcome::default();
                 
-                for (batch_id, mut proof_record) in failed_proofs {
-                    proof_record.retry_count += 1;
-                    proof_record.status = ProofStatus::Generated;
-                    proof_record.updated_at = SystemTime::now();
-                    proof_record.error_message = Some(format!("Manual retry attempt #{}", proof_record.retry_count));
-                    
-                    db.batch_proofs.insert(batch_id.clone(), proof_record.clone());
-                    
-                    let retry_job = SettlementJob {
-                        batch_id: batch_id.clone(),
-                        proof_data: Some(proof_record.proof_data),
-                        transaction_signatures: proof_record.transaction_signatures,
-                        proof_file_path: Some(format!("build/proof_batch_{}.json", batch_id)),
-                    };
+                for group in failed_groups {
+                    db.update_group(&group, |proof| {
+                        proof.retry_count += 1;
+                        proof.status = ProofStatus::Generated;
+                        proof.error_message = Some(format!("Manual retry attempt #{}", proof.retry_count));
+                    });
+                    let attempt = db.batch_proofs[&group[0]].retry_count;
+                    let span = tracing::info_span!("settlement_retry", batch_id = %group[0], attempt);
                     
-                    match settlement_sender.try_send(retry_job) {
+                    match db.resubmit(&group, span, &settlement_sender, &proving_queue) {
                         Ok(()) => {
-                            log::info!("  - Successfully queued manual retry: {}", batch_id);
-                            success_count += 1;
+                            log::info!("  - Successfully queued manual retry: {:?}", group);
+                            outcome.queued.extend(group);
                         }
                         Err(e) => {
-                            log::error!("  - Failed to queue manual retry {}: {}", batch_id, e);
-                            fail_count += 1;
-                            
-                            if let Some(proof) = db.batch_proofs.get_mut(&batch_id) {
+                            log::error!("  - Failed to queue manual retry {:?}: {}", group, e);
+                            db.update_group(&group, |proof| {
                                 proof.status = ProofStatus::Failed;
                                 proof.error_message = Some(format!("Failed to queue retry: {}", e));
-                            }
+                            });
+                            outcome.failed.extend(group);
                         }
                     }
                 }
                 
-                log::info!("Manual retry complete - Success: {}, Failed: {}", success_count, fail_count);
+                log::info!("Manual retry complete - Success: {}, Failed: {}", outcome.queued.len(), outcome.failed.len());
+                outcome.message = format!("Queued {} failed batches again", outcome.queued.len());
+                send_admin_response(reply, Ok(outcome));
+            }
+
+            else if let Some(batch_id) = msg.requeue_batch {
+                log::info!("DB: Operator requeue of batch {}", batch_id);
+                // an aggregated batch can only settle together with the rest of its group
+                let group = db.settlement_group(&batch_id);
+                let result = if !db.batch_proofs.contains_key(&batch_id) {
+                    Err(format!("No proof found for batch {}", batch_id))
+                } else if let Some(verified) = db.verified_member(&group) {
+                    Err(format!("Batch {} is already verified", verified))
+                } else {
+                    // an operator requeue starts a fresh automatic retry budget
+                    db.update_group(&group, |proof| {
+                        proof.retry_count = 0;
+                        proof.status = ProofStatus::Generated;
+                        proof.error_message = Some("Requeued by operator".to_string());
+                    });
+
+                    let span = tracing::info_span!("settlement_requeue", batch_id = %group[0]);
+                    let mut outcome = AdminOutcome::default();
+                    match db.resubmit(&group, span, &settlement_sender, &proving_queue) {
+                        Ok(()) => {
+                            outcome.message = format!("Batches {:?} queued again", group);
+                            outcome.queued = group;
+                        }
+                        Err(e) => {
+                            log::error!("Failed to queue requeued batches {:?}: {}", group, e);
+                            db.update_group(&group, |proof| {
+                                proof.status = ProofStatus::Failed;
+                                proof.error_message = Some(format!("Failed to queue requeue: {}", e));
+                            });
+                            outcome.message = format!("Batches {:?} could not be queued: {}", group, e);
+                            outcome.failed = group;
+                        }
+                    }
+                    Ok(outcome)
+                };
+                send_admin_response(reply, result);
+            }
+
+            else if let Some(batch_id) = msg.abandon_batch {
+                log::info!("DB: Operator abandoned batch {}", batch_id);
+                // the rest of an aggregate group cannot settle without it
+                let group = db.settlement_group(&batch_id);
+                let result = if !db.batch_proofs.contains_key(&batch_id) {
+                    Err(format!("No proof found for batch {}", batch_id))
+                } else if let Some(verified) = db.verified_member(&group) {
+                    Err(format!("Batch {} is already verified", verified))
+                } else {
+                    db.update_group(&group, |proof| {
+                        proof.status = ProofStatus::Abandoned;
+                        proof.error_message = Some("Abandoned by operator".to_string());
+                    });
+                    Ok(AdminOutcome {
+                        message: format!("Batches {:?} abandoned", group),
+                        ..Default::default()
+                    })
+                };
+                send_admin_response(reply, result);
+            }
+
+            else if let Some(_reset) = msg.reset_circuit_breaker {
+                log::info!("DB: Operator reset the retry circuit breaker (was {} consecutive failures)",
+                          db.consecutive_retry_failures);
+                db.consecutive_retry_failures = 0;
+                db.last_retry_cycle = None;
+                METRICS.retry_consecutive_failures.set(0);
+                send_admin_response(reply, Ok(AdminOutcome {
+                    message: "Circuit breaker reset".to_string(),
+                    ..Default::default()
+                }));
             }
 
             else if let Some(_trigger_retry) = msg.trigger_retry_cycle {
@@ -388,80 +796,52 @@ impl RollupDB {
                     continue;
                 }
                 
-                let failed_proofs: Vec<(String, BatchProofRecord)> = db.batch_proofs
-                    .iter()
-                    .filter(|(_, proof)| proof.status == ProofStatus::Failed && proof.retry_count < 3)
-                    .map(|(batch_id, proof)| (batch_id.clone(), proof.clone()))
-                    .collect();
+                let failed_groups = db.retryable_groups();
 
-                if failed_proofs.is_empty() {
+                if failed_groups.is_empty() {
                     log::info!("DB: No failed proofs found for retry cycle #{}", db.retry_cycle_count);
                     db.record_retry_cycle_result(0, 0);
                     continue;
                 }
 
-                log::info!("DB: Retry cycle #{} processing {} failed proofs", 
-                          db.retry_cycle_count, failed_proofs.len());
+                log::info!("DB: Retry cycle #{} processing {} failed settlements", 
+                          db.retry_cycle_count, failed_groups.len());
                 
                 let mut success_count = 0;
                 let mut fail_count = 0;
-                let mut skip_count = 0;
                 
-                for (batch_id, mut proof_record) in failed_proofs {
-                    // don't retry if already at max attempts
-                    if proof_record.retry_count >= 3 {
-                        log::debug!("  - Skipping {}: max retry attempts reached", batch_id);
-                        skip_count += 1;
-                        continue;
-                    }
-                    
+                for group in failed_groups {
                     // here we increment the retry count
-                    proof_record.retry_count += 1;
-                    proof_record.status = ProofStatus::Generated;
-                    proof_record.updated_at = SystemTime::now();
-                    proof_record.error_message = Some(format!("Auto-retry cycle #{}, attempt #{}", 
-                                                             db.retry_cycle_count, proof_record.retry_count));
-                    
-                    db.batch_proofs.insert(batch_id.clone(), proof_record.clone());
-                    
-                    let retry_job = SettlementJob {
-                        batch_id: batch_id.clone(),
-                        proof_data: Some(proof_record.proof_data),
-                        transaction_signatures: proof_record.transaction_signatures,
-                        proof_file_path: Some(format!("build/proof_batch_{}.json", batch_id)),
-                    };
+                    let cycle = db.retry_cycle_count;
+                    db.update_group(&group, |proof| {
+                        proof.retry_count += 1;
+                        proof.status = ProofStatus::Generated;
+                        proof.error_message = Some(format!("Auto-retry cycle #{}, attempt #{}", cycle, proof.retry_count));
+                    });
+                    let attempt = db.batch_proofs[&group[0]].retry_count;
+                    let span = tracing::info_span!("settlement_retry", batch_id = %group[0], attempt);
                     
-                    match settlement_sender.try_send(retry_job) {
+                    let error = match db.resubmit(&group, span, &settlement_sender, &proving_queue) {
                         Ok(()) => {
-                            log::info!("  - Auto-retry queued: {} (attempt {})", batch_id, proof_record.retry_count);
+                            log::info!("  - Auto-retry queued: {:?} (attempt {})", group, attempt);
                             success_count += 1;
+                            continue;
                         }
-                        Err(crossbeam::channel::TrySendError::Full(_)) => {
-                            log::warn!("  - Settlement queue full for {}", batch_id);
-                            fail_count += 1;
-                            
-                            if let Some(proof) = db.batch_proofs.get_mut(&batch_id) {
-                                proof.status = ProofStatus::Failed;
-                                proof.error_message = Some("Settlement queue full".to_string());
-                            }
-                        }
-                        Err(crossbeam::channel::TrySendError::Disconnected(_)) => {
-                            log::error!("  - Settlement channel disconnected for {}", batch_id);
-                            fail_count += 1;
-                            
-                            if let Some(proof) = db.batch_proofs.get_mut(&batch_id) {
-                                proof.status = ProofStatus::Failed;
-                                proof.error_message = Some("Settlement channel disconnected".to_string());
-                            }
-                        }
-                    }
+                        Err(e) => e,
+                    };
+                    log::warn!("  - Could not retry {:?}: {}", group, error);
+                    fail_count += 1;
+                    db.update_group(&group, |proof| {
+                        proof.status = ProofStatus::Failed;
+                        proof.error_message = Some(error.clone());
+                    });
                 }
                 
                 // here we record results for our circuit breaker
                 db.record_retry_cycle_result(success_count, fail_count);
                 
-                log::info!("DB: Retry cycle #{} complete - Success: {}, Failed: {}, Skipped: {}", 
-                          db.retry_cycle_count, success_count, fail_count, skip_count);
+                log::info!("DB: Retry cycle #{} complete - Success: {}, Failed: {}", 
+                          db.retry_cycle_count, success_count, fail_count);
                 
                 if fail_count > success_count && fail_count > 0 {
                     log::warn!("DB: Retry cycle #{} had more failures than successes - system may be degraded", 
@@ -473,44 +853,36 @@ impl RollupDB {
             else if let Some(settle_proof) = msg.add_settle_proof {
                 log::warn!("DB: Received deprecated add_settle_proof: {} - use store_batch_proof instead", settle_proof);
             }
+
+            db.publish_metrics();
         }
         
         log::info!("RollupDB shutting down");
     }
 }
 
-impl ProofData {
-    pub fn from_json_file(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let file_content = std::fs::read_to_string(file_path)?;
-        let json_value: serde_json::Value = serde_json::from_str(&file_content)?;
-        
-        Ok(ProofData {
-            pi_a: [
-                json_value["pi_a"][0].as_str().ok_or_else(|| format!("Missing or invalid pi_a[0] in {}", file_path))?.to_string(),
-                json_value["pi_a"][1].as_str().ok_or_else(|| format!("Missing or invalid pi_a[1] in {}", file_path))?.to_string(),
-                json_value["pi_a"][2].as_str().ok_or_else(|| format!("Missing or invalid pi_a[2] in {}", file_path))?.to_string(),
-            ],
-            pi_b: [
-                [
-                    json_value["pi_b"][0][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[0][0] in {}", file_path))?.to_string(),
-                    json_value["pi_b"][0][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[0][1] in {}", file_path))?.to_string(),
-                ],
-                [
-                    json_value["pi_b"][1][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[1][0] in {}", file_path))?.to_string(),
-                    json_value["pi_b"][1][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[1][1] in {}", file_path))?.to_string(),
-                ],
-                [
-                    json_value["pi_b"][2][0].as_str().ok_or_else(|| format!("Missing or invalid pi_b[2][0] in {}", file_path))?.to_string(),
-                    json_value["pi_b"][2][1].as_str().ok_or_else(|| format!("Missing or invalid pi_b[2][1] in {}", file_path))?.to_string(),
-                ],
-            ],
-            pi_c: [
-                json_value["pi_c"][0].as_str().ok_or_else(|| format!("Missing or invalid pi_c[0] in {}", file_path))?.to_string(),
-                json_value["pi_c"][1].as_str().ok_or_else(|| format!("Missing or invalid pi_c[1] in {}", file_path))?.to_string(),
-                json_value["pi_c"][2].as_str().ok_or_else(|| format!("Missing or invalid pi_c[2] in {}", file_path))?.to_string(),
-            ],
-            protocol: json_value["protocol"].as_str().ok_or_else(|| format!("Missing or invalid protocol in {}", file_path))?.to_string(),
-            curve: json_value["curve"].as_str().ok_or_else(|| format!("Missing or invalid curve in {}", file_path))?.to_string(),
-        })
+/// Answers the frontend request that `reply` came with. A request that timed out
+/// has dropped its end, and its answer goes nowhere.
+fn send_reply(reply: Option<oneshot::Sender<FrontendMessage>>, response: FrontendMessage) {
+    match reply {
+        Some(reply) => {
+            if reply.send(response).is_err() {
+                log::warn!("Frontend stopped waiting for a RollupDB reply");
+            }
+        }
+        None => log::error!("RollupDB request expects a reply but came without a reply channel"),
     }
-}
\ No newline at end of file
+}
+
+fn send_admin_response(reply: Option<oneshot::Sender<FrontendMessage>>, result: Result<AdminOutcome, String>) {
+    let (admin, error) = match result {
+        Ok(outcome) => (Some(outcome), None),
+        Err(e) => (None, Some(e)),
+    };
+    let response = FrontendMessage {
+        admin,
+        error,
+        ..Default::default()
+    };
+    send_reply(reply, response);
+}
@@ -1,13 +1,11 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, RwLock},
-    process::Command,
-    fs,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 use anyhow::{anyhow, Result};
 use async_channel::Receiver;
-use crossbeam::channel::{Receiver as CBReceiver, Sender as CBSender};
+use crossbeam::channel::Sender as CBSender;
 use solana_client::rpc_client::RpcClient;
 use solana_compute_budget::compute_budget::SVMTransactionExecutionBudget;
 use solana_sdk::{
@@ -19,39 +17,38 @@ use solana_sdk::{
     transaction::{SanitizedTransaction, Transaction},
 };
 use solana_svm::{
+    transaction_processing_callback::TransactionProcessingCallback,
     transaction_processing_result::ProcessedTransaction,
     transaction_processor::{
         TransactionProcessingConfig, TransactionProcessingEnvironment,
     },
 };
 use solana_svm_feature_set::SVMFeatureSet;
-use std::{convert::TryInto, os::unix::fs::PermissionsExt};
-use serde::{Deserialize, Serialize};
-use serde_json;
+use std::convert::TryInto;
+use tracing::{Instrument, Span};
 
 
 use crate::{
+    config::NodeConfig,
+    health::{Component, NodeHealth},
     loader::RollupAccountLoader,
+    mempool::Mempool,
+    metrics::METRICS,
     processor::{create_transaction_batch_processor, get_transaction_check_results, RollupForkGraph},
-    rollupdb::{RollupDBMessage, StoreBatchProofMessage, ProofData},
-    SettlementJob,
+    prover::{AccountPath, AccountWitness, BatchWitness, StateTree, TransferWitness},
+    proving::{ProvingJob, ProvingQueue},
+    rollupdb::{DroppedTransaction, RollupDBMessage},
+    validation::{find_system_transfer, SystemTransfer},
 };
 
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Clone)]
 struct TransactionBatch {
     pub transactions: Vec<Transaction>,
-    pub signatures: Vec<String>,
     pub batch_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatchCircuitInput {
-    pub amounts: Vec<String>,
-    pub signature_first_bytes: Vec<String>,
-    pub from_balances_before: Vec<String>,
-    pub from_balances_after: Vec<String>,
-}
-
 impl TransactionBatch {
     fn new(transactions: Vec<Transaction>) -> Self {
         let signatures: Vec<String> = transactions
@@ -63,7 +60,6 @@ impl TransactionBatch {
         
         Self {
             transactions,
-            signatures,
             batch_id,
         }
     }
@@ -78,42 +74,87 @@ impl TransactionBatch {
     }
 }
 
-impl BatchCircuitInput {
-    pub fn new() -> Self {
-        Self {
-            amounts: Vec::new(),
-            signature_first_bytes: Vec::new(),
-            from_balances_before: Vec::new(),
-            from_balances_after: Vec::new(),
-        }
-    }
-
-    pub fn add_transaction(&mut self, amount: u64, signature_first_byte: u32, balance_before: u64, balance_after: u64) {
-        self.amounts.push(amount.to_string());
-        self.signature_first_bytes.push(signature_first_byte.to_string());
-        self.from_balances_before.push(balance_before.to_string());
-        self.from_balances_after.push(balance_after.to_string());
-    }
-
-    pub fn pad_to_size(&mut self, target_size: usize) {
-        while self.amounts.len() < target_size {
-            self.amounts.push("1".to_string());
-            self.signature_first_bytes.push("1".to_string());
-            self.from_balances_before.push("0".to_string());
-            self.from_balances_after.push("0".to_string());
-        }
-    }
+/// The transactions of a batch that made it into L2, with everything the prover needs.
+struct ExecutedBatch {
+    transactions: Vec<Transaction>,
+    transfers: Vec<TransferWitness>,
+    accounts: Vec<AccountWitness>,
+    state_paths: Vec<AccountPath>,
+}
 
-    pub fn len(&self) -> usize {
-        self.amounts.len()
-    }
+/// One run of a batch through the SVM, not yet committed.
+struct Execution<'a> {
+    commits: Vec<(&'a Transaction, Vec<(Pubkey, AccountSharedData)>)>,
+    transfers: Vec<TransferWitness>,
+    pre_state: BTreeMap<Pubkey, AccountWitness>,
+    /// Each account's lamports after the batch.
+    balances: HashMap<Pubkey, u64>,
+    paths: BTreeMap<Pubkey, AccountPath>,
 }
 
+/// Executes and commits a batch, leaving out every transaction that fails to execute or
+/// cannot be proved. RollupDB records why and unlocks its accounts, and the rest run again
+/// without it, since their balances may have depended on it. `None` if nothing is left.
 fn process_transaction_batch(
     transaction_batch: &[Transaction],
     rollup_account_loader: &mut RollupAccountLoader,
+    state_tree: &mut StateTree,
     rollupdb_sender: &CBSender<RollupDBMessage>,
-) -> Result<bool> {
+) -> Result<Option<ExecutedBatch>> {
+    let mut pending: Vec<&Transaction> = transaction_batch.iter().collect();
+    let execution = loop {
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        match execute_batch(&pending, rollup_account_loader, state_tree) {
+            Ok(execution) => break execution,
+            // last first, so the indices of the others still hold
+            Err(dropped) => {
+                for (i, reason) in dropped.into_iter().rev() {
+                    let transaction = pending.remove(i).clone();
+                    log::warn!("Dropping transaction {} from the batch: {}", transaction.signatures[0], reason);
+                    METRICS.transactions_dropped.inc();
+                    rollupdb_sender.send(RollupDBMessage {
+                        drop_transaction: Some(DroppedTransaction { transaction, reason }),
+                        ..Default::default()
+                    })?;
+                }
+            }
+        }
+    };
+
+    // paths were taken for every touched account, so none of their leaves can be taken
+    for (key, balance) in &execution.balances {
+        state_tree.set(&key.to_bytes(), *balance).map_err(|e| anyhow!("{}", e))?;
+    }
+    for (tx, new_data) in &execution.commits {
+        for (pubkey, account_data) in new_data {
+            rollup_account_loader.add_account(*pubkey, account_data.clone());
+        }
+        log::info!("Transaction successful. Sending state update to DB for tx: {:?}", tx.signatures[0]);
+        rollupdb_sender.send(RollupDBMessage {
+            add_processed_transaction: Some((*tx).clone()),
+            add_new_data: Some(new_data.clone()),
+            span: Some(tracing::info_span!("commit_transaction", signature = %tx.signatures[0])),
+            ..Default::default()
+        })?;
+    }
+    Ok(Some(ExecutedBatch {
+        transactions: execution.commits.into_iter().map(|(tx, _)| tx.clone()).collect(),
+        transfers: execution.transfers,
+        accounts: execution.pre_state.into_values().collect(),
+        state_paths: execution.paths.into_values().collect(),
+    }))
+}
+
+/// Runs `transactions` through the SVM without committing anything. `Err` maps the position of
+/// each transaction that has to go to the reason: every one that can't be proved over the state
+/// as it is, or else the first that fails to execute, since later ones ran on top of it.
+fn execute_batch<'a>(
+    transactions: &[&'a Transaction],
+    rollup_account_loader: &RollupAccountLoader,
+    state_tree: &StateTree,
+) -> std::result::Result<Execution<'a>, BTreeMap<usize, String>> {
     let compute_budget = SVMTransactionExecutionBudget::default();
     let feature_set = SVMFeatureSet::all_enabled();
     let fee_structure = FeeStructure::default();
@@ -135,12 +176,55 @@ fn process_transaction_batch(
         rent_collector: Some(&rent_collector),
     };
 
-    let sanitized_txs: Vec<SanitizedTransaction> = transaction_batch
-        .iter()
-        .map(|tx| SanitizedTransaction::try_from_legacy_transaction(tx.clone(), &HashSet::new()).unwrap())
-        .collect();
+    // transactions are validated at submission, but never let a malformed one take down the loop
+    let mut dropped = BTreeMap::new();
+    let mut sanitized_txs: Vec<SanitizedTransaction> = Vec::with_capacity(transactions.len());
+    // every account a transfer touches as the batch found it, for the zkVM guest to re-execute
+    let mut pre_state: BTreeMap<Pubkey, AccountWitness> = BTreeMap::new();
+    // the circuit proves the batch against the global state tree, so accounts already in it must agree with L2
+    let mut paths: BTreeMap<Pubkey, AccountPath> = BTreeMap::new();
+    for (i, tx) in transactions.iter().enumerate() {
+        match SanitizedTransaction::try_from_legacy_transaction((*tx).clone(), &HashSet::new()) {
+            Ok(sanitized_tx) => sanitized_txs.push(sanitized_tx),
+            Err(e) => {
+                dropped.insert(i, format!("sanitization failed: {}", e));
+                continue;
+            }
+        }
+        let transfer = match find_system_transfer(tx) {
+            Ok(transfer) => transfer,
+            Err(e) => {
+                dropped.insert(i, format!("cannot be proved: {}", e));
+                continue;
+            }
+        };
+        for key in tx.message.account_keys.first().into_iter().chain([&transfer.from, &transfer.to]) {
+            let account = pre_state.entry(*key).or_insert_with(|| {
+                account_witness(key, &rollup_account_loader.get_account_shared_data(key).unwrap_or_default())
+            });
+            match state_tree.path(&key.to_bytes()) {
+                Ok(path) if path.balance.is_some_and(|balance| balance != account.lamports) => {
+                    let reason = format!("account {} holds {} lamports, the state tree {:?}", key, account.lamports, path.balance);
+                    dropped.insert(i, reason);
+                }
+                Ok(path) => {
+                    paths.insert(*key, path);
+                }
+                Err(e) => {
+                    dropped.insert(i, format!("account {} has no place in the state tree: {}", key, e));
+                }
+            }
+        }
+    }
+    if !dropped.is_empty() {
+        return Err(dropped);
+    }
+    // lamports of each account a transfer touches, as of the previous transaction in the batch
+    let mut balances: HashMap<Pubkey, u64> = pre_state.iter().map(|(key, account)| (*key, account.lamports)).collect();
 
     log::info!("SVM is executing a batch of {} sanitized transactions...", sanitized_txs.len());
+    let svm_span = tracing::info_span!("svm_execute", transactions = sanitized_txs.len()).entered();
+    let svm_timer = METRICS.svm_execution_seconds.start_timer();
     let results = processor.load_and_execute_sanitized_transactions(
         rollup_account_loader,
         &sanitized_txs,
@@ -148,293 +232,156 @@ fn process_transaction_batch(
         &processing_environment,
         &TransactionProcessingConfig::default(),
     );
+    svm_timer.observe_duration();
+    drop(svm_span);
 
-    let mut batch_failed = false;
+    let mut transfers = Vec::with_capacity(transactions.len());
+    // state only moves once every transaction has a witness, so L2 never gets ahead of what can settle
+    let mut commits = Vec::with_capacity(transactions.len());
     for (i, res) in results.processing_results.iter().enumerate() {
-        let original_tx = &transaction_batch[i];
-
-        match res {
-            Ok(ProcessedTransaction::Executed(tx_details)) => {
-                let new_data = tx_details.loaded_transaction.accounts.clone();
-
-                if let Some((payer_pubkey, payer_account)) = new_data.first() {
-                    log::info!("Balance after execution for {}: {} lamports", payer_pubkey, payer_account.lamports());
-                }
-                for (pubkey, account_data) in &new_data {
-                    rollup_account_loader.add_account(*pubkey, account_data.clone());
-                }
-                
-                log::info!("Transaction successful. Sending state update to DB for tx: {:?}", original_tx.signatures[0]);
-                rollupdb_sender.send(RollupDBMessage {
-                    lock_accounts: None,
-                    add_processed_transaction: Some(original_tx.clone()),
-                    add_new_data: Some(new_data.clone()),
-                    frontend_get_tx: None,
-                    add_settle_proof: None,
-                    store_batch_proof: None,
-                    update_proof_status: None,
-                    get_proof_by_batch_id: None,
-                    get_unsettled_proofs: None,
-                    retry_failed_proofs: None,
-                    list_offset: None,
-                    list_limit: None,
-                    trigger_retry_cycle: None,
-                })?;
-            }
-            Err(e) => {
-                log::error!("Transaction in batch failed: {:?}, Error: {}", original_tx.signatures[0], e);
-                batch_failed = true;
-            }
-            _ => {
-                log::warn!("Transaction in batch had no effect: {:?}", original_tx.signatures[0]);
-                batch_failed = true;
-            }
-        }
-    }
-    Ok(!batch_failed)
-}
-
-fn make_script_executable(script_path: &str) -> Result<()> {
-    let mut perms = fs::metadata(script_path)?.permissions();
-    perms.set_mode(perms.mode() | 0o111); 
-    fs::set_permissions(script_path, perms)?;
-    Ok(())
-}
+        let original_tx = transactions[i];
+        let failed = |reason: String| Err(BTreeMap::from([(i, reason)]));
 
-fn verify_circuit_files() -> Result<()> {
-    log::info!("Verifying circuit files and directories...");
-    let current_dir = std::env::current_dir()?;
-    log::info!("Current working directory: {}", current_dir.display());
-    let directories = ["circuit", "scripts"];
-    for dir in directories {
-        let dir_path = current_dir.join(dir);
-        if dir_path.exists() {
-            log::info!("Directory exists: {}", dir_path.display());
-        } else {
-            log::error!("Directory missing: {}", dir_path.display());
-            return Err(anyhow!("Missing directory: {}", dir));
-        }
-    }
-    let circuit_files = [
-        "circuit/system_transfer.circom",
-        "circuit/batch_system_transfer.circom"
-    ];
-    for file in circuit_files {
-        let file_path = current_dir.join(file);
-        if file_path.exists() {
-            log::info!("Circuit file exists: {}", file_path.display());
-        } else {
-            log::error!("Circuit file missing: {}", file_path.display());
-            return Err(anyhow!("Missing circuit file: {}", file));
-        }
-    }
-
-    let script_path = current_dir.join("scripts/setup_and_prove.sh");
-    if script_path.exists() {
-        log::info!("Script exists: {}", script_path.display());
-        let metadata = fs::metadata(&script_path)?;
-        let permissions = metadata.permissions();
-        if permissions.mode() & 0o111 != 0 {
-            log::info!("Script is already executable");
-        } else {
-            log::info!("Script is not executable, fixing permissions...");
-            make_script_executable(script_path.to_str().unwrap())?;
-            log::info!("Script is now executable");
+        let tx_details = match res {
+            Ok(ProcessedTransaction::Executed(tx_details)) => tx_details,
+            Err(e) => return failed(format!("failed to load: {}", e)),
+            _ => return failed("had no effect".to_string()),
+        };
+        if let Err(e) = &tx_details.execution_details.status {
+            return failed(format!("failed to execute: {}", e));
         }
-    } else {
-        log::error!("Script missing: {}", script_path.display());
-        return Err(anyhow!("Missing script: {}", script_path.display()));
-    }
-    
-    log::info!("All circuit files verified and ready!");
-    Ok(())
-}
-
-fn generate_zk_proof(batch: &TransactionBatch) -> Result<ProofData> {
-    log::info!("Generating ZK proof for batch: {}", batch.batch_id);
-    if let Err(e) = verify_circuit_files() {
-        log::error!("Circuit file verification failed: {}", e);
-        return Err(e);
-    }
-    
-    let batch_input = create_batch_circuit_input(batch)?;
-
-    fs::create_dir_all("circuit/build")?;
-    
-    let input_file_path = format!("circuit/build/input_batch_{}.json", batch.batch_id);
-    
-    fs::write(&input_file_path, serde_json::to_string_pretty(&batch_input)?)?;
-    
-    log::info!("Created circuit input file: {} with {} transactions", input_file_path, batch_input.len());
-
-    log::info!("Executing: ./scripts/setup_and_prove.sh");
-    log::info!("   Working directory: {}", std::env::current_dir().unwrap().display());
-    log::info!("   BATCH_ID: {}", batch.batch_id);
-    log::info!("   INPUT_FILE: {}", input_file_path);
-
-    let output = Command::new("./scripts/setup_and_prove.sh")
-        .current_dir(".")
-        .env("BATCH_ID", &batch.batch_id)
-        .env("INPUT_FILE", &input_file_path)
-        .output();
-    
-    match output {
-        Ok(result) => {
-            log::info!("Script execution completed");
-            log::info!("Exit status: {}", result.status);
-            log::info!("Stdout: {}", String::from_utf8_lossy(&result.stdout));
-            log::info!("Stderr: {}", String::from_utf8_lossy(&result.stderr));
-            
-            if result.status.success() {
-                log::info!("ZK proof generation successful for batch: {}", batch.batch_id);
-                
-                let proof_file_path = format!("build/proof_batch_{}.json", batch.batch_id);
-                if fs::metadata(&proof_file_path).is_ok() {
-                    ProofData::from_json_file(&proof_file_path)
-                        .map_err(|e| anyhow!("Failed to load proof file: {}", e))
-                } else {
-                    ProofData::from_json_file("build/proof_batch.json")
-                        .map_err(|e| anyhow!("Failed to load proof file: {}", e))
-                }
-            } else {
-                log::error!("ZK proof generation failed for batch: {}", batch.batch_id);
-                Err(anyhow!("Proof generation failed with exit code: {}", result.status))
+        let new_data = tx_details.loaded_transaction.accounts.clone();
+        let fee = tx_details.loaded_transaction.fee_details.total_fee();
+        // the provers only accept a transfer its signed transaction authorizes; find out before anything commits
+        let authorized = |witness: TransferWitness| -> Result<TransferWitness> {
+            let transaction = bincode::serialize(original_tx)?;
+            zk::authorize_transfer(&witness, &transaction).map_err(|reason| anyhow!("transaction {}", reason))?;
+            Ok(witness)
+        };
+        match find_system_transfer(original_tx)
+            .and_then(|transfer| transfer_witness(original_tx, transfer, fee, &balances, &new_data))
+            .and_then(authorized)
+        {
+            Ok(witness) => {
+                log::info!("Transfer of {} lamports (fee {}): sender {} -> {}, receiver {} -> {}",
+                          witness.amount, witness.fee, witness.from_balance_before, witness.from_balance_after,
+                          witness.to_balance_before, witness.to_balance_after);
+                transfers.push(witness);
             }
+            Err(e) => return failed(format!("cannot be proved: {}", e)),
         }
-        Err(e) => {
-            log::error!("Failed to execute proof generation script: {}", e);
-            Err(anyhow!("Script execution failed: {}", e))
+        for (pubkey, account_data) in &new_data {
+            if let Some(balance) = balances.get_mut(pubkey) {
+                *balance = account_data.lamports();
+            }
         }
+        commits.push((original_tx, new_data));
     }
+    Ok(Execution { commits, transfers, pre_state, balances, paths })
 }
 
-fn create_batch_circuit_input(batch: &TransactionBatch) -> Result<BatchCircuitInput> {
-    log::info!("Creating circuit input for {} system transfers with account data", batch.transactions.len());
-    
-    let mut circuit_input = BatchCircuitInput::new();
-    
-    for (i, tx) in batch.transactions.iter().enumerate() {
-        let amount = extract_transfer_amount(tx)?;
-
-        let sig_first_byte = if !tx.signatures.is_empty() {
-            tx.signatures[0].as_ref()[0] as u32
-        } else {
-            return Err(anyhow!("Transaction {} has no signature", i));
-        };
-        
-        let (balance_before, balance_after) = get_account_balances(tx, batch)?;
-        
-        circuit_input.add_transaction(amount, sig_first_byte, balance_before, balance_after);
-        
-        log::info!("  Transfer {}: amount={} lamports, sig_byte={}, balance_before={}, balance_after={}", 
-                  i + 1, amount, sig_first_byte, balance_before, balance_after);
-    }
-
-    circuit_input.pad_to_size(3);
-    
-    log::info!("Circuit input created with {} transactions (padded if necessary)", circuit_input.len());
-    Ok(circuit_input)
-}
-
-fn extract_transfer_amount(tx: &Transaction) -> Result<u64> {
-    for instruction in &tx.message.instructions {
-        if instruction.program_id_index == 0 { 
-            if instruction.data.len() >= 12 && instruction.data[0..4] == [2, 0, 0, 0] {
-                let amount_bytes: [u8; 8] = instruction.data[4..12].try_into()
-                    .map_err(|_| anyhow!("Failed to parse transfer amount"))?;
-                return Ok(u64::from_le_bytes(amount_bytes));
-            }
-        }
+fn account_witness(pubkey: &Pubkey, account: &AccountSharedData) -> AccountWitness {
+    AccountWitness {
+        pubkey: pubkey.to_bytes(),
+        lamports: account.lamports(),
+        owner: account.owner().to_bytes(),
+        executable: account.executable(),
+        data: account.data().to_vec(),
     }
-    // if we can't parse, assume it's our known transfer amount
-    Ok(1000000) 
 }
 
-fn get_account_balances(tx: &Transaction, _batch: &TransactionBatch) -> Result<(u64, u64)> {
-    if tx.message.account_keys.is_empty() {
-        return Err(anyhow!("Transaction has no account keys"));
-    }
-    
-    let payer_pubkey = &tx.message.account_keys[0];
-    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
-    
-    match rpc_client.get_balance(payer_pubkey) {
-        Ok(current_balance) => {
-            // Scale down large balances to avoid circuit issues
-            // Convert to SOL units (divide by 10^9) then back to smaller lamport amounts
-            let balance_in_sol = current_balance / 1000000000; // Convert to SOL
-            let scaled_balance_before = balance_in_sol * 1000000; // Scale to microSOL (6 decimals)
-            let scaled_balance_after = scaled_balance_before - 5000; // Minus typical fee
-            
-            log::info!("Account balance for {}: original={} lamports, scaled_before={}, scaled_after={}", 
-                      payer_pubkey, current_balance, scaled_balance_before, scaled_balance_after);
-            
-            Ok((scaled_balance_before, scaled_balance_after))
-        }
-        Err(e) => {
-            log::warn!("Failed to fetch real balance for {}: {}", payer_pubkey, e);
-            // here we are just using reasonable scaled values
-            Ok((5000000, 4995000)) 
-        }
+/// The circuit's view of one executed transfer. `balances` holds each account's
+/// lamports before the transaction, `post_accounts` what the SVM left behind.
+fn transfer_witness(
+    tx: &Transaction,
+    transfer: SystemTransfer,
+    fee: u64,
+    balances: &HashMap<Pubkey, u64>,
+    post_accounts: &[(Pubkey, AccountSharedData)],
+) -> Result<TransferWitness> {
+    // the circuit charges the fee to the sender
+    if tx.message.account_keys.first() != Some(&transfer.from) {
+        return Err(anyhow!("fee payer is not the transfer source"));
     }
-}
+    let signature_first_byte = match tx.signatures.first() {
+        Some(signature) => signature.as_ref()[0],
+        None => return Err(anyhow!("transaction has no signature")),
+    };
+    let before = |key: &Pubkey| balances.get(key).copied().unwrap_or(0);
+    let after = |key: &Pubkey| {
+        post_accounts
+            .iter()
+            .find(|(pubkey, _)| pubkey == key)
+            .map(|(_, account)| account.lamports())
+            .unwrap_or(0)
+    };
 
-fn store_batch_proof(
-    batch: &TransactionBatch,
-    proof_data: ProofData,
-    rollupdb_sender: &CBSender<RollupDBMessage>,
-) -> Result<()> {
-    log::info!("Storing batch proof in RollupDB for batch: {}", batch.batch_id);
-    
-    let store_message = StoreBatchProofMessage {
-        batch_id: batch.batch_id.clone(),
-        proof_data,
-        public_inputs: vec!["1".to_string()], // batch_valid = 1
-        transaction_signatures: batch.signatures.clone(),
+    let from_balance_before = before(&transfer.from);
+    let (from_balance_after, to_balance_before) = if transfer.from == transfer.to {
+        // paying yourself: the circuit debits then credits the same account
+        let debited = from_balance_before.saturating_sub(transfer.lamports.saturating_add(fee));
+        (debited, debited)
+    } else {
+        (after(&transfer.from), before(&transfer.to))
     };
-    
-    rollupdb_sender.send(RollupDBMessage {
-        lock_accounts: None,
-        add_processed_transaction: None,
-        add_new_data: None,
-        frontend_get_tx: None,
-        add_settle_proof: None,
-        store_batch_proof: Some(store_message),
-        update_proof_status: None,
-        get_proof_by_batch_id: None,
-        get_unsettled_proofs: None,
-        retry_failed_proofs: None,
-        list_offset: None,
-        list_limit: None,
-        trigger_retry_cycle: None,
-    })?;
-    
-    log::info!("Batch proof stored successfully");
-    Ok(())
+    Ok(TransferWitness {
+        from: transfer.from.to_bytes(),
+        to: transfer.to.to_bytes(),
+        amount: transfer.lamports,
+        fee,
+        signature_first_byte,
+        from_balance_before,
+        from_balance_after,
+        to_balance_before,
+        to_balance_after: after(&transfer.to),
+    })
 }
 
 pub async fn run(
-    sequencer_receiver_channel: CBReceiver<Transaction>,
+    mempool: Arc<Mempool>,
     rollupdb_sender: CBSender<RollupDBMessage>,
     account_receiver: Receiver<Option<Vec<(Pubkey, AccountSharedData)>>>,
-    settler_sender: CBSender<SettlementJob>
+    proving_queue: ProvingQueue,
+    config: Arc<NodeConfig>,
+    health: Arc<NodeHealth>,
 ) -> Result<()> {
+    let _alive = health.start(Component::Sequencer);
     let mut tx_counter = 0u32;
-    let batch_size = 3;
-    let mut transaction_batch: Vec<Transaction> = Vec::with_capacity(batch_size);
-    let rpc_client_temp = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let batch_size = config.sequencer.batch_size;
+    let rpc_client_temp = RpcClient::new(config.l1.rpc_url.clone());
 
     log::info!("Sequencer running with ZK proof generation (batch size: {})", batch_size);
     let mut rollup_account_loader = RollupAccountLoader::new(&rpc_client_temp);
+    // lamports of every account batches have touched; each proof starts from the root the last one left
+    let mut state_tree = StateTree::new();
+
+    loop {
+        health.record_activity(Component::Sequencer);
+        // the condvar wait blocks, so keep it off the async workers and let shutdown cancel between polls
+        let pool = Arc::clone(&mempool);
+        let Some(pending) = tokio::task::spawn_blocking(move || {
+            pool.wait_for_batch(batch_size, MEMPOOL_POLL_INTERVAL)
+        })
+        .await?
+        else {
+            continue;
+        };
 
-    while let Ok(transaction) = sequencer_receiver_channel.recv() {
-        transaction_batch.push(transaction);
-        log::info!("Transaction added to batch. Current size: {}/{}", transaction_batch.len(), batch_size);
+        let (transaction_batch, transaction_spans): (Vec<Transaction>, Vec<Span>) = pending.into_iter().unzip();
+        let batch = TransactionBatch::new(transaction_batch);
+
+        // the batch span lives until settlement finishes, so one trace covers the whole lifecycle
+        let batch_span = tracing::info_span!("batch", batch_id = %batch.batch_id, size = batch.transactions.len());
+        for transaction_span in &transaction_spans {
+            batch_span.follows_from(transaction_span);
+        }
+        drop(transaction_spans);
 
-        if transaction_batch.len() >= batch_size {
-            log::info!("Batch is full. Beginning processing...");
+        async {
+            log::info!("Batch is full. Beginning processing ({} still pending)...", mempool.len());
+            METRICS.batch_size.observe(batch.transactions.len() as f64);
 
-            let accounts_to_lock: Vec<Pubkey> = transaction_batch
+            let accounts_to_lock: Vec<Pubkey> = batch.transactions
                 .iter()
                 .flat_map(|tx| tx.message.account_keys.clone())
                 .collect::<HashSet<_>>()
@@ -444,70 +391,55 @@ pub async fn run(
             log::info!("Requesting state for {} unique accounts from DB.", accounts_to_lock.len());
             rollupdb_sender.send(RollupDBMessage {
                 lock_accounts: Some(accounts_to_lock),
-                add_processed_transaction: None,
-                add_new_data: None,
-                frontend_get_tx: None,
-                add_settle_proof: None,
-                store_batch_proof: None,
-                update_proof_status: None,
-                get_proof_by_batch_id: None,
-                get_unsettled_proofs: None,
-                retry_failed_proofs: None,
-                list_offset: None,
-                list_limit: None,
-                trigger_retry_cycle: None,
+                span: Some(Span::current()),
+                ..Default::default()
             })?;
 
             if let Some(Some(accounts_data)) = account_receiver.recv().await.ok() {
-                if process_transaction_batch(
-                    &transaction_batch,
+                let executed = process_transaction_batch(
+                    &batch.transactions,
                     &mut rollup_account_loader,
+                    &mut state_tree,
                     &rollupdb_sender,
-                )? {
-                    let batch = TransactionBatch::new(transaction_batch.clone());
-                    log::info!("📋 Created batch: {} with {} transactions", batch.batch_id, batch.transactions.len());
-
-                    match generate_zk_proof(&batch) {
-                        Ok(proof_data) => {
-                            log::info!("ZK proof generated successfully for batch: {}", batch.batch_id);
-
-                            if let Err(e) = store_batch_proof(&batch, proof_data.clone(), &rollupdb_sender) {
-                                log::error!("Failed to store proof in DB: {}", e);
-                            }
-
-                            let settlement_job = SettlementJob {
-                                batch_id: batch.batch_id.clone(),
-                                proof_data: Some(proof_data),
-                                transaction_signatures: batch.signatures.clone(),
-                                proof_file_path: Some(format!("build/proof_batch_{}.json", batch.batch_id)),
-                            };
-                            
-                            log::info!("Sending batch to settlement: {}", batch.batch_id);
-                            settler_sender.send(settlement_job)?;
-                            
-                            // increment counter if everything succeeded
-                            tx_counter += transaction_batch.len() as u32;
-                            log::info!("Batch processing complete. TX counter: {}", tx_counter);
-                        }
-                        Err(e) => {
-                            log::error!("ZK proof generation failed for batch {}: {}", batch.batch_id, e);
-                            // still increment counter but don't send to settlement
-                            tx_counter += transaction_batch.len() as u32;
-                        }
-                    }
+                )?;
+                if let Some(ExecutedBatch { transactions, transfers, accounts, state_paths }) = executed {
+                    log::info!("📋 Created batch: {} with {} transactions", batch.batch_id, transactions.len());
+
+                    // the witness comes straight from execution, proving happens in the pipeline;
+                    // the smallest circuit that fits pads the rest with no-op transfers
+                    let slots = config.prover.size_class_for(transfers.len())
+                        .ok_or_else(|| anyhow!("No circuit size class fits {} transfers", transfers.len()))?;
+                    let transaction_signatures = transactions.iter().map(|tx| tx.signatures[0].to_string()).collect();
+                    let transactions = transactions.iter().map(bincode::serialize).collect::<Result<Vec<_>, _>>()?;
+                    let transaction_count = transactions.len();
+                    let witness =
+                        BatchWitness { batch_id: batch.batch_id.clone(), slots, transfers, transactions, accounts, state_paths };
+                    log::info!("Queueing batch {} for the {}-slot circuit ({} already queued)", batch.batch_id, slots, proving_queue.len());
+                    proving_queue
+                        .submit(ProvingJob {
+                            witness,
+                            transaction_signatures,
+                            span: Span::current(),
+                        })
+                        .await?;
+                    tx_counter += transaction_count as u32;
+                    log::info!("Batch processing complete. TX counter: {}", tx_counter);
                 } else {
-                    log::error!("Batch processing failed. Skipping proof generation.");
+                    log::error!("Every transaction of the batch was dropped. Skipping proof generation.");
+                    METRICS.batches_failed.inc();
                 }
             } else {
                 log::error!("Failed to receive account data from DB. Skipping batch.");
+                METRICS.batches_failed.inc();
             }
             
-            transaction_batch.clear();
             log::info!("Batch processing finished. Ready for new transactions.");
+            Ok::<(), anyhow::Error>(())
         }
+        .instrument(batch_span)
+        .await?;
         
         // Note: Settlement trigger is now handled per-batch rather than by counter
         // each successful batch triggers its own settlement
     }
-    Ok(())
 }
\ No newline at end of file
@@ -3,20 +3,26 @@ use anchor_lang::{InstructionData, ToAccountMetas};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     hash::Hash,
-    commitment_config::CommitmentConfig,
     instruction::{AccountMeta,Instruction},
     pubkey::Pubkey,
-    signature::Signer,
+    signature::{Keypair, Signature, Signer},
     signer,
     transaction::Transaction,
 };
 use solana_system_interface::instruction as system_instruction;
-use dotenvy::dotenv;
 use crossbeam::channel::{Receiver as CBReceiver, Sender as CBSender};
-use std::{fs, str::FromStr, time::Duration};
+use std::{collections::{HashMap, HashSet}, fs, sync::Arc, time::Duration};
 use tokio::time::sleep;
+use tracing::{Instrument, Span};
 use serde::Deserialize;
-use crate::rollupdb::{RollupDBMessage, UpdateProofStatusMessage, ProofStatus, ProofData};
+use crate::{
+    config::{NodeConfig, ProverBackendKind, ProverConfig},
+    health::{Component, NodeHealth},
+    metrics::METRICS,
+    prover::StateTree,
+    rollupdb::{RollupDBMessage, UpdateProofStatusMessage, ProofArtifact, ProofStatus, ProofData},
+    vk_registry::registry_address,
+};
 
 use onchain_verifier::{
     accounts::VerifyGroth16 as VerifyAccounts, instruction::VerifyGroth16Proof as VerifyInstruction,
@@ -27,15 +33,28 @@ use num_bigint::BigUint;
 #[derive(Debug, Clone)]
 pub struct SettlementJob {
     pub batch_id: String,
-    pub proof_data: Option<ProofData>,
+    /// Batches after `batch_id` whose proofs were folded into the same aggregate
+    /// proof; they settle together with it.
+    pub aggregated: Vec<String>,
+    pub artifact: ProofArtifact,
     pub transaction_signatures: Vec<String>,
-    pub proof_file_path: Option<String>,
+    /// Span of the batch (or retry) this job settles; the settlement span is its child.
+    pub span: Span,
+}
+
+impl SettlementJob {
+    /// Every batch this job settles, oldest first.
+    pub fn batch_ids(&self) -> Vec<String> {
+        std::iter::once(self.batch_id.clone()).chain(self.aggregated.iter().cloned()).collect()
+    }
 }
 
 #[derive(Debug)]
 pub enum SettlementResult {
     Success(String), 
     Failed(String),  
+    /// The proof failed off-chain verification and was never posted.
+    Invalid(String),
     Retry,       
 }
 //temprary struct to deserialize the vk.json file
@@ -56,24 +75,45 @@ pub struct JsonVerifyingKey {
 pub async fn settle_batch_with_proof(
     settlement_job: SettlementJob,
     rollupdb_sender: &CBSender<RollupDBMessage>,
+    config: &NodeConfig,
 ) -> Result<SettlementResult> {
     log::info!("Starting settlement for batch: {}", settlement_job.batch_id);
+    if !settlement_job.aggregated.is_empty() {
+        log::info!("Aggregate proof also settles batches {:?}", settlement_job.aggregated);
+    }
     
+    // a proof the verifier program would reject only buys a failed transaction, its fees and a wasted retry
+    if let ProofArtifact::Groth16 { proof, public_inputs, slots } = &settlement_job.artifact {
+        if let Some(rejected) = verify_before_posting(&settlement_job, proof, public_inputs, *slots, rollupdb_sender, config)? {
+            return Ok(rejected);
+        }
+    }
+
     // here we update proof status to 'posted'
     update_proof_status(
-        &settlement_job.batch_id,
+        &settlement_job.batch_ids(),
         ProofStatus::Posted,
         None,
         rollupdb_sender,
     )?;
     
-    match settlement_job.proof_data.clone() {
-        Some(proof_data) => {
-            settle_with_proof(settlement_job, proof_data, rollupdb_sender).await
+    match settlement_job.artifact.clone() {
+        ProofArtifact::Groth16 { proof, public_inputs, slots } => {
+            settle_with_proof(settlement_job, *proof, public_inputs, slots, rollupdb_sender, config).await
+        }
+        ProofArtifact::RiscZero { seal, image_id, journal_digest } => {
+            settle_with_risc0_receipt(settlement_job, &seal, &image_id, &journal_digest, rollupdb_sender, config).await
         }
-        None => {
-            log::warn!("No proof data provided for batch: {}, using fallback settlement", settlement_job.batch_id);
-            settle_with_fallback_proof(settlement_job, rollupdb_sender).await
+        ProofArtifact::RiscZeroSuccinct { .. } => {
+            // no retry can change that, so the batch is not left to the retry cycle
+            let error = "proof is a succinct receipt that was never aggregated and cannot be verified on-chain";
+            log::error!("Settlement failed for batch {}: {}", settlement_job.batch_id, error);
+            update_proof_status(&settlement_job.batch_ids(), ProofStatus::Invalid, Some(error.to_string()), rollupdb_sender)?;
+            Ok(SettlementResult::Invalid(error.to_string()))
+        }
+        ProofArtifact::Mock => {
+            log::warn!("Batch {} has a mock proof, using fallback settlement", settlement_job.batch_id);
+            settle_with_fallback_proof(settlement_job, rollupdb_sender, config).await
         }
     }
 }
@@ -81,55 +121,137 @@ pub async fn settle_batch_with_proof(
 async fn settle_with_proof(
     settlement_job: SettlementJob,
     proof_data: ProofData,
+    public_inputs: Vec<String>,
+    slots: usize,
     rollupdb_sender: &CBSender<RollupDBMessage>,
+    config: &NodeConfig,
 ) -> Result<SettlementResult> {
     log::info!("Attempting proof settlement for batch: {}", settlement_job.batch_id);
     
-    dotenv().ok();
-    let rpc_client = RpcClient::new_with_commitment(
-        "https://api.devnet.solana.com".into(),
-        CommitmentConfig::confirmed(),
-    );
-    
+    let rpc_client = RpcClient::new_with_commitment(config.l1.rpc_url.clone(), config.commitment());
     
-    let path = std::env::var("KEYPAIR2")?;
-    let payer = signer::keypair::read_keypair_file(path)
+    let payer = signer::keypair::read_keypair_file(&config.settlement.payer_keypair_path)
         .map_err(|e| anyhow::anyhow!("Failed to read keypair file: {}", e))?;
 
-    let vk_file  = fs::File::open("build/keys/verification_key_batch.json")?;
-    let json_vk:JsonVerifyingKey = serde_json::from_reader(std::io::BufReader::new(vk_file))?;
+    // the verification key of the size class that proved the batch
+    let verifying_key = published_verifying_key(&config.prover, slots)?;
 
-    let verifying_key = convert_vk_to_onchain_format(&json_vk)?;
-    let proof = convert_proof_to_onchain_format(&proof_data)?;
-    
-    let public_input_file = fs::File::open("build/public_batch.json")?;
-    let public_input_str : Vec<String> = serde_json::from_reader(std::io::BufReader::new(public_input_file))?;
-    let public_inputs = convert_public_inputs_to_onchain_format(&public_input_str)?;
+    let ix = groth16_verify_instruction(
+        &config.verifier_program_id()?,
+        &payer.pubkey(),
+        &settlement_job.batch_id,
+        slots,
+        &proof_data,
+        &public_inputs,
+        &verifying_key,
+    )?;
+
+    send_verification(&settlement_job.batch_ids(), ix, &payer, &rpc_client, rollupdb_sender).await
+}
 
-    let ix = create_onchain_verifier_instruction(&payer.pubkey(), &settlement_job.batch_id, proof, public_inputs, verifying_key)?;
+/// Verifies a Groth16 proof off-chain against the key it would be posted with.
+/// Marks the batches `Invalid` if it does not verify, or `Failed` if the key cannot
+/// be read, and returns what settlement came to; `None` if the proof may be posted.
+fn verify_before_posting(
+    settlement_job: &SettlementJob,
+    proof: &ProofData,
+    public_inputs: &[String],
+    slots: usize,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    config: &NodeConfig,
+) -> Result<Option<SettlementResult>> {
+    let verifying_key = match published_verifying_key(&config.prover, slots) {
+        Ok(vk) => vk,
+        Err(e) => {
+            // nothing wrong with the proof as far as we know, so a retry may still settle it
+            update_proof_status(&settlement_job.batch_ids(), ProofStatus::Failed, Some(e.to_string()), rollupdb_sender)?;
+            return Ok(Some(SettlementResult::Failed(e.to_string())));
+        }
+    };
+    match verify_groth16_offchain(&verifying_key, proof, public_inputs) {
+        Ok(()) => {
+            log::info!("Proof for batch {} verified off-chain", settlement_job.batch_id);
+            Ok(None)
+        }
+        Err(reason) => {
+            let error = format!("proof failed off-chain verification: {}", reason);
+            update_proof_status(&settlement_job.batch_ids(), ProofStatus::Invalid, Some(error.clone()), rollupdb_sender)?;
+            Ok(Some(SettlementResult::Invalid(error)))
+        }
+    }
+}
+
+#[cfg(feature = "risc0")]
+async fn settle_with_risc0_receipt(
+    settlement_job: SettlementJob,
+    seal: &str,
+    image_id: &str,
+    journal_digest: &str,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    config: &NodeConfig,
+) -> Result<SettlementResult> {
+    log::info!("Attempting RISC Zero receipt settlement for batch: {}", settlement_job.batch_id);
 
+    let rpc_client = RpcClient::new_with_commitment(config.l1.rpc_url.clone(), config.commitment());
+
+    let payer = signer::keypair::read_keypair_file(&config.settlement.payer_keypair_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read keypair file: {}", e))?;
+
+    let ix = create_risc0_verifier_instruction(&config.risc0_verifier_program_id()?, seal, image_id, journal_digest)?;
+
+    send_verification(&settlement_job.batch_ids(), ix, &payer, &rpc_client, rollupdb_sender).await
+}
+
+#[cfg(not(feature = "risc0"))]
+async fn settle_with_risc0_receipt(
+    settlement_job: SettlementJob,
+    _seal: &str,
+    _image_id: &str,
+    _journal_digest: &str,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+    _config: &NodeConfig,
+) -> Result<SettlementResult> {
+    let error = "node was built without the risc0 feature and cannot settle RISC Zero receipts";
+    log::error!("Settlement failed for batch {}: {}", settlement_job.batch_id, error);
+    update_proof_status(&settlement_job.batch_ids(), ProofStatus::Failed, Some(error.to_string()), rollupdb_sender)?;
+    Ok(SettlementResult::Failed(error.to_string()))
+}
+
+async fn send_verification(
+    batch_ids: &[String],
+    ix: Instruction,
+    payer: &Keypair,
+    rpc_client: &RpcClient,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+) -> Result<SettlementResult> {
     let recent_blockhash = rpc_client.get_latest_blockhash().await?;
     let transaction = Transaction::new_signed_with_payer(
         &[ix],
         Some(&payer.pubkey()),
-        &[&payer],
+        &[payer],
         recent_blockhash,
     );
 
      match rpc_client.send_and_confirm_transaction(&transaction).await {
         Ok(signature) => {
             log::info!("Settlement transaction confirmed: {}", signature);
-            update_proof_status(&settlement_job.batch_id, ProofStatus::Verified, None, rollupdb_sender)?;
+            mark_verified(batch_ids, &signature, rollupdb_sender)?;
+            // the base fee of the one transaction, shared by every batch it settles
+            match rpc_client.get_fee_for_message(&transaction.message).await {
+                Ok(fee) => METRICS.settlement_fee_per_batch_lamports.observe(fee as f64 / batch_ids.len() as f64),
+                Err(e) => log::warn!("Could not look up the settlement fee: {}", e),
+            }
+            METRICS.batches_per_settlement.observe(batch_ids.len() as f64);
             Ok(SettlementResult::Success(signature.to_string()))
         }
         Err(e) => {
             log::error!(
-                "Settlement transaction failed for batch {}: {}",
-                settlement_job.batch_id,
+                "Settlement transaction failed for batches {:?}: {}",
+                batch_ids,
                 e
             );
             update_proof_status(
-                &settlement_job.batch_id,
+                batch_ids,
                 ProofStatus::Failed,
                 Some(e.to_string()),
                 rollupdb_sender,
@@ -158,16 +280,17 @@ async fn settle_with_proof(
 
 
 fn create_onchain_verifier_instruction(
+    program_id: &Pubkey,
     payer: &Pubkey,
     batch_id: &str,
+    slots: usize,
     proof: Groth16Proof,
     public_inputs: PublicInputs,
     verifying_key: Groth16VerifyingKey,
 ) -> Result<Instruction> {
-    let program_id = Pubkey::from_str("Aa3rXCBoxPVZ537nqccEiVsLBoZ2G7gdfNjypM9wP8Yi")?;
     let (proof_account_pda, _) = Pubkey::find_program_address(
         &[b"groth16_proof", payer.as_ref(), batch_id.as_bytes()],
-        &program_id,
+        program_id,
     );
 
     let instruction_args = VerifyInstruction {
@@ -180,31 +303,68 @@ fn create_onchain_verifier_instruction(
     let accounts = VerifyAccounts {
         authority: *payer,
         proof_account: proof_account_pda,
+        // the program only accepts the key registered for the batch's size class
+        registry: registry_address(program_id, payer, slots),
         system_program: solana_sdk::system_program::id(),
     };
 
     Ok(Instruction {
-        program_id,
+        program_id: *program_id,
+        accounts: accounts.to_account_metas(None),
+        data: instruction_args.data(),
+    })
+}
+
+#[cfg(feature = "risc0")]
+fn create_risc0_verifier_instruction(
+    program_id: &Pubkey,
+    seal: &str,
+    image_id: &str,
+    journal_digest: &str,
+) -> Result<Instruction> {
+    use base64::Engine;
+
+    let seal = base64::engine::general_purpose::STANDARD.decode(seal)?;
+    if seal.len() != 256 {
+        return Err(anyhow!("RISC Zero seal is {} bytes, expected 256", seal.len()));
+    }
+    let pi_a: [u8; 64] = seal[..64].try_into()?;
+    let proof = proof_risc0_verifier::Proof {
+        // the verifier expects pi_a negated
+        pi_a: proof_risc0_verifier::negate_g1(&pi_a),
+        pi_b: seal[64..192].try_into()?,
+        pi_c: seal[192..].try_into()?,
+    };
+
+    let instruction_args = proof_risc0_verifier::instruction::Verify {
+        proof,
+        image_id: hex_to_32_bytes(image_id)?,
+        journal_digest: hex_to_32_bytes(journal_digest)?,
+    };
+
+    let accounts = proof_risc0_verifier::accounts::VerifyProof {
+        system_program: solana_sdk::system_program::id(),
+    };
+
+    Ok(Instruction {
+        program_id: *program_id,
         accounts: accounts.to_account_metas(None),
         data: instruction_args.data(),
     })
 }
 
-// Settlement for when no proof data is available
+// Settlement for when no proof data is available (dev mode only). Nothing is verified on L1,
+// so the batch is recorded as unproven, never as verified.
 async fn settle_with_fallback_proof(
     settlement_job: SettlementJob,
     rollupdb_sender: &CBSender<RollupDBMessage>,
+    config: &NodeConfig,
 ) -> Result<SettlementResult> {
     log::warn!("Using settlement for batch: {}", settlement_job.batch_id);
     
-    dotenv().ok();
-    let rpc_client = RpcClient::new_with_commitment(
-        "https://api.devnet.solana.com".into(),
-        CommitmentConfig::confirmed(),
-    );
+    let rpc_client = RpcClient::new_with_commitment(config.l1.rpc_url.clone(), config.commitment());
     
-    let path = std::env::var("KEYPAIR2")?;
-    let payer = signer::keypair::read_keypair_file(path)
+    let payer = signer::keypair::read_keypair_file(&config.settlement.payer_keypair_path)
         .map_err(|e| anyhow::anyhow!("Failed to read keypair file: {}", e))?;
 
     let settle_instruction = system_instruction::transfer(
@@ -225,11 +385,11 @@ async fn settle_with_fallback_proof(
         Ok(signature) => {
             log::info!(" Settlement completed: {}", signature);
             
-            // here we update proof status to 'verified'
-            update_proof_status(
-                &settlement_job.batch_id,
-                ProofStatus::Verified,
-                Some("Fallback settlement".to_string()),
+            send_proof_update(
+                &settlement_job.batch_ids(),
+                ProofStatus::Unproven,
+                Some("Fallback settlement without a proof".to_string()),
+                Some(signature.to_string()),
                 rollupdb_sender,
             )?;
             
@@ -239,7 +399,7 @@ async fn settle_with_fallback_proof(
             log::error!(" Settlement failed: {}", e);
             
             update_proof_status(
-                &settlement_job.batch_id,
+                &settlement_job.batch_ids(),
                 ProofStatus::Failed,
                 Some(format!("Settlement failed: {}", e)),
                 rollupdb_sender,
@@ -251,48 +411,176 @@ async fn settle_with_fallback_proof(
 }
 
 fn update_proof_status(
-    batch_id: &str,
+    batch_ids: &[String],
     status: ProofStatus,
     error_message: Option<String>,
     rollupdb_sender: &CBSender<RollupDBMessage>,
 ) -> Result<()> {
-    let update_message = UpdateProofStatusMessage {
-        batch_id: batch_id.to_string(),
-        new_status: status,
-        error_message,
-    };
-    rollupdb_sender.send(RollupDBMessage {
-        update_proof_status: Some(update_message),
-        ..Default::default()
-    })?;
+    send_proof_update(batch_ids, status, error_message, None, rollupdb_sender)
+}
+
+fn mark_verified(
+    batch_ids: &[String],
+    settlement_signature: &Signature,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+) -> Result<()> {
+    send_proof_update(batch_ids, ProofStatus::Verified, None, Some(settlement_signature.to_string()), rollupdb_sender)
+}
+
+fn send_proof_update(
+    batch_ids: &[String],
+    status: ProofStatus,
+    error_message: Option<String>,
+    settlement_signature: Option<String>,
+    rollupdb_sender: &CBSender<RollupDBMessage>,
+) -> Result<()> {
+    for batch_id in batch_ids {
+        let update_message = UpdateProofStatusMessage {
+            batch_id: batch_id.clone(),
+            new_status: status.clone(),
+            error_message: error_message.clone(),
+            settlement_signature: settlement_signature.clone(),
+        };
+        rollupdb_sender.send(RollupDBMessage {
+            update_proof_status: Some(update_message),
+            span: Some(Span::current()),
+            ..Default::default()
+        })?;
+    }
     
     Ok(())
 }
 
+/// Roots of the global state tree before and after the batches a proof settles, big-endian;
+/// `None` for proofs that do not commit to the state tree (circom, mock).
+fn state_roots(artifact: &ProofArtifact, config: &NodeConfig) -> Result<Option<([u8; 32], [u8; 32])>> {
+    match artifact {
+        ProofArtifact::Groth16 { public_inputs, .. } if config.prover.backend != ProverBackendKind::Circom => {
+            let [pre, post] = public_inputs.as_slice() else {
+                return Err(anyhow!("proof has {} public inputs, expected the two state roots", public_inputs.len()));
+            };
+            Ok(Some((biguint_to_32_bytes(biguint_from_str(pre)?)?, biguint_to_32_bytes(biguint_from_str(post)?)?)))
+        }
+        ProofArtifact::RiscZero { pre_state_root, post_state_root, .. } => {
+            Ok(Some((hex_to_32_bytes(pre_state_root)?, hex_to_32_bytes(post_state_root)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The state tree roots settled proofs went through. A proof only settles from the root the
+/// last one left, so what L1 verified is one unbroken run of state transitions; one that
+/// arrives before the proof it builds on waits for it.
+struct StateChain {
+    last_root: [u8; 32],
+    /// Every root a settled proof started from; nothing can settle from them again.
+    settled_from: HashSet<[u8; 32]>,
+    /// Proved jobs by the root they start from.
+    waiting: HashMap<[u8; 32], SettlementJob>,
+}
+
+impl StateChain {
+    fn new() -> Self {
+        // the sequencer's tree starts out empty with the node
+        Self { last_root: StateTree::new().root_bytes(), settled_from: HashSet::new(), waiting: HashMap::new() }
+    }
+
+    /// The job if it may settle now. Otherwise it waits for its predecessor, or is marked
+    /// invalid if it starts from a root the chain has already moved on from.
+    fn admit(
+        &mut self,
+        job: SettlementJob,
+        roots: Option<([u8; 32], [u8; 32])>,
+        rollupdb_sender: &CBSender<RollupDBMessage>,
+    ) -> Result<Option<SettlementJob>> {
+        let Some((pre, _)) = roots else { return Ok(Some(job)) };
+        if pre == self.last_root {
+            return Ok(Some(job));
+        }
+        if self.settled_from.contains(&pre) {
+            let error = format!("proof starts from state root {} that a settled proof already moved on from", hex(&pre));
+            log::error!("Batch {} was not posted: {}", job.batch_id, error);
+            METRICS.settlements.with_label_values(&["invalid"]).inc();
+            update_proof_status(&job.batch_ids(), ProofStatus::Invalid, Some(error), rollupdb_sender)?;
+            return Ok(None);
+        }
+        log::info!("Batch {} waits for the proof that leaves state root {}", job.batch_id, hex(&pre));
+        let note = format!("Waiting for the batch before it to settle (state root {})", hex(&pre));
+        send_proof_update(&job.batch_ids(), ProofStatus::Generated, Some(note), None, rollupdb_sender)?;
+        self.waiting.insert(pre, job);
+        Ok(None)
+    }
+
+    /// Moves the chain past a settled proof and returns the job waiting for it, if any.
+    fn settled(&mut self, roots: Option<([u8; 32], [u8; 32])>) -> Option<SettlementJob> {
+        let (pre, post) = roots?;
+        self.settled_from.insert(pre);
+        self.last_root = post;
+        self.waiting.remove(&post)
+    }
+}
+
 // Settlement worker that processes settlement jobs
 pub async fn run_settlement_worker(
     settlement_receiver: CBReceiver<SettlementJob>,
     rollupdb_sender: CBSender<RollupDBMessage>,
+    config: Arc<NodeConfig>,
+    health: Arc<NodeHealth>,
 ) -> Result<()> {
+    let _alive = health.start(Component::SettlementWorker);
     log::info!("Settlement worker started");
+    let mut chain = StateChain::new();
     
-    while let Ok(settlement_job) = settlement_receiver.recv() {
-        log::info!("Received settlement job for batch: {}", settlement_job.batch_id);
-        
-        match settle_batch_with_proof(settlement_job.clone(), &rollupdb_sender).await {
-            Ok(SettlementResult::Success(signature)) => {
-                log::info!(" Settlement successful for batch {}: {}", settlement_job.batch_id, signature);
-            }
-            Ok(SettlementResult::Failed(error)) => {
-                log::error!(" Settlement failed for batch {}: {}", settlement_job.batch_id, error);
-            }
-            Ok(SettlementResult::Retry) => {
-                log::warn!("Settlement needs retry for batch: {}", settlement_job.batch_id);
-                // TODO: Retry logic
-            }
-            Err(e) => {
-                log::error!("Settlement error for batch {}: {}", settlement_job.batch_id, e);
-            }
+    while let Ok(received) = settlement_receiver.recv() {
+        let mut next = Some(received);
+        while let Some(settlement_job) = next.take() {
+            health.record_activity(Component::SettlementWorker);
+            log::info!("Received settlement job for batch: {}", settlement_job.batch_id);
+
+            let roots = match state_roots(&settlement_job.artifact, &config) {
+                Ok(roots) => roots,
+                Err(e) => {
+                    let error = format!("proof has unreadable state roots: {}", e);
+                    log::error!("Batch {} was not posted: {}", settlement_job.batch_id, error);
+                    METRICS.settlements.with_label_values(&["invalid"]).inc();
+                    update_proof_status(&settlement_job.batch_ids(), ProofStatus::Invalid, Some(error), &rollupdb_sender)?;
+                    continue;
+                }
+            };
+            let Some(settlement_job) = chain.admit(settlement_job, roots, &rollupdb_sender)? else { continue };
+
+            let settlement_span = tracing::info_span!(parent: &settlement_job.span, "settlement", batch_id = %settlement_job.batch_id);
+            let settlement_timer = METRICS.settlement_seconds.start_timer();
+            let result = settle_batch_with_proof(settlement_job.clone(), &rollupdb_sender, &config)
+                .instrument(settlement_span.clone())
+                .await;
+            settlement_timer.observe_duration();
+
+            settlement_span.in_scope(|| match result {
+                Ok(SettlementResult::Success(signature)) => {
+                    log::info!(" Settlement successful for batch {}: {}", settlement_job.batch_id, signature);
+                    METRICS.settlements.with_label_values(&["success"]).inc();
+                    health.record_settlement();
+                    next = chain.settled(roots);
+                }
+                Ok(SettlementResult::Failed(error)) => {
+                    log::error!(" Settlement failed for batch {}: {}", settlement_job.batch_id, error);
+                    METRICS.settlements.with_label_values(&["failed"]).inc();
+                }
+                Ok(SettlementResult::Invalid(error)) => {
+                    log::error!("Batch {} was not posted: {}", settlement_job.batch_id, error);
+                    METRICS.settlements.with_label_values(&["invalid"]).inc();
+                }
+                Ok(SettlementResult::Retry) => {
+                    log::warn!("Settlement needs retry for batch: {}", settlement_job.batch_id);
+                    METRICS.settlements.with_label_values(&["retry"]).inc();
+                    // TODO: Retry logic
+                }
+                Err(e) => {
+                    log::error!("Settlement error for batch {}: {}", settlement_job.batch_id, e);
+                    METRICS.settlements.with_label_values(&["error"]).inc();
+                }
+            });
         }
     }
     
@@ -304,6 +592,7 @@ impl Default for RollupDBMessage {
         RollupDBMessage {
             lock_accounts: None,
             add_processed_transaction: None,
+            drop_transaction: None,
             frontend_get_tx: None,
             list_offset: None,
             list_limit: None,
@@ -314,7 +603,15 @@ impl Default for RollupDBMessage {
             get_proof_by_batch_id: None,
             get_unsettled_proofs: None,
             retry_failed_proofs: None,
-            trigger_retry_cycle:None
+            trigger_retry_cycle:None,
+            get_account_snapshot: None,
+            list_batches: None,
+            get_batch_by_signature: None,
+            requeue_batch: None,
+            abandon_batch: None,
+            reset_circuit_breaker: None,
+            span: None,
+            reply: None,
         }
     }
 }
@@ -340,6 +637,66 @@ fn convert_proof_to_onchain_format(proof_data: &ProofData) -> Result<Groth16Proo
     })
 }
 
+/// The snarkjs verifying key the prover backend published for `slots`-slot batches,
+/// which settlement posts with their proofs.
+pub fn published_verifying_key(config: &ProverConfig, slots: usize) -> Result<zk::SnarkJsVerifyingKey> {
+    let vk_path = config.verification_key_path(slots);
+    let contents = fs::read_to_string(&vk_path)
+        .map_err(|e| anyhow!("No verification key for {} slots at {}: {}", slots, vk_path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| anyhow!("Invalid verification key {}: {}", vk_path.display(), e))
+}
+
+/// Checks a Groth16 proof with arkworks against `vk`: the pairing check the
+/// verifier program runs, plus the same strict parsing of every point and input.
+/// `Err` says why the proof was rejected.
+pub fn verify_groth16_offchain(
+    vk: &zk::SnarkJsVerifyingKey,
+    proof: &ProofData,
+    public_inputs: &[String],
+) -> std::result::Result<(), String> {
+    let proof = zk::SnarkJsProof {
+        pi_a: proof.pi_a.clone(),
+        pi_b: proof.pi_b.clone(),
+        pi_c: proof.pi_c.clone(),
+        protocol: proof.protocol.clone(),
+        curve: proof.curve.clone(),
+    };
+    match zk::verify_snarkjs(vk, &proof, public_inputs) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("the pairing check failed".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The verifier program instruction that checks (and records, under `proof_id`) a
+/// Groth16 proof of a `slots` batch, with everything in the layout the prover backends produce.
+pub(crate) fn groth16_verify_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    proof_id: &str,
+    slots: usize,
+    proof: &ProofData,
+    public_inputs: &[String],
+    vk: &zk::SnarkJsVerifyingKey,
+) -> Result<Instruction> {
+    let json_vk: JsonVerifyingKey = serde_json::from_value(serde_json::to_value(vk)?)?;
+    create_onchain_verifier_instruction(
+        program_id,
+        payer,
+        proof_id,
+        slots,
+        convert_proof_to_onchain_format(proof)?,
+        convert_public_inputs_to_onchain_format(public_inputs)?,
+        convert_vk_to_onchain_format(&json_vk)?,
+    )
+}
+
+/// The hash the verifier program records for (and registers) a verifying key.
+pub(crate) fn onchain_vk_hash(vk: &zk::SnarkJsVerifyingKey) -> Result<[u8; 32]> {
+    let json_vk: JsonVerifyingKey = serde_json::from_value(serde_json::to_value(vk)?)?;
+    Ok(onchain_verifier::hash_verifying_key(&convert_vk_to_onchain_format(&json_vk)?))
+}
+
 fn convert_vk_to_onchain_format(json_vk: &JsonVerifyingKey) -> Result<Groth16VerifyingKey> {
     let ic_onchain: Result<Vec<[u8; 64]>> = json_vk.ic.iter().map(g1_from_str_array).collect();
     Ok(Groth16VerifyingKey {
@@ -385,4 +742,20 @@ fn biguint_to_32_bytes(val: BigUint) -> Result<[u8; 32]> {
     }
     bytes[(32 - val_bytes.len())..].copy_from_slice(&val_bytes);
     Ok(bytes)
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "risc0")]
+fn hex_to_32_bytes(hex: &str) -> Result<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(anyhow!("Expected 32 hex-encoded bytes, got {:?}", hex));
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)?;
+    }
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,149 @@
+//! Dry-run execution of a single transaction against a snapshot of L2 state.
+//!
+//! The snapshot is loaded into a throwaway `RollupAccountLoader`, so nothing
+//! the SVM produces here ever reaches RollupDB or the sequencer's cache.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use solana_client::rpc_client::RpcClient;
+use solana_compute_budget::compute_budget::SVMTransactionExecutionBudget;
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    fee::FeeStructure,
+    hash::Hash,
+    pubkey::Pubkey,
+    rent_collector::RentCollector,
+    transaction::{SanitizedTransaction, Transaction},
+};
+use solana_svm::{
+    transaction_processing_result::ProcessedTransaction,
+    transaction_processor::{
+        ExecutionRecordingConfig, TransactionProcessingConfig, TransactionProcessingEnvironment,
+    },
+};
+use solana_svm_feature_set::SVMFeatureSet;
+
+use crate::{
+    frontend::{AccountDiff, SimulationResult},
+    loader::RollupAccountLoader,
+    processor::{create_transaction_batch_processor, get_transaction_check_results, RollupForkGraph},
+};
+
+impl SimulationResult {
+    fn failed(signature: Option<String>, error: String) -> Self {
+        Self {
+            signature,
+            logs: Vec::new(),
+            units_consumed: 0,
+            account_diffs: Vec::new(),
+            error: Some(error),
+        }
+    }
+}
+
+pub fn simulate_transaction(
+    transaction: &Transaction,
+    snapshot: Vec<(Pubkey, AccountSharedData)>,
+    rpc_client: &RpcClient,
+) -> SimulationResult {
+    let signature = transaction.signatures.first().map(|sig| sig.to_string());
+
+    let sanitized_tx =
+        match SanitizedTransaction::try_from_legacy_transaction(transaction.clone(), &HashSet::new()) {
+            Ok(tx) => tx,
+            Err(e) => return SimulationResult::failed(signature, format!("Sanitization failed: {}", e)),
+        };
+
+    let pre_state: HashMap<Pubkey, AccountSharedData> = snapshot.into_iter().collect();
+    let mut rollup_account_loader = RollupAccountLoader::new(rpc_client);
+    for (pubkey, account) in &pre_state {
+        rollup_account_loader.add_account(*pubkey, account.clone());
+    }
+
+    let compute_budget = SVMTransactionExecutionBudget::default();
+    let feature_set = SVMFeatureSet::all_enabled();
+    let fee_structure = FeeStructure::default();
+    let rent_collector = RentCollector::default();
+    let fork_graph = Arc::new(RwLock::new(RollupForkGraph {}));
+
+    let processor = create_transaction_batch_processor(
+        &rollup_account_loader,
+        &feature_set,
+        &compute_budget,
+        Arc::clone(&fork_graph),
+    );
+
+    let processing_environment = TransactionProcessingEnvironment {
+        blockhash: Hash::default(),
+        blockhash_lamports_per_signature: fee_structure.lamports_per_signature,
+        epoch_total_stake: 0,
+        feature_set,
+        rent_collector: Some(&rent_collector),
+    };
+
+    // unlike the sequencer we want the program logs back
+    let processing_config = TransactionProcessingConfig {
+        recording_config: ExecutionRecordingConfig::new_single_setting(true),
+        ..Default::default()
+    };
+
+    log::info!("Simulating transaction {:?}", signature);
+    let results = processor.load_and_execute_sanitized_transactions(
+        &rollup_account_loader,
+        &[sanitized_tx],
+        get_transaction_check_results(1),
+        &processing_environment,
+        &processing_config,
+    );
+
+    match results.processing_results.into_iter().next() {
+        Some(Ok(ProcessedTransaction::Executed(executed))) => {
+            let details = &executed.execution_details;
+            SimulationResult {
+                signature,
+                logs: details.log_messages.clone().unwrap_or_default(),
+                units_consumed: details.executed_units,
+                account_diffs: diff_accounts(&pre_state, &executed.loaded_transaction.accounts),
+                error: details.status.clone().err().map(|e| e.to_string()),
+            }
+        }
+        Some(Ok(ProcessedTransaction::FeesOnly(fees_only))) => {
+            SimulationResult::failed(signature, fees_only.load_error.to_string())
+        }
+        Some(Err(e)) => SimulationResult::failed(signature, e.to_string()),
+        None => SimulationResult::failed(signature, "SVM returned no result".to_string()),
+    }
+}
+
+fn diff_accounts(
+    pre_state: &HashMap<Pubkey, AccountSharedData>,
+    post_state: &[(Pubkey, AccountSharedData)],
+) -> Vec<AccountDiff> {
+    post_state
+        .iter()
+        .filter_map(|(pubkey, after)| {
+            let before = pre_state.get(pubkey);
+            let lamports_before = before.map(|acc| acc.lamports()).unwrap_or(0);
+            let data_changed = before.map(|acc| acc.data() != after.data()).unwrap_or(!after.data().is_empty());
+            let owner_changed = before.map(|acc| acc.owner() != after.owner()).unwrap_or(true);
+
+            if lamports_before == after.lamports() && !data_changed && !owner_changed {
+                return None;
+            }
+
+            Some(AccountDiff {
+                pubkey: pubkey.to_string(),
+                lamports_before,
+                lamports_after: after.lamports(),
+                owner_before: before.map(|acc| acc.owner().to_string()),
+                owner_after: after.owner().to_string(),
+                data_len_before: before.map(|acc| acc.data().len()).unwrap_or(0),
+                data_len_after: after.data().len(),
+                data_changed,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,40 @@
+//! Process-wide `tracing` setup.
+//!
+//! Existing `log::` call sites are bridged into `tracing`, so they are printed
+//! with the span they ran in (batch id, signature) and, when an OTLP target is
+//! configured, exported as events of that span.
+
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::{config::TelemetryConfig, otlp::{self, OtlpExporterHandle}};
+
+/// Keep alive for the lifetime of the node; dropping it flushes pending spans.
+pub struct TelemetryGuard {
+    _otlp: Option<OtlpExporterHandle>,
+}
+
+pub fn init(config: &TelemetryConfig) -> TelemetryGuard {
+    // RUST_LOG still works as it did with env_logger
+    // arkworks opens an "r1cs" span per gadget call; keep those out of the logs and traces
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("debug,r1cs=warn"));
+
+    let (otlp_layer, otlp_handle) = match config.otlp_target() {
+        Some(target) => {
+            let (layer, handle) = otlp::layer(&config.service_name, target);
+            (Some(layer), Some(handle))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(otlp_layer)
+        .init();
+
+    if let Some(target) = config.otlp_target() {
+        log::info!("Exporting traces as OTLP/JSON to {:?}", target);
+    }
+
+    TelemetryGuard { _otlp: otlp_handle }
+}
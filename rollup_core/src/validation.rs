@@ -0,0 +1,309 @@
+//! Checks run on a transaction at submission time, before it reaches the
+//! sequencer. Rejections carry a stable code so callers can react to them.
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::CompiledInstruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    sanitize::Sanitize,
+    transaction::{SanitizedTransaction, Transaction},
+};
+use solana_sdk_ids::{compute_budget, system_program};
+
+pub use rollup_api::{RejectCode, TransactionEncoding, TransactionRejection};
+
+// longest strings a PACKET_DATA_SIZE transaction can encode to
+const MAX_BASE58_SIZE: usize = 1683;
+const MAX_BASE64_SIZE: usize = 1644;
+
+/// Decodes standard bincode wire bytes from base64 or base58.
+pub fn decode_transaction(encoded: &str, encoding: TransactionEncoding) -> Result<Transaction, TransactionRejection> {
+    let max_len = match encoding {
+        TransactionEncoding::Base64 => MAX_BASE64_SIZE,
+        TransactionEncoding::Base58 => MAX_BASE58_SIZE,
+    };
+    if encoded.len() > max_len {
+        return Err(TransactionRejection::new(
+            RejectCode::PacketTooLarge,
+            format!("Encoded transaction is {} characters, limit is {}", encoded.len(), max_len),
+        ));
+    }
+
+    let decoded = match encoding {
+        TransactionEncoding::Base64 => STANDARD.decode(encoded).map_err(|e| e.to_string()),
+        TransactionEncoding::Base58 => bs58::decode(encoded).into_vec().map_err(|e| e.to_string()),
+    };
+    let bytes = decoded.map_err(|e| {
+        TransactionRejection::new(RejectCode::MalformedTransaction, format!("Invalid {:?} encoding: {}", encoding, e))
+    })?;
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(TransactionRejection::new(
+            RejectCode::PacketTooLarge,
+            format!("Transaction is {} bytes, limit is {}", bytes.len(), PACKET_DATA_SIZE),
+        ));
+    }
+
+    bincode::deserialize::<Transaction>(&bytes).map_err(|e| {
+        TransactionRejection::new(RejectCode::MalformedTransaction, format!("Failed to deserialize transaction: {}", e))
+    })
+}
+
+/// Stateless checks: size limit, sanitization, signature verification and whether
+/// the batch circuit can prove the transaction at all.
+pub fn check_transaction(tx: &Transaction) -> Result<(), TransactionRejection> {
+    let size = bincode::serialized_size(tx)
+        .map_err(|e| TransactionRejection::new(RejectCode::SanitizeFailure, format!("Failed to serialize transaction: {}", e)))?;
+    if size as usize > PACKET_DATA_SIZE {
+        return Err(TransactionRejection::new(
+            RejectCode::PacketTooLarge,
+            format!("Transaction is {} bytes, limit is {}", size, PACKET_DATA_SIZE),
+        ));
+    }
+
+    tx.sanitize()
+        .map_err(|e| TransactionRejection::new(RejectCode::SanitizeFailure, e.to_string()))?;
+    SanitizedTransaction::try_from_legacy_transaction(tx.clone(), &HashSet::new())
+        .map_err(|e| TransactionRejection::new(RejectCode::SanitizeFailure, e.to_string()))?;
+
+    if !tx.is_signed() {
+        return Err(TransactionRejection::new(RejectCode::MissingSignature, "Transaction is not fully signed"));
+    }
+    tx.verify()
+        .map_err(|e| TransactionRejection::new(RejectCode::InvalidSignature, e.to_string()))?;
+
+    check_provable(tx)
+}
+
+/// The batch circuit proves one system transfer per transaction, with the fee charged to
+/// its sender. Anything else would execute but could never settle, so it is refused here.
+fn check_provable(tx: &Transaction) -> Result<(), TransactionRejection> {
+    let unsupported = |error: String| TransactionRejection::new(RejectCode::UnsupportedTransaction, error);
+    let keys = &tx.message.account_keys;
+    for instruction in &tx.message.instructions {
+        // sanitization guarantees the index is in range
+        let program_id = keys[instruction.program_id_index as usize];
+        let supported = (program_id == system_program::id() && is_transfer(instruction)) || program_id == compute_budget::id();
+        if !supported {
+            return Err(unsupported(format!("Only system transfers are supported, not instructions of {}", program_id)));
+        }
+    }
+
+    let transfer = find_system_transfer(tx).map_err(|e| unsupported(e.to_string()))?;
+    if keys.first() != Some(&transfer.from) {
+        return Err(unsupported(format!("Fee payer must be the transfer sender {}", transfer.from)));
+    }
+    Ok(())
+}
+
+/// A transaction's single system transfer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SystemTransfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+// bincode SystemInstruction::Transfer: a little-endian u32 tag of 2, then the lamports
+fn is_transfer(instruction: &CompiledInstruction) -> bool {
+    instruction.data.len() == 12 && instruction.data[0..4] == [2, 0, 0, 0]
+}
+
+pub(crate) fn find_system_transfer(tx: &Transaction) -> Result<SystemTransfer> {
+    let keys = &tx.message.account_keys;
+    let account = |instruction: &CompiledInstruction, position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| keys.get(*index as usize))
+            .copied()
+            .ok_or_else(|| anyhow!("system transfer is missing account {}", position))
+    };
+
+    let mut transfer = None;
+    for instruction in &tx.message.instructions {
+        let program_id = keys
+            .get(instruction.program_id_index as usize)
+            .ok_or_else(|| anyhow!("instruction program index out of range"))?;
+        if *program_id != system_program::id() || !is_transfer(instruction) {
+            continue;
+        }
+        let found = SystemTransfer {
+            from: account(instruction, 0)?,
+            to: account(instruction, 1)?,
+            lamports: u64::from_le_bytes(instruction.data[4..12].try_into()?),
+        };
+        if transfer.replace(found).is_some() {
+            return Err(anyhow!("more than one system transfer, the batch circuit proves one per transaction"));
+        }
+    }
+    transfer.ok_or_else(|| anyhow!("not a system transfer"))
+}
+
+/// The fee payer must exist in L2 (or L1) state and cover what the batch will debit
+/// it: the signature and prioritization fees plus the lamports it transfers. Expects
+/// a transaction `check_transaction` accepted.
+pub fn check_fee_payer(
+    tx: &Transaction,
+    fee_payer_account: Option<&AccountSharedData>,
+) -> Result<(), TransactionRejection> {
+    let fee_payer = tx.message.account_keys.first()
+        .ok_or_else(|| TransactionRejection::new(RejectCode::SanitizeFailure, "Transaction has no account keys"))?;
+
+    let account = fee_payer_account.ok_or_else(|| {
+        TransactionRejection::new(RejectCode::FeePayerNotFound, format!("Fee payer {} not found", fee_payer))
+    })?;
+
+    let bytes = bincode::serialize(tx)
+        .map_err(|e| TransactionRejection::new(RejectCode::SanitizeFailure, format!("Failed to serialize transaction: {}", e)))?;
+    let fee = zk::transaction_fee(&bytes)
+        .map_err(|e| TransactionRejection::new(RejectCode::SanitizeFailure, format!("Transaction {}", e)))?;
+    // the circuit only proves transfers sent by the fee payer, so it pays both
+    let transfer = find_system_transfer(tx)
+        .map_err(|e| TransactionRejection::new(RejectCode::UnsupportedTransaction, e.to_string()))?;
+    let required = fee.saturating_add(transfer.lamports);
+    if account.lamports() < required {
+        return Err(TransactionRejection::new(
+            RejectCode::InsufficientFundsForFee,
+            format!(
+                "Fee payer {} has {} lamports, cannot pay the fee of {} plus the {} lamports it transfers",
+                fee_payer,
+                account.lamports(),
+                fee,
+                transfer.lamports
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        signature::Keypair,
+        signer::Signer,
+    };
+    use solana_system_interface::instruction as system_instruction;
+
+    use super::*;
+
+    fn transfers(payer: &Keypair, count: usize) -> Transaction {
+        let instructions: Vec<_> = (0..count)
+            .map(|_| system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1))
+            .collect();
+        Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[payer], Hash::new_unique())
+    }
+
+    fn code<T>(result: Result<T, TransactionRejection>) -> Option<RejectCode> {
+        result.err().map(|rejection| rejection.code)
+    }
+
+    #[test]
+    fn decodes_both_encodings() {
+        let tx = transfers(&Keypair::new(), 1);
+        let bytes = bincode::serialize(&tx).unwrap();
+
+        assert_eq!(decode_transaction(&STANDARD.encode(&bytes), TransactionEncoding::Base64).unwrap(), tx);
+        assert_eq!(decode_transaction(&bs58::encode(&bytes).into_string(), TransactionEncoding::Base58).unwrap(), tx);
+    }
+
+    #[test]
+    fn decode_rejects_oversized_and_malformed_input() {
+        let too_long = "A".repeat(MAX_BASE64_SIZE + 1);
+        assert_eq!(code(decode_transaction(&too_long, TransactionEncoding::Base64)), Some(RejectCode::PacketTooLarge));
+        let too_long = "1".repeat(MAX_BASE58_SIZE + 1);
+        assert_eq!(code(decode_transaction(&too_long, TransactionEncoding::Base58)), Some(RejectCode::PacketTooLarge));
+
+        // within the character limit but more bytes than a packet holds
+        let oversized = STANDARD.encode(vec![0u8; PACKET_DATA_SIZE + 1]);
+        assert!(oversized.len() <= MAX_BASE64_SIZE);
+        assert_eq!(code(decode_transaction(&oversized, TransactionEncoding::Base64)), Some(RejectCode::PacketTooLarge));
+
+        assert_eq!(code(decode_transaction("not base64!", TransactionEncoding::Base64)), Some(RejectCode::MalformedTransaction));
+        assert_eq!(code(decode_transaction("0OIl", TransactionEncoding::Base58)), Some(RejectCode::MalformedTransaction));
+        let garbage = STANDARD.encode([1u8, 2, 3]);
+        assert_eq!(code(decode_transaction(&garbage, TransactionEncoding::Base64)), Some(RejectCode::MalformedTransaction));
+    }
+
+    #[test]
+    fn check_transaction_rejects_unsigned_and_forged_signatures() {
+        let payer = Keypair::new();
+        check_transaction(&transfers(&payer, 1)).unwrap();
+
+        let ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let unsigned = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+        assert_eq!(code(check_transaction(&unsigned)), Some(RejectCode::MissingSignature));
+
+        // the signature no longer covers the message
+        let mut forged = transfers(&payer, 1);
+        forged.message.recent_blockhash = Hash::new_unique();
+        assert_eq!(code(check_transaction(&forged)), Some(RejectCode::InvalidSignature));
+    }
+
+    #[test]
+    fn check_transaction_rejects_oversized_and_unsanitary_transactions() {
+        assert_eq!(code(check_transaction(&transfers(&Keypair::new(), 40))), Some(RejectCode::PacketTooLarge));
+
+        let mut no_program = transfers(&Keypair::new(), 1);
+        no_program.message.instructions[0].program_id_index = 99;
+        assert_eq!(code(check_transaction(&no_program)), Some(RejectCode::SanitizeFailure));
+    }
+
+    #[test]
+    fn check_transaction_rejects_what_the_circuit_cannot_prove() {
+        let payer = Keypair::new();
+        let signed = |instructions: &[Instruction], signers: &[&Keypair]| {
+            Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), signers, Hash::new_unique())
+        };
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let priced = signed(&[ComputeBudgetInstruction::set_compute_unit_price(10), transfer.clone()], &[&payer]);
+        check_transaction(&priced).unwrap();
+
+        let other_program = Instruction::new_with_bytes(Pubkey::new_unique(), b"hi", vec![AccountMeta::new(payer.pubkey(), true)]);
+        let other_system = system_instruction::assign(&payer.pubkey(), &Pubkey::new_unique());
+        let sender = Keypair::new();
+        let not_the_payer = system_instruction::transfer(&sender.pubkey(), &Pubkey::new_unique(), 1);
+        for unsupported in [
+            signed(&[transfer.clone(), other_program], &[&payer]),
+            signed(&[transfer.clone(), other_system], &[&payer]),
+            signed(&[transfer.clone(), transfer.clone()], &[&payer]),
+            signed(&[ComputeBudgetInstruction::set_compute_unit_price(10)], &[&payer]),
+            signed(&[not_the_payer], &[&payer, &sender]),
+        ] {
+            assert_eq!(code(check_transaction(&unsupported)), Some(RejectCode::UnsupportedTransaction));
+        }
+    }
+
+    #[test]
+    fn fee_payer_must_exist_and_cover_the_fee_and_the_transfer() {
+        let payer = Keypair::new();
+        let tx = transfers(&payer, 1);
+        let fee = zk::execute::LAMPORTS_PER_SIGNATURE;
+
+        assert_eq!(code(check_fee_payer(&tx, None)), Some(RejectCode::FeePayerNotFound));
+        // the transfer's lamport on top of the signature fee
+        let poor = AccountSharedData::new(fee, 0, &system_program::id());
+        assert_eq!(code(check_fee_payer(&tx, Some(&poor))), Some(RejectCode::InsufficientFundsForFee));
+        let funded = AccountSharedData::new(fee + 1, 0, &system_program::id());
+        check_fee_payer(&tx, Some(&funded)).unwrap();
+
+        // 1_000_000 micro-lamports per unit over the 3_000 units of one builtin
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_price(1_000_000),
+            system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+        ];
+        let priced =
+            Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&payer], Hash::new_unique());
+        assert_eq!(code(check_fee_payer(&priced, Some(&funded))), Some(RejectCode::InsufficientFundsForFee));
+        let funded = AccountSharedData::new(fee + 3_000 + 1, 0, &system_program::id());
+        check_fee_payer(&priced, Some(&funded)).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! Startup check of the Groth16 verifying keys against the verifier program.
+//!
+//! `verify_groth16_proof` only accepts a verifying key whose hash the settlement
+//! payer registered for the batch's size class, in a `RegisteredVerifyingKey`
+//! account. The operator registers every class once (`--register-verifying-keys`)
+//! and replaces a class's key only with an explicit rotation
+//! (`--rotate-verifying-keys`); every start compares the keys the prover loaded
+//! against the registered hashes, for every size class.
+//!
+//! In dev mode a class nobody registered, or a registry that cannot be reached,
+//! is only a warning; a registered hash that differs is always an error.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anyhow::{anyhow, Context, Result};
+use onchain_verifier::{
+    accounts::{RegisterVerifyingKey as RegisterAccounts, RotateVerifyingKey as RotateAccounts},
+    instruction::{RegisterVerifyingKey as RegisterInstruction, RotateVerifyingKey as RotateInstruction},
+    RegisteredVerifyingKey,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    signer,
+    transaction::Transaction,
+};
+use zk::keys::to_hex;
+
+use crate::{
+    config::{NodeConfig, ProverBackendKind, CIRCOM_CIRCUIT_SLOTS},
+    prover::{BackendVerifyingKey, ProverBackend},
+    settle::onchain_vk_hash,
+};
+
+/// Checks the verifying key hash of every size class against its registration,
+/// registering classes that have none with `prover.keys.register` and replacing
+/// differing hashes with `prover.keys.rotate`. Fails if a hash is missing or differs.
+pub fn check(config: &NodeConfig, prover: &dyn ProverBackend) -> Result<()> {
+    let dev_mode = config.api.dev_mode;
+    let keys = &config.prover.keys;
+    let classes = match config.prover.backend {
+        ProverBackendKind::Circom => vec![CIRCOM_CIRCUIT_SLOTS],
+        ProverBackendKind::Arkworks | ProverBackendKind::Remote => config.prover.size_classes.clone(),
+        // receipts are checked against the image id compiled into the verifier, and mock proofs not at all
+        ProverBackendKind::RiscZero | ProverBackendKind::Mock => return Ok(()),
+    };
+
+    let program_id = config.verifier_program_id()?;
+    let payer = signer::keypair::read_keypair_file(&config.settlement.payer_keypair_path)
+        .map_err(|e| anyhow!("Failed to read keypair file: {}", e))?;
+    let rpc_client = RpcClient::new_with_commitment(config.l1.rpc_url.clone(), config.commitment());
+
+    for slots in classes {
+        let BackendVerifyingKey::Groth16(vk) = prover.verifying_key(slots)? else {
+            continue;
+        };
+        let vk_hash = onchain_vk_hash(&vk)?;
+        let address = registry_address(&program_id, &payer.pubkey(), slots);
+
+        let registered = match rpc_client.get_account_with_commitment(&address, config.commitment()) {
+            Ok(response) => response.value.filter(|account| account.owner == program_id),
+            Err(e) if dev_mode => {
+                log::warn!("Could not look up the registered verifying key for {} slots: {}", slots, e);
+                continue;
+            }
+            Err(e) => return Err(anyhow!("Could not look up the registered verifying key for {} slots: {}", slots, e)),
+        };
+        let Some(account) = registered else {
+            if keys.register {
+                send(&rpc_client, &payer, register_instruction(&program_id, &payer.pubkey(), slots, vk_hash))
+                    .with_context(|| format!("Failed to register the verifying key for {} slots", slots))?;
+                log::info!("Registered verifying key {} for {} slots at {}", to_hex(&vk_hash), slots, address);
+                continue;
+            }
+            if dev_mode {
+                log::warn!("No verifying key registered for {} slots at {}", slots, address);
+                continue;
+            }
+            return Err(anyhow!(
+                "No verifying key registered for {} slots at {}; start once with --register-verifying-keys",
+                slots,
+                address
+            ));
+        };
+        let registered = RegisteredVerifyingKey::try_deserialize(&mut account.data.as_slice())
+            .with_context(|| format!("Invalid verifying key registration at {}", address))?;
+        if registered.vk_hash == vk_hash {
+            log::info!("Verifying key for {} slots matches its registration", slots);
+            continue;
+        }
+        if !keys.rotate {
+            return Err(anyhow!(
+                "The verifying key for {} slots hashes to {}, but {} is registered at {}; \
+                 start with --rotate-verifying-keys if the keys were rotated on purpose",
+                slots,
+                to_hex(&vk_hash),
+                to_hex(&registered.vk_hash),
+                address
+            ));
+        }
+        send(&rpc_client, &payer, rotate_instruction(&program_id, &payer.pubkey(), slots, vk_hash))
+            .with_context(|| format!("Failed to rotate the verifying key for {} slots", slots))?;
+        log::warn!(
+            "Rotated the verifying key for {} slots at {} from {} to {}",
+            slots,
+            address,
+            to_hex(&registered.vk_hash),
+            to_hex(&vk_hash)
+        );
+    }
+    Ok(())
+}
+
+/// The `RegisteredVerifyingKey` account of `authority` for `slots` batches, and its bump.
+pub(crate) fn registry_pda(program_id: &Pubkey, authority: &Pubkey, slots: usize) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"verifying_key", authority.as_ref(), &(slots as u32).to_le_bytes()], program_id)
+}
+
+pub(crate) fn registry_address(program_id: &Pubkey, authority: &Pubkey, slots: usize) -> Pubkey {
+    registry_pda(program_id, authority, slots).0
+}
+
+fn register_instruction(program_id: &Pubkey, authority: &Pubkey, slots: usize, vk_hash: [u8; 32]) -> Instruction {
+    let accounts = RegisterAccounts {
+        authority: *authority,
+        registry: registry_address(program_id, authority, slots),
+        system_program: solana_sdk::system_program::id(),
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts.to_account_metas(None),
+        data: RegisterInstruction { size_class: slots as u32, vk_hash }.data(),
+    }
+}
+
+fn rotate_instruction(program_id: &Pubkey, authority: &Pubkey, slots: usize, vk_hash: [u8; 32]) -> Instruction {
+    let accounts = RotateAccounts { authority: *authority, registry: registry_address(program_id, authority, slots) };
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts.to_account_metas(None),
+        data: RotateInstruction { size_class: slots as u32, vk_hash }.data(),
+    }
+}
+
+fn send(rpc_client: &RpcClient, payer: &Keypair, ix: Instruction) -> Result<()> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        rpc_client.get_latest_blockhash()?,
+    );
+    rpc_client.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
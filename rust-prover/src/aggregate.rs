@@ -0,0 +1,154 @@
+//! Folding of consecutive proved batches into one statement, for the RISC Zero
+//! aggregation guest.
+//!
+//! Every proof's state roots are roots of the global state tree, so consecutive
+//! proofs chain exactly when each starts from the root the one before it left;
+//! no account has to be looked at. What comes out has the same shape as its
+//! inputs: the root the first proof started from and the one the last left.
+//! Aggregates therefore fold into larger aggregates like batches do, which is
+//! what lets the tree be deeper.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    execute::ExecutionError,
+    journal::BatchJournal,
+    witness::BatchWitness,
+};
+
+/// What the aggregation guest commits for the proofs it folded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateJournal {
+    /// Image id of the batch guest that proved every batch below this aggregate.
+    pub batch_image_id: [u32; 8],
+    /// Image id of the aggregation guest, which proved every aggregate below this one.
+    /// Settlement checks it is the image the receipt itself was verified against.
+    pub aggregate_image_id: [u32; 8],
+    /// Every batch covered, in execution order.
+    pub batch_ids: Vec<String>,
+    /// Roots of the global state tree before the first batch and after the last.
+    pub pre_state_root: [u8; 32],
+    pub post_state_root: [u8; 32],
+    /// SHA-256 over the folded proofs' transactions digests, in order.
+    pub transactions_digest: [u8; 32],
+}
+
+/// The journal of a proof being folded: a batch's, or an inner aggregate's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvedJournal {
+    Batch(BatchJournal),
+    Aggregate(AggregateJournal),
+}
+
+impl ProvedJournal {
+    pub fn state_roots(&self) -> ([u8; 32], [u8; 32]) {
+        match self {
+            ProvedJournal::Batch(journal) => (journal.pre_state_root, journal.post_state_root),
+            ProvedJournal::Aggregate(journal) => (journal.pre_state_root, journal.post_state_root),
+        }
+    }
+
+    pub fn batch_ids(&self) -> &[String] {
+        match self {
+            ProvedJournal::Batch(journal) => std::slice::from_ref(&journal.batch_id),
+            ProvedJournal::Aggregate(journal) => &journal.batch_ids,
+        }
+    }
+
+    pub fn transactions_digest(&self) -> [u8; 32] {
+        match self {
+            ProvedJournal::Batch(journal) => journal.transactions_digest,
+            ProvedJournal::Aggregate(journal) => journal.transactions_digest,
+        }
+    }
+}
+
+/// A proof to fold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregationChild {
+    pub journal: ProvedJournal,
+}
+
+impl AggregationChild {
+    /// A batch as the batch guest proves it, executed natively.
+    pub fn batch(witness: &BatchWitness) -> Result<Self, ExecutionError> {
+        Ok(Self { journal: ProvedJournal::Batch(BatchJournal::execute(witness)?) })
+    }
+}
+
+/// What the aggregation guest reads: consecutive proofs, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregationInput {
+    pub batch_image_id: [u32; 8],
+    pub aggregate_image_id: [u32; 8],
+    pub children: Vec<AggregationChild>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregation {
+    pub journal: AggregateJournal,
+}
+
+impl Aggregation {
+    /// This aggregate as one proof of a larger one.
+    pub fn into_child(self) -> AggregationChild {
+        AggregationChild { journal: ProvedJournal::Aggregate(self.journal) }
+    }
+}
+
+/// Why proof `child` of an aggregation input cannot be folded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregationError {
+    pub child: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "proof {} of the aggregate {}", self.child, self.reason)
+    }
+}
+
+impl std::error::Error for AggregationError {}
+
+/// Folds `input.children` in order. Checks everything except the proofs
+/// themselves, which the guest verifies before calling this.
+pub fn aggregate(input: &AggregationInput) -> Result<Aggregation, AggregationError> {
+    let Some(first) = input.children.first() else {
+        return Err(AggregationError { child: 0, reason: "is missing, there is nothing to fold" });
+    };
+
+    let (pre_state_root, mut post_state_root) = first.journal.state_roots();
+    let mut batch_ids = Vec::new();
+    let mut digest = Sha256::new();
+    for (child, proved) in input.children.iter().enumerate() {
+        let reject = |reason| Err(AggregationError { child, reason });
+        if let ProvedJournal::Aggregate(inner) = &proved.journal {
+            if inner.batch_image_id != input.batch_image_id || inner.aggregate_image_id != input.aggregate_image_id {
+                return reject("was proved by other guests");
+            }
+        }
+
+        let (pre, post) = proved.journal.state_roots();
+        if child > 0 && pre != post_state_root {
+            return reject("starts from a state root the proof before it did not leave");
+        }
+        post_state_root = post;
+        batch_ids.extend_from_slice(proved.journal.batch_ids());
+        digest.update(proved.journal.transactions_digest());
+    }
+
+    Ok(Aggregation {
+        journal: AggregateJournal {
+            batch_image_id: input.batch_image_id,
+            aggregate_image_id: input.aggregate_image_id,
+            batch_ids,
+            pre_state_root,
+            post_state_root,
+            transactions_digest: digest.finalize().into(),
+        },
+    })
+}
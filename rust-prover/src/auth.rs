@@ -0,0 +1,212 @@
+//! Authorization of a batch's transfers: the signed transaction behind each one
+//! must carry a valid ed25519 signature from every required signer, and its one
+//! system transfer must be exactly the transfer the witness claims.
+//!
+//! Everything here runs inside the RISC Zero guest as well as natively, so the
+//! legacy wire format is parsed by hand rather than through the Solana SDK.
+
+use std::fmt;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::witness::{BatchWitness, TransferWitness};
+
+/// The system program's id, all zeroes.
+pub(crate) const SYSTEM_PROGRAM: [u8; 32] = [0; 32];
+/// bincode `SystemInstruction::Transfer`: a little-endian u32 tag of 2, then the lamports.
+const TRANSFER_TAG: [u8; 4] = [2, 0, 0, 0];
+
+/// Why the transaction behind transfer `slot` does not authorize it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthError {
+    pub slot: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction {} {}", self.slot, self.reason)
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// The system transfer a fully signed transaction authorizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignedTransfer {
+    pub fee_payer: [u8; 32],
+    pub from: [u8; 32],
+    pub to: [u8; 32],
+    pub lamports: u64,
+}
+
+/// Checks every transfer of `witness` against its signed transaction.
+pub fn authorize(witness: &BatchWitness) -> Result<(), AuthError> {
+    if witness.transactions.len() != witness.transfers.len() {
+        let slot = witness.transactions.len().min(witness.transfers.len());
+        return Err(AuthError { slot, reason: "is missing from the witness" });
+    }
+
+    for (slot, (transfer, transaction)) in witness.transfers.iter().zip(&witness.transactions).enumerate() {
+        authorize_transfer(transfer, transaction).map_err(|reason| AuthError { slot, reason })?;
+    }
+    Ok(())
+}
+
+/// Checks one transfer against the signed transaction behind it.
+pub fn authorize_transfer(transfer: &TransferWitness, transaction: &[u8]) -> Result<(), &'static str> {
+    let signed = signed_transfer(transaction)?;
+    if signed.from != transfer.from || signed.to != transfer.to || signed.lamports != transfer.amount {
+        return Err("signs a different transfer");
+    }
+    // the circuit charges the fee to the sender
+    if signed.fee_payer != transfer.from {
+        return Err("has a fee payer other than the sender");
+    }
+    Ok(())
+}
+
+/// Verifies all signatures of a legacy wire-format transaction and returns its
+/// single system transfer. Other instructions are allowed, as in the sequencer.
+pub fn signed_transfer(transaction: &[u8]) -> Result<SignedTransfer, &'static str> {
+    verify_message(transaction)?.system_transfer()
+}
+
+/// A legacy transaction message whose signatures all verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedMessage<'a> {
+    /// The first `required_signatures` keys signed; the first of them pays the fee.
+    pub required_signatures: usize,
+    pub keys: Vec<[u8; 32]>,
+    pub instructions: Vec<Instruction<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    pub program: [u8; 32],
+    /// Indexes into the message's keys, all in range.
+    pub accounts: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl Instruction<'_> {
+    pub fn is_system_transfer(&self) -> bool {
+        self.program == SYSTEM_PROGRAM && self.data.len() == 12 && self.data[..4] == TRANSFER_TAG
+    }
+}
+
+impl VerifiedMessage<'_> {
+    pub fn fee_payer(&self) -> [u8; 32] {
+        self.keys[0]
+    }
+
+    /// The message's only system transfer, whose sender must have signed.
+    pub fn system_transfer(&self) -> Result<SignedTransfer, &'static str> {
+        let mut transfers = self.instructions.iter().filter(|instruction| instruction.is_system_transfer());
+        let instruction = transfers.next().ok_or("has no system transfer")?;
+        if transfers.next().is_some() {
+            return Err("has more than one system transfer");
+        }
+
+        let account = |position: usize| {
+            instruction
+                .accounts
+                .get(position)
+                .map(|&index| index as usize)
+                .ok_or("has a system transfer with a missing account")
+        };
+        let from = account(0)?;
+        if from >= self.required_signatures {
+            return Err("has a system transfer its sender did not sign");
+        }
+        Ok(SignedTransfer {
+            fee_payer: self.fee_payer(),
+            from: self.keys[from],
+            to: self.keys[account(1)?],
+            lamports: u64::from_le_bytes(instruction.data[4..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Parses a legacy wire-format transaction and verifies the signature of every
+/// required signer over its message.
+pub fn verify_message(transaction: &[u8]) -> Result<VerifiedMessage<'_>, &'static str> {
+    let mut reader = Reader(transaction);
+    let signature_count = reader.short_len()?;
+    let signatures = (0..signature_count).map(|_| reader.array::<64>()).collect::<Result<Vec<_>, _>>()?;
+    let message = reader.0;
+
+    let header = reader.take(3)?;
+    if header[0] & 0x80 != 0 {
+        return Err("is a versioned transaction");
+    }
+    let required_signatures = header[0] as usize;
+    let keys = (0..reader.short_len()?).map(|_| reader.array::<32>()).collect::<Result<Vec<_>, _>>()?;
+    reader.take(32)?; // recent blockhash
+
+    if required_signatures == 0 || required_signatures > keys.len() {
+        return Err("has an invalid message header");
+    }
+    if signatures.len() != required_signatures {
+        return Err("does not carry one signature per required signer");
+    }
+    for (key, signature) in keys.iter().zip(&signatures) {
+        let key = VerifyingKey::from_bytes(key).map_err(|_| "has a signer that is not an ed25519 key")?;
+        key.verify_strict(message, &Signature::from_bytes(signature))
+            .map_err(|_| "has an invalid signature")?;
+    }
+
+    let mut instructions = Vec::new();
+    for _ in 0..reader.short_len()? {
+        let program = *keys.get(reader.byte()? as usize).ok_or("has an out of range program index")?;
+        let accounts = reader.take_len()?;
+        if accounts.iter().any(|&index| index as usize >= keys.len()) {
+            return Err("has an out of range account index");
+        }
+        instructions.push(Instruction { program, accounts, data: reader.take_len()? });
+    }
+    if !reader.0.is_empty() {
+        return Err("has trailing bytes");
+    }
+    Ok(VerifiedMessage { required_signatures, keys, instructions })
+}
+
+/// Reads the wire format front to back.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        if self.0.len() < len {
+            return Err("is truncated");
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    /// A compact-u16 length: seven bits per byte, low bits first, at most three bytes.
+    fn short_len(&mut self) -> Result<usize, &'static str> {
+        let mut len = 0;
+        for position in 0..3 {
+            let byte = self.byte()?;
+            len |= ((byte & 0x7f) as usize) << (7 * position);
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+        }
+        Err("has a malformed length")
+    }
+
+    fn take_len(&mut self) -> Result<&'a [u8], &'static str> {
+        let len = self.short_len()?;
+        self.take(len)
+    }
+}
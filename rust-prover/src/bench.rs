@@ -0,0 +1,54 @@
+//! Measurements of the batch circuit for the node's benchmark harness, beyond
+//! the proving and verifying times any backend can be timed for.
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+
+use crate::{
+    circuit::BatchTransferCircuit,
+    prover::ProverError,
+    witness::{AccountPath, TransferWitness},
+};
+
+/// Size of a circuit, and what it costs to compute a witness for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub constraints: usize,
+    /// Replaying the transfers over the state tree and assigning every variable.
+    pub witness_time: Duration,
+}
+
+/// Builds the witness of `transfers` over the state `paths` lead to, padded to
+/// `batch_size` slots, the part of `BatchProver::prove` that runs before Groth16 itself.
+pub fn synthesize(transfers: Vec<TransferWitness>, paths: &[AccountPath], batch_size: usize) -> Result<CircuitStats, ProverError> {
+    let start = Instant::now();
+    let circuit = BatchTransferCircuit::new(transfers, paths, batch_size)?;
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    let witness_time = start.elapsed();
+
+    if !cs.is_satisfied()? {
+        return Err(ProverError::Unsatisfied);
+    }
+    Ok(CircuitStats { constraints: cs.num_constraints(), witness_time })
+}
+
+/// Restarts the process's peak memory count, so the next `peak_memory_bytes`
+/// only covers what ran in between. Linux only; elsewhere this returns `false`.
+pub fn reset_peak_memory() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Peak resident memory of this process (`VmHWM`), if the platform reports it.
+/// Work done in child processes, such as snarkjs, is not included.
+pub fn peak_memory_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::{constraints::CryptographicSpongeVar, poseidon::constraints::PoseidonSpongeVar};
+use ark_ff::{PrimeField, Zero};
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    boolean::Boolean,
+    convert::ToBitsGadget,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+    select::CondSelectGadget,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use crate::{
+    prover::ProverError,
+    state::{key_limbs, poseidon_config, StateTree, STATE_TREE_DEPTH},
+    witness::{AccountPath, TransferWitness},
+};
+
+/// Amounts, fees and balances are range checked to lamports' width.
+const BALANCE_BITS: usize = 64;
+
+impl TransferWitness {
+    /// Moves nothing: an account paying itself zero lamports, to fill unused slots.
+    fn no_op(account: [u8; 32], balance: u64) -> Self {
+        Self {
+            from: account,
+            to: account,
+            amount: 0,
+            fee: 0,
+            signature_first_byte: 0,
+            from_balance_before: balance,
+            from_balance_after: balance,
+            to_balance_before: balance,
+            to_balance_after: balance,
+        }
+    }
+}
+
+/// One transfer slot, with the Merkle paths of both accounts at the time it applies.
+#[derive(Debug, Clone)]
+struct TransferStep {
+    transfer: TransferWitness,
+    /// The sender enters the state with this transfer: its leaf was empty.
+    from_enters: bool,
+    from_path: Vec<Fr>,
+    to_enters: bool,
+    to_path: Vec<Fr>,
+}
+
+/// Proves a fixed-size batch of system transfers against the global state tree
+/// (`state::StateTree`). Public inputs are its roots before and after the batch.
+///
+/// Each slot debits `amount + fee` from the sender, then credits `amount` to
+/// the receiver, moving the root one leaf update at a time, so accounts that
+/// appear in several transfers carry their balance from one to the next.
+///
+/// Not covered: an account whose leaf is empty enters the state with whatever
+/// balance the witness says it had on L1. There are no proven deposits yet, so
+/// a dishonest sequencer can enter an account with lamports it never had. A
+/// proof only shows that the batch moved lamports correctly from the state
+/// under its pre-state root plus those entry balances.
+#[derive(Debug, Clone)]
+pub struct BatchTransferCircuit {
+    pub pre_state_root: Fr,
+    pub post_state_root: Fr,
+    steps: Vec<TransferStep>,
+}
+
+impl BatchTransferCircuit {
+    /// Replays `transfers` over the state `paths` lead to and pads the batch up to
+    /// `batch_size` slots. `paths` must hold every account the transfers touch, as
+    /// the state tree had it before the batch. Fails on the first transfer the
+    /// circuit would reject.
+    pub fn new(transfers: Vec<TransferWitness>, paths: &[AccountPath], batch_size: usize) -> Result<Self, ProverError> {
+        if transfers.len() > batch_size {
+            return Err(ProverError::TooManyTransfers { transfers: transfers.len(), slots: batch_size });
+        }
+        let Some(first) = transfers.first().map(|transfer| transfer.from) else {
+            return Err(ProverError::EmptyBatch);
+        };
+
+        let mut tree = StateTree::from_paths(paths)?;
+        let pre_state_root = tree.root();
+        let mut balances: BTreeMap<[u8; 32], Option<u64>> = paths.iter().map(|path| (path.pubkey, path.balance)).collect();
+
+        let mut steps = Vec::with_capacity(batch_size);
+        for slot in 0..batch_size {
+            let invalid = |reason| ProverError::InvalidTransfer { slot, reason };
+            // unused slots come after every real transfer, so the first sender is in the state by then
+            let transfer = match transfers.get(slot) {
+                Some(transfer) => *transfer,
+                None => TransferWitness::no_op(first, balances[&first].expect("the first sender entered the state")),
+            };
+            if !transfer.balances_add_up() {
+                return Err(invalid("balances do not add up"));
+            }
+
+            let from_enters = match balances.get(&transfer.from) {
+                None => return Err(invalid("sender has no state path")),
+                Some(Some(balance)) if *balance != transfer.from_balance_before => {
+                    return Err(invalid("sender balance does not match the state"));
+                }
+                Some(balance) => balance.is_none(),
+            };
+            let from_path = tree.path(&transfer.from)?.siblings.iter().map(|node| Fr::from_be_bytes_mod_order(node)).collect();
+            balances.insert(transfer.from, Some(transfer.from_balance_after));
+            tree.set(&transfer.from, transfer.from_balance_after)?;
+
+            let to_enters = match balances.get(&transfer.to) {
+                None => return Err(invalid("receiver has no state path")),
+                Some(Some(balance)) if *balance != transfer.to_balance_before => {
+                    return Err(invalid("receiver balance does not match the state"));
+                }
+                Some(balance) => balance.is_none(),
+            };
+            let to_path = tree.path(&transfer.to)?.siblings.iter().map(|node| Fr::from_be_bytes_mod_order(node)).collect();
+            balances.insert(transfer.to, Some(transfer.to_balance_after));
+            tree.set(&transfer.to, transfer.to_balance_after)?;
+
+            steps.push(TransferStep { transfer, from_enters, from_path, to_enters, to_path });
+        }
+
+        Ok(Self { pre_state_root, post_state_root: tree.root(), steps })
+    }
+
+    /// An all-zero circuit, enough to derive keys for `batch_size` slots.
+    pub fn blank(batch_size: usize) -> Self {
+        let step = TransferStep {
+            transfer: TransferWitness::no_op([0; 32], 0),
+            from_enters: false,
+            from_path: vec![Fr::zero(); STATE_TREE_DEPTH],
+            to_enters: false,
+            to_path: vec![Fr::zero(); STATE_TREE_DEPTH],
+        };
+        Self {
+            pre_state_root: Fr::zero(),
+            post_state_root: Fr::zero(),
+            steps: vec![step; batch_size],
+        }
+    }
+
+    /// The public inputs a proof of this circuit verifies against.
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        vec![self.pre_state_root, self.post_state_root]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BatchTransferCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let pre_state_root = FpVar::new_input(cs.clone(), || Ok(self.pre_state_root))?;
+        let post_state_root = FpVar::new_input(cs.clone(), || Ok(self.post_state_root))?;
+
+        let mut root = pre_state_root;
+        for step in &self.steps {
+            root = apply_transfer(cs.clone(), &root, step)?;
+        }
+        root.enforce_equal(&post_state_root)
+    }
+}
+
+/// Enforces one transfer and returns the state root after it.
+fn apply_transfer(cs: ConstraintSystemRef<Fr>, root: &FpVar<Fr>, step: &TransferStep) -> Result<FpVar<Fr>, SynthesisError> {
+    let transfer = &step.transfer;
+    let amount = lamports_var(cs.clone(), transfer.amount)?;
+    let fee = lamports_var(cs.clone(), transfer.fee)?;
+    let from_after = lamports_var(cs.clone(), transfer.from_balance_after)?;
+    let to_before = lamports_var(cs.clone(), transfer.to_balance_before)?;
+    let to_after = lamports_var(cs.clone(), transfer.to_balance_after)?;
+    let from_before = FpVar::new_witness(cs.clone(), || Ok(Fr::from(transfer.from_balance_before)))?;
+
+    // every term is range checked, so neither sum can wrap and no balance goes negative
+    from_before.enforce_equal(&(&from_after + &amount + &fee))?;
+    to_after.enforce_equal(&(&to_before + &amount))?;
+
+    let debited = update_leaf(cs.clone(), root, &transfer.from, &from_before, &from_after, step.from_enters, &step.from_path)?;
+    update_leaf(cs, &debited, &transfer.to, &to_before, &to_after, step.to_enters, &step.to_path)
+}
+
+/// Enforces that `key` holds `before` in the tree under `root` (or that its leaf is
+/// empty, if it `enters`) and returns the root with `after` in its place.
+fn update_leaf(
+    cs: ConstraintSystemRef<Fr>,
+    root: &FpVar<Fr>,
+    key: &[u8; 32],
+    before: &FpVar<Fr>,
+    after: &FpVar<Fr>,
+    enters: bool,
+    path: &[Fr],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let key_vars = key_vars(cs.clone(), key)?;
+    let index_bits = index_bits(cs.clone(), &key_vars)?;
+    let path = path_vars(cs.clone(), path)?;
+    let enters = Boolean::new_witness(cs.clone(), || Ok(enters))?;
+
+    let held = leaf_hash_var(cs.clone(), &key_vars, before)?;
+    let old_leaf = FpVar::conditionally_select(&enters, &FpVar::zero(), &held)?;
+    root_from_path(cs.clone(), old_leaf, &index_bits, &path)?.enforce_equal(root)?;
+    let new_leaf = leaf_hash_var(cs.clone(), &key_vars, after)?;
+    root_from_path(cs, new_leaf, &index_bits, &path)
+}
+
+/// A witness that must fit in `BALANCE_BITS` bits.
+fn lamports_var(cs: ConstraintSystemRef<Fr>, value: u64) -> Result<FpVar<Fr>, SynthesisError> {
+    let bits = (0..BALANCE_BITS)
+        .map(|i| Boolean::new_witness(cs.clone(), || Ok((value >> i) & 1 == 1)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let var = FpVar::new_witness(cs, || Ok(Fr::from(value)))?;
+    Boolean::le_bits_to_fp(&bits)?.enforce_equal(&var)?;
+    Ok(var)
+}
+
+fn key_vars(cs: ConstraintSystemRef<Fr>, key: &[u8; 32]) -> Result<[FpVar<Fr>; 2], SynthesisError> {
+    let [hi, lo] = key_limbs(key);
+    Ok([FpVar::new_witness(cs.clone(), || Ok(hi))?, FpVar::new_witness(cs, || Ok(lo))?])
+}
+
+/// The leaf index of a key, bit by bit: the low `STATE_TREE_DEPTH` bits of the hash of
+/// the whole key, so an account can only be proved at its own leaf.
+fn index_bits(cs: ConstraintSystemRef<Fr>, key: &[FpVar<Fr>; 2]) -> Result<Vec<Boolean<Fr>>, SynthesisError> {
+    // the decomposition is the canonical one, so the prover has no other index to pick
+    let mut bits = hash_var(cs, key.to_vec())?.to_bits_le()?;
+    bits.truncate(STATE_TREE_DEPTH);
+    Ok(bits)
+}
+
+fn path_vars(cs: ConstraintSystemRef<Fr>, path: &[Fr]) -> Result<Vec<FpVar<Fr>>, SynthesisError> {
+    path.iter().map(|sibling| FpVar::new_witness(cs.clone(), || Ok(*sibling))).collect()
+}
+
+/// Hashes up from a leaf; a set index bit means the node is the right child.
+fn root_from_path(
+    cs: ConstraintSystemRef<Fr>,
+    leaf: FpVar<Fr>,
+    index_bits: &[Boolean<Fr>],
+    path: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut node = leaf;
+    for (is_right, sibling) in index_bits.iter().zip(path) {
+        let left = FpVar::conditionally_select(is_right, sibling, &node)?;
+        let right = FpVar::conditionally_select(is_right, &node, sibling)?;
+        node = hash_var(cs.clone(), vec![left, right])?;
+    }
+    Ok(node)
+}
+
+fn leaf_hash_var(cs: ConstraintSystemRef<Fr>, key: &[FpVar<Fr>; 2], balance: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    hash_var(cs, vec![key[0].clone(), key[1].clone(), balance.clone()])
+}
+
+fn hash_var(cs: ConstraintSystemRef<Fr>, inputs: Vec<FpVar<Fr>>) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut sponge = PoseidonSpongeVar::new(cs, poseidon_config());
+    sponge.absorb(&inputs)?;
+    Ok(sponge.squeeze_field_elements(1)?.remove(0))
+}
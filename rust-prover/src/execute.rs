@@ -0,0 +1,192 @@
+//! Re-execution of a batch from its signed transactions and the accounts they
+//! touch, for the RISC Zero guest. It trusts nothing the sequencer computed:
+//! fees and balances come out of the transactions, and every account is opened
+//! against the global state tree (`state::StateTree`), the same one the batch
+//! circuit proves against. The roots before and after are that tree's, so
+//! consecutive batches chain through them whichever backend proved them.
+//!
+//! As in the circuit, an account whose leaf is empty enters the state with the
+//! lamports the witness gives it; those are not proven until deposits are. The
+//! tree only holds lamports, so owner, data and executable are taken as given.
+//!
+//! Only what the batch circuit covers is executed so far: system transfers,
+//! optionally with compute budget instructions. Any other instruction fails the
+//! batch. Rent is not checked.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{verify_message, Instruction, VerifiedMessage, SYSTEM_PROGRAM},
+    state::StateTree,
+    witness::AccountPath,
+};
+
+/// `FeeStructure::default()` of the node's SVM.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// ComputeBudget111111111111111111111111111111
+const COMPUTE_BUDGET_PROGRAM: [u8; 32] = [
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187, 197, 247, 18, 107, 44,
+    67, 155, 58, 64, 0, 0, 0,
+];
+/// Compute units a builtin instruction gets without a `SetComputeUnitLimit`.
+const BUILTIN_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 3_000;
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// One account as the batch found it (or left it).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountWitness {
+    pub pubkey: [u8; 32],
+    pub lamports: u64,
+    pub owner: [u8; 32],
+    pub executable: bool,
+    pub data: Vec<u8>,
+}
+
+impl AccountWitness {
+    /// An account that does not exist yet, as the SVM loads it.
+    pub fn empty(pubkey: [u8; 32]) -> Self {
+        Self { pubkey, lamports: 0, owner: SYSTEM_PROGRAM, executable: false, data: Vec::new() }
+    }
+}
+
+/// Why transaction `slot` of the batch could not be executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionError {
+    pub slot: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction {} {}", self.slot, self.reason)
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    /// `StateTree::root_bytes` before and after the batch.
+    pub pre_state_root: [u8; 32],
+    pub post_state_root: [u8; 32],
+    /// The same accounts as the pre-state, sorted by key, after the batch.
+    pub accounts: Vec<AccountWitness>,
+}
+
+/// Executes `transactions` in order over `accounts`, which must hold every account
+/// they touch (`AccountWitness::empty` for ones that do not exist yet), each with
+/// its path in `state_paths`. Every transaction has to succeed, as the sequencer
+/// only proves batches that did. Every account goes back into the tree afterwards.
+pub fn execute(
+    accounts: &[AccountWitness],
+    state_paths: &[AccountPath],
+    transactions: &[Vec<u8>],
+) -> Result<Execution, ExecutionError> {
+    let pre_state = |reason| ExecutionError { slot: 0, reason };
+    let mut tree = StateTree::from_paths(state_paths).map_err(|_| pre_state("has state paths that do not open one state tree"))?;
+    let held: BTreeMap<[u8; 32], Option<u64>> = state_paths.iter().map(|path| (path.pubkey, path.balance)).collect();
+
+    let mut state = BTreeMap::new();
+    for account in accounts {
+        match held.get(&account.pubkey) {
+            None => return Err(pre_state("has an account without a state path")),
+            Some(Some(lamports)) if *lamports != account.lamports => {
+                return Err(pre_state("has an account with other lamports than the state tree"));
+            }
+            Some(_) => {}
+        }
+        if state.insert(account.pubkey, account.clone()).is_some() {
+            return Err(pre_state("has an account listed twice in the pre-state"));
+        }
+    }
+    let pre_state_root = tree.root_bytes();
+
+    for (slot, transaction) in transactions.iter().enumerate() {
+        execute_transaction(&mut state, transaction).map_err(|reason| ExecutionError { slot, reason })?;
+    }
+
+    for account in state.values() {
+        // an empty leaf on a valid path is free, so only another path could claim it
+        tree.set(&account.pubkey, account.lamports).map_err(|_| pre_state("has two accounts that share a state tree leaf"))?;
+    }
+    Ok(Execution { pre_state_root, post_state_root: tree.root_bytes(), accounts: state.into_values().collect() })
+}
+
+fn execute_transaction(state: &mut BTreeMap<[u8; 32], AccountWitness>, transaction: &[u8]) -> Result<(), &'static str> {
+    let message = verify_message(transaction)?;
+    if let Some(other) = message
+        .instructions
+        .iter()
+        .find(|instruction| instruction.program != COMPUTE_BUDGET_PROGRAM && !instruction.is_system_transfer())
+    {
+        return Err(if other.program == SYSTEM_PROGRAM {
+            "has a system instruction other than a transfer"
+        } else {
+            "calls a program the guest cannot execute yet"
+        });
+    }
+    let transfer = message.system_transfer()?;
+    let fee = message_fee(&message)?;
+
+    // the fee is charged before any instruction runs
+    let payer = system_account(state, &transfer.fee_payer)?;
+    payer.lamports = payer.lamports.checked_sub(fee).ok_or("has a fee payer that cannot pay the fee")?;
+
+    let from = system_account(state, &transfer.from)?;
+    from.lamports = from.lamports.checked_sub(transfer.lamports).ok_or("transfers more lamports than the sender has")?;
+    let to = state.get_mut(&transfer.to).ok_or("touches an account missing from the pre-state")?;
+    to.lamports = to.lamports.checked_add(transfer.lamports).ok_or("overflows the receiver's lamports")?;
+    Ok(())
+}
+
+/// The fee the batch charges `transaction`'s fee payer: the signature fee plus
+/// its prioritization fee.
+pub fn transaction_fee(transaction: &[u8]) -> Result<u64, &'static str> {
+    message_fee(&verify_message(transaction)?)
+}
+
+fn message_fee(message: &VerifiedMessage) -> Result<u64, &'static str> {
+    let signature_fee = message.required_signatures as u64 * LAMPORTS_PER_SIGNATURE;
+    signature_fee.checked_add(prioritization_fee(&message.instructions)?).ok_or("has a fee that overflows")
+}
+
+/// An account the system program may debit: owned by it and without data.
+fn system_account<'a>(
+    state: &'a mut BTreeMap<[u8; 32], AccountWitness>,
+    key: &[u8; 32],
+) -> Result<&'a mut AccountWitness, &'static str> {
+    let account = state.get_mut(key).ok_or("touches an account missing from the pre-state")?;
+    if account.owner != SYSTEM_PROGRAM || !account.data.is_empty() {
+        return Err("debits an account the system program does not own");
+    }
+    Ok(account)
+}
+
+/// Compute unit price times the compute unit limit, rounded up to whole lamports.
+fn prioritization_fee(instructions: &[Instruction]) -> Result<u64, &'static str> {
+    let mut unit_price = None;
+    let mut unit_limit = None;
+    for instruction in instructions.iter().filter(|instruction| instruction.program == COMPUTE_BUDGET_PROGRAM) {
+        // ComputeBudgetInstruction discriminants: 2 = SetComputeUnitLimit(u32), 3 = SetComputeUnitPrice(u64)
+        match instruction.data.split_first() {
+            Some((&2, limit)) if limit.len() == 4 && unit_limit.is_none() => {
+                unit_limit = Some(u32::from_le_bytes(limit.try_into().unwrap()) as u64);
+            }
+            Some((&3, price)) if price.len() == 8 && unit_price.is_none() => {
+                unit_price = Some(u64::from_le_bytes(price.try_into().unwrap()));
+            }
+            // the SVM fails a transaction with a duplicate or malformed one
+            _ => return Err("has an invalid compute budget instruction"),
+        }
+    }
+
+    let builtins = instructions.iter().filter(|instruction| instruction.program != COMPUTE_BUDGET_PROGRAM).count() as u64;
+    let unit_limit = unit_limit
+        .unwrap_or(builtins * BUILTIN_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+    let micro_lamports = unit_price.unwrap_or(0) as u128 * unit_limit as u128;
+    Ok(micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64)
+}
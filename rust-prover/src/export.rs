@@ -0,0 +1,152 @@
+//! snarkjs' JSON layout for proofs and verifying keys: decimal coordinates,
+//! projective with z = 1, and Fq2 elements written imaginary part first.
+//!
+//! Parsing back is strict: every number must be a canonical field element and
+//! every point must lie in the right subgroup, so a proof that parses here is one
+//! the on-chain verifier's syscalls also accept as input.
+
+use std::str::FromStr;
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::prover::ProverError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnarkJsProof {
+    pub pi_a: [String; 3],
+    pub pi_b: [[String; 2]; 3],
+    pub pi_c: [String; 3],
+    pub protocol: String,
+    pub curve: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnarkJsVerifyingKey {
+    pub protocol: String,
+    pub curve: String,
+    pub nPublic: usize,
+    pub vk_alpha_1: [String; 3],
+    pub vk_beta_2: [[String; 2]; 3],
+    pub vk_gamma_2: [[String; 2]; 3],
+    pub vk_delta_2: [[String; 2]; 3],
+    pub IC: Vec<[String; 3]>,
+}
+
+impl From<&Proof<Bn254>> for SnarkJsProof {
+    fn from(proof: &Proof<Bn254>) -> Self {
+        Self {
+            pi_a: g1_to_snarkjs(&proof.a),
+            pi_b: g2_to_snarkjs(&proof.b),
+            pi_c: g1_to_snarkjs(&proof.c),
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+        }
+    }
+}
+
+impl From<&VerifyingKey<Bn254>> for SnarkJsVerifyingKey {
+    fn from(vk: &VerifyingKey<Bn254>) -> Self {
+        Self {
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+            nPublic: vk.gamma_abc_g1.len() - 1,
+            vk_alpha_1: g1_to_snarkjs(&vk.alpha_g1),
+            vk_beta_2: g2_to_snarkjs(&vk.beta_g2),
+            vk_gamma_2: g2_to_snarkjs(&vk.gamma_g2),
+            vk_delta_2: g2_to_snarkjs(&vk.delta_g2),
+            IC: vk.gamma_abc_g1.iter().map(g1_to_snarkjs).collect(),
+        }
+    }
+}
+
+impl TryFrom<&SnarkJsProof> for Proof<Bn254> {
+    type Error = ProverError;
+
+    fn try_from(proof: &SnarkJsProof) -> Result<Self, ProverError> {
+        Ok(Proof {
+            a: g1_from_snarkjs(&proof.pi_a, "pi_a")?,
+            b: g2_from_snarkjs(&proof.pi_b, "pi_b")?,
+            c: g1_from_snarkjs(&proof.pi_c, "pi_c")?,
+        })
+    }
+}
+
+impl TryFrom<&SnarkJsVerifyingKey> for VerifyingKey<Bn254> {
+    type Error = ProverError;
+
+    fn try_from(vk: &SnarkJsVerifyingKey) -> Result<Self, ProverError> {
+        if vk.IC.len() != vk.nPublic + 1 {
+            return Err(ProverError::InvalidEncoding(format!("IC has {} points for {} public inputs", vk.IC.len(), vk.nPublic)));
+        }
+        Ok(VerifyingKey {
+            alpha_g1: g1_from_snarkjs(&vk.vk_alpha_1, "vk_alpha_1")?,
+            beta_g2: g2_from_snarkjs(&vk.vk_beta_2, "vk_beta_2")?,
+            gamma_g2: g2_from_snarkjs(&vk.vk_gamma_2, "vk_gamma_2")?,
+            delta_g2: g2_from_snarkjs(&vk.vk_delta_2, "vk_delta_2")?,
+            gamma_abc_g1: vk.IC.iter().map(|point| g1_from_snarkjs(point, "IC")).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// A public input as snarkjs writes it to `public.json`.
+pub fn fr_to_decimal(x: &Fr) -> String {
+    x.into_bigint().to_string()
+}
+
+pub fn fq_to_decimal(x: &Fq) -> String {
+    x.into_bigint().to_string()
+}
+
+pub fn fq2_to_snarkjs(x: &Fq2) -> [String; 2] {
+    [fq_to_decimal(&x.c1), fq_to_decimal(&x.c0)]
+}
+
+pub fn g1_to_snarkjs(p: &G1Affine) -> [String; 3] {
+    [fq_to_decimal(&p.x), fq_to_decimal(&p.y), "1".to_string()]
+}
+
+pub fn g2_to_snarkjs(p: &G2Affine) -> [[String; 2]; 3] {
+    [fq2_to_snarkjs(&p.x), fq2_to_snarkjs(&p.y), ["1".to_string(), "0".to_string()]]
+}
+
+/// Parses a public input from `public.json`.
+pub fn fr_from_decimal(s: &str) -> Result<Fr, ProverError> {
+    field_from_decimal(s, "public input")
+}
+
+fn field_from_decimal<F: PrimeField>(s: &str, name: &str) -> Result<F, ProverError> {
+    // `F::from_str` would reduce out-of-range values; the syscalls reject them
+    F::BigInt::from_str(s)
+        .ok()
+        .and_then(F::from_bigint)
+        .ok_or_else(|| ProverError::InvalidEncoding(format!("{} {:?} is not a field element", name, s)))
+}
+
+fn g1_from_snarkjs(p: &[String; 3], name: &str) -> Result<G1Affine, ProverError> {
+    if p[2] != "1" {
+        return Err(ProverError::InvalidEncoding(format!("{} is not an affine point", name)));
+    }
+    let point = G1Affine::new_unchecked(field_from_decimal(&p[0], name)?, field_from_decimal(&p[1], name)?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ProverError::InvalidEncoding(format!("{} is not a G1 point", name)));
+    }
+    Ok(point)
+}
+
+fn g2_from_snarkjs(p: &[[String; 2]; 3], name: &str) -> Result<G2Affine, ProverError> {
+    if p[2] != ["1", "0"] {
+        return Err(ProverError::InvalidEncoding(format!("{} is not an affine point", name)));
+    }
+    let fq2 = |x: &[String; 2]| -> Result<Fq2, ProverError> {
+        Ok(Fq2::new(field_from_decimal(&x[1], name)?, field_from_decimal(&x[0], name)?))
+    };
+    let point = G2Affine::new_unchecked(fq2(&p[0])?, fq2(&p[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ProverError::InvalidEncoding(format!("{} is not a G2 point", name)));
+    }
+    Ok(point)
+}
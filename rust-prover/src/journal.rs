@@ -0,0 +1,49 @@
+//! What the RISC Zero guest commits for a batch it proved. The receipt only
+//! exists if the guest ran to the end, so the journal is never a verdict of
+//! "invalid": a batch that fails re-execution has no receipt at all.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    execute::{execute, Execution, ExecutionError},
+    witness::BatchWitness,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchJournal {
+    pub batch_id: String,
+    /// Roots of the global state tree before and after the batch, as `StateTree::root_bytes`.
+    pub pre_state_root: [u8; 32],
+    pub post_state_root: [u8; 32],
+    /// `transactions_digest` of the batch's signed transactions.
+    pub transactions_digest: [u8; 32],
+}
+
+impl BatchJournal {
+    /// Re-executes the batch's transactions over its pre-state, as the guest does.
+    pub fn execute(witness: &BatchWitness) -> Result<Self, ExecutionError> {
+        let execution = execute(&witness.accounts, &witness.state_paths, &witness.transactions)?;
+        Ok(Self::from_execution(witness, &execution))
+    }
+
+    pub(crate) fn from_execution(witness: &BatchWitness, execution: &Execution) -> Self {
+        Self {
+            batch_id: witness.batch_id.clone(),
+            pre_state_root: execution.pre_state_root,
+            post_state_root: execution.post_state_root,
+            transactions_digest: transactions_digest(&witness.transactions),
+        }
+    }
+}
+
+/// SHA-256 over the wire-format transactions, each prefixed with its length as a
+/// little-endian u64, so that splitting them differently changes the digest.
+pub fn transactions_digest(transactions: &[Vec<u8>]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for transaction in transactions {
+        hasher.update((transaction.len() as u64).to_le_bytes());
+        hasher.update(transaction);
+    }
+    hasher.finalize().into()
+}
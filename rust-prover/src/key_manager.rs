@@ -0,0 +1,141 @@
+//! Manages the versioned proving keys the node and prover workers load.
+//!
+//! ```text
+//! key-manager setup <keys_dir> <ptau_file> <slots>[,<slots>...] [--name <contributor>]
+//! key-manager contribute <keys_dir> <slots> [--name <contributor>]
+//! key-manager contribute-file <proving_key.bin> <out>
+//! key-manager import <keys_dir> <slots> <proving_key.bin> [--name <contributor>]
+//! key-manager activate <keys_dir> <slots> <version>
+//! key-manager list <keys_dir>
+//! ```
+//!
+//! `setup` imports a snarkjs Powers of Tau file and makes the first phase 2
+//! contribution. Further contributors either run `contribute` on the key
+//! directory, or take a copy of the current `proving_key.bin`, run
+//! `contribute-file` on their own machine and hand the result back for `import`,
+//! which checks it before installing it as a new version.
+
+use std::{env, error::Error, path::PathBuf, process};
+
+use zk::{
+    keys::to_hex,
+    prover::entropy_rng,
+    setup::{self, file_sha256, PowersOfTau},
+    BatchProver, KeyManifest, KeyStore,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "usage:
+  key-manager setup <keys_dir> <ptau_file> <slots>[,<slots>...] [--name <contributor>]
+  key-manager contribute <keys_dir> <slots> [--name <contributor>]
+  key-manager contribute-file <proving_key.bin> <out>
+  key-manager import <keys_dir> <slots> <proving_key.bin> [--name <contributor>]
+  key-manager activate <keys_dir> <slots> <version>
+  key-manager list <keys_dir>";
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let name = match take_option(&mut args, "--name") {
+        Ok(name) => name.unwrap_or_else(|| env::var("USER").unwrap_or_else(|_| "anonymous".to_string())),
+        Err(e) => exit(e),
+    };
+    if let Err(e) = run(&args, &name) {
+        exit(e);
+    }
+}
+
+fn run(args: &[String], name: &str) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["setup", dir, ptau, classes] => {
+            let classes = classes.split(',').map(str::parse).collect::<std::result::Result<Vec<usize>, _>>()?;
+            let max_domain = classes.iter().map(|&slots| setup::domain_size(slots)).try_fold(0, |max, size| {
+                size.map(|size| max.max(size))
+            })?;
+            let ptau_path = PathBuf::from(ptau);
+            println!("Reading {} ({} powers needed)", ptau_path.display(), max_domain);
+            let ptau_sha256 = file_sha256(&ptau_path)?;
+            let ceremony = PowersOfTau::read(&ptau_path, max_domain)?;
+            ceremony.check(&mut entropy_rng())?;
+            println!("Ceremony sha256 {}, 2^{} powers", to_hex(&ptau_sha256), ceremony.power);
+
+            let store = KeyStore::new(dir);
+            for slots in classes {
+                let manifest = store.setup_from_ptau(&ceremony, ptau_sha256, slots, name, &mut entropy_rng())?;
+                print_manifest(&manifest);
+            }
+        }
+        ["contribute", dir, slots] => {
+            print_manifest(&KeyStore::new(dir).contribute(slots.parse()?, name, &mut entropy_rng())?);
+        }
+        ["contribute-file", input, output] => {
+            let prover = BatchProver::load(&PathBuf::from(input))?;
+            let next = BatchProver::from_proving_key(
+                prover.batch_size(),
+                setup::contribute(prover.proving_key(), &mut entropy_rng()),
+            );
+            next.save(&PathBuf::from(output))?;
+            println!("Wrote the contributed key for {} slots to {}", next.batch_size(), output);
+        }
+        ["import", dir, slots, file] => {
+            let store = KeyStore::new(dir);
+            print_manifest(&store.import_contribution(slots.parse()?, name, &PathBuf::from(file), &mut entropy_rng())?);
+        }
+        ["activate", dir, slots, version] => {
+            print_manifest(&KeyStore::new(dir).activate(slots.parse()?, version.parse()?)?);
+        }
+        ["list", dir] => {
+            let store = KeyStore::new(dir);
+            for slots in store.size_classes()? {
+                let current = store.current(slots)?;
+                for version in store.versions(slots)? {
+                    let manifest = store.manifest(slots, version)?;
+                    let marker = if Some(version) == current { "*" } else { " " };
+                    println!(
+                        "{} batch_{} v{}  {} contribution(s)  pk {}  {}",
+                        marker,
+                        slots,
+                        version,
+                        manifest.contributions.len(),
+                        &manifest.proving_key_sha256[..16],
+                        manifest.insecure_reason().unwrap_or("ok"),
+                    );
+                }
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn print_manifest(manifest: &KeyManifest) {
+    println!(
+        "batch_{} v{} is current: proving key sha256 {}, verification key sha256 {}",
+        manifest.slots, manifest.version, manifest.proving_key_sha256, manifest.verification_key_sha256
+    );
+    for contribution in &manifest.contributions {
+        println!("  contributed by {} -> {}", contribution.name, contribution.proving_key_sha256);
+    }
+    if let Some(reason) = manifest.insecure_reason() {
+        println!("  not safe for settlement: {}", reason);
+    }
+}
+
+/// Removes `--flag <value>` from the arguments.
+fn take_option(args: &mut Vec<String>, flag: &str) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("{} needs a value", flag).into());
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+fn exit(e: Box<dyn Error>) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
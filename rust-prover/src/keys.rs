@@ -0,0 +1,389 @@
+//! Versioned proving keys, one directory per circuit size class:
+//!
+//! ```text
+//! <dir>/batch_<slots>/CURRENT                   version in use
+//! <dir>/batch_<slots>/v<n>/proving_key.bin
+//! <dir>/batch_<slots>/v<n>/verification_key.json
+//! <dir>/batch_<slots>/v<n>/manifest.json        origin, contributions and SHA-256 of both keys
+//! ```
+//!
+//! Versions are never rewritten; a setup or contribution writes the next one
+//! and then moves `CURRENT` to it, so a rollback is a matter of pointing
+//! `CURRENT` back. Keys from a seeded setup, or from a ceremony nobody has
+//! contributed to, are refused unless the store is in dev mode.
+//!
+//! The verification key is stored apart from the proving key, so a node whose
+//! proving happens elsewhere reads only `verification_key.json`.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+use ark_std::rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    export::SnarkJsVerifyingKey,
+    prover::{BatchProver, ProverError},
+    setup::{self, PowersOfTau},
+};
+
+const CURRENT: &str = "CURRENT";
+const PROVING_KEY: &str = "proving_key.bin";
+const VERIFICATION_KEY: &str = "verification_key.json";
+const MANIFEST: &str = "manifest.json";
+
+/// Where a key's τ, α and β came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KeyOrigin {
+    /// `BatchProver::setup` with `StdRng::seed_from_u64(seed)`; anyone can forge proofs.
+    DevSeed { seed: u64 },
+    /// A phase 1 ceremony, pinned by the SHA-256 of its `.ptau` file.
+    PowersOfTau { ptau_sha256: String, power: u32 },
+}
+
+/// One phase 2 contribution, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub name: String,
+    /// The proving key right after this contribution.
+    pub proving_key_sha256: String,
+    pub contributed_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyManifest {
+    pub slots: usize,
+    pub version: u32,
+    pub origin: KeyOrigin,
+    pub contributions: Vec<Contribution>,
+    pub proving_key_sha256: String,
+    pub verification_key_sha256: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+impl KeyManifest {
+    /// Why a key with this history is not safe to settle proofs with, if it is not.
+    pub fn insecure_reason(&self) -> Option<&'static str> {
+        match self.origin {
+            KeyOrigin::DevSeed { .. } => Some("it comes from a deterministic seed"),
+            KeyOrigin::PowersOfTau { .. } if self.contributions.is_empty() => Some("it has no phase 2 contribution"),
+            KeyOrigin::PowersOfTau { .. } => None,
+        }
+    }
+}
+
+/// Proving keys for several circuit size classes. A class's key is read, and
+/// checked against its manifest, the first time a batch needs it.
+pub struct KeyStore {
+    dir: PathBuf,
+    dev_seed: Option<u64>,
+    // held across a load or setup, so a class is never set up twice
+    provers: Mutex<BTreeMap<usize, Arc<BatchProver>>>,
+}
+
+impl KeyStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), dev_seed: None, provers: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Dev mode: classes without a key get a deterministic setup from `seed`
+    /// instead of failing, and keys that are not safe for settlement load anyway.
+    pub fn with_dev_setup(mut self, seed: u64) -> Self {
+        self.dev_seed = Some(seed);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn class_dir(&self, slots: usize) -> PathBuf {
+        self.dir.join(format!("batch_{}", slots))
+    }
+
+    pub fn version_dir(&self, slots: usize, version: u32) -> PathBuf {
+        self.class_dir(slots).join(format!("v{}", version))
+    }
+
+    /// The size classes with at least one key version.
+    pub fn size_classes(&self) -> Result<Vec<usize>, ProverError> {
+        let mut classes = Vec::new();
+        if !self.dir.is_dir() {
+            return Ok(classes);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            if let Some(slots) = name.to_str().and_then(|name| name.strip_prefix("batch_")?.parse().ok()) {
+                classes.push(slots);
+            }
+        }
+        classes.sort_unstable();
+        Ok(classes)
+    }
+
+    /// Every version written for `slots`, oldest first.
+    pub fn versions(&self, slots: usize) -> Result<Vec<u32>, ProverError> {
+        let dir = self.class_dir(slots);
+        let mut versions = Vec::new();
+        if !dir.is_dir() {
+            return Ok(versions);
+        }
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            if let Some(version) = name.to_str().and_then(|name| name.strip_prefix('v')?.parse().ok()) {
+                versions.push(version);
+            }
+        }
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    /// The version `slots` proves with, if it has one.
+    pub fn current(&self, slots: usize) -> Result<Option<u32>, ProverError> {
+        let path = self.class_dir(slots).join(CURRENT);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let version = fs::read_to_string(&path)?
+            .trim()
+            .parse()
+            .map_err(|e| ProverError::Manifest { path, reason: format!("bad version: {}", e) })?;
+        Ok(Some(version))
+    }
+
+    pub fn manifest(&self, slots: usize, version: u32) -> Result<KeyManifest, ProverError> {
+        let path = self.version_dir(slots, version).join(MANIFEST);
+        let manifest: KeyManifest = serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|e| ProverError::Manifest { path: path.clone(), reason: e.to_string() })?;
+        if manifest.slots != slots || manifest.version != version {
+            return Err(ProverError::Manifest { path, reason: "written for another key".to_string() });
+        }
+        Ok(manifest)
+    }
+
+    /// The manifest of the version `slots` proves with.
+    pub fn current_manifest(&self, slots: usize) -> Result<Option<KeyManifest>, ProverError> {
+        self.current(slots)?.map(|version| self.manifest(slots, version)).transpose()
+    }
+
+    /// Whether `slots` already has a key in memory or on disk.
+    pub fn has_key(&self, slots: usize) -> bool {
+        self.provers.lock().unwrap().contains_key(&slots) || self.class_dir(slots).join(CURRENT).is_file()
+    }
+
+    /// The prover for circuits of `slots` slots, loading (or, in dev mode, setting up) its key on first use.
+    pub fn get(&self, slots: usize) -> Result<Arc<BatchProver>, ProverError> {
+        let mut provers = self.provers.lock().unwrap();
+        if let Some(prover) = provers.get(&slots) {
+            return Ok(prover.clone());
+        }
+
+        let prover = match (self.current(slots)?, self.dev_seed) {
+            (Some(version), dev_seed) => {
+                let (manifest, prover) = self.load(slots, version)?;
+                if let (Some(reason), None) = (manifest.insecure_reason(), dev_seed) {
+                    return Err(ProverError::UntrustedKey { slots, reason });
+                }
+                prover
+            }
+            (None, Some(seed)) => {
+                let prover = BatchProver::setup(slots, &mut StdRng::seed_from_u64(seed))?;
+                self.install(&prover, KeyOrigin::DevSeed { seed }, Vec::new(), None)?;
+                prover
+            }
+            (None, None) => return Err(ProverError::MissingKey { slots, path: self.class_dir(slots) }),
+        };
+
+        let prover = Arc::new(prover);
+        provers.insert(slots, prover.clone());
+        Ok(prover)
+    }
+
+    /// The verifying key of `slots`' current version, read from its own file without
+    /// the proving key, for a node that only verifies. Checked against the manifest
+    /// like the proving key is; in dev mode a class without a key is set up first.
+    pub fn verifying_key(&self, slots: usize) -> Result<SnarkJsVerifyingKey, ProverError> {
+        if let Some(prover) = self.provers.lock().unwrap().get(&slots) {
+            return Ok(prover.verifying_key_snarkjs());
+        }
+        let Some(version) = self.current(slots)? else {
+            return self.get(slots).map(|prover| prover.verifying_key_snarkjs());
+        };
+
+        let manifest = self.manifest(slots, version)?;
+        if let (Some(reason), None) = (manifest.insecure_reason(), self.dev_seed) {
+            return Err(ProverError::UntrustedKey { slots, reason });
+        }
+        let bytes = fs::read(self.version_dir(slots, version).join(VERIFICATION_KEY))?;
+        if sha256_hex(&bytes) != manifest.verification_key_sha256 {
+            return Err(ProverError::KeyHashMismatch { slots, version });
+        }
+        let key: SnarkJsVerifyingKey =
+            serde_json::from_slice(&bytes).map_err(|e| ProverError::InvalidEncoding(e.to_string()))?;
+        VerifyingKey::<Bn254>::try_from(&key)?;
+        Ok(key)
+    }
+
+    /// Makes `version` the one `slots` proves with, e.g. to roll a contribution back.
+    pub fn activate(&self, slots: usize, version: u32) -> Result<KeyManifest, ProverError> {
+        let (manifest, _) = self.load(slots, version)?;
+        self.set_current(slots, version)?;
+        self.provers.lock().unwrap().remove(&slots);
+        Ok(manifest)
+    }
+
+    /// Starts a key for `slots` from a ceremony and makes the first phase 2
+    /// contribution right away, so an uncontributed key is never installed.
+    pub fn setup_from_ptau<R: RngCore + CryptoRng>(
+        &self,
+        ptau: &PowersOfTau,
+        ptau_sha256: [u8; 32],
+        slots: usize,
+        contributor: &str,
+        rng: &mut R,
+    ) -> Result<KeyManifest, ProverError> {
+        let initial = setup::initialize(ptau, slots)?;
+        let prover = BatchProver::from_proving_key(slots, setup::contribute(&initial, rng));
+        let origin = KeyOrigin::PowersOfTau { ptau_sha256: to_hex(&ptau_sha256), power: ptau.power };
+        self.install_current(&prover, origin, Vec::new(), Some(contributor))
+    }
+
+    /// Adds a contribution made here, with randomness from `rng`.
+    pub fn contribute<R: RngCore + CryptoRng>(
+        &self,
+        slots: usize,
+        contributor: &str,
+        rng: &mut R,
+    ) -> Result<KeyManifest, ProverError> {
+        let (manifest, prover) = self.load_contributable(slots)?;
+        let next = BatchProver::from_proving_key(slots, setup::contribute(prover.proving_key(), rng));
+        self.install_current(&next, manifest.origin, manifest.contributions, Some(contributor))
+    }
+
+    /// Adds a contribution made elsewhere to a copy of the current key, after
+    /// checking that it changed δ and nothing else.
+    pub fn import_contribution<R: RngCore>(
+        &self,
+        slots: usize,
+        contributor: &str,
+        path: &Path,
+        rng: &mut R,
+    ) -> Result<KeyManifest, ProverError> {
+        let (manifest, prover) = self.load_contributable(slots)?;
+        let next = BatchProver::load_for(path, slots)?;
+        setup::verify_contribution(prover.proving_key(), next.proving_key(), rng)?;
+        self.install_current(&next, manifest.origin, manifest.contributions, Some(contributor))
+    }
+
+    fn load_contributable(&self, slots: usize) -> Result<(KeyManifest, BatchProver), ProverError> {
+        let version = self
+            .current(slots)?
+            .ok_or_else(|| ProverError::MissingKey { slots, path: self.class_dir(slots) })?;
+        let (manifest, prover) = self.load(slots, version)?;
+        if let KeyOrigin::DevSeed { .. } = manifest.origin {
+            // the seed gives away τ, α and β, which no phase 2 contribution replaces
+            return Err(ProverError::UntrustedKey { slots, reason: "keys from a seed cannot be contributed to" });
+        }
+        Ok((manifest, prover))
+    }
+
+    /// Reads a version's key, checked against its manifest.
+    fn load(&self, slots: usize, version: u32) -> Result<(KeyManifest, BatchProver), ProverError> {
+        let manifest = self.manifest(slots, version)?;
+        let bytes = fs::read(self.version_dir(slots, version).join(PROVING_KEY))?;
+        if sha256_hex(&bytes) != manifest.proving_key_sha256 {
+            return Err(ProverError::KeyHashMismatch { slots, version });
+        }
+        let prover = BatchProver::from_bytes(&bytes)?;
+        if prover.batch_size() != slots {
+            return Err(ProverError::KeyMismatch { expected: slots, found: prover.batch_size() });
+        }
+        Ok((manifest, prover))
+    }
+
+    fn install_current(
+        &self,
+        prover: &BatchProver,
+        origin: KeyOrigin,
+        contributions: Vec<Contribution>,
+        contributor: Option<&str>,
+    ) -> Result<KeyManifest, ProverError> {
+        let mut provers = self.provers.lock().unwrap();
+        let manifest = self.install(prover, origin, contributions, contributor)?;
+        provers.remove(&prover.batch_size());
+        Ok(manifest)
+    }
+
+    /// Writes `prover`'s key as the next version of its class and makes it current.
+    fn install(
+        &self,
+        prover: &BatchProver,
+        origin: KeyOrigin,
+        mut contributions: Vec<Contribution>,
+        contributor: Option<&str>,
+    ) -> Result<KeyManifest, ProverError> {
+        let slots = prover.batch_size();
+        let version = self.versions(slots)?.last().map_or(1, |latest| latest + 1);
+        let dir = self.version_dir(slots, version);
+        fs::create_dir_all(&dir)?;
+
+        let proving_key = prover.to_bytes()?;
+        let verification_key =
+            serde_json::to_vec_pretty(&prover.verifying_key_snarkjs()).expect("verifying keys serialize to JSON");
+        let proving_key_sha256 = sha256_hex(&proving_key);
+        let created_at = unix_time();
+        if let Some(name) = contributor {
+            contributions.push(Contribution {
+                name: name.to_string(),
+                proving_key_sha256: proving_key_sha256.clone(),
+                contributed_at: created_at,
+            });
+        }
+        let manifest = KeyManifest {
+            slots,
+            version,
+            origin,
+            contributions,
+            proving_key_sha256,
+            verification_key_sha256: sha256_hex(&verification_key),
+            created_at,
+        };
+
+        fs::write(dir.join(PROVING_KEY), proving_key)?;
+        fs::write(dir.join(VERIFICATION_KEY), verification_key)?;
+        fs::write(dir.join(MANIFEST), serde_json::to_vec_pretty(&manifest).expect("manifests serialize to JSON"))?;
+        self.set_current(slots, version)?;
+        Ok(manifest)
+    }
+
+    // a rename, so a crash leaves either the old or the new version current
+    fn set_current(&self, slots: usize, version: u32) -> Result<(), ProverError> {
+        let dir = self.class_dir(slots);
+        let staged = dir.join(format!("{}.tmp", CURRENT));
+        fs::write(&staged, format!("{}\n", version))?;
+        fs::rename(staged, dir.join(CURRENT))?;
+        Ok(())
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
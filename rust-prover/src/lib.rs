@@ -0,0 +1,53 @@
+//! Native Groth16 prover for the rollup's batch transfer circuit.
+//!
+//! The circuit proves a batch of system transfers against the rollup's global
+//! Poseidon state tree (`state`), so consecutive proofs share their roots.
+//! Proofs and verifying keys come out in the snarkjs JSON layout the on-chain
+//! verifier already consumes.
+//!
+//! The circuit does not check signatures; in-circuit ed25519 would cost millions
+//! of constraints per transfer. `auth` checks them instead, and `execute` replays
+//! a batch from its signed transactions, natively or inside the RISC Zero guest,
+//! which builds this crate without the `groth16` feature. `aggregate` folds
+//! consecutive batch proofs for the RISC Zero aggregation guest.
+//!
+//! Keys come from a Powers of Tau ceremony and phase 2 contributions (`setup`)
+//! and are kept, versioned and hashed, in a `keys::KeyStore`.
+
+pub mod aggregate;
+pub mod auth;
+#[cfg(feature = "groth16")]
+pub mod bench;
+#[cfg(feature = "groth16")]
+pub mod circuit;
+pub mod execute;
+#[cfg(feature = "groth16")]
+pub mod export;
+pub mod journal;
+#[cfg(feature = "groth16")]
+pub mod keys;
+#[cfg(feature = "groth16")]
+pub mod prover;
+#[cfg(feature = "groth16")]
+pub mod setup;
+pub mod state;
+pub mod witness;
+
+pub use aggregate::{aggregate, AggregateJournal, AggregationChild, AggregationError, AggregationInput};
+pub use auth::{authorize, authorize_transfer, AuthError};
+#[cfg(feature = "groth16")]
+pub use bench::CircuitStats;
+#[cfg(feature = "groth16")]
+pub use circuit::BatchTransferCircuit;
+pub use execute::{execute, transaction_fee, AccountWitness, ExecutionError};
+#[cfg(feature = "groth16")]
+pub use export::{SnarkJsProof, SnarkJsVerifyingKey};
+pub use journal::BatchJournal;
+#[cfg(feature = "groth16")]
+pub use keys::{Contribution, KeyManifest, KeyOrigin, KeyStore};
+#[cfg(feature = "groth16")]
+pub use prover::{verify_snarkjs, BatchProof, BatchProver, ProverError};
+#[cfg(feature = "groth16")]
+pub use setup::{PowersOfTau, SetupError};
+pub use state::{account_index, StateError, StateTree, STATE_TREE_DEPTH};
+pub use witness::{AccountPath, BatchWitness, TransferWitness};
@@ -0,0 +1,260 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+
+use crate::{
+    circuit::BatchTransferCircuit,
+    export::{fr_from_decimal, fr_to_decimal, SnarkJsProof, SnarkJsVerifyingKey},
+    setup::SetupError,
+    state::StateError,
+    witness::{AccountPath, TransferWitness},
+};
+
+#[derive(Debug)]
+pub enum ProverError {
+    Io(io::Error),
+    Serialization(SerializationError),
+    Synthesis(SynthesisError),
+    /// More transfers than the circuit has slots.
+    TooManyTransfers { transfers: usize, slots: usize },
+    /// A transfer the circuit rejects; `slot` counts from zero.
+    InvalidTransfer { slot: usize, reason: &'static str },
+    /// There is no account to pad the batch with.
+    EmptyBatch,
+    /// The state paths of a batch do not describe one state tree.
+    InvalidStatePath(&'static str),
+    /// The leaf an account would go in already belongs to another account.
+    LeafTaken { index: u64 },
+    /// The witness does not satisfy the constraints; a proof of it would not verify.
+    Unsatisfied,
+    /// The key file was generated for a different number of slots.
+    KeyMismatch { expected: usize, found: usize },
+    /// No key file for this size class, and the store may not run a setup.
+    MissingKey { slots: usize, path: PathBuf },
+    Setup(SetupError),
+    /// A key manifest is missing or unreadable.
+    Manifest { path: PathBuf, reason: String },
+    /// A key file is not the one its manifest was written for.
+    KeyHashMismatch { slots: usize, version: u32 },
+    /// The key is only fit for development, and the store is not in dev mode.
+    UntrustedKey { slots: usize, reason: &'static str },
+    /// A proof, verifying key or public input in snarkjs' layout that does not parse.
+    InvalidEncoding(String),
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::Io(e) => write!(f, "proving key I/O failed: {}", e),
+            ProverError::Serialization(e) => write!(f, "proving key is corrupt: {}", e),
+            ProverError::Synthesis(e) => write!(f, "constraint synthesis failed: {}", e),
+            ProverError::TooManyTransfers { transfers, slots } => {
+                write!(f, "{} transfers do not fit a circuit with {} slots", transfers, slots)
+            }
+            ProverError::InvalidTransfer { slot, reason } => write!(f, "transfer {} is invalid: {}", slot, reason),
+            ProverError::EmptyBatch => write!(f, "batch has no transfers"),
+            ProverError::InvalidStatePath(reason) => write!(f, "invalid state path: {}", reason),
+            ProverError::LeafTaken { index } => write!(f, "state tree leaf {} belongs to another account", index),
+            ProverError::Unsatisfied => write!(f, "witness does not satisfy the batch circuit"),
+            ProverError::KeyMismatch { expected, found } => {
+                write!(f, "proving key is for {} slots, expected {}", found, expected)
+            }
+            ProverError::MissingKey { slots, path } => {
+                write!(f, "no proving key for {} slots at {}", slots, path.display())
+            }
+            ProverError::Setup(e) => write!(f, "key setup failed: {}", e),
+            ProverError::Manifest { path, reason } => write!(f, "key manifest {} is unusable: {}", path.display(), reason),
+            ProverError::KeyHashMismatch { slots, version } => {
+                write!(f, "key version {} for {} slots does not match the hashes in its manifest", version, slots)
+            }
+            ProverError::UntrustedKey { slots, reason } => {
+                write!(f, "proving key for {} slots is only fit for development: {}", slots, reason)
+            }
+            ProverError::InvalidEncoding(reason) => write!(f, "invalid snarkjs encoding: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+impl From<io::Error> for ProverError {
+    fn from(e: io::Error) -> Self {
+        ProverError::Io(e)
+    }
+}
+
+impl From<SerializationError> for ProverError {
+    fn from(e: SerializationError) -> Self {
+        ProverError::Serialization(e)
+    }
+}
+
+impl From<SynthesisError> for ProverError {
+    fn from(e: SynthesisError) -> Self {
+        ProverError::Synthesis(e)
+    }
+}
+
+impl From<StateError> for ProverError {
+    fn from(e: StateError) -> Self {
+        match e {
+            StateError::InvalidPath(reason) => ProverError::InvalidStatePath(reason),
+            StateError::LeafTaken { index } => ProverError::LeafTaken { index },
+        }
+    }
+}
+
+impl From<SetupError> for ProverError {
+    fn from(e: SetupError) -> Self {
+        ProverError::Setup(e)
+    }
+}
+
+/// A proof together with the public inputs it verifies against.
+#[derive(Debug, Clone)]
+pub struct BatchProof {
+    pub proof: Proof<Bn254>,
+    pub public_inputs: Vec<Fr>,
+    /// Size class of the circuit; picks the verifying key.
+    pub slots: usize,
+}
+
+impl BatchProof {
+    pub fn to_snarkjs(&self) -> SnarkJsProof {
+        SnarkJsProof::from(&self.proof)
+    }
+
+    /// Public inputs as decimal strings, the layout of snarkjs' `public.json`.
+    pub fn public_inputs_decimal(&self) -> Vec<String> {
+        self.public_inputs.iter().map(fr_to_decimal).collect()
+    }
+}
+
+/// Holds the proving key for one circuit size. Load it once and reuse it for every batch.
+pub struct BatchProver {
+    batch_size: usize,
+    pk: ProvingKey<Bn254>,
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+impl BatchProver {
+    /// Runs a circuit-specific setup. Whoever controls `rng` can forge proofs, so
+    /// keys from here are only as trustworthy as the machine that generated them.
+    pub fn setup<R: RngCore + CryptoRng>(batch_size: usize, rng: &mut R) -> Result<Self, ProverError> {
+        let (pk, _) = Groth16::<Bn254>::circuit_specific_setup(BatchTransferCircuit::blank(batch_size), rng)?;
+        Ok(Self::from_proving_key(batch_size, pk))
+    }
+
+    pub fn from_proving_key(batch_size: usize, pk: ProvingKey<Bn254>) -> Self {
+        let pvk = Groth16::<Bn254>::process_vk(&pk.vk).expect("processing a verifying key cannot fail");
+        Self { batch_size, pk, pvk }
+    }
+
+    /// Reads a key written by `save`.
+    pub fn load(path: &Path) -> Result<Self, ProverError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Parses the contents of a key file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProverError> {
+        let mut reader = bytes;
+        let batch_size = u64::deserialize_uncompressed(&mut reader)? as usize;
+        // our own file, so skip the (slow) subgroup checks
+        let pk = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(&mut reader)?;
+        Ok(Self::from_proving_key(batch_size, pk))
+    }
+
+    /// Like `load`, but fails unless the key has `batch_size` slots.
+    pub fn load_for(path: &Path, batch_size: usize) -> Result<Self, ProverError> {
+        let prover = Self::load(path)?;
+        if prover.batch_size != batch_size {
+            return Err(ProverError::KeyMismatch { expected: batch_size, found: prover.batch_size });
+        }
+        Ok(prover)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ProverError> {
+        let bytes = self.to_bytes()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// The contents of the file `save` writes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ProverError> {
+        let mut bytes = Vec::new();
+        (self.batch_size as u64).serialize_uncompressed(&mut bytes)?;
+        self.pk.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn proving_key(&self) -> &ProvingKey<Bn254> {
+        &self.pk
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey<Bn254> {
+        &self.pk.vk
+    }
+
+    pub fn verifying_key_snarkjs(&self) -> SnarkJsVerifyingKey {
+        SnarkJsVerifyingKey::from(&self.pk.vk)
+    }
+
+    /// Proves a batch over the state `paths` lead to, padding it to the circuit
+    /// size. The public inputs are the state tree's roots before and after it.
+    pub fn prove(&self, transfers: Vec<TransferWitness>, paths: &[AccountPath]) -> Result<BatchProof, ProverError> {
+        let circuit = BatchTransferCircuit::new(transfers, paths, self.batch_size)?;
+
+        // Groth16 happily proves an unsatisfied witness, the proof just never verifies
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone())?;
+        if !cs.is_satisfied()? {
+            return Err(ProverError::Unsatisfied);
+        }
+
+        let public_inputs = circuit.public_inputs();
+        let proof = Groth16::<Bn254>::prove(&self.pk, circuit, &mut entropy_rng())?;
+        Ok(BatchProof { proof, public_inputs, slots: self.batch_size })
+    }
+
+    pub fn verify(&self, proof: &BatchProof) -> Result<bool, ProverError> {
+        Ok(Groth16::<Bn254>::verify_with_processed_vk(&self.pvk, &proof.public_inputs, &proof.proof)?)
+    }
+}
+
+/// Checks a proof against a verifying key, both in snarkjs' layout as settlement
+/// posts them, without needing the proving key. Only a proof that parses, has
+/// as many public inputs as the key and satisfies the pairing check is `true`.
+pub fn verify_snarkjs(vk: &SnarkJsVerifyingKey, proof: &SnarkJsProof, public_inputs: &[String]) -> Result<bool, ProverError> {
+    let vk = VerifyingKey::<Bn254>::try_from(vk)?;
+    if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+        return Err(ProverError::InvalidEncoding(format!(
+            "{} public inputs for a key that takes {}",
+            public_inputs.len(),
+            vk.gamma_abc_g1.len() - 1
+        )));
+    }
+    let proof = Proof::<Bn254>::try_from(proof)?;
+    let public_inputs = public_inputs.iter().map(|input| fr_from_decimal(input)).collect::<Result<Vec<_>, _>>()?;
+    let pvk = Groth16::<Bn254>::process_vk(&vk)?;
+    Ok(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &public_inputs, &proof)?)
+}
+
+/// ark-std pins rand 0.8 without OS entropy, so seed its CSPRNG from rand 0.9's.
+pub fn entropy_rng() -> StdRng {
+    StdRng::from_seed(rand::random())
+}
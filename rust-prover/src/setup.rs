@@ -0,0 +1,500 @@
+//! Groth16 keys from a Powers of Tau ceremony instead of one machine's randomness.
+//!
+//! `PowersOfTau` reads the phase 1 output of a snarkjs ceremony (a `.ptau` file).
+//! `initialize` turns it into a key for one circuit size class whose
+//! circuit-specific secret δ is still 1, which anyone could forge proofs with.
+//! Each phase 2 `contribute` multiplies δ by fresh randomness, and the key is
+//! sound as long as one contributor threw theirs away. `verify_contribution`
+//! checks that a contribution someone else ran changed δ and nothing else.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInt, Field, One, PrimeField, UniformRand, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_std::rand::{CryptoRng, RngCore};
+
+use crate::circuit::BatchTransferCircuit;
+
+const PTAU_MAGIC: &[u8; 4] = b"ptau";
+const PTAU_VERSION: u32 = 1;
+// section ids of the snarkjs format; later sections are not needed here
+const HEADER: u32 = 1;
+const TAU_G1: u32 = 2;
+const TAU_G2: u32 = 3;
+const ALPHA_TAU_G1: u32 = 4;
+const BETA_TAU_G1: u32 = 5;
+const BETA_G2: u32 = 6;
+
+#[derive(Debug)]
+pub enum SetupError {
+    Io(io::Error),
+    Synthesis(SynthesisError),
+    /// The file is not a BN254 `.ptau` file.
+    Format(&'static str),
+    /// The ceremony is too small for the circuit's evaluation domain.
+    TooSmall { needed: usize, available: usize },
+    /// The parameters fail a consistency check.
+    Inconsistent(&'static str),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::Io(e) => write!(f, "ceremony file I/O failed: {}", e),
+            SetupError::Synthesis(e) => write!(f, "constraint synthesis failed: {}", e),
+            SetupError::Format(reason) => write!(f, "not a usable .ptau file: {}", reason),
+            SetupError::TooSmall { needed, available } => {
+                write!(f, "the circuit needs {} powers of tau, the ceremony has {}", needed, available)
+            }
+            SetupError::Inconsistent(reason) => write!(f, "parameters are inconsistent: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+impl From<io::Error> for SetupError {
+    fn from(e: io::Error) -> Self {
+        SetupError::Io(e)
+    }
+}
+
+impl From<SynthesisError> for SetupError {
+    fn from(e: SynthesisError) -> Self {
+        SetupError::Synthesis(e)
+    }
+}
+
+/// The part of a phase 1 ceremony that circuits up to some domain size need:
+/// `[τ^i]` in both groups, `[ατ^i]`, `[βτ^i]` and `[β]₂`.
+pub struct PowersOfTau {
+    /// The ceremony covers domains of up to `2^power` points.
+    pub power: u32,
+    tau_g1: Vec<G1Affine>,
+    tau_g2: Vec<G2Affine>,
+    alpha_tau_g1: Vec<G1Affine>,
+    beta_tau_g1: Vec<G1Affine>,
+    beta_g2: G2Affine,
+}
+
+impl PowersOfTau {
+    /// Reads what domains of up to `max_domain` points need from a snarkjs
+    /// `.ptau` file, skipping the rest; ceremony files run to gigabytes.
+    pub fn read(path: &Path, max_domain: usize) -> Result<Self, SetupError> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != PTAU_MAGIC {
+            return Err(SetupError::Format("wrong magic bytes"));
+        }
+        read_u32(&mut file)?;
+        let mut sections = BTreeMap::new();
+        for _ in 0..read_u32(&mut file)? {
+            let id = read_u32(&mut file)?;
+            let size = read_u64(&mut file)?;
+            sections.insert(id, file.stream_position()?);
+            file.seek(SeekFrom::Current(size as i64))?;
+        }
+        let section = |id: u32| -> Result<BufReader<File>, SetupError> {
+            let start = *sections.get(&id).ok_or(SetupError::Format("a section is missing"))?;
+            let mut reader = BufReader::new(file.get_ref().try_clone()?);
+            reader.seek(SeekFrom::Start(start))?;
+            Ok(reader)
+        };
+
+        let header = &mut section(HEADER)?;
+        if read_u32(header)? != 32 {
+            return Err(SetupError::Format("field elements are not 32 bytes"));
+        }
+        let mut modulus = [0; 32];
+        header.read_exact(&mut modulus)?;
+        if modulus[..] != bigint_bytes(&Fq::MODULUS)[..] {
+            return Err(SetupError::Format("the curve is not BN254"));
+        }
+        let power = read_u32(header)?;
+        let available = 1usize.checked_shl(power).ok_or(SetupError::Format("power is out of range"))?;
+        if max_domain > available {
+            return Err(SetupError::TooSmall { needed: max_domain, available });
+        }
+
+        let tau_g1 = read_points(&mut section(TAU_G1)?, 2 * max_domain - 1, read_g1)?;
+        let tau_g2 = read_points(&mut section(TAU_G2)?, max_domain, read_g2)?;
+        let alpha_tau_g1 = read_points(&mut section(ALPHA_TAU_G1)?, max_domain, read_g1)?;
+        let beta_tau_g1 = read_points(&mut section(BETA_TAU_G1)?, max_domain, read_g1)?;
+        let beta_g2 = read_g2(&mut section(BETA_G2)?)?;
+        Ok(Self { power, tau_g1, tau_g2, alpha_tau_g1, beta_tau_g1, beta_g2 })
+    }
+
+    /// A ceremony of one, for tests and local experiments: whoever controls `rng`
+    /// knows τ, α and β and can forge proofs for any key derived from it.
+    pub fn insecure<R: RngCore + CryptoRng>(power: u32, rng: &mut R) -> Self {
+        let domain = 1usize << power;
+        let (tau, alpha, beta) = (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+        let powers: Vec<Fr> = std::iter::successors(Some(Fr::one()), |x| Some(*x * tau)).take(2 * domain - 1).collect();
+        let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+        let scaled_g1 = |factor: Fr, count: usize| {
+            G1Projective::normalize_batch(&powers[..count].iter().map(|x| g1 * (*x * factor)).collect::<Vec<_>>())
+        };
+        Self {
+            power,
+            tau_g1: scaled_g1(Fr::one(), 2 * domain - 1),
+            tau_g2: G2Projective::normalize_batch(&powers[..domain].iter().map(|x| g2 * x).collect::<Vec<_>>()),
+            alpha_tau_g1: scaled_g1(alpha, domain),
+            beta_tau_g1: scaled_g1(beta, domain),
+            beta_g2: (g2 * beta).into_affine(),
+        }
+    }
+
+    /// Writes the sections `read` uses, in the snarkjs layout. snarkjs itself
+    /// also wants the contributions section, so this is only for `read`.
+    pub fn write(&self, path: &Path) -> Result<(), SetupError> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(PTAU_MAGIC)?;
+        file.write_all(&PTAU_VERSION.to_le_bytes())?;
+        file.write_all(&6u32.to_le_bytes())?;
+
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend(bigint_bytes(&Fq::MODULUS));
+        header.extend(self.power.to_le_bytes());
+        header.extend(self.power.to_le_bytes());
+        let sections: [(u32, Vec<u8>); 6] = [
+            (HEADER, header),
+            (TAU_G1, self.tau_g1.iter().flat_map(g1_bytes).collect()),
+            (TAU_G2, self.tau_g2.iter().flat_map(g2_bytes).collect()),
+            (ALPHA_TAU_G1, self.alpha_tau_g1.iter().flat_map(g1_bytes).collect()),
+            (BETA_TAU_G1, self.beta_tau_g1.iter().flat_map(g1_bytes).collect()),
+            (BETA_G2, g2_bytes(&self.beta_g2)),
+        ];
+        for (id, data) in sections {
+            file.write_all(&id.to_le_bytes())?;
+            file.write_all(&(data.len() as u64).to_le_bytes())?;
+            file.write_all(&data)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// The largest evaluation domain the loaded powers cover.
+    pub fn max_domain(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    /// Checks that the loaded powers really are successive powers of one τ and
+    /// that the α and β terms use the same τ. Says nothing about whether anyone
+    /// knows τ; that is what trusting the ceremony (and pinning its hash) is for.
+    pub fn check<R: RngCore>(&self, rng: &mut R) -> Result<(), SetupError> {
+        let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+        if self.tau_g1[0] != g1 || self.tau_g2[0] != g2 {
+            return Err(SetupError::Inconsistent("the powers do not start at the generators"));
+        }
+        if self.tau_g1.len() < 2 || self.tau_g2.len() < 2 {
+            return Err(SetupError::Inconsistent("there are too few powers to check"));
+        }
+        let (tau_g1, tau_g2) = (self.tau_g1[1], self.tau_g2[1]);
+
+        // one pairing check per relation, over a random combination of all its terms
+        let r = random_scalars(self.tau_g1.len() - 1, rng);
+        let g1_powers = G1Projective::msm_unchecked(&self.tau_g1[..r.len()], &r);
+        let g1_next = G1Projective::msm_unchecked(&self.tau_g1[1..], &r);
+        if !pairings_equal(g1_next, g2, g1_powers, tau_g2) {
+            return Err(SetupError::Inconsistent("the G1 powers are not powers of τ"));
+        }
+        let r = random_scalars(self.tau_g2.len() - 1, rng);
+        let g2_powers = G2Projective::msm_unchecked(&self.tau_g2[..r.len()], &r);
+        let g2_next = G2Projective::msm_unchecked(&self.tau_g2[1..], &r);
+        if !pairings_equal(g1, g2_next, tau_g1, g2_powers) {
+            return Err(SetupError::Inconsistent("the G2 powers are not powers of τ"));
+        }
+
+        let r = random_scalars(self.tau_g2.len(), rng);
+        let g2_powers = G2Projective::msm_unchecked(&self.tau_g2, &r);
+        let alpha_powers = G1Projective::msm_unchecked(&self.alpha_tau_g1, &r);
+        if !pairings_equal(alpha_powers, g2, self.alpha_tau_g1[0], g2_powers) {
+            return Err(SetupError::Inconsistent("the α powers do not match τ"));
+        }
+        let beta_powers = G1Projective::msm_unchecked(&self.beta_tau_g1, &r);
+        if !pairings_equal(beta_powers, g2, self.beta_tau_g1[0], g2_powers) {
+            return Err(SetupError::Inconsistent("the β powers do not match τ"));
+        }
+        if !pairings_equal(self.beta_tau_g1[0], g2, g1, self.beta_g2) {
+            return Err(SetupError::Inconsistent("β differs between G1 and G2"));
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of a file, read in chunks; what a ceremony is pinned by.
+pub fn file_sha256(path: &Path) -> Result<[u8; 32], SetupError> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Points in the evaluation domain of the `slots`-slot circuit, which is the
+/// number of powers of tau its keys need.
+pub fn domain_size(slots: usize) -> Result<usize, SetupError> {
+    let cs = constraints(slots)?;
+    Ok(domain(&cs)?.size())
+}
+
+/// A key for the `slots`-slot circuit with δ = 1. Not safe to prove with until
+/// it has had at least one `contribute`.
+///
+/// This is arkworks' own generator with τ, α and β taken from the ceremony and
+/// γ = δ = 1, computed in the exponent: the Lagrange basis at τ comes from an
+/// inverse FFT over the powers of τ, in the domain the prover uses.
+pub fn initialize(ptau: &PowersOfTau, slots: usize) -> Result<ProvingKey<Bn254>, SetupError> {
+    let cs = constraints(slots)?;
+    let domain = domain(&cs)?;
+    let n = domain.size();
+    if n > ptau.max_domain() {
+        return Err(SetupError::TooSmall { needed: n, available: ptau.max_domain() });
+    }
+    let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+    let (num_constraints, num_instance) = (cs.num_constraints(), cs.num_instance_variables());
+    let num_variables = num_instance + cs.num_witness_variables();
+
+    let lagrange_g1 = |powers: &[G1Affine]| {
+        G1Projective::normalize_batch(&domain.ifft(&powers[..n].iter().map(|p| p.into_group()).collect::<Vec<_>>()))
+    };
+    let lagrange = lagrange_g1(&ptau.tau_g1);
+    let alpha_lagrange = lagrange_g1(&ptau.alpha_tau_g1);
+    let beta_lagrange = lagrange_g1(&ptau.beta_tau_g1);
+    let lagrange_g2 =
+        G2Projective::normalize_batch(&domain.ifft(&ptau.tau_g2[..n].iter().map(|p| p.into_group()).collect::<Vec<_>>()));
+
+    // u_i, v_i and w_i of each variable as (domain point, coefficient) terms, like arkworks'
+    // libsnark reduction: the circuit's constraints, then one extra per public input in u
+    let mut u = vec![Vec::new(); num_variables];
+    let mut v = vec![Vec::new(); num_variables];
+    let mut w = vec![Vec::new(); num_variables];
+    for (point, ((a, b), c)) in matrices.a.iter().zip(&matrices.b).zip(&matrices.c).enumerate() {
+        for (terms, row) in [(&mut u, a), (&mut v, b), (&mut w, c)] {
+            for &(coeff, variable) in row {
+                terms[variable].push((point, coeff));
+            }
+        }
+    }
+    for (variable, terms) in u.iter_mut().enumerate().take(num_instance) {
+        terms.push((num_constraints + variable, Fr::one()));
+    }
+
+    let a_query = G1Projective::normalize_batch(&u.iter().map(|terms| evaluate(terms, &lagrange)).collect::<Vec<_>>());
+    let b_g1_query = G1Projective::normalize_batch(&v.iter().map(|terms| evaluate(terms, &lagrange)).collect::<Vec<_>>());
+    let b_g2_query =
+        G2Projective::normalize_batch(&v.iter().map(|terms| evaluate(terms, &lagrange_g2)).collect::<Vec<_>>());
+    // β·u_i(τ) + α·v_i(τ) + w_i(τ), over γ for public inputs and over δ for the rest
+    let abc: Vec<G1Projective> = (0..num_variables)
+        .map(|i| evaluate(&u[i], &beta_lagrange) + evaluate(&v[i], &alpha_lagrange) + evaluate(&w[i], &lagrange))
+        .collect();
+    let mut l_query = G1Projective::normalize_batch(&abc);
+    let gamma_abc_g1 = l_query.drain(..num_instance).collect();
+    // τ^i·t(τ) with t(X) = X^n - 1
+    let h_query = G1Projective::normalize_batch(
+        &(0..n - 1).map(|i| ptau.tau_g1[n + i].into_group() - ptau.tau_g1[i]).collect::<Vec<_>>(),
+    );
+
+    let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+    Ok(ProvingKey {
+        vk: VerifyingKey { alpha_g1: ptau.alpha_tau_g1[0], beta_g2: ptau.beta_g2, gamma_g2: g2, delta_g2: g2, gamma_abc_g1 },
+        beta_g1: ptau.beta_tau_g1[0],
+        delta_g1: g1,
+        a_query,
+        b_g1_query,
+        b_g2_query,
+        h_query,
+        l_query,
+    })
+}
+
+/// A phase 2 contribution: multiplies δ by a secret from `rng`. The key is only
+/// as safe as the promise that this secret is gone once the call returns.
+pub fn contribute<R: RngCore + CryptoRng>(key: &ProvingKey<Bn254>, rng: &mut R) -> ProvingKey<Bn254> {
+    let secret = loop {
+        let secret = Fr::rand(rng);
+        if !secret.is_zero() {
+            break secret;
+        }
+    };
+    let inverse = secret.inverse().expect("a non-zero field element is invertible");
+    let scale = |points: &[G1Affine]| G1Projective::normalize_batch(&points.iter().map(|p| *p * inverse).collect::<Vec<_>>());
+
+    let mut next = key.clone();
+    next.delta_g1 = (key.delta_g1 * secret).into_affine();
+    next.vk.delta_g2 = (key.vk.delta_g2 * secret).into_affine();
+    next.l_query = scale(&key.l_query);
+    next.h_query = scale(&key.h_query);
+    next
+}
+
+/// Checks that `after` is `before` with one more contribution: the same key
+/// except for a new δ, the same in both groups, with the L and H queries
+/// divided by the same factor δ was multiplied by.
+pub fn verify_contribution<R: RngCore>(
+    before: &ProvingKey<Bn254>,
+    after: &ProvingKey<Bn254>,
+    rng: &mut R,
+) -> Result<(), SetupError> {
+    let (vk, next) = (&before.vk, &after.vk);
+    let unchanged = next.alpha_g1 == vk.alpha_g1
+        && next.beta_g2 == vk.beta_g2
+        && next.gamma_g2 == vk.gamma_g2
+        && next.gamma_abc_g1 == vk.gamma_abc_g1
+        && after.beta_g1 == before.beta_g1
+        && after.a_query == before.a_query
+        && after.b_g1_query == before.b_g1_query
+        && after.b_g2_query == before.b_g2_query
+        && after.l_query.len() == before.l_query.len()
+        && after.h_query.len() == before.h_query.len();
+    if !unchanged {
+        return Err(SetupError::Inconsistent("the contribution changed more than δ"));
+    }
+    if after.delta_g1.is_zero() || after.delta_g1 == before.delta_g1 {
+        return Err(SetupError::Inconsistent("the contribution did not change δ"));
+    }
+    let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+    if !pairings_equal(after.delta_g1, g2, g1, next.delta_g2) {
+        return Err(SetupError::Inconsistent("δ differs between G1 and G2"));
+    }
+
+    for (query_before, query_after) in [(&before.l_query, &after.l_query), (&before.h_query, &after.h_query)] {
+        let r = random_scalars(query_before.len(), rng);
+        let combined_before = G1Projective::msm_unchecked(query_before, &r);
+        let combined_after = G1Projective::msm_unchecked(query_after, &r);
+        if !pairings_equal(combined_after, next.delta_g2, combined_before, vk.delta_g2) {
+            return Err(SetupError::Inconsistent("the L or H query does not follow δ"));
+        }
+    }
+    Ok(())
+}
+
+/// The circuit's constraints as the Groth16 generator and prover see them.
+fn constraints(slots: usize) -> Result<ConstraintSystemRef<Fr>, SetupError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    BatchTransferCircuit::blank(slots).generate_constraints(cs.clone())?;
+    cs.finalize();
+    Ok(cs)
+}
+
+fn domain(cs: &ConstraintSystemRef<Fr>) -> Result<GeneralEvaluationDomain<Fr>, SetupError> {
+    Ok(GeneralEvaluationDomain::new(cs.num_constraints() + cs.num_instance_variables())
+        .ok_or(SynthesisError::PolynomialDegreeTooLarge)?)
+}
+
+/// `Σ coeff·basis[point]` over a polynomial's terms.
+fn evaluate<G: AffineRepr<ScalarField = Fr>>(terms: &[(usize, Fr)], basis: &[G]) -> G::Group {
+    let points: Vec<G> = terms.iter().map(|&(point, _)| basis[point]).collect();
+    let coeffs: Vec<Fr> = terms.iter().map(|&(_, coeff)| coeff).collect();
+    G::Group::msm_unchecked(&points, &coeffs)
+}
+
+/// e(a, b) == e(c, d)
+fn pairings_equal(
+    a: impl Into<G1Affine>,
+    b: impl Into<G2Affine>,
+    c: impl Into<G1Affine>,
+    d: impl Into<G2Affine>,
+) -> bool {
+    let (a, c): (G1Affine, G1Affine) = (a.into(), c.into());
+    Bn254::multi_pairing([a, -c], [b.into(), d.into()]).is_zero()
+}
+
+fn random_scalars<R: RngCore>(count: usize, rng: &mut R) -> Vec<Fr> {
+    (0..count).map(|_| Fr::rand(rng)).collect()
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_points<T>(
+    reader: &mut impl Read,
+    count: usize,
+    read: impl Fn(&mut dyn Read) -> Result<T, SetupError>,
+) -> Result<Vec<T>, SetupError> {
+    (0..count).map(|_| read(reader)).collect()
+}
+
+// snarkjs stores field elements little-endian in Montgomery form, which is
+// also arkworks' internal representation
+fn read_fq(reader: &mut dyn Read) -> Result<Fq, SetupError> {
+    let mut bytes = [0u8; 32];
+    reader.read_exact(&mut bytes)?;
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().expect("chunks of 8 bytes"));
+    }
+    let repr = BigInt::new(limbs);
+    if repr >= Fq::MODULUS {
+        return Err(SetupError::Format("a coordinate is not a field element"));
+    }
+    Ok(Fq::new_unchecked(repr))
+}
+
+fn read_g1(reader: &mut dyn Read) -> Result<G1Affine, SetupError> {
+    let (x, y) = (read_fq(reader)?, read_fq(reader)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(SetupError::Format("a G1 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+fn read_g2(reader: &mut dyn Read) -> Result<G2Affine, SetupError> {
+    let x = Fq2::new(read_fq(reader)?, read_fq(reader)?);
+    let y = Fq2::new(read_fq(reader)?, read_fq(reader)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(SetupError::Format("a G2 point is not in the group"));
+    }
+    Ok(point)
+}
+
+fn bigint_bytes(value: &BigInt<4>) -> Vec<u8> {
+    value.0.iter().flat_map(|limb| limb.to_le_bytes()).collect()
+}
+
+fn g1_bytes(point: &G1Affine) -> Vec<u8> {
+    match point.xy() {
+        Some((x, y)) => [bigint_bytes(&x.0), bigint_bytes(&y.0)].concat(),
+        None => vec![0; 64],
+    }
+}
+
+fn g2_bytes(point: &G2Affine) -> Vec<u8> {
+    match point.xy() {
+        Some((x, y)) => [x.c0, x.c1, y.c0, y.c1].iter().flat_map(|c| bigint_bytes(&c.0)).collect(),
+        None => vec![0; 128],
+    }
+}
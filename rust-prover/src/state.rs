@@ -0,0 +1,221 @@
+//! The rollup's global state: a sparse Poseidon Merkle tree over the lamports of
+//! every account, which each batch is proved against. Consecutive batches share
+//! it, so one batch's post-state root is the next one's pre-state root.
+//!
+//! An account's leaf sits at the index given by the low bits of a Poseidon hash
+//! of its whole key, so it has exactly one place in the tree, and the circuit
+//! checks that it is there. Two keys whose hashes share those bits cannot both
+//! hold lamports in the rollup; with 64 of them, pushing another account out of
+//! its leaf means finding a key that hits that one index out of 2^64.
+//!
+//! Hashing here is native Poseidon over BN254, the same the circuit constrains,
+//! and needs no part of the Groth16 prover, so the RISC Zero guest opens and
+//! updates the same tree.
+
+use std::{collections::HashMap, fmt, sync::OnceLock};
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+    CryptographicSponge,
+};
+use ark_ff::{BigInteger, PrimeField, Zero};
+
+use crate::witness::AccountPath;
+
+/// Levels below the root of the tree the circuit proves against.
+pub const STATE_TREE_DEPTH: usize = 64;
+
+/// The leaf of `key` in a tree of `STATE_TREE_DEPTH` levels: the low 64 bits of its index hash.
+pub fn account_index(key: &[u8; 32]) -> u64 {
+    index_hash(key).into_bigint().0[0]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The state paths of a batch do not describe one state tree.
+    InvalidPath(&'static str),
+    /// The leaf an account would go in already belongs to another account.
+    LeafTaken { index: u64 },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidPath(reason) => write!(f, "invalid state path: {}", reason),
+            StateError::LeafTaken { index } => write!(f, "state tree leaf {} belongs to another account", index),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// The global state tree, keeping only the nodes that differ from an empty subtree.
+#[derive(Debug, Clone)]
+pub struct StateTree {
+    depth: usize,
+    /// Non-empty nodes by level and index; level 0 holds the leaves.
+    nodes: HashMap<(usize, u64), Fr>,
+    /// The account and lamports behind each non-empty leaf.
+    accounts: HashMap<u64, ([u8; 32], u64)>,
+}
+
+impl Default for StateTree {
+    fn default() -> Self {
+        Self::with_depth(STATE_TREE_DEPTH)
+    }
+}
+
+impl StateTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A tree of `depth` levels, with a leaf index of the low `depth` bits of
+    /// `account_index`. The circuit only proves against `STATE_TREE_DEPTH`.
+    pub fn with_depth(depth: usize) -> Self {
+        assert!((1..=STATE_TREE_DEPTH).contains(&depth), "a state tree has 1 to {} levels", STATE_TREE_DEPTH);
+        Self { depth, nodes: HashMap::new(), accounts: HashMap::new() }
+    }
+
+    /// The part of a tree the `paths` pass through, enough to replay a batch over
+    /// the accounts they lead to. Fails unless every path leads to the same root.
+    pub fn from_paths(paths: &[AccountPath]) -> Result<Self, StateError> {
+        let mut tree = Self::new();
+        let mut root = None;
+        for path in paths {
+            if path.siblings.len() != STATE_TREE_DEPTH {
+                return Err(StateError::InvalidPath("a path does not reach the root"));
+            }
+            let index = tree.index(&path.pubkey);
+            let mut node = path.balance.map_or(Fr::zero(), |balance| leaf_hash(&path.pubkey, balance));
+            for (level, sibling) in path.siblings.iter().enumerate() {
+                let sibling = Fr::from_be_bytes_mod_order(sibling);
+                tree.nodes.insert((level, (index >> level) ^ 1), sibling);
+                node = if (index >> level) & 1 == 1 { hash(vec![sibling, node]) } else { hash(vec![node, sibling]) };
+            }
+            if *root.get_or_insert(node) != node {
+                return Err(StateError::InvalidPath("paths lead to different roots"));
+            }
+        }
+        // the siblings of one path may lie on another; set every leaf last, so those are recomputed
+        for path in paths {
+            match path.balance {
+                Some(balance) => tree.set(&path.pubkey, balance)?,
+                None if tree.accounts.contains_key(&tree.index(&path.pubkey)) => {
+                    return Err(StateError::InvalidPath("an account is both present and absent"));
+                }
+                None => {}
+            }
+        }
+        Ok(tree)
+    }
+
+    pub fn root(&self) -> Fr {
+        self.node(self.depth, 0)
+    }
+
+    /// `root` as a big-endian field element, the encoding of the RISC Zero journals.
+    pub fn root_bytes(&self) -> [u8; 32] {
+        to_bytes(self.root())
+    }
+
+    /// Lamports of `key`, `None` if it has not entered the state.
+    pub fn balance(&self, key: &[u8; 32]) -> Option<u64> {
+        self.accounts.get(&self.index(key)).filter(|(owner, _)| owner == key).map(|(_, balance)| *balance)
+    }
+
+    /// The leaf of `key` and its siblings from the leaf up. Fails if another account holds the leaf.
+    pub fn path(&self, key: &[u8; 32]) -> Result<AccountPath, StateError> {
+        let index = self.claim(key)?;
+        Ok(AccountPath {
+            pubkey: *key,
+            balance: self.balance(key),
+            siblings: (0..self.depth).map(|level| to_bytes(self.node(level, (index >> level) ^ 1))).collect(),
+        })
+    }
+
+    pub fn paths(&self, keys: &[[u8; 32]]) -> Result<Vec<AccountPath>, StateError> {
+        keys.iter().map(|key| self.path(key)).collect()
+    }
+
+    /// Sets the lamports of `key`, adding it to the state if it is not there yet.
+    pub fn set(&mut self, key: &[u8; 32], balance: u64) -> Result<(), StateError> {
+        let index = self.claim(key)?;
+        self.accounts.insert(index, (*key, balance));
+        let mut node = leaf_hash(key, balance);
+        self.nodes.insert((0, index), node);
+        for level in 0..self.depth {
+            let i = index >> level;
+            let sibling = self.node(level, i ^ 1);
+            node = if i & 1 == 1 { hash(vec![sibling, node]) } else { hash(vec![node, sibling]) };
+            self.nodes.insert((level + 1, i >> 1), node);
+        }
+        Ok(())
+    }
+
+    /// The leaf index of `key`, unless another account holds it.
+    fn claim(&self, key: &[u8; 32]) -> Result<u64, StateError> {
+        let index = self.index(key);
+        match self.accounts.get(&index) {
+            Some((owner, _)) if owner != key => Err(StateError::LeafTaken { index }),
+            _ => Ok(index),
+        }
+    }
+
+    fn index(&self, key: &[u8; 32]) -> u64 {
+        account_index(key) & (u64::MAX >> (64 - self.depth))
+    }
+
+    fn node(&self, level: usize, index: u64) -> Fr {
+        self.nodes.get(&(level, index)).copied().unwrap_or_else(|| empty_subtree(level))
+    }
+}
+
+/// Root of an empty subtree `level` levels high.
+fn empty_subtree(level: usize) -> Fr {
+    static EMPTY: OnceLock<Vec<Fr>> = OnceLock::new();
+    EMPTY.get_or_init(|| {
+        let mut roots = vec![Fr::zero()];
+        for level in 0..STATE_TREE_DEPTH {
+            roots.push(hash(vec![roots[level], roots[level]]));
+        }
+        roots
+    })[level]
+}
+
+fn to_bytes(node: Fr) -> [u8; 32] {
+    node.into_bigint().to_bytes_be().try_into().expect("a BN254 field element is 32 bytes")
+}
+
+pub(crate) fn leaf_hash(key: &[u8; 32], balance: u64) -> Fr {
+    let [hi, lo] = key_limbs(key);
+    hash(vec![hi, lo, Fr::from(balance)])
+}
+
+/// What the leaf index of `key` is taken from.
+pub(crate) fn index_hash(key: &[u8; 32]) -> Fr {
+    hash(key_limbs(key).to_vec())
+}
+
+pub(crate) fn hash(inputs: Vec<Fr>) -> Fr {
+    let mut sponge = PoseidonSponge::new(poseidon_config());
+    sponge.absorb(&inputs);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// A 32-byte key does not fit one field element, so it goes in as two big-endian 128-bit halves.
+pub(crate) fn key_limbs(key: &[u8; 32]) -> [Fr; 2] {
+    let hi = u128::from_be_bytes(key[..16].try_into().expect("16 bytes"));
+    let lo = u128::from_be_bytes(key[16..].try_into().expect("16 bytes"));
+    [Fr::from(hi), Fr::from(lo)]
+}
+
+/// Poseidon over BN254 with width 3 and x^5 S-boxes: 8 full and 57 partial rounds.
+pub(crate) fn poseidon_config() -> &'static PoseidonConfig<Fr> {
+    static CONFIG: OnceLock<PoseidonConfig<Fr>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(Fr::MODULUS_BIT_SIZE as u64, 2, 8, 57, 0);
+        PoseidonConfig::new(8, 57, 5, mds, ark, 2, 1)
+    })
+}
@@ -0,0 +1,65 @@
+//! What the sequencer hands to provers: the transfers of one executed batch, the
+//! balances around them and the signed transactions they came from.
+
+use serde::{Deserialize, Serialize};
+
+use crate::execute::AccountWitness;
+
+/// The private inputs for one transfer slot of the batch circuit: both accounts
+/// and their lamports around the transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferWitness {
+    pub from: [u8; 32],
+    pub to: [u8; 32],
+    pub amount: u64,
+    /// Paid by `from` on top of `amount`.
+    pub fee: u64,
+    /// Only the circom circuit reads this.
+    pub signature_first_byte: u8,
+    pub from_balance_before: u64,
+    pub from_balance_after: u64,
+    /// For a transfer to itself, the sender's balance after paying.
+    pub to_balance_before: u64,
+    pub to_balance_after: u64,
+}
+
+impl TransferWitness {
+    /// Same balance equations the circuit enforces, evaluated natively.
+    pub fn balances_add_up(&self) -> bool {
+        let sender = self
+            .from_balance_before
+            .checked_sub(self.amount)
+            .and_then(|balance| balance.checked_sub(self.fee))
+            == Some(self.from_balance_after);
+        let receiver = self.to_balance_before.checked_add(self.amount) == Some(self.to_balance_after);
+        sender && receiver
+    }
+}
+
+/// Where an account sat in the global state tree (`state::StateTree`) before a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountPath {
+    pub pubkey: [u8; 32],
+    /// Its lamports in the tree; `None` if it has not entered the state and its leaf is empty.
+    pub balance: Option<u64>,
+    /// Siblings from the leaf up to the root, each a big-endian field element.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// Everything any prover backend needs to prove one batch; transfers are not padded yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchWitness {
+    pub batch_id: String,
+    /// Size class of the circuit that proves the batch, at least `transfers.len()`.
+    pub slots: usize,
+    pub transfers: Vec<TransferWitness>,
+    /// The signed wire-format transaction behind each transfer, in the same order.
+    /// Only the RISC Zero guest reads them, and `accounts`.
+    pub transactions: Vec<Vec<u8>>,
+    /// Every account the transactions touch, as it was before the batch.
+    pub accounts: Vec<AccountWitness>,
+    /// The state tree paths of every account the transfers touch, before the batch.
+    /// The Groth16 circuit and the RISC Zero guest both open the accounts against them.
+    #[serde(default)]
+    pub state_paths: Vec<AccountPath>,
+}
@@ -0,0 +1,132 @@
+use ed25519_dalek::{Signer, SigningKey};
+use zk::{
+    aggregate, aggregate::ProvedJournal, execute, AccountWitness, AggregationChild, AggregationInput, BatchJournal,
+    BatchWitness, StateTree,
+};
+
+const BATCH_IMAGE: [u32; 8] = [1; 8];
+const AGGREGATE_IMAGE: [u32; 8] = [2; 8];
+
+/// A legacy transaction in wire format: `signer` pays the fee and sends `lamports` to `to`.
+fn transfer(signer: &SigningKey, to: [u8; 32], lamports: u64) -> Vec<u8> {
+    let mut message = vec![1, 0, 1, 3];
+    message.extend(signer.verifying_key().to_bytes());
+    message.extend(to);
+    message.extend([0; 32]);
+    message.extend([9; 32]);
+    message.extend([1, 2, 2, 0, 1, 12, 2, 0, 0, 0]);
+    message.extend(lamports.to_le_bytes());
+
+    let mut wire = vec![1];
+    wire.extend(signer.sign(&message).to_bytes());
+    wire.extend(message);
+    wire
+}
+
+fn account(pubkey: [u8; 32], lamports: u64) -> AccountWitness {
+    AccountWitness { lamports, ..AccountWitness::empty(pubkey) }
+}
+
+/// A batch over `accounts`, opened against `state`.
+fn batch(batch_id: &str, transactions: Vec<Vec<u8>>, accounts: Vec<AccountWitness>, state: &StateTree) -> BatchWitness {
+    let state_paths = state.paths(&accounts.iter().map(|account| account.pubkey).collect::<Vec<_>>()).unwrap();
+    BatchWitness { batch_id: batch_id.to_string(), slots: 4, transfers: Vec::new(), transactions, accounts, state_paths }
+}
+
+fn input(children: Vec<AggregationChild>) -> AggregationInput {
+    AggregationInput { batch_image_id: BATCH_IMAGE, aggregate_image_id: AGGREGATE_IMAGE, children }
+}
+
+/// Applies a batch to `state` as the sequencer does once it executed.
+fn apply(state: &mut StateTree, batch: &BatchWitness) {
+    for account in execute(&batch.accounts, &batch.state_paths, &batch.transactions).unwrap().accounts {
+        state.set(&account.pubkey, account.lamports).unwrap();
+    }
+}
+
+/// alice pays bob, bob pays carol, carol pays alice; each batch starts from the state tree the one before left.
+fn chained_batches(alice: &SigningKey, bob: &SigningKey, carol: &SigningKey) -> Vec<BatchWitness> {
+    let key = |signer: &SigningKey| signer.verifying_key().to_bytes();
+    let mut state = StateTree::new();
+    let mut batches = Vec::new();
+    for (batch_id, transaction, accounts) in [
+        ("1", transfer(alice, key(bob), 300_000), [account(key(alice), 1_000_000), account(key(bob), 0)]),
+        ("2", transfer(bob, key(carol), 100_000), [account(key(bob), 300_000), account(key(carol), 50_000)]),
+        ("3", transfer(carol, key(alice), 10_000), [account(key(carol), 150_000), account(key(alice), 695_000)]),
+    ] {
+        let next = batch(batch_id, vec![transaction], accounts.to_vec(), &state);
+        apply(&mut state, &next);
+        batches.push(next);
+    }
+    batches
+}
+
+#[test]
+fn folds_batches_into_one_execution_of_all_their_transactions() {
+    let signers = [1, 2, 3].map(|seed| SigningKey::from_bytes(&[seed; 32]));
+    let batches = chained_batches(&signers[0], &signers[1], &signers[2]);
+    let children = batches.iter().map(|batch| AggregationChild::batch(batch).unwrap()).collect();
+
+    let aggregation = aggregate(&input(children)).unwrap();
+    let key = |index: usize| signers[index].verifying_key().to_bytes();
+    let whole = batch(
+        "whole",
+        batches.iter().flat_map(|batch| batch.transactions.clone()).collect(),
+        vec![account(key(0), 1_000_000), account(key(1), 0), account(key(2), 50_000)],
+        &StateTree::new(),
+    );
+    let expected = BatchJournal::execute(&whole).unwrap();
+    assert_eq!(aggregation.journal.pre_state_root, expected.pre_state_root);
+    assert_eq!(aggregation.journal.post_state_root, expected.post_state_root);
+    assert_eq!(aggregation.journal.batch_ids, ["1", "2", "3"]);
+
+    let mut left = StateTree::new();
+    for (index, lamports) in [705_000, 195_000, 135_000].into_iter().enumerate() {
+        left.set(&key(index), lamports).unwrap();
+    }
+    assert_eq!(aggregation.journal.post_state_root, left.root_bytes());
+}
+
+#[test]
+fn nested_aggregates_commit_the_same_state_as_a_flat_one() {
+    let signers = [1, 2, 3].map(|seed| SigningKey::from_bytes(&[seed; 32]));
+    let children: Vec<_> = chained_batches(&signers[0], &signers[1], &signers[2])
+        .iter()
+        .map(|batch| AggregationChild::batch(batch).unwrap())
+        .collect();
+
+    let flat = aggregate(&input(children.clone())).unwrap();
+    let inner = aggregate(&input(children[..2].to_vec())).unwrap();
+    let nested = aggregate(&input(vec![inner.into_child(), children[2].clone()])).unwrap();
+    assert_eq!(nested.journal.pre_state_root, flat.journal.pre_state_root);
+    assert_eq!(nested.journal.post_state_root, flat.journal.post_state_root);
+    assert_eq!(nested.journal.batch_ids, flat.journal.batch_ids);
+}
+
+#[test]
+fn rejects_proofs_that_do_not_chain() {
+    let signers = [1, 2, 3].map(|seed| SigningKey::from_bytes(&[seed; 32]));
+    let mut batches = chained_batches(&signers[0], &signers[1], &signers[2]);
+    // the second batch starts over from the empty state, where bob can enter with lamports the first never gave him
+    let bob = signers[1].verifying_key().to_bytes();
+    batches[1] = batch("2", batches[1].transactions.clone(), vec![account(bob, 900_000), batches[1].accounts[1].clone()], &StateTree::new());
+    let children: Vec<_> = batches.iter().map(|batch| AggregationChild::batch(batch).unwrap()).collect();
+    let error = aggregate(&input(children.clone())).unwrap_err();
+    assert_eq!(error.child, 1);
+
+    // a proof that claims to have left another state than the next one starts from
+    let honest: Vec<_> = chained_batches(&signers[0], &signers[1], &signers[2])
+        .iter()
+        .map(|batch| AggregationChild::batch(batch).unwrap())
+        .collect();
+    let mut tampered = honest.clone();
+    if let ProvedJournal::Batch(journal) = &mut tampered[0].journal {
+        journal.post_state_root[31] ^= 1;
+    }
+    assert_eq!(aggregate(&input(tampered)).unwrap_err().child, 1);
+    aggregate(&input(honest)).unwrap();
+
+    let mut foreign = aggregate(&input(children[..1].to_vec())).unwrap();
+    foreign.journal.aggregate_image_id = [3; 8];
+    assert_eq!(aggregate(&input(vec![foreign.into_child()])).unwrap_err().child, 0);
+}
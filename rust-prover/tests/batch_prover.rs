@@ -0,0 +1,197 @@
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use zk::{export::fr_to_decimal, AccountPath, BatchProver, KeyStore, ProverError, StateError, StateTree, TransferWitness};
+
+const ALICE: [u8; 32] = [1; 32];
+const BOB: [u8; 32] = [2; 32];
+const CAROL: [u8; 32] = [3; 32];
+
+fn transfer(from: ([u8; 32], u64), to: ([u8; 32], u64), amount: u64, fee: u64) -> TransferWitness {
+    TransferWitness {
+        from: from.0,
+        to: to.0,
+        amount,
+        fee,
+        signature_first_byte: 42,
+        from_balance_before: from.1,
+        from_balance_after: from.1 - amount - fee,
+        to_balance_before: to.1,
+        to_balance_after: to.1 + amount,
+    }
+}
+
+/// Paths of accounts that have not entered the state yet.
+fn fresh(keys: &[[u8; 32]]) -> Vec<AccountPath> {
+    StateTree::new().paths(keys).unwrap()
+}
+
+fn test_prover() -> BatchProver {
+    BatchProver::setup(3, &mut StdRng::seed_from_u64(7)).unwrap()
+}
+
+#[test]
+fn proves_and_verifies_a_short_batch() {
+    let prover = test_prover();
+    // bob spends lamports received earlier in the same batch
+    let proof = prover
+        .prove(vec![
+            transfer((ALICE, 5_000_000), (BOB, 0), 1_000_000, 5_000),
+            transfer((BOB, 1_000_000), (CAROL, 10), 400_000, 5_000),
+        ], &fresh(&[ALICE, BOB, CAROL]))
+        .unwrap();
+    assert!(prover.verify(&proof).unwrap());
+
+    let mut post = StateTree::new();
+    for (key, balance) in [(ALICE, 3_995_000), (BOB, 595_000), (CAROL, 400_010)] {
+        post.set(&key, balance).unwrap();
+    }
+    assert_eq!(
+        proof.public_inputs_decimal(),
+        vec![fr_to_decimal(&StateTree::new().root()), fr_to_decimal(&post.root())]
+    );
+
+    let snarkjs = proof.to_snarkjs();
+    assert_eq!(snarkjs.protocol, "groth16");
+    assert_eq!(snarkjs.pi_a[2], "1");
+    assert_eq!(prover.verifying_key_snarkjs().IC.len(), 3);
+}
+
+#[test]
+fn consecutive_batches_share_their_state_roots() {
+    let prover = test_prover();
+    let mut state = StateTree::new();
+
+    let first = vec![transfer((ALICE, 5_000_000), (BOB, 0), 1_000_000, 5_000)];
+    let proof = prover.prove(first, &state.paths(&[ALICE, BOB]).unwrap()).unwrap();
+    state.set(&ALICE, 3_995_000).unwrap();
+    state.set(&BOB, 1_000_000).unwrap();
+    assert_eq!(proof.public_inputs[1], state.root());
+
+    // the next batch starts from where the last one left the whole state, not just its own accounts
+    let second = vec![transfer((BOB, 1_000_000), (CAROL, 0), 400_000, 5_000)];
+    let next = prover.prove(second.clone(), &state.paths(&[BOB, CAROL]).unwrap()).unwrap();
+    assert!(prover.verify(&next).unwrap());
+    assert_eq!(next.public_inputs[0], proof.public_inputs[1]);
+
+    // bob cannot enter the state again with a balance of his choosing
+    let reentry = TransferWitness { from_balance_before: 5_000_000, from_balance_after: 4_595_000, ..second[0] };
+    let reentered = prover.prove(vec![reentry], &state.paths(&[BOB, CAROL]).unwrap());
+    assert!(matches!(reentered, Err(ProverError::InvalidTransfer { slot: 0, .. })));
+    // paths from before the first batch prove against a root that no longer is the state
+    let stale = prover.prove(vec![transfer((BOB, 0), (CAROL, 0), 0, 0)], &fresh(&[BOB, CAROL])).unwrap();
+    assert_ne!(stale.public_inputs[0], proof.public_inputs[1]);
+}
+
+#[test]
+fn rejects_transfers_the_circuit_would_not_satisfy() {
+    let prover = test_prover();
+    let paths = fresh(&[ALICE, BOB, CAROL]);
+
+    let overdrawn = TransferWitness { from_balance_after: 0, ..transfer((ALICE, 10), (BOB, 0), 5, 0) };
+    assert!(matches!(prover.prove(vec![overdrawn], &paths), Err(ProverError::InvalidTransfer { slot: 0, .. })));
+
+    let minted = TransferWitness { to_balance_after: 100, ..transfer((ALICE, 10), (BOB, 0), 5, 0) };
+    assert!(matches!(prover.prove(vec![minted], &paths), Err(ProverError::InvalidTransfer { slot: 0, .. })));
+
+    // the second transfer claims alice still has her starting balance
+    let stale = vec![transfer((ALICE, 10), (BOB, 0), 5, 0), transfer((ALICE, 10), (CAROL, 0), 5, 0)];
+    assert!(matches!(prover.prove(stale, &paths), Err(ProverError::InvalidTransfer { slot: 1, .. })));
+
+    // every account needs its path, and all paths must come from the same tree
+    let unknown = prover.prove(vec![transfer((ALICE, 10), (BOB, 0), 5, 0)], &fresh(&[ALICE]));
+    assert!(matches!(unknown, Err(ProverError::InvalidTransfer { slot: 0, .. })));
+    let mut other = StateTree::new();
+    other.set(&CAROL, 1).unwrap();
+    let mixed = [fresh(&[ALICE]), other.paths(&[BOB]).unwrap()].concat();
+    let mixed = prover.prove(vec![transfer((ALICE, 10), (BOB, 0), 5, 0)], &mixed);
+    assert!(matches!(mixed, Err(ProverError::InvalidStatePath(_))));
+
+    let too_many = prover.prove(vec![transfer((ALICE, 10), (BOB, 0), 0, 0); 4], &paths);
+    assert!(matches!(too_many, Err(ProverError::TooManyTransfers { transfers: 4, slots: 3 })));
+    assert!(matches!(prover.prove(Vec::new(), &paths), Err(ProverError::EmptyBatch)));
+}
+
+#[test]
+fn an_account_has_one_leaf() {
+    // keys that agree in their low bytes still land far apart: the index hashes the whole key
+    let mut twin = BOB;
+    twin[28..].copy_from_slice(&ALICE[28..]);
+    assert_ne!(zk::account_index(&twin), zk::account_index(&ALICE));
+
+    // a small tree makes a collision easy to find; in the full one it takes about 2^64 tries per account
+    let mut state = StateTree::with_depth(4);
+    let keys: Vec<[u8; 32]> = (0..=16u8).map(|i| [i; 32]).collect();
+    let leaf = |key: &[u8; 32]| zk::account_index(key) % 16;
+    let (first, second) = keys
+        .iter()
+        .enumerate()
+        .find_map(|(i, a)| keys[i + 1..].iter().find(|b| leaf(a) == leaf(b)).map(|b| (*a, *b)))
+        .expect("17 keys in 16 leaves");
+    state.set(&first, 10).unwrap();
+    assert!(matches!(state.path(&second), Err(StateError::LeafTaken { .. })));
+    assert!(matches!(state.set(&second, 5), Err(StateError::LeafTaken { .. })));
+    assert_eq!((state.balance(&first), state.balance(&second)), (Some(10), None));
+    state.set(&first, 7).unwrap();
+    assert_eq!(state.path(&first).unwrap().balance, Some(7));
+}
+
+#[test]
+fn saved_key_round_trips() {
+    let prover = test_prover();
+    let path = std::env::temp_dir().join(format!("zk_batch_key_{}.bin", std::process::id()));
+    prover.save(&path).unwrap();
+
+    let loaded = BatchProver::load_for(&path, 3).unwrap();
+    let proof = loaded.prove(vec![transfer((ALICE, 10), (ALICE, 4), 5, 1)], &fresh(&[ALICE])).unwrap();
+    assert!(prover.verify(&proof).unwrap());
+    assert!(matches!(BatchProver::load_for(&path, 4), Err(ProverError::KeyMismatch { expected: 4, found: 3 })));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn key_store_keeps_one_key_per_size_class() {
+    let dir = std::env::temp_dir().join(format!("zk_key_store_{}", std::process::id()));
+    let offline = KeyStore::new(&dir);
+    assert!(matches!(offline.get(1), Err(ProverError::MissingKey { slots: 1, .. })));
+
+    let dev = KeyStore::new(&dir).with_dev_setup(7);
+    let small = dev.get(1).unwrap();
+    let large = dev.get(2).unwrap();
+    assert_eq!((small.batch_size(), large.batch_size()), (1, 2));
+    assert_eq!((dev.current(1).unwrap(), dev.current(2).unwrap()), (Some(1), Some(1)));
+
+    // seeded keys are only good for dev mode
+    assert!(matches!(offline.get(2), Err(ProverError::UntrustedKey { slots: 2, .. })));
+
+    // another dev store finds the saved keys instead of setting up its own
+    let prover = KeyStore::new(&dir).with_dev_setup(8).get(2).unwrap();
+    let proof = prover.prove(vec![transfer((ALICE, 10), (BOB, 0), 5, 1)], &fresh(&[ALICE, BOB])).unwrap();
+    assert_eq!(proof.slots, 2);
+    assert!(large.verify(&proof).unwrap());
+    assert!(!small.verify(&proof).unwrap_or(false));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn verifies_snarkjs_proofs_without_the_proving_key() {
+    let prover = test_prover();
+    let transfers = vec![transfer((ALICE, 5_000_000), (BOB, 0), 1_000_000, 5_000)];
+    let paths = fresh(&[ALICE, BOB]);
+    let proof = prover.prove(transfers.clone(), &paths).unwrap();
+    let (vk, snarkjs, inputs) = (prover.verifying_key_snarkjs(), proof.to_snarkjs(), proof.public_inputs_decimal());
+    assert!(zk::verify_snarkjs(&vk, &snarkjs, &inputs).unwrap());
+
+    // the roots the other way round
+    assert!(!zk::verify_snarkjs(&vk, &snarkjs, &[inputs[1].clone(), inputs[0].clone()]).unwrap());
+    assert!(matches!(zk::verify_snarkjs(&vk, &snarkjs, &inputs[..1]), Err(ProverError::InvalidEncoding(_))));
+    // the field modulus itself is not a canonical public input
+    let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617".to_string();
+    assert!(matches!(zk::verify_snarkjs(&vk, &snarkjs, &[modulus, inputs[1].clone()]), Err(ProverError::InvalidEncoding(_))));
+    let off_curve = zk::SnarkJsProof { pi_a: [snarkjs.pi_a[0].clone(), "1".to_string(), "1".to_string()], ..snarkjs.clone() };
+    assert!(matches!(zk::verify_snarkjs(&vk, &off_curve, &inputs), Err(ProverError::InvalidEncoding(_))));
+
+    let stats = zk::bench::synthesize(transfers, &paths, 3).unwrap();
+    assert!(stats.constraints > 0);
+    assert!(matches!(zk::bench::synthesize(Vec::new(), &paths, 3), Err(ProverError::EmptyBatch)));
+}
@@ -0,0 +1,115 @@
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use zk::{
+    setup::{self, PowersOfTau, SetupError},
+    BatchProver, KeyOrigin, KeyStore, ProverError, StateTree, TransferWitness,
+};
+
+const ALICE: [u8; 32] = [1; 32];
+const BOB: [u8; 32] = [2; 32];
+
+fn transfer(amount: u64) -> TransferWitness {
+    TransferWitness {
+        from: ALICE,
+        to: BOB,
+        amount,
+        fee: 0,
+        signature_first_byte: 42,
+        from_balance_before: 100,
+        from_balance_after: 100 - amount,
+        to_balance_before: 0,
+        to_balance_after: amount,
+    }
+}
+
+/// The smallest ceremony that covers the one-slot circuit.
+fn ceremony(rng: &mut StdRng) -> PowersOfTau {
+    let power = setup::domain_size(1).unwrap().trailing_zeros();
+    PowersOfTau::insecure(power, rng)
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("zk_setup_{}_{}", name, std::process::id()))
+}
+
+#[test]
+fn ptau_file_round_trips() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let ptau = PowersOfTau::insecure(3, &mut rng);
+    let path = temp_dir("ptau");
+    ptau.write(&path).unwrap();
+
+    let read = PowersOfTau::read(&path, 8).unwrap();
+    assert_eq!((read.power, read.max_domain()), (3, 8));
+    read.check(&mut rng).unwrap();
+    // a smaller domain reads only a prefix of each section
+    assert_eq!(PowersOfTau::read(&path, 4).unwrap().max_domain(), 4);
+    assert!(matches!(PowersOfTau::read(&path, 16), Err(SetupError::TooSmall { needed: 16, available: 8 })));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn ceremony_keys_prove_and_check_contributions() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let initial = setup::initialize(&ceremony(&mut rng), 1).unwrap();
+    let first = setup::contribute(&initial, &mut rng);
+    setup::verify_contribution(&initial, &first, &mut rng).unwrap();
+
+    let prover = BatchProver::from_proving_key(1, first.clone());
+    let proof = prover.prove(vec![transfer(30)], &StateTree::new().paths(&[ALICE, BOB]).unwrap()).unwrap();
+    assert!(prover.verify(&proof).unwrap());
+
+    // no contribution at all
+    assert!(setup::verify_contribution(&first, &first, &mut rng).is_err());
+    // δ moved, but one L term was left behind
+    let mut second = setup::contribute(&first, &mut rng);
+    second.l_query[0] = first.l_query[0];
+    assert!(setup::verify_contribution(&first, &second, &mut rng).is_err());
+    // a contribution to another key
+    let other = BatchProver::setup(1, &mut rng).unwrap();
+    assert!(setup::verify_contribution(&first, &setup::contribute(other.proving_key(), &mut rng), &mut rng).is_err());
+}
+
+#[test]
+fn key_store_versions_contributions() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let dir = temp_dir("store");
+    let store = KeyStore::new(&dir);
+    let first = store.setup_from_ptau(&ceremony(&mut rng), [7; 32], 1, "alice", &mut rng).unwrap();
+    assert_eq!((first.version, first.contributions.len()), (1, 1));
+    assert!(matches!(first.origin, KeyOrigin::PowersOfTau { power, .. } if power == setup::domain_size(1).unwrap().trailing_zeros()));
+    assert_eq!(first.insecure_reason(), None);
+
+    // a contribution made elsewhere, from a copy of the current key
+    let second = store.contribute(1, "bob", &mut rng).unwrap();
+    let copy = store.get(1).unwrap();
+    let contributed = temp_dir("contributed");
+    BatchProver::from_proving_key(1, setup::contribute(copy.proving_key(), &mut rng)).save(&contributed).unwrap();
+    let third = store.import_contribution(1, "carol", &contributed, &mut rng).unwrap();
+    assert_eq!((second.version, third.version, store.current(1).unwrap()), (2, 3, Some(3)));
+    let names: Vec<&str> = third.contributions.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["alice", "bob", "carol"]);
+    // the same file again does not follow the current key any more
+    assert!(matches!(
+        store.import_contribution(1, "carol", &contributed, &mut rng),
+        Err(ProverError::Setup(SetupError::Inconsistent(_)))
+    ));
+
+    let prover = store.get(1).unwrap();
+    assert!(prover.verify(&prover.prove(vec![transfer(5)], &StateTree::new().paths(&[ALICE, BOB]).unwrap()).unwrap()).unwrap());
+
+    // a store that only verifies reads the verification key without the proving key
+    assert_eq!(KeyStore::new(&dir).verifying_key(1).unwrap().vk_delta_2, prover.verifying_key_snarkjs().vk_delta_2);
+
+    // roll back, and refuse a version whose key file was swapped
+    store.activate(1, 1).unwrap();
+    assert_eq!(store.current(1).unwrap(), Some(1));
+    std::fs::copy(store.version_dir(1, 1).join("proving_key.bin"), store.version_dir(1, 3).join("proving_key.bin")).unwrap();
+    assert!(matches!(store.activate(1, 3), Err(ProverError::KeyHashMismatch { slots: 1, version: 3 })));
+    let verification_key = |version| store.version_dir(1, version).join("verification_key.json");
+    std::fs::copy(verification_key(3), verification_key(1)).unwrap();
+    assert!(matches!(KeyStore::new(&dir).verifying_key(1), Err(ProverError::KeyHashMismatch { slots: 1, version: 1 })));
+
+    std::fs::remove_file(contributed).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+}
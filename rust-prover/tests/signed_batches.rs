@@ -0,0 +1,195 @@
+use ed25519_dalek::{Signer, SigningKey};
+use zk::{
+    auth::signed_transfer, authorize, execute, journal::transactions_digest, AccountWitness, AuthError, BatchJournal,
+    BatchWitness, StateTree, TransferWitness,
+};
+
+const COMPUTE_BUDGET: [u8; 32] = [
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187, 197, 247, 18, 107, 44,
+    67, 155, 58, 64, 0, 0, 0,
+];
+
+/// A legacy transaction with one system transfer, signed by `signer`, in wire format.
+/// `from_signs: false` makes `from` an unsigned account instead; `unit_price` adds
+/// a `SetComputeUnitPrice`.
+fn transaction(
+    signer: &SigningKey,
+    from: [u8; 32],
+    to: [u8; 32],
+    lamports: u64,
+    from_signs: bool,
+    unit_price: Option<u64>,
+) -> Vec<u8> {
+    let payer = signer.verifying_key().to_bytes();
+    let mut keys = vec![payer];
+    let from_index = if from == payer {
+        0
+    } else {
+        keys.push(from);
+        1
+    };
+    let to_index = keys.len() as u8;
+    keys.extend([to, [0; 32]]);
+    if unit_price.is_some() {
+        keys.push(COMPUTE_BUDGET);
+    }
+
+    let readonly = keys.len() as u8 - to_index - 1;
+    let header = if from_signs { [1, 0, readonly] } else { [1, 0, readonly + 1] };
+    let mut message = header.to_vec();
+    message.push(keys.len() as u8);
+    keys.iter().for_each(|key| message.extend(key));
+    message.extend([9; 32]);
+
+    message.push(1 + unit_price.is_some() as u8);
+    let mut data = vec![2, 0, 0, 0];
+    data.extend(lamports.to_le_bytes());
+    message.extend([to_index + 1, 2, from_index, to_index, data.len() as u8]);
+    message.extend(data);
+    if let Some(price) = unit_price {
+        message.extend([to_index + 2, 0, 9, 3]);
+        message.extend(price.to_le_bytes());
+    }
+
+    let mut wire = vec![1];
+    wire.extend(signer.sign(&message).to_bytes());
+    wire.extend(message);
+    wire
+}
+
+fn transfer(from: [u8; 32], to: [u8; 32], amount: u64) -> TransferWitness {
+    TransferWitness {
+        from,
+        to,
+        amount,
+        fee: 5_000,
+        signature_first_byte: 1,
+        from_balance_before: 1_000_000,
+        from_balance_after: 1_000_000 - amount - 5_000,
+        to_balance_before: 0,
+        to_balance_after: amount,
+    }
+}
+
+fn batch(transfers: Vec<TransferWitness>, transactions: Vec<Vec<u8>>) -> BatchWitness {
+    BatchWitness { batch_id: "batch".to_string(), slots: 4, transfers, transactions, accounts: Vec::new(), state_paths: Vec::new() }
+}
+
+#[test]
+fn authorizes_transfers_signed_by_their_senders() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let bob = SigningKey::from_bytes(&[2; 32]);
+    let (alice_key, bob_key) = (alice.verifying_key().to_bytes(), bob.verifying_key().to_bytes());
+
+    let transactions = vec![
+        transaction(&alice, alice_key, bob_key, 300, true, None),
+        transaction(&bob, bob_key, alice_key, 100, true, None),
+    ];
+    let witness = batch(vec![transfer(alice_key, bob_key, 300), transfer(bob_key, alice_key, 100)], transactions);
+    assert_eq!(authorize(&witness), Ok(()));
+
+    let signed = signed_transfer(&witness.transactions[0]).unwrap();
+    assert_eq!((signed.fee_payer, signed.from, signed.to, signed.lamports), (alice_key, alice_key, bob_key, 300));
+
+    // the digest pins the exact transactions
+    let mut reordered = witness.transactions.clone();
+    reordered.swap(0, 1);
+    assert_ne!(transactions_digest(&witness.transactions), transactions_digest(&reordered));
+}
+
+#[test]
+fn rejects_transfers_nobody_authorized() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let mallory = SigningKey::from_bytes(&[3; 32]);
+    let (alice_key, mallory_key) = (alice.verifying_key().to_bytes(), mallory.verifying_key().to_bytes());
+    let reason = |witness: &BatchWitness| authorize(witness).unwrap_err().reason;
+
+    // a different amount than the one signed
+    let signed = transaction(&alice, alice_key, mallory_key, 300, true, None);
+    let inflated = batch(vec![transfer(alice_key, mallory_key, 3_000)], vec![signed.clone()]);
+    assert_eq!(reason(&inflated), "signs a different transfer");
+
+    // a tampered message
+    let mut tampered = signed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let tampered = batch(vec![transfer(alice_key, mallory_key, 300)], vec![tampered]);
+    assert_eq!(reason(&tampered), "has an invalid signature");
+
+    // mallory pays the fee and lists alice as an unsigned sender
+    let unsigned = transaction(&mallory, alice_key, mallory_key, 300, false, None);
+    let unsigned = batch(vec![transfer(alice_key, mallory_key, 300)], vec![unsigned]);
+    assert_eq!(reason(&unsigned), "has a system transfer its sender did not sign");
+
+    // a second transfer without its transaction
+    let missing = batch(vec![transfer(alice_key, mallory_key, 300), transfer(alice_key, mallory_key, 1)], vec![signed]);
+    assert_eq!(authorize(&missing), Err(AuthError { slot: 1, reason: "is missing from the witness" }));
+}
+
+fn account(pubkey: [u8; 32], lamports: u64) -> AccountWitness {
+    AccountWitness { lamports, ..AccountWitness::empty(pubkey) }
+}
+
+#[test]
+fn executes_signed_transfers_over_the_pre_state() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let bob = SigningKey::from_bytes(&[2; 32]);
+    let (alice_key, bob_key, carol_key) = (alice.verifying_key().to_bytes(), bob.verifying_key().to_bytes(), [7; 32]);
+
+    let mut witness = batch(
+        Vec::new(),
+        vec![
+            transaction(&alice, alice_key, bob_key, 300_000, true, None),
+            // 1 lamport per compute unit over the 3,000 units of one builtin instruction
+            transaction(&bob, bob_key, carol_key, 100_000, true, Some(1_000_000)),
+        ],
+    );
+    // alice is in the state already, bob and carol enter it with this batch
+    let mut state = StateTree::new();
+    state.set(&alice_key, 1_000_000).unwrap();
+    witness.accounts = vec![account(alice_key, 1_000_000), account(bob_key, 10_000), AccountWitness::empty(carol_key)];
+    witness.state_paths = state.paths(&[alice_key, bob_key, carol_key]).unwrap();
+
+    let execution = execute(&witness.accounts, &witness.state_paths, &witness.transactions).unwrap();
+    let mut expected = vec![account(alice_key, 695_000), account(bob_key, 202_000), account(carol_key, 100_000)];
+    expected.sort_by_key(|account| account.pubkey);
+    assert_eq!(execution.accounts, expected);
+    assert_eq!(execution.pre_state_root, state.root_bytes());
+    for account in &expected {
+        state.set(&account.pubkey, account.lamports).unwrap();
+    }
+    assert_eq!(execution.post_state_root, state.root_bytes());
+
+    let journal = BatchJournal::execute(&witness).unwrap();
+    assert_eq!((journal.pre_state_root, journal.post_state_root), (execution.pre_state_root, execution.post_state_root));
+    assert_ne!(journal.pre_state_root, journal.post_state_root);
+}
+
+#[test]
+fn rejects_batches_that_do_not_execute() {
+    let alice = SigningKey::from_bytes(&[1; 32]);
+    let alice_key = alice.verifying_key().to_bytes();
+    let bob_key = [7; 32];
+    let signed = transaction(&alice, alice_key, bob_key, 300_000, true, None);
+    let fresh = StateTree::new().paths(&[alice_key, bob_key]).unwrap();
+    let reason = |accounts: Vec<AccountWitness>| execute(&accounts, &fresh, std::slice::from_ref(&signed)).unwrap_err().reason;
+
+    assert_eq!(
+        reason(vec![account(alice_key, 302_000), AccountWitness::empty(bob_key)]),
+        "transfers more lamports than the sender has"
+    );
+    assert_eq!(reason(vec![account(alice_key, 4_000), AccountWitness::empty(bob_key)]), "has a fee payer that cannot pay the fee");
+    assert_eq!(reason(vec![account(alice_key, 1_000_000)]), "touches an account missing from the pre-state");
+
+    let program_owned = AccountWitness { owner: [5; 32], ..account(alice_key, 1_000_000) };
+    assert_eq!(reason(vec![program_owned, AccountWitness::empty(bob_key)]), "debits an account the system program does not own");
+
+    // every account is opened against the state tree
+    let accounts = [account(alice_key, 1_000_000), AccountWitness::empty(bob_key)];
+    let opened = |paths: &[zk::AccountPath]| execute(&accounts, paths, std::slice::from_ref(&signed)).map(|_| ()).unwrap_err().reason;
+    assert_eq!(opened(&fresh[..1]), "has an account without a state path");
+    let mut state = StateTree::new();
+    state.set(&alice_key, 2_000_000).unwrap();
+    assert_eq!(opened(&state.paths(&[alice_key, bob_key]).unwrap()), "has an account with other lamports than the state tree");
+    let mixed = [fresh[0].clone(), state.path(&bob_key).unwrap()];
+    assert_eq!(opened(&mixed), "has state paths that do not open one state tree");
+}